@@ -0,0 +1,105 @@
+//! Token amounts in ui units, e.g. "1.5" of a mint with 6 decimals is
+//! 1_500_000 base units.
+
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AmountError {
+    Invalid(String),
+    /// More fractional digits than the mint has decimals
+    TooPrecise {
+        amount: String,
+        decimals: u8,
+    },
+    Overflow(String),
+}
+
+impl fmt::Display for AmountError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AmountError::Invalid(amount) => {
+                write!(f, "invalid amount {amount:?}")
+            }
+            AmountError::TooPrecise { amount, decimals } => {
+                write!(f, "{amount} has more than {decimals} decimals")
+            }
+            AmountError::Overflow(amount) => {
+                write!(f, "{amount} is too large")
+            }
+        }
+    }
+}
+
+impl std::error::Error for AmountError {}
+
+/// Base units of a ui `amount` of a mint with `decimals`
+pub fn parse(amount: &str, decimals: u8) -> Result<u64, AmountError> {
+    let invalid = || AmountError::Invalid(amount.to_string());
+    let (whole, fraction) = amount
+        .split_once('.')
+        .unwrap_or((amount, ""));
+    if whole.is_empty() && fraction.is_empty()
+        || !whole
+            .chars()
+            .chain(fraction.chars())
+            .all(|c| c.is_ascii_digit())
+    {
+        return Err(invalid());
+    }
+    if fraction.len() > decimals as usize {
+        return Err(AmountError::TooPrecise {
+            amount: amount.to_string(),
+            decimals,
+        });
+    }
+
+    let digits =
+        format!("{whole}{fraction:0<width$}", width = decimals as usize);
+    let digits = digits.trim_start_matches('0');
+    if digits.is_empty() {
+        return Ok(0);
+    }
+    digits
+        .parse()
+        .map_err(|_| AmountError::Overflow(amount.to_string()))
+}
+
+/// Ui amount of `amount` base units, without trailing zeros
+pub fn format(amount: u64, decimals: u8) -> String {
+    let digits = format!("{amount:0>width$}", width = decimals as usize + 1);
+    let (whole, fraction) = digits.split_at(digits.len() - decimals as usize);
+    match fraction.trim_end_matches('0') {
+        "" => whole.to_string(),
+        fraction => format!("{whole}.{fraction}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_formats_ui_amounts() {
+        assert_eq!(parse("1.5", 6), Ok(1_500_000));
+        assert_eq!(parse(".25", 2), Ok(25));
+        assert_eq!(parse("7", 0), Ok(7));
+        assert_eq!(parse("0.000", 3), Ok(0));
+        assert_eq!(parse("18446744073709551615", 0), Ok(u64::MAX));
+        assert!(matches!(
+            parse("1.234", 2),
+            Err(AmountError::TooPrecise { .. })
+        ));
+        assert!(matches!(
+            parse("18446744073709551616", 0),
+            Err(AmountError::Overflow(_))
+        ));
+        assert!(matches!(parse("-1", 2), Err(AmountError::Invalid(_))));
+        assert!(matches!(parse(".", 2), Err(AmountError::Invalid(_))));
+
+        assert_eq!(format(1_500_000, 6), "1.5");
+        assert_eq!(format(25, 2), "0.25");
+        assert_eq!(format(100, 2), "1");
+        assert_eq!(format(0, 9), "0");
+        assert_eq!(format(7, 0), "7");
+    }
+}
@@ -0,0 +1,1274 @@
+//! Subcommand implementations.
+
+use std::{
+    cell::RefCell,
+    collections::{hash_map::Entry, HashMap, HashSet},
+    error::Error,
+    fs,
+    path::Path,
+    rc::Rc,
+    sync::Arc,
+    time::Duration,
+};
+
+use futures_util::StreamExt;
+use nanotoken::{
+    consts::{NATIVE_DECIMALS, NATIVE_MINT_INDEX},
+    ix::{InitializeMintArgs, TransferFeeConfig},
+    Mint, TokenAccount,
+};
+use nanotoken_client::{
+    batch::BatchBuilder,
+    filters, instructions, lookup_table, pda,
+    priority_fee::StaticFee,
+    rpc::{NanotokenRpc, RpcError},
+};
+use nanotoken_subscriber::{ws::Subscriber, AccountState, Tracker};
+use solana_client::{
+    nonblocking::rpc_client::RpcClient,
+    nonce_utils::nonblocking::{
+        data_from_account, get_account_with_commitment,
+    },
+    rpc_client::GetConfirmedSignaturesForAddress2Config,
+    rpc_request::MAX_MULTIPLE_ACCOUNTS,
+};
+use solana_remote_wallet::remote_wallet::RemoteWalletManager;
+use solana_sdk::{
+    account::Account,
+    address_lookup_table_account::AddressLookupTableAccount,
+    commitment_config::CommitmentConfig,
+    hash::Hash,
+    instruction::Instruction,
+    message::Message,
+    pubkey::Pubkey,
+    signature::Signature,
+    signer::Signer,
+    system_instruction,
+    transaction::{Transaction, VersionedTransaction},
+};
+use tokio::time::sleep;
+
+use crate::{
+    amount,
+    distribute::{self, Progress, Row},
+    signer::signer_from_path,
+    snapshot::Snapshot,
+    Authority, Command, OfflineArgs,
+};
+
+type CliResult<T = ()> = Result<T, Box<dyn Error>>;
+
+/// Offset of `decimals` in a tokenkeg (or token-2022) mint
+const TOKENKEG_DECIMALS_OFFSET: usize = 44;
+
+pub struct Context {
+    rpc: NanotokenRpc,
+    owner: Box<dyn Signer>,
+    /// None if the owner pays
+    fee_payer: Option<Box<dyn Signer>>,
+    compute_unit_price: Option<u64>,
+    /// Set up by the first hardware signer
+    wallet_manager: RefCell<Option<Rc<RemoteWalletManager>>>,
+    offline: OfflineArgs,
+    /// None if the owner advances the nonce, or there is none
+    nonce_authority: Option<Box<dyn Signer>>,
+}
+
+impl Context {
+    pub fn new(
+        url: String,
+        keypair: &str,
+        fee_payer: Option<&str>,
+        compute_unit_price: Option<u64>,
+        offline: OfflineArgs,
+    ) -> CliResult<Self> {
+        let mut wallet_manager = None;
+        let owner = signer_from_path(
+            keypair,
+            "keypair",
+            &mut wallet_manager,
+            &offline,
+        )?;
+        let fee_payer = fee_payer
+            .map(|path| {
+                signer_from_path(
+                    path,
+                    "fee payer",
+                    &mut wallet_manager,
+                    &offline,
+                )
+            })
+            .transpose()?;
+        let nonce_authority = offline
+            .nonce_authority
+            .as_deref()
+            .map(|path| {
+                signer_from_path(
+                    path,
+                    "nonce authority",
+                    &mut wallet_manager,
+                    &offline,
+                )
+            })
+            .transpose()?;
+        Ok(Self {
+            rpc: NanotokenRpc::new(RpcClient::new_with_commitment(
+                url,
+                CommitmentConfig::confirmed(),
+            )),
+            owner,
+            fee_payer,
+            compute_unit_price,
+            wallet_manager: RefCell::new(wallet_manager),
+            offline,
+            nonce_authority,
+        })
+    }
+
+    fn payer(&self) -> &dyn Signer {
+        self.fee_payer
+            .as_deref()
+            .unwrap_or(self.owner.as_ref())
+    }
+
+    /// The signer at `path`, if given
+    fn signer(
+        &self,
+        path: Option<&str>,
+        name: &str,
+    ) -> CliResult<Option<Box<dyn Signer>>> {
+        path.map(|path| {
+            signer_from_path(
+                path,
+                name,
+                &mut self.wallet_manager.borrow_mut(),
+                &self.offline,
+            )
+        })
+        .transpose()
+    }
+
+    pub async fn run(&self, command: Command) -> CliResult {
+        match command {
+            Command::CreateMint {
+                decimals,
+                mint_authority,
+                freeze_authority,
+                max_supply,
+            } => {
+                self.create_mint(
+                    decimals,
+                    mint_authority,
+                    freeze_authority,
+                    max_supply,
+                )
+                .await
+            }
+            Command::CreateAccount { mint, owner } => {
+                self.create_account(&mint, owner).await
+            }
+            Command::Mint {
+                mint,
+                amount,
+                recipient_token_account,
+                mint_authority,
+                offline: _,
+            } => {
+                let mint_authority =
+                    self.signer(mint_authority.as_deref(), "mint authority")?;
+                self.mint(
+                    &mint,
+                    &amount,
+                    recipient_token_account,
+                    mint_authority.as_deref(),
+                )
+                .await
+            }
+            Command::Transfer {
+                mint,
+                amount,
+                recipient,
+                fund_recipient,
+                from,
+                offline: _,
+            } => {
+                self.transfer(&mint, &amount, &recipient, fund_recipient, from)
+                    .await
+            }
+            Command::Distribute {
+                mint,
+                csv,
+                progress,
+                lookup_table,
+                from,
+                max_attempts,
+            } => {
+                let progress = progress.unwrap_or_else(|| {
+                    let mut path = csv.clone().into_os_string();
+                    path.push(".progress");
+                    path.into()
+                });
+                self.distribute(
+                    &mint,
+                    &csv,
+                    &progress,
+                    lookup_table,
+                    from,
+                    max_attempts,
+                )
+                .await
+            }
+            Command::Burn { mint, amount, from } => {
+                self.burn(&mint, &amount, from).await
+            }
+            Command::Balance {
+                mint,
+                owner,
+                address,
+            } => {
+                self.balance(&mint, owner, address)
+                    .await
+            }
+            Command::Supply { mint } => self.supply(&mint).await,
+            Command::Snapshot {
+                mint,
+                output,
+                min_balance,
+            } => {
+                self.snapshot(mint, &output, min_balance)
+                    .await
+            }
+            Command::Watch {
+                address,
+                mint,
+                ws_url,
+            } => {
+                let ws_url = ws_url
+                    .unwrap_or_else(|| crate::ws_url(&self.rpc.client().url()));
+                self.watch(&address, mint, &ws_url)
+                    .await
+            }
+            Command::Authorize {
+                address,
+                authority_type,
+                new_authority,
+                disable: _,
+                authority,
+            } => {
+                let authority =
+                    self.signer(authority.as_deref(), "authority")?;
+                self.authorize(
+                    &address,
+                    authority_type,
+                    new_authority.as_ref(),
+                    authority.as_deref(),
+                )
+                .await
+            }
+            Command::Wrap { amount, mint } => match mint {
+                Some(mint) => self.wrap_spl(&mint, &amount).await,
+                None => self.wrap_sol(&amount).await,
+            },
+            Command::Unwrap { amount, mint } => match mint {
+                Some(mint) => self.unwrap_spl(&mint, &amount).await,
+                None => self.unwrap_sol(&amount).await,
+            },
+        }
+    }
+
+    async fn create_mint(
+        &self,
+        decimals: u8,
+        mint_authority: Option<Pubkey>,
+        freeze_authority: Option<Pubkey>,
+        max_supply: Option<String>,
+    ) -> CliResult {
+        let mint_index = self
+            .rpc
+            .get_config()
+            .await?
+            .next_mint_index();
+        let (mint, _bump) = pda::mint_address(mint_index);
+        let max_supply = max_supply
+            .map(|max_supply| amount::parse(&max_supply, decimals))
+            .transpose()?;
+        let args = InitializeMintArgs {
+            authority: mint_authority.unwrap_or(self.owner.pubkey()),
+            decimals: decimals as u64,
+            freeze_authority: freeze_authority.unwrap_or_default(),
+            transfer_fee: TransferFeeConfig::default(),
+            pause_authority: Pubkey::default(),
+            max_supply: max_supply.unwrap_or_default(),
+            clawback_authority: Pubkey::default(),
+        };
+        println!("Creating mint {mint}");
+        println!("Mint index: {mint_index}");
+        println!("Decimals: {decimals}");
+        self.send_ops(
+            vec![instructions::initialize_mint_pda(
+                mint_index,
+                args,
+                &self.payer().pubkey(),
+            )],
+            &[],
+        )
+        .await
+    }
+
+    async fn create_account(
+        &self,
+        mint: &Pubkey,
+        owner: Option<Pubkey>,
+    ) -> CliResult {
+        let mint_index = self.get_mint(mint).await?.mint_index;
+        let owner = owner.unwrap_or(self.owner.pubkey());
+        let (address, _bump) = pda::token_account_address(&owner, mint_index);
+        let Some(ix) = self
+            .rpc
+            .initialize_account_if_needed(
+                &owner,
+                mint_index,
+                &self.payer().pubkey(),
+            )
+            .await?
+        else {
+            return Err(format!("account {address} already exists").into());
+        };
+        println!("Creating account {address}");
+        self.send_ops(vec![ix], &[]).await
+    }
+
+    async fn mint(
+        &self,
+        mint: &Pubkey,
+        amount: &str,
+        recipient: Option<Pubkey>,
+        mint_authority: Option<&dyn Signer>,
+    ) -> CliResult {
+        let mint_authority = mint_authority.unwrap_or(self.owner.as_ref());
+        let (mint_index, decimals) = self.mint_info(mint).await?;
+        let amount = amount::parse(amount, decimals)?;
+        let recipient =
+            recipient.unwrap_or_else(|| self.token_account(mint_index));
+        println!("Minting {} tokens", amount::format(amount, decimals));
+        println!("  Mint: {mint}");
+        println!("  Recipient: {recipient}");
+        self.send_ops(
+            vec![instructions::mint_to(
+                &recipient,
+                mint,
+                &mint_authority.pubkey(),
+                amount,
+            )],
+            &[mint_authority],
+        )
+        .await
+    }
+
+    async fn transfer(
+        &self,
+        mint: &Pubkey,
+        amount: &str,
+        recipient: &Pubkey,
+        fund_recipient: bool,
+        from: Option<Pubkey>,
+    ) -> CliResult {
+        let (mint_index, decimals) = self.mint_info(mint).await?;
+        let from = from.unwrap_or_else(|| self.token_account(mint_index));
+        let mut ops = vec![];
+
+        // A token account of the mint, or else a wallet. With the mint given
+        // offline, always a wallet.
+        let lookup = match self.offline.mint_index {
+            Some(_) => Ok(None),
+            None => {
+                self.rpc
+                    .get_token_account(recipient)
+                    .await
+            }
+        };
+        let to = match lookup {
+            Ok(Some(account)) if account.mint == mint_index => *recipient,
+            Ok(Some(account)) => {
+                return Err(format!(
+                    "recipient {recipient} is a token account of mint index {}",
+                    account.mint
+                )
+                .into())
+            }
+            Ok(None) if self.offline.mint_index.is_some() => {
+                let (to, _bump) =
+                    pda::token_account_address(recipient, mint_index);
+                if fund_recipient {
+                    println!("Funding recipient token account {to} if needed");
+                    ops.push(instructions::create_associated_account(
+                        recipient,
+                        mint_index,
+                        &self.payer().pubkey(),
+                    ));
+                }
+                to
+            }
+            Ok(None) | Err(RpcError::Decode(_)) => {
+                let (to, _bump) =
+                    pda::token_account_address(recipient, mint_index);
+                if let Some(ix) = self
+                    .rpc
+                    .initialize_account_if_needed(
+                        recipient,
+                        mint_index,
+                        &self.payer().pubkey(),
+                    )
+                    .await?
+                {
+                    if !fund_recipient {
+                        return Err(format!(
+                            "recipient's token account {to} does not exist. \
+                             Add --fund-recipient to create it"
+                        )
+                        .into());
+                    }
+                    println!("Funding recipient token account {to}");
+                    ops.push(ix);
+                }
+                to
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        if amount.eq_ignore_ascii_case("all") {
+            println!("Transferring all tokens");
+            ops.push(instructions::transfer_all(
+                &from,
+                &to,
+                &self.owner.pubkey(),
+            ));
+        } else {
+            let amount = amount::parse(amount, decimals)?;
+            println!(
+                "Transferring {} tokens",
+                amount::format(amount, decimals)
+            );
+            ops.push(instructions::transfer(
+                &from,
+                &to,
+                &self.owner.pubkey(),
+                amount,
+            ));
+        }
+        println!("  Sender: {from}");
+        println!("  Recipient: {to}");
+        self.send_ops(ops, &[]).await
+    }
+
+    async fn burn(
+        &self,
+        mint: &Pubkey,
+        amount: &str,
+        from: Option<Pubkey>,
+    ) -> CliResult {
+        let state = self.get_mint(mint).await?;
+        let from = from.unwrap_or_else(|| self.token_account(state.mint_index));
+        let amount = amount::parse(amount, state.decimals)?;
+        println!("Burning {} tokens", amount::format(amount, state.decimals));
+        println!("  Source: {from}");
+        self.send_ops(
+            vec![instructions::burn_checked(
+                &from,
+                mint,
+                &self.owner.pubkey(),
+                amount,
+                state.decimals,
+            )],
+            &[],
+        )
+        .await
+    }
+
+    async fn balance(
+        &self,
+        mint: &Pubkey,
+        owner: Option<Pubkey>,
+        address: Option<Pubkey>,
+    ) -> CliResult {
+        let state = self.get_mint(mint).await?;
+        let address = address.unwrap_or_else(|| {
+            let owner = owner.unwrap_or(self.owner.pubkey());
+            pda::token_account_address(&owner, state.mint_index).0
+        });
+        let account = self
+            .rpc
+            .get_token_account(&address)
+            .await?
+            .ok_or(format!("account {address} does not exist"))?;
+        if account.mint != state.mint_index {
+            return Err(format!("{address} is not an account of {mint}").into());
+        }
+        println!("{}", amount::format(account.balance, state.decimals));
+        Ok(())
+    }
+
+    async fn supply(&self, mint: &Pubkey) -> CliResult {
+        let state = self.get_mint(mint).await?;
+        println!("{}", amount::format(state.supply, state.decimals));
+        Ok(())
+    }
+
+    /// Pages through the mint's token accounts, annotated with the slot and
+    /// block height they were read at (or after)
+    async fn snapshot(
+        &self,
+        mint_index: u64,
+        output: &Path,
+        min_balance: Option<String>,
+    ) -> CliResult {
+        let (mint, state) = self
+            .rpc
+            .get_mint_by_index(mint_index)
+            .await?
+            .ok_or(format!("mint index {mint_index} does not exist"))?;
+        let min_balance = min_balance
+            .map(|min_balance| amount::parse(&min_balance, state.decimals))
+            .transpose()?
+            .unwrap_or_default();
+        let client = self.rpc.client();
+        let slot = client.get_slot().await?;
+        let block_height = client.get_block_height().await?;
+        let holders = self
+            .rpc
+            .get_holders_paged(mint_index, Some(slot))
+            .await?;
+        let snapshot = Snapshot::new(
+            &mint,
+            &state,
+            slot,
+            block_height,
+            min_balance,
+            holders,
+        );
+        snapshot.write(output)?;
+        println!(
+            "Wrote {} holders of {mint} to {}",
+            snapshot.holders.len(),
+            output.display()
+        );
+        println!("  Slot: {slot}");
+        println!("  Block height: {block_height}");
+        Ok(())
+    }
+
+    /// Streams balance changes of the owner's token accounts. Their current
+    /// balances are read first, so each change has a known delta; a change
+    /// before the subscription starts is folded into the account's next one.
+    /// Closes aren't seen, see [Subscriber::state_diffs].
+    async fn watch(
+        &self,
+        address: &Pubkey,
+        mint: Option<Pubkey>,
+        ws_url: &str,
+    ) -> CliResult {
+        let client = self.rpc.client();
+        // A token account is watched through its owner's accounts
+        let (owner, only) = match self
+            .rpc
+            .get_token_account(address)
+            .await
+        {
+            Ok(Some(account)) => (account.owner, Some(*address)),
+            Ok(None) | Err(RpcError::Decode(_)) => (*address, None),
+            Err(err) => return Err(err.into()),
+        };
+        let mint_index = match mint {
+            Some(mint) => Some(self.get_mint(&mint).await?.mint_index),
+            None => None,
+        };
+
+        let mut tracker = Tracker::default();
+        let slot = client.get_slot().await?;
+        for (address, account) in self
+            .rpc
+            .get_all_token_accounts_by_owner(&owner)
+            .await?
+        {
+            tracker.apply_state(
+                address,
+                slot,
+                AccountState::TokenAccount(account),
+            );
+        }
+        let subscriber = Subscriber::new(ws_url).await?;
+        let mut diffs = subscriber
+            .state_diffs(
+                tracker,
+                filters::token_accounts_by_owner(&owner),
+                client.commitment(),
+            )
+            .await?;
+        match only {
+            Some(account) => println!("Watching {account}"),
+            None => println!("Watching token accounts of {owner}"),
+        }
+
+        let mut decimals = HashMap::new();
+        while let Some(diff) = diffs.next().await {
+            let Some(change) = diff.balance_change() else {
+                continue;
+            };
+            if only.is_some_and(|only| only != change.address)
+                || mint_index.is_some_and(|index| index != change.mint_index)
+            {
+                continue;
+            }
+            let decimals = match decimals.entry(change.mint_index) {
+                Entry::Occupied(entry) => *entry.get(),
+                Entry::Vacant(entry) => *entry.insert(
+                    self.rpc
+                        .get_mint_by_index(change.mint_index)
+                        .await?
+                        .map_or(0, |(_, mint)| mint.decimals),
+                ),
+            };
+            let delta = match change.delta() {
+                Some(delta) if delta < 0 => {
+                    format!(
+                        "-{}",
+                        amount::format(delta.unsigned_abs() as u64, decimals)
+                    )
+                }
+                Some(delta) => {
+                    format!("+{}", amount::format(delta as u64, decimals))
+                }
+                None => "?".to_string(),
+            };
+            println!(
+                "Slot {}: {delta} to {} in {} (mint index {})",
+                change.slot,
+                amount::format(change.after, decimals),
+                change.address,
+                change.mint_index
+            );
+            match self
+                .signatures_at(&change.address, change.slot)
+                .await
+            {
+                Ok(signatures) => {
+                    for signature in signatures {
+                        println!("  Signature: {signature}");
+                    }
+                }
+                Err(err) => eprintln!("  Could not get signatures: {err}"),
+            }
+        }
+        Err("subscription closed".into())
+    }
+
+    /// Successful transactions in `slot` that touched `address`
+    async fn signatures_at(
+        &self,
+        address: &Pubkey,
+        slot: u64,
+    ) -> CliResult<Vec<String>> {
+        let client = self.rpc.client();
+        let config = GetConfirmedSignaturesForAddress2Config {
+            limit: Some(20),
+            commitment: Some(client.commitment()),
+            ..Default::default()
+        };
+        Ok(client
+            .get_signatures_for_address_with_config(address, config)
+            .await?
+            .into_iter()
+            .filter(|status| status.slot == slot && status.err.is_none())
+            .map(|status| status.signature)
+            .collect())
+    }
+
+    async fn authorize(
+        &self,
+        address: &Pubkey,
+        authority_type: Authority,
+        new_authority: Option<&Pubkey>,
+        authority: Option<&dyn Signer>,
+    ) -> CliResult {
+        let authority = authority.unwrap_or(self.owner.as_ref());
+        println!("Updating {address}");
+        println!("  Current authority: {}", authority.pubkey());
+        match new_authority {
+            Some(new_authority) => println!("  New authority: {new_authority}"),
+            None => println!("  New authority: disabled"),
+        }
+        self.send_ops(
+            vec![instructions::set_authority(
+                address,
+                &authority.pubkey(),
+                new_authority,
+                authority_type.into(),
+            )],
+            &[authority],
+        )
+        .await
+    }
+
+    /// Transfers to each row not yet in `progress`, creating missing token
+    /// accounts along the way. Rows whose transaction fails are left for a
+    /// rerun.
+    async fn distribute(
+        &self,
+        mint: &Pubkey,
+        csv: &Path,
+        progress: &Path,
+        lookup_table: Option<Pubkey>,
+        from: Option<Pubkey>,
+        max_attempts: u32,
+    ) -> CliResult {
+        let state = self.get_mint(mint).await?;
+        let mut progress = Progress::open(progress)?;
+        let rows: Vec<Row> =
+            distribute::parse(&fs::read_to_string(csv)?, state.decimals)?
+                .into_iter()
+                .filter(|row| !progress.done.contains(&row.line))
+                .collect();
+        if rows.is_empty() {
+            println!("Nothing left to distribute");
+            return Ok(());
+        }
+
+        let from = from.unwrap_or_else(|| self.token_account(state.mint_index));
+        let total = rows
+            .iter()
+            .try_fold(0u64, |total, row| total.checked_add(row.amount))
+            .ok_or("total amount overflows")?;
+        let balance = self
+            .rpc
+            .get_token_account(&from)
+            .await?
+            .ok_or(format!("account {from} does not exist"))?
+            .balance;
+        if balance < total {
+            return Err(format!(
+                "{from} holds {} of the {} left to distribute",
+                amount::format(balance, state.decimals),
+                amount::format(total, state.decimals)
+            )
+            .into());
+        }
+        println!(
+            "Distributing {} tokens to {} rows",
+            amount::format(total, state.decimals),
+            rows.len()
+        );
+        println!("  Sender: {from}");
+
+        let table = match lookup_table.or(progress.lookup_table) {
+            Some(table) => table,
+            None => {
+                let table = self
+                    .create_distribution_table(&from)
+                    .await?;
+                progress.record_lookup_table(&table)?;
+                table
+            }
+        };
+        let table = self
+            .rpc
+            .get_lookup_table(&table)
+            .await?
+            .ok_or(format!("lookup table {table} does not exist"))?;
+
+        let payer = self.payer().pubkey();
+        let mut batch = BatchBuilder::new(&payer);
+        if let Some(price) = self.compute_unit_price {
+            batch.priority_fee(Some(Arc::new(StaticFee(price))));
+        }
+        batch.lookup_tables(vec![table.clone()]);
+        // The CSV line each op pays, if it's a transfer
+        let mut lines = vec![];
+        let mut funded = HashSet::new();
+        let destinations = self
+            .distribution_destinations(&rows, state.mint_index)
+            .await?;
+        for (row, (to, missing)) in rows.iter().zip(destinations) {
+            if missing && funded.insert(to) {
+                batch.push(instructions::create_associated_account(
+                    &row.recipient,
+                    state.mint_index,
+                    &payer,
+                ));
+                lines.push(None);
+            }
+            batch.push(instructions::transfer(
+                &from,
+                &to,
+                &self.owner.pubkey(),
+                row.amount,
+            ));
+            lines.push(Some(row.line));
+        }
+        if !funded.is_empty() {
+            println!("  Funding {} recipient token accounts", funded.len());
+        }
+
+        let transactions = batch.build_transactions_with_ops();
+        let count = transactions.len();
+        let mut unpaid = 0;
+        for (i, (ops, ixs)) in transactions.into_iter().enumerate() {
+            let paid: Vec<usize> = lines[ops]
+                .iter()
+                .flatten()
+                .copied()
+                .collect();
+            match self
+                .send_v0(&ixs, std::slice::from_ref(&table), max_attempts)
+                .await
+            {
+                Ok(signature) => {
+                    progress.record(&signature, &paid)?;
+                    println!(
+                        "[{}/{count}] Paid {} rows: {signature}",
+                        i + 1,
+                        paid.len()
+                    );
+                }
+                Err(err) => {
+                    unpaid += paid.len();
+                    eprintln!("[{}/{count}] Failed: {err}", i + 1);
+                }
+            }
+        }
+        if unpaid > 0 {
+            return Err(format!(
+                "{unpaid} rows were not paid. Rerun to retry them"
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    /// The token account each row pays, and whether it must be created.
+    /// Recipients are token accounts of the mint, or else wallets.
+    async fn distribution_destinations(
+        &self,
+        rows: &[Row],
+        mint_index: u64,
+    ) -> CliResult<Vec<(Pubkey, bool)>> {
+        let recipients: Vec<Pubkey> = rows
+            .iter()
+            .map(|row| row.recipient)
+            .collect();
+        let mut destinations = vec![];
+        for (row, account) in rows
+            .iter()
+            .zip(self.get_accounts(&recipients).await?)
+        {
+            let token_account = account
+                .filter(|account| account.owner == nanotoken::ID)
+                .and_then(|account| {
+                    TokenAccount::try_unpack(&account.data).ok()
+                });
+            destinations.push(match token_account {
+                Some(account) if account.mint == mint_index => row.recipient,
+                Some(account) => {
+                    return Err(format!(
+                        "line {}: recipient {} is a token account of mint \
+                         index {}",
+                        row.line, row.recipient, account.mint
+                    )
+                    .into())
+                }
+                None => {
+                    pda::token_account_address(&row.recipient, mint_index).0
+                }
+            });
+        }
+        let accounts = self.get_accounts(&destinations).await?;
+        Ok(destinations
+            .into_iter()
+            .zip(accounts)
+            .map(|(to, account)| (to, account.is_none()))
+            .collect())
+    }
+
+    async fn get_accounts(
+        &self,
+        addresses: &[Pubkey],
+    ) -> CliResult<Vec<Option<Account>>> {
+        let mut accounts = vec![];
+        for chunk in addresses.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            accounts.extend(
+                self.rpc
+                    .client()
+                    .get_multiple_accounts(chunk)
+                    .await?,
+            );
+        }
+        Ok(accounts)
+    }
+
+    /// Creates a lookup table, owned by the payer, of the accounts every
+    /// transaction of a distribution from `from` shares
+    async fn create_distribution_table(
+        &self,
+        from: &Pubkey,
+    ) -> CliResult<Pubkey> {
+        let payer = self.payer().pubkey();
+        let client = self.rpc.client();
+        let recent_slot = client
+            .get_slot_with_commitment(CommitmentConfig::finalized())
+            .await?;
+        let (create, table) = lookup_table::create(&payer, &payer, recent_slot);
+        let mut addresses = lookup_table::hot_addresses(&[], &[]);
+        addresses.push(*from);
+        let mut ixs = vec![create];
+        ixs.extend(lookup_table::extend(&table, &payer, &payer, &addresses));
+        println!("Creating lookup table {table}");
+        self.send(&ixs, &[]).await?;
+
+        // Addresses can be looked up from the slot after they're added
+        let slot = client.get_slot().await?;
+        while client.get_slot().await? <= slot {
+            sleep(Duration::from_millis(400)).await;
+        }
+        Ok(table)
+    }
+
+    /// Creates the native token account first if needed
+    async fn wrap_sol(&self, amount: &str) -> CliResult {
+        let lamports = amount::parse(amount, NATIVE_DECIMALS)?;
+        let owner = self.owner.pubkey();
+        let payer = self.payer().pubkey();
+        let mut ops = vec![];
+        ops.extend(
+            self.rpc
+                .initialize_account_if_needed(&owner, NATIVE_MINT_INDEX, &payer)
+                .await?,
+        );
+        ops.push(instructions::wrap_sol(&owner, &owner, lamports, &payer));
+        println!(
+            "Wrapping {} SOL into {}",
+            amount::format(lamports, NATIVE_DECIMALS),
+            self.token_account(NATIVE_MINT_INDEX)
+        );
+        self.send_ops(ops, &[]).await
+    }
+
+    async fn unwrap_sol(&self, amount: &str) -> CliResult {
+        let owner = self.owner.pubkey();
+        let lamports = if amount.eq_ignore_ascii_case("all") {
+            self.rpc
+                .get_token_balance(&owner, NATIVE_MINT_INDEX)
+                .await?
+        } else {
+            amount::parse(amount, NATIVE_DECIMALS)?
+        };
+        println!(
+            "Unwrapping {} SOL to {owner}",
+            amount::format(lamports, NATIVE_DECIMALS)
+        );
+        self.send_ops(
+            vec![instructions::unwrap_sol(&owner, &owner, lamports)],
+            &[],
+        )
+        .await
+    }
+
+    /// Creates the vault first if the tokenkeg mint doesn't have one
+    async fn wrap_spl(
+        &self,
+        tokenkeg_mint: &Pubkey,
+        amount: &str,
+    ) -> CliResult {
+        let decimals = self
+            .tokenkeg_decimals(tokenkeg_mint)
+            .await?;
+        let amount = amount::parse(amount, decimals)?;
+        let wrap = self
+            .rpc
+            .wrap_spl_to_nanotoken(
+                tokenkeg_mint,
+                &self.owner.pubkey(),
+                amount,
+                &self.payer().pubkey(),
+            )
+            .await?;
+        if let Some(mint) = &wrap.nanotoken_mint {
+            println!("Creating vault with nanotoken mint {}", mint.pubkey());
+        }
+        println!(
+            "Wrapping {} tokens of {tokenkeg_mint}",
+            amount::format(amount, decimals)
+        );
+        let signers: Vec<&dyn Signer> = wrap
+            .nanotoken_mint
+            .iter()
+            .map(|keypair| keypair as &dyn Signer)
+            .collect();
+        self.send(&wrap.instructions, &signers)
+            .await
+    }
+
+    async fn unwrap_spl(
+        &self,
+        tokenkeg_mint: &Pubkey,
+        amount: &str,
+    ) -> CliResult {
+        let decimals = self
+            .tokenkeg_decimals(tokenkeg_mint)
+            .await?;
+        let owner = self.owner.pubkey();
+        let amount = if amount.eq_ignore_ascii_case("all") {
+            let info = self
+                .rpc
+                .get_vault_info(tokenkeg_mint)
+                .await?
+                .ok_or(format!("{tokenkeg_mint} has no vault"))?;
+            let mint_index = self
+                .get_mint(info.nanotoken_mint())
+                .await?
+                .mint_index;
+            self.rpc
+                .get_token_balance(&owner, mint_index)
+                .await?
+        } else {
+            amount::parse(amount, decimals)?
+        };
+        println!(
+            "Unwrapping {} tokens of {tokenkeg_mint}",
+            amount::format(amount, decimals)
+        );
+        let ix = self
+            .rpc
+            .unwrap_nanotoken_to_spl(
+                tokenkeg_mint,
+                &owner,
+                amount,
+                &self.payer().pubkey(),
+            )
+            .await?;
+        self.send(&[ix], &[]).await
+    }
+
+    async fn get_mint(&self, mint: &Pubkey) -> CliResult<Mint> {
+        Ok(self
+            .rpc
+            .get_mint(mint)
+            .await?
+            .ok_or(format!("mint {mint} does not exist"))?)
+    }
+
+    /// Index and decimals of `mint`, from --mint-index and --mint-decimals if
+    /// given
+    async fn mint_info(&self, mint: &Pubkey) -> CliResult<(u64, u8)> {
+        if let (Some(mint_index), Some(decimals)) =
+            (self.offline.mint_index, self.offline.mint_decimals)
+        {
+            return Ok((mint_index, decimals));
+        }
+        let state = self.get_mint(mint).await?;
+        Ok((state.mint_index, state.decimals))
+    }
+
+    async fn tokenkeg_decimals(&self, tokenkeg_mint: &Pubkey) -> CliResult<u8> {
+        let data = self
+            .rpc
+            .client()
+            .get_account_data(tokenkeg_mint)
+            .await?;
+        data.get(TOKENKEG_DECIMALS_OFFSET)
+            .copied()
+            .ok_or(format!("{tokenkeg_mint} is not a tokenkeg mint").into())
+    }
+
+    /// The owner's (owner, mint) pda token account
+    fn token_account(&self, mint_index: u64) -> Pubkey {
+        pda::token_account_address(&self.owner.pubkey(), mint_index).0
+    }
+
+    /// Sends a v0 transaction of `ixs` signed by the payer and owner, up to
+    /// `max_attempts` times. A failed send is only retried once it can no
+    /// longer land.
+    async fn send_v0(
+        &self,
+        ixs: &[Instruction],
+        tables: &[AddressLookupTableAccount],
+        max_attempts: u32,
+    ) -> CliResult<Signature> {
+        let client = self.rpc.client();
+        let mut signers = vec![self.payer()];
+        if self.owner.pubkey() != self.payer().pubkey() {
+            signers.push(self.owner.as_ref());
+        }
+        let mut attempt = 1;
+        loop {
+            let blockhash = client.get_latest_blockhash().await?;
+            let message = lookup_table::v0_message(
+                ixs,
+                &self.payer().pubkey(),
+                tables,
+                blockhash,
+            )?;
+            let transaction = VersionedTransaction::try_new(message, &signers)?;
+            let signature = transaction.signatures[0];
+            let err = match client
+                .send_and_confirm_transaction(&transaction)
+                .await
+            {
+                Ok(_) => return Ok(signature),
+                Err(err) => err,
+            };
+            // Unless it failed outright, it may still land until its
+            // blockhash expires
+            if err.get_transaction_error().is_none() {
+                while client
+                    .is_blockhash_valid(
+                        &blockhash,
+                        CommitmentConfig::processed(),
+                    )
+                    .await?
+                {
+                    sleep(Duration::from_secs(2)).await;
+                }
+                match client
+                    .get_signature_status(&signature)
+                    .await?
+                {
+                    Some(Ok(())) => return Ok(signature),
+                    Some(Err(err)) => return Err(err.into()),
+                    None => {}
+                }
+            }
+            if attempt >= max_attempts {
+                return Err(err.into());
+            }
+            eprintln!("Attempt {attempt} failed, retrying: {err}");
+            attempt += 1;
+        }
+    }
+
+    /// Batches nanotoken `ops` with a compute budget and sends them, signed
+    /// as in [Context::send]
+    async fn send_ops(
+        &self,
+        ops: Vec<Instruction>,
+        signers: &[&dyn Signer],
+    ) -> CliResult {
+        let mut batch = BatchBuilder::new(&self.payer().pubkey());
+        if let Some(price) = self.compute_unit_price {
+            batch.priority_fee(Some(Arc::new(StaticFee(price))));
+        }
+        for op in ops {
+            batch.push(op);
+        }
+        let transactions = batch.build_transactions();
+        if self.offline.nonce.is_some() && transactions.len() > 1 {
+            return Err(
+                "a nonce can only be used by a single transaction".into()
+            );
+        }
+        for ixs in transactions {
+            self.send(&ixs, signers).await?;
+        }
+        Ok(())
+    }
+
+    /// Signs with whichever of the payer, owner, nonce authority, and
+    /// `signers` the instructions need. With --sign-only, prints the
+    /// signatures instead of sending.
+    async fn send(
+        &self,
+        ixs: &[Instruction],
+        signers: &[&dyn Signer],
+    ) -> CliResult {
+        let nonce_authority = self
+            .nonce_authority
+            .as_deref()
+            .unwrap_or(self.owner.as_ref());
+        let mut ixs = ixs.to_vec();
+        if let Some(nonce) = &self.offline.nonce {
+            ixs.insert(
+                0,
+                system_instruction::advance_nonce_account(
+                    nonce,
+                    &nonce_authority.pubkey(),
+                ),
+            );
+        }
+        let message = Message::new(&ixs, Some(&self.payer().pubkey()));
+        let required = &message.account_keys
+            [..message.header.num_required_signatures as usize];
+        let mut needed: Vec<&dyn Signer> = vec![];
+        for signer in [self.payer(), self.owner.as_ref(), nonce_authority]
+            .into_iter()
+            .chain(signers.iter().copied())
+        {
+            let pubkey = signer.pubkey();
+            if required.contains(&pubkey)
+                && !needed
+                    .iter()
+                    .any(|s| s.pubkey() == pubkey)
+            {
+                needed.push(signer);
+            }
+        }
+        let blockhash = self.blockhash().await?;
+        if needed
+            .iter()
+            .any(|signer| signer.is_interactive())
+        {
+            println!("Approve the transaction on your hardware wallet");
+        }
+        let mut transaction = Transaction::new_unsigned(message);
+        transaction.try_partial_sign(&needed, blockhash)?;
+
+        if self.offline.sign_only {
+            println!("Blockhash: {blockhash}");
+            println!("Signers (Pubkey=Signature):");
+            let signatures = transaction
+                .message
+                .account_keys
+                .iter()
+                .zip(&transaction.signatures);
+            for (pubkey, signature) in signatures.clone() {
+                if *signature != Default::default() {
+                    println!("  {pubkey}={signature}");
+                }
+            }
+            println!("Absent Signers (Pubkey):");
+            for (pubkey, signature) in signatures {
+                if *signature == Default::default() {
+                    println!("  {pubkey}");
+                }
+            }
+            return Ok(());
+        }
+        if !transaction.is_signed() {
+            return Err("missing signatures. Pass them with --signer".into());
+        }
+        let signature = self
+            .rpc
+            .client()
+            .send_and_confirm_transaction(&transaction)
+            .await?;
+        println!("Signature: {signature}");
+        Ok(())
+    }
+
+    /// --blockhash, or else the nonce's blockhash, or else the latest
+    async fn blockhash(&self) -> CliResult<Hash> {
+        if let Some(blockhash) = self.offline.blockhash {
+            return Ok(blockhash);
+        }
+        let client = self.rpc.client();
+        match &self.offline.nonce {
+            Some(nonce) => {
+                let account = get_account_with_commitment(
+                    client,
+                    nonce,
+                    client.commitment(),
+                )
+                .await?;
+                Ok(data_from_account(&account)?.blockhash())
+            }
+            None => Ok(client.get_latest_blockhash().await?),
+        }
+    }
+}
@@ -0,0 +1,191 @@
+//! Distribution lists and their progress files.
+//!
+//! A distribution is a CSV of `recipient,amount` rows, with amounts in ui
+//! units. A first row that doesn't start with a pubkey is taken as a header,
+//! and blank lines and `#` comments are skipped.
+//!
+//! Progress is appended to a file as transactions land, one line per
+//! transaction with its signature and the CSV lines it paid, so a rerun
+//! skips them. Don't edit the CSV between runs.
+
+use std::{
+    collections::HashSet,
+    error::Error,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::Path,
+};
+
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+
+use crate::amount;
+
+const LOOKUP_TABLE: &str = "lookup-table";
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Row {
+    /// 1-based line in the CSV
+    pub line: usize,
+    /// A wallet, or a token account of the mint
+    pub recipient: Pubkey,
+    pub amount: u64,
+}
+
+/// Rows of a distribution CSV of a mint with `decimals`
+pub fn parse(csv: &str, decimals: u8) -> Result<Vec<Row>, Box<dyn Error>> {
+    let mut rows = vec![];
+    for (i, text) in csv.lines().enumerate() {
+        let line = i + 1;
+        let text = text.trim();
+        if text.is_empty() || text.starts_with('#') {
+            continue;
+        }
+        let (recipient, amount) = text
+            .split_once(',')
+            .ok_or(format!("line {line}: expected recipient,amount"))?;
+        let Ok(recipient) = recipient.trim().parse() else {
+            if rows.is_empty() && line == 1 {
+                continue;
+            }
+            return Err(
+                format!("line {line}: invalid recipient {recipient}").into()
+            );
+        };
+        let amount = amount::parse(amount.trim(), decimals)
+            .map_err(|err| format!("line {line}: {err}"))?;
+        rows.push(Row {
+            line,
+            recipient,
+            amount,
+        });
+    }
+    Ok(rows)
+}
+
+/// What a progress file has recorded so far
+pub struct Progress {
+    file: File,
+    /// Created for the distribution by an earlier run
+    pub lookup_table: Option<Pubkey>,
+    /// CSV lines that have been paid
+    pub done: HashSet<usize>,
+}
+
+impl Progress {
+    /// Reads the progress file at `path`, creating it if needed
+    pub fn open(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let mut progress = Self {
+            file: OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)?,
+            lookup_table: None,
+            done: HashSet::new(),
+        };
+        for (i, text) in fs::read_to_string(path)?
+            .lines()
+            .enumerate()
+        {
+            progress.read_line(text).ok_or(format!(
+                "{}:{}: invalid progress",
+                path.display(),
+                i + 1
+            ))?;
+        }
+        Ok(progress)
+    }
+
+    fn read_line(&mut self, text: &str) -> Option<()> {
+        let mut words = text.split_whitespace();
+        match words.next() {
+            None => {}
+            Some(LOOKUP_TABLE) => {
+                self.lookup_table = Some(words.next()?.parse().ok()?);
+            }
+            Some(signature) => {
+                signature.parse::<Signature>().ok()?;
+                for line in words {
+                    self.done.insert(line.parse().ok()?);
+                }
+            }
+        }
+        Some(())
+    }
+
+    pub fn record_lookup_table(&mut self, table: &Pubkey) -> io::Result<()> {
+        self.lookup_table = Some(*table);
+        self.append(&format!("{LOOKUP_TABLE} {table}"))
+    }
+
+    /// Records that the transaction `signature` paid `lines`
+    pub fn record(
+        &mut self,
+        signature: &Signature,
+        lines: &[usize],
+    ) -> io::Result<()> {
+        self.done.extend(lines);
+        let lines: Vec<String> = lines
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        self.append(&format!("{signature} {}", lines.join(" ")))
+    }
+
+    fn append(&mut self, text: &str) -> io::Result<()> {
+        writeln!(self.file, "{text}")?;
+        self.file.sync_data()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_rows_and_resumes_progress() {
+        let [alice, bob] = [(); 2].map(|_| Pubkey::new_unique());
+        let csv = format!(
+            "recipient,amount\n{alice},1.5\n\n# bonus\n{bob}, 2\n{alice},0.25\n"
+        );
+        let rows = parse(&csv, 2).unwrap();
+        assert_eq!(
+            rows,
+            [
+                Row {
+                    line: 2,
+                    recipient: alice,
+                    amount: 150
+                },
+                Row {
+                    line: 5,
+                    recipient: bob,
+                    amount: 200
+                },
+                Row {
+                    line: 6,
+                    recipient: alice,
+                    amount: 25
+                },
+            ]
+        );
+        assert!(parse(&format!("{alice},1\nbob,1"), 2).is_err());
+        assert!(parse(&format!("{alice},1.234"), 2).is_err());
+
+        let path = std::env::temp_dir()
+            .join(format!("nanotoken-distribute-{}", Pubkey::new_unique()));
+        let table = Pubkey::new_unique();
+        let mut progress = Progress::open(&path).unwrap();
+        progress
+            .record_lookup_table(&table)
+            .unwrap();
+        progress
+            .record(&Signature::default(), &[2, 5])
+            .unwrap();
+        drop(progress);
+
+        let progress = Progress::open(&path).unwrap();
+        assert_eq!(progress.lookup_table, Some(table));
+        assert_eq!(progress.done, HashSet::from([2, 5]));
+        fs::remove_file(path).unwrap();
+    }
+}
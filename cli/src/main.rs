@@ -0,0 +1,439 @@
+//! Command-line tool for nanotoken holders and mint authorities, in the
+//! style of `spl-token`.
+//!
+//! Mints are given by address, and amounts in ui units of the mint's
+//! decimals. Token accounts default to the (owner, mint) pda of the
+//! `--keypair` wallet. Signers are keypair files or Ledger uris like
+//! `usb://ledger?key=0`, see [signer].
+//!
+//! ```text
+//! nanotoken-cli create-mint --decimals 6
+//! nanotoken-cli create-account <MINT>
+//! nanotoken-cli mint <MINT> 100
+//! nanotoken-cli transfer <MINT> 2.5 <RECIPIENT> --fund-recipient
+//! nanotoken-cli wrap 1 # SOL
+//! nanotoken-cli wrap 10 --mint <TOKENKEG_MINT>
+//! nanotoken-cli authorize <MINT> mint <NEW_AUTHORITY> \
+//!     --authority usb://ledger
+//! nanotoken-cli distribute <MINT> airdrop.csv
+//! nanotoken-cli snapshot --mint 3 --output holders.json --min-balance 10
+//! nanotoken-cli watch <OWNER> --mint <MINT>
+//! ```
+//!
+//! `mint` and `transfer` can be signed on an offline machine, see
+//! [OfflineArgs]:
+//!
+//! ```text
+//! # Offline, with the nonce's blockhash and the fee payer's pubkey
+//! nanotoken-cli mint <MINT> 100 --mint-decimals 6 --mint-index 3 \
+//!     --nonce <NONCE> --blockhash <NONCE_HASH> --fee-payer <PAYER> \
+//!     --sign-only
+//! # Online, with the printed signature
+//! nanotoken-cli mint <MINT> 100 --mint-decimals 6 --mint-index 3 \
+//!     --nonce <NONCE> --blockhash <NONCE_HASH> --keypair <AUTHORITY> \
+//!     --signer <AUTHORITY>=<SIGNATURE>
+//! ```
+
+mod amount;
+mod commands;
+mod distribute;
+mod signer;
+mod snapshot;
+
+use std::{env, error::Error, path::PathBuf};
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use nanotoken::ix::AuthorityType;
+use solana_sdk::{hash::Hash, pubkey::Pubkey, signature::Signature};
+
+use crate::commands::Context;
+
+#[derive(Parser)]
+#[clap(version, about = "Create and use nanotoken mints and accounts")]
+struct Cli {
+    /// RPC url, or one of mainnet-beta, devnet, testnet, localhost (or their
+    /// first letter)
+    #[clap(short = 'u', long, global = true, default_value = "localhost")]
+    url: String,
+
+    /// Wallet that owns token accounts and signs as authority, as a keypair
+    /// file, usb:// uri, or pubkey signed for with --signer. Defaults to
+    /// ~/.config/solana/id.json
+    #[clap(long, global = true)]
+    keypair: Option<String>,
+
+    /// Pays fees and rent. Defaults to --keypair
+    #[clap(long, global = true)]
+    fee_payer: Option<String>,
+
+    /// Compute unit price in micro-lamports
+    #[clap(long, global = true)]
+    with_compute_unit_price: Option<u64>,
+
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Creates a mint at the next mint index
+    CreateMint {
+        #[clap(long, default_value_t = 9)]
+        decimals: u8,
+
+        /// Defaults to --keypair
+        #[clap(long)]
+        mint_authority: Option<Pubkey>,
+
+        /// Without one, accounts can never be frozen
+        #[clap(long)]
+        freeze_authority: Option<Pubkey>,
+
+        /// Supply cap in ui units
+        #[clap(long)]
+        max_supply: Option<String>,
+    },
+
+    /// Creates the (owner, mint) token account
+    CreateAccount {
+        mint: Pubkey,
+
+        /// Defaults to --keypair
+        #[clap(long)]
+        owner: Option<Pubkey>,
+    },
+
+    /// Mints tokens
+    Mint {
+        mint: Pubkey,
+        amount: String,
+
+        /// Defaults to the account of --keypair
+        recipient_token_account: Option<Pubkey>,
+
+        /// Mint authority signer. Defaults to --keypair
+        #[clap(long)]
+        mint_authority: Option<String>,
+
+        #[clap(flatten)]
+        offline: OfflineArgs,
+    },
+
+    /// Transfers tokens from an account of --keypair
+    Transfer {
+        mint: Pubkey,
+
+        /// Ui amount, or ALL for the whole balance
+        amount: String,
+
+        /// Wallet address, or a token account of the mint
+        recipient: Pubkey,
+
+        /// Create the recipient's token account if it doesn't exist. With
+        /// --mint-index, the recipient is taken to be a wallet and its account
+        /// is created idempotently.
+        #[clap(long)]
+        fund_recipient: bool,
+
+        /// Defaults to the account of --keypair
+        #[clap(long)]
+        from: Option<Pubkey>,
+
+        #[clap(flatten)]
+        offline: OfflineArgs,
+    },
+
+    /// Transfers to every row of a CSV, see [distribute]. Missing token
+    /// accounts are created, and a rerun resumes where the last one stopped.
+    Distribute {
+        mint: Pubkey,
+
+        /// Rows of recipient,amount, recipients being wallets or token
+        /// accounts of the mint
+        csv: PathBuf,
+
+        /// Progress file. Defaults to <CSV>.progress
+        #[clap(long)]
+        progress: Option<PathBuf>,
+
+        /// Lookup table to compile transactions with. Defaults to one
+        /// created for the distribution and kept in the progress file.
+        #[clap(long)]
+        lookup_table: Option<Pubkey>,
+
+        /// Defaults to the account of --keypair
+        #[clap(long)]
+        from: Option<Pubkey>,
+
+        /// Sends of each transaction before giving up on it
+        #[clap(long, default_value_t = 5)]
+        max_attempts: u32,
+    },
+
+    /// Burns tokens from an account of --keypair
+    Burn {
+        mint: Pubkey,
+        amount: String,
+
+        /// Defaults to the account of --keypair
+        #[clap(long)]
+        from: Option<Pubkey>,
+    },
+
+    /// Balance of a token account
+    Balance {
+        mint: Pubkey,
+
+        /// Defaults to --keypair
+        #[clap(long, conflicts_with = "address")]
+        owner: Option<Pubkey>,
+
+        /// A token account address instead of the owner's
+        #[clap(long)]
+        address: Option<Pubkey>,
+    },
+
+    /// Total supply of a mint
+    Supply { mint: Pubkey },
+
+    /// Writes the holders of a mint, largest first, see [snapshot]
+    Snapshot {
+        /// Mint index
+        #[clap(long)]
+        mint: u64,
+
+        /// JSON if it ends in .json, otherwise CSV
+        #[clap(long, short)]
+        output: PathBuf,
+
+        /// Leave out holders with less, in ui units
+        #[clap(long)]
+        min_balance: Option<String>,
+    },
+
+    /// Prints balance changes of a wallet's token accounts, or of a single
+    /// token account, as they happen
+    Watch {
+        /// Wallet, or token account
+        address: Pubkey,
+
+        /// Only accounts of this mint
+        #[clap(long)]
+        mint: Option<Pubkey>,
+
+        /// Websocket url. Defaults to --url on the next port, as the
+        /// validator serves it
+        #[clap(long)]
+        ws_url: Option<String>,
+    },
+
+    /// Changes an authority of a mint, or the owner of a token account
+    Authorize {
+        /// Mint, or token account for `owner`
+        address: Pubkey,
+
+        authority_type: Authority,
+
+        #[clap(required_unless_present = "disable")]
+        new_authority: Option<Pubkey>,
+
+        /// Disable the authority for good
+        #[clap(long, conflicts_with = "new_authority")]
+        disable: bool,
+
+        /// Current authority signer. Defaults to --keypair
+        #[clap(long)]
+        authority: Option<String>,
+    },
+
+    /// Wraps SOL, or with --mint spl tokens, into nanotoken
+    Wrap {
+        amount: String,
+
+        /// Tokenkeg mint to wrap through its vault instead of SOL
+        #[clap(long)]
+        mint: Option<Pubkey>,
+    },
+
+    /// Unwraps nanotoken SOL, or with --mint spl tokens
+    Unwrap {
+        /// Ui amount, or ALL for the whole balance
+        amount: String,
+
+        /// Tokenkeg mint to unwrap through its vault instead of SOL
+        #[clap(long)]
+        mint: Option<Pubkey>,
+    },
+}
+
+impl Command {
+    fn offline(&self) -> Option<&OfflineArgs> {
+        match self {
+            Command::Mint { offline, .. }
+            | Command::Transfer { offline, .. } => Some(offline),
+            _ => None,
+        }
+    }
+}
+
+/// Offline signing and durable nonces.
+///
+/// Build the same transaction on both machines: same command and amounts,
+/// `--blockhash`, `--nonce`, and `--mint-decimals` and `--mint-index` so
+/// nothing is fetched. The offline machine adds `--sign-only` and gives
+/// absent signers by pubkey; the online one passes the signatures it printed
+/// with `--signer`.
+#[derive(Args, Clone, Default)]
+pub struct OfflineArgs {
+    /// Sign without sending, and print the signatures. Signers given by
+    /// pubkey are left for others to sign.
+    #[clap(long, requires = "blockhash")]
+    pub sign_only: bool,
+
+    /// Blockhash to sign with instead of fetching one. With --nonce, the
+    /// blockhash stored in the nonce account.
+    #[clap(long, requires_all = ["mint_decimals", "mint_index"])]
+    pub blockhash: Option<Hash>,
+
+    /// A signature from --sign-only, as PUBKEY=SIGNATURE
+    #[clap(long = "signer", value_parser = parse_presigner)]
+    pub signers: Vec<(Pubkey, Signature)>,
+
+    /// Durable nonce account to advance instead of using a recent
+    /// blockhash
+    #[clap(long)]
+    pub nonce: Option<Pubkey>,
+
+    /// Nonce authority signer. Defaults to --keypair
+    #[clap(long, requires = "nonce")]
+    pub nonce_authority: Option<String>,
+
+    /// Decimals of the mint, so that it isn't fetched
+    #[clap(long, requires = "mint_index")]
+    pub mint_decimals: Option<u8>,
+
+    /// Index of the mint, so that it isn't fetched
+    #[clap(long, requires = "mint_decimals")]
+    pub mint_index: Option<u64>,
+}
+
+fn parse_presigner(signer: &str) -> Result<(Pubkey, Signature), String> {
+    let (pubkey, signature) = signer
+        .split_once('=')
+        .ok_or("expected PUBKEY=SIGNATURE")?;
+    Ok((
+        pubkey
+            .parse()
+            .map_err(|err| format!("invalid pubkey: {err}"))?,
+        signature
+            .parse()
+            .map_err(|err| format!("invalid signature: {err}"))?,
+    ))
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Authority {
+    Mint,
+    Freeze,
+    Pause,
+    Clawback,
+    Owner,
+}
+
+impl From<Authority> for AuthorityType {
+    fn from(authority: Authority) -> Self {
+        match authority {
+            Authority::Mint => AuthorityType::MintAuthority,
+            Authority::Freeze => AuthorityType::FreezeAccount,
+            Authority::Pause => AuthorityType::PauseMint,
+            Authority::Clawback => AuthorityType::ClawbackTokens,
+            Authority::Owner => AuthorityType::AccountOwner,
+        }
+    }
+}
+
+fn rpc_url(url: &str) -> String {
+    match url {
+        "m" | "mainnet-beta" => "https://api.mainnet-beta.solana.com",
+        "d" | "devnet" => "https://api.devnet.solana.com",
+        "t" | "testnet" => "https://api.testnet.solana.com",
+        "l" | "localhost" => "http://localhost:8899",
+        url => url,
+    }
+    .to_string()
+}
+
+/// ws(s):// on the port after the rpc's, if it has one
+fn ws_url(rpc_url: &str) -> String {
+    let (scheme, rest) = match rpc_url.split_once("://") {
+        Some(("https", rest)) => ("wss", rest),
+        Some((_, rest)) => ("ws", rest),
+        None => ("ws", rpc_url),
+    };
+    let (host, path) = rest
+        .split_once('/')
+        .map_or((rest, ""), |(host, path)| (host, path));
+    let host = match host
+        .rsplit_once(':')
+        .and_then(|(name, port)| Some((name, port.parse::<u16>().ok()?)))
+    {
+        Some((name, port)) => format!("{name}:{}", port.wrapping_add(1)),
+        None => host.to_string(),
+    };
+    match path {
+        "" => format!("{scheme}://{host}"),
+        path => format!("{scheme}://{host}/{path}"),
+    }
+}
+
+fn default_keypair() -> Result<String, Box<dyn Error>> {
+    Ok(PathBuf::from(env::var("HOME")?)
+        .join(".config/solana/id.json")
+        .to_string_lossy()
+        .into_owned())
+}
+
+#[tokio::main]
+async fn main() {
+    let cli = Cli::parse();
+    if let Err(err) = run(cli).await {
+        eprintln!("Error: {err}");
+        std::process::exit(1);
+    }
+}
+
+async fn run(cli: Cli) -> Result<(), Box<dyn Error>> {
+    let keypair = match cli.keypair {
+        Some(path) => path,
+        None => default_keypair()?,
+    };
+    let context = Context::new(
+        rpc_url(&cli.url),
+        &keypair,
+        cli.fee_payer.as_deref(),
+        cli.with_compute_unit_price,
+        cli.command
+            .offline()
+            .cloned()
+            .unwrap_or_default(),
+    )?;
+    context.run(cli.command).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derives_websocket_urls() {
+        assert_eq!(ws_url("http://localhost:8899"), "ws://localhost:8900");
+        assert_eq!(
+            ws_url("https://api.devnet.solana.com"),
+            "wss://api.devnet.solana.com"
+        );
+        assert_eq!(
+            ws_url("https://rpc.example.com:8899/v1/key"),
+            "wss://rpc.example.com:8900/v1/key"
+        );
+    }
+}
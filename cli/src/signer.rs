@@ -0,0 +1,90 @@
+//! Signers from keypair files, hardware wallets, or offline signatures.
+//!
+//! A signer is given as a keypair file path, or as a
+//! `usb://ledger[/<WALLET_PUBKEY>][?key=<ACCOUNT>[/<CHANGE>]]` uri for a Ledger
+//! running the Solana app, as with the `solana` CLI. Ledger signers prompt
+//! for approval on the device for every transaction.
+//!
+//! A signer can also be given by pubkey, when its signature comes from
+//! `--signer`, or is left absent with `--sign-only`.
+
+use std::{error::Error, rc::Rc};
+
+use solana_remote_wallet::{
+    locator::Locator,
+    remote_keypair::generate_remote_keypair,
+    remote_wallet::{maybe_wallet_manager, RemoteWalletManager},
+};
+use solana_sdk::{
+    derivation_path::DerivationPath,
+    pubkey::Pubkey,
+    signature::read_keypair_file,
+    signer::{null_signer::NullSigner, presigner::Presigner, Signer},
+};
+use uriparse::URIReference;
+
+use crate::OfflineArgs;
+
+const USB_SCHEME: &str = "usb://";
+
+/// Reads the signer at `path`. The wallet manager is set up on the first
+/// hardware signer and reused for the rest. `name` identifies the signer in
+/// errors and device prompts.
+pub fn signer_from_path(
+    path: &str,
+    name: &str,
+    wallet_manager: &mut Option<Rc<RemoteWalletManager>>,
+    offline: &OfflineArgs,
+) -> Result<Box<dyn Signer>, Box<dyn Error>> {
+    if let Ok(pubkey) = path.parse::<Pubkey>() {
+        if let Some((_, signature)) = offline
+            .signers
+            .iter()
+            .find(|(signer, _)| *signer == pubkey)
+        {
+            return Ok(Box::new(Presigner::new(&pubkey, signature)));
+        }
+        if offline.sign_only {
+            return Ok(Box::new(NullSigner::new(&pubkey)));
+        }
+        return Err(format!(
+            "{name} {pubkey} is a pubkey. Pass its signature with \
+             --signer {pubkey}=<SIGNATURE>"
+        )
+        .into());
+    }
+    if !path
+        .to_ascii_lowercase()
+        .starts_with(USB_SCHEME)
+    {
+        return read_keypair_file(path)
+            .map(|keypair| Box::new(keypair) as Box<dyn Signer>)
+            .map_err(|err| {
+                format!("could not read {name} keypair {path}: {err}").into()
+            });
+    }
+
+    if !cfg!(feature = "ledger") {
+        return Err(format!(
+            "{name} is a hardware wallet, which needs the `ledger` feature"
+        )
+        .into());
+    }
+    let uri = URIReference::try_from(path)?;
+    let locator = Locator::new_from_uri(&uri)?;
+    let derivation_path =
+        DerivationPath::from_uri_any_query(&uri)?.unwrap_or_default();
+    if wallet_manager.is_none() {
+        *wallet_manager = maybe_wallet_manager()?;
+    }
+    let Some(wallet_manager) = wallet_manager else {
+        return Err(format!("no hardware wallet found for {name}").into());
+    };
+    Ok(Box::new(generate_remote_keypair(
+        locator,
+        derivation_path,
+        wallet_manager,
+        false,
+        name,
+    )?))
+}
@@ -0,0 +1,156 @@
+//! Holder snapshots, for governance and airdrop eligibility.
+//!
+//! A snapshot is written as JSON if its file ends in `.json`, and otherwise
+//! as CSV with the mint and slot in a leading `#` comment. Balances are
+//! given in base units and in ui units.
+
+use std::{error::Error, fs, path::Path};
+
+use nanotoken::{Mint, TokenAccount};
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::amount;
+
+/// Amounts are strings since JSON numbers lose u64 precision
+#[derive(Debug, Serialize)]
+pub struct Snapshot {
+    pub mint: String,
+    pub mint_index: u64,
+    pub decimals: u8,
+    pub supply: String,
+    /// Every page was read at this slot or later
+    pub slot: u64,
+    pub block_height: u64,
+    pub min_balance: String,
+    pub holders: Vec<Holder>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Holder {
+    pub address: String,
+    pub owner: String,
+    pub balance: String,
+    pub ui_balance: String,
+}
+
+impl Snapshot {
+    /// Holders with at least `min_balance`, largest first
+    pub fn new(
+        mint: &Pubkey,
+        state: &Mint,
+        slot: u64,
+        block_height: u64,
+        min_balance: u64,
+        mut holders: Vec<(Pubkey, TokenAccount)>,
+    ) -> Self {
+        holders.retain(|(_, account)| account.balance >= min_balance);
+        holders.sort_by(|(a, a_account), (b, b_account)| {
+            b_account
+                .balance
+                .cmp(&a_account.balance)
+                .then(a.cmp(b))
+        });
+        Self {
+            mint: mint.to_string(),
+            mint_index: state.mint_index,
+            decimals: state.decimals,
+            supply: state.supply.to_string(),
+            slot,
+            block_height,
+            min_balance: min_balance.to_string(),
+            holders: holders
+                .into_iter()
+                .map(|(address, account)| Holder {
+                    address: address.to_string(),
+                    owner: account.owner.to_string(),
+                    balance: account.balance.to_string(),
+                    ui_balance: amount::format(account.balance, state.decimals),
+                })
+                .collect(),
+        }
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let json = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("json"));
+        let contents = match json {
+            true => serde_json::to_string_pretty(self)? + "\n",
+            false => self.to_csv(),
+        };
+        fs::write(path, contents)?;
+        Ok(())
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = format!(
+            "# mint {} (index {}), slot {}, block height {}, min balance {}\n\
+             address,owner,balance,ui_balance\n",
+            self.mint,
+            self.mint_index,
+            self.slot,
+            self.block_height,
+            self.min_balance
+        );
+        for holder in &self.holders {
+            csv += &format!(
+                "{},{},{},{}\n",
+                holder.address, holder.owner, holder.balance, holder.ui_balance
+            );
+        }
+        csv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytemuck::Zeroable;
+
+    use super::*;
+
+    #[test]
+    fn sorts_and_filters_holders() {
+        let mint = Pubkey::new_unique();
+        let state = Mint {
+            mint_index: 3,
+            decimals: 2,
+            supply: 1_000,
+            ..Mint::zeroed()
+        };
+        let holders: Vec<(Pubkey, TokenAccount)> = [50, 700, 5, 50]
+            .into_iter()
+            .map(|balance| {
+                let account = TokenAccount {
+                    owner: Pubkey::new_unique(),
+                    mint: 3,
+                    balance,
+                    ..TokenAccount::zeroed()
+                };
+                (Pubkey::new_unique(), account)
+            })
+            .collect();
+
+        let snapshot = Snapshot::new(&mint, &state, 10, 9, 50, holders);
+        let balances: Vec<&str> = snapshot
+            .holders
+            .iter()
+            .map(|holder| holder.ui_balance.as_str())
+            .collect();
+        assert_eq!(balances, ["7", "0.5", "0.5"]);
+        assert!(snapshot.holders[1].address < snapshot.holders[2].address);
+
+        let csv = snapshot.to_csv();
+        let mut lines = csv.lines();
+        assert!(lines
+            .next()
+            .unwrap()
+            .starts_with(&format!("# mint {mint} (index 3), slot 10")));
+        assert_eq!(lines.next(), Some("address,owner,balance,ui_balance"));
+        assert!(lines
+            .next()
+            .unwrap()
+            .ends_with(",700,7"));
+        assert_eq!(lines.count(), 2);
+    }
+}
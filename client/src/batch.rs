@@ -0,0 +1,440 @@
+//! Positional batching of single-op instructions.
+//!
+//! The entrypoint hands each op the remaining accounts and advances past the
+//! ones it consumes, so a batch's accounts must be each op's own accounts in
+//! op order, then any optional accounts ops look for "anywhere after" their
+//! own, and finally the config, system program, and payer. [BatchBuilder]
+//! lays them out that way from the single-op builders in
+//! [crate::instructions], and splits the batch when it no longer fits in a
+//! transaction. [BatchBuilder::build_transactions] also puts a
+//! [ComputeBudget] and any [PriorityFeeStrategy] price in front of each
+//! batch. With
+//! [BatchBuilder::lookup_tables], batches are sized for v0 transactions
+//! using those tables, which fit far more ops.
+
+use std::{ops::Range, sync::Arc};
+
+use nanotoken::consts::CONFIG_ACCOUNT;
+use solana_sdk::{
+    address_lookup_table_account::AddressLookupTableAccount,
+    hash::Hash,
+    instruction::{AccountMeta, Instruction},
+    message::{v0, Message, VersionedMessage},
+    packet::PACKET_DATA_SIZE,
+    pubkey::Pubkey,
+    system_program,
+};
+
+use crate::{
+    compute::ComputeBudget, lookup_table::MAX_TRANSACTION_ACCOUNTS,
+    priority_fee::PriorityFeeStrategy,
+};
+
+struct BatchOp {
+    data: Vec<u8>,
+    accounts: Vec<AccountMeta>,
+    optional: Vec<AccountMeta>,
+    /// Whether the op had a trailing config, marked writable
+    config_writable: bool,
+}
+
+/// Accumulates nanotoken ops into as few instructions as fit in a
+/// transaction.
+///
+/// ```ignore
+/// let mut batch = BatchBuilder::new(&payer);
+/// batch
+///     .push(instructions::transfer(&from, &to, &owner, 10))
+///     .push(instructions::burn(&from, &mint, &owner, 5));
+/// for ixs in batch.build_transactions() {
+///     // send each in its own transaction
+/// }
+/// ```
+pub struct BatchBuilder {
+    payer: Pubkey,
+    ops: Vec<BatchOp>,
+    compute_budget: Option<ComputeBudget>,
+    lookup_tables: Vec<AddressLookupTableAccount>,
+    priority_fee: Option<Arc<dyn PriorityFeeStrategy + Send + Sync>>,
+}
+
+impl BatchBuilder {
+    pub fn new(payer: &Pubkey) -> Self {
+        Self {
+            payer: *payer,
+            ops: vec![],
+            compute_budget: Some(ComputeBudget::default()),
+            lookup_tables: vec![],
+            priority_fee: None,
+        }
+    }
+
+    /// Sets the limits [BatchBuilder::build_transactions] attaches, or None
+    /// to attach none (e.g. to set them yourself).
+    pub fn compute_budget(
+        &mut self,
+        compute_budget: Option<ComputeBudget>,
+    ) -> &mut Self {
+        self.compute_budget = compute_budget;
+        self
+    }
+
+    /// Prices each transaction [BatchBuilder::build_transactions] returns
+    /// with `priority_fee`, or None (the default) to pay no priority fee.
+    pub fn priority_fee(
+        &mut self,
+        priority_fee: Option<Arc<dyn PriorityFeeStrategy + Send + Sync>>,
+    ) -> &mut Self {
+        self.priority_fee = priority_fee;
+        self
+    }
+
+    /// Sizes batches for v0 transactions that look up accounts in `tables`,
+    /// see [crate::lookup_table]. Compile each transaction with
+    /// [crate::lookup_table::v0_message] and the same tables.
+    pub fn lookup_tables(
+        &mut self,
+        tables: Vec<AddressLookupTableAccount>,
+    ) -> &mut Self {
+        self.lookup_tables = tables;
+        self
+    }
+
+    /// Adds a single-op instruction from [crate::instructions]. Its trailing
+    /// config, system program, and payer (or padding, for ops with fewer
+    /// than three accounts) are moved to the end of the batch.
+    ///
+    /// Panics if `op` is not a nanotoken instruction.
+    pub fn push(&mut self, op: Instruction) -> &mut Self {
+        self.push_with_optional(op, &[])
+    }
+
+    /// Like [BatchBuilder::push], for ops that find optional accounts
+    /// anywhere after their own (e.g. the mint and royalty recipient of a
+    /// royalty transfer, or the instructions sysvar for memos). These are
+    /// placed after every op's accounts so they don't shift later ops.
+    ///
+    /// Don't pass ops built with [crate::instructions::with_royalty] or
+    /// [crate::instructions::with_instructions_sysvar]; pass the plain op
+    /// and its optional accounts here instead.
+    pub fn push_with_optional(
+        &mut self,
+        op: Instruction,
+        optional: &[AccountMeta],
+    ) -> &mut Self {
+        assert_eq!(op.program_id, nanotoken::ID, "not a nanotoken op");
+        let mut accounts = op.accounts;
+
+        // Strip the trailing config, system program, and payer
+        let n = accounts.len();
+        let config_writable = if n >= 3
+            && accounts[n - 3].pubkey == CONFIG_ACCOUNT
+            && accounts[n - 2].pubkey == system_program::ID
+        {
+            let config_writable = accounts[n - 3].is_writable;
+            accounts.truncate(n - 3);
+            config_writable
+        } else {
+            // Strip padding, see instructions::MIN_ACCOUNTS
+            while accounts.len() <= 3
+                && accounts.last().is_some_and(|meta| {
+                    meta.pubkey == system_program::ID
+                        && !meta.is_signer
+                        && !meta.is_writable
+                })
+            {
+                accounts.pop();
+            }
+            false
+        };
+
+        self.ops.push(BatchOp {
+            data: op.data,
+            accounts,
+            optional: optional.to_vec(),
+            config_writable,
+        });
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    /// One instruction with every op, regardless of size.
+    pub fn build(&self) -> Instruction {
+        self.combine(&self.ops)
+    }
+
+    /// Like [BatchBuilder::build], but drops the optional accounts that ops
+    /// find after their own accounts anyway: in a later op's accounts, or
+    /// the trailing config, system program, and payer. With the `rpc`
+    /// feature, [crate::rpc::NanotokenRpc::build_deduplicated] checks this
+    /// layout against a simulation.
+    pub fn build_deduplicated(&self) -> Instruction {
+        self.combine_with(&self.ops, true)
+    }
+
+    pub fn payer(&self) -> &Pubkey {
+        &self.payer
+    }
+
+    /// Splits the ops, in order, across as few instructions as possible such
+    /// that each fits in its own transaction paid for by the payer. An op
+    /// that doesn't fit on its own still gets its own instruction.
+    pub fn build_split(&self) -> Vec<Instruction> {
+        self.split(|ix| vec![ix])
+            .into_iter()
+            .map(|range| self.combine(&self.ops[range]))
+            .collect()
+    }
+
+    /// Like [BatchBuilder::build_split], but returns each transaction's
+    /// instructions: the compute budget instructions, unless disabled with
+    /// [BatchBuilder::compute_budget], the compute unit price, if a
+    /// [BatchBuilder::priority_fee] is set, then the batch.
+    pub fn build_transactions(&self) -> Vec<Vec<Instruction>> {
+        self.build_transactions_with_ops()
+            .into_iter()
+            .map(|(_ops, ixs)| ixs)
+            .collect()
+    }
+
+    /// Like [BatchBuilder::build_transactions], along with the ops (by
+    /// position in push order) in each transaction, e.g. to track which
+    /// landed.
+    pub fn build_transactions_with_ops(
+        &self,
+    ) -> Vec<(Range<usize>, Vec<Instruction>)> {
+        self.split(|ix| self.with_compute_budget(ix))
+            .into_iter()
+            .map(|range| {
+                let ixs = self.with_compute_budget(
+                    self.combine(&self.ops[range.clone()]),
+                );
+                (range, ixs)
+            })
+            .collect()
+    }
+
+    fn with_compute_budget(&self, ix: Instruction) -> Vec<Instruction> {
+        let mut ixs = self
+            .compute_budget
+            .map(|budget| budget.instructions(&ix))
+            .unwrap_or_default();
+        if let Some(price) = self
+            .priority_fee
+            .as_ref()
+            .and_then(|fee| fee.price_instruction(std::slice::from_ref(&ix)))
+        {
+            ixs.push(price);
+        }
+        ixs.push(ix);
+        ixs
+    }
+
+    /// Splits the ops, in order, into as few ranges as possible such that
+    /// each range's `transaction` fits in a packet and the account lock
+    /// limit
+    fn split(
+        &self,
+        transaction: impl Fn(Instruction) -> Vec<Instruction>,
+    ) -> Vec<Range<usize>> {
+        let mut ranges = vec![];
+        let mut start = 0;
+        for end in 1..=self.ops.len() {
+            if end - start > 1
+                && !self.fits(&transaction(self.combine(&self.ops[start..end])))
+            {
+                ranges.push(start..end - 1);
+                start = end - 1;
+            }
+        }
+        if start < self.ops.len() {
+            ranges.push(start..self.ops.len());
+        }
+        ranges
+    }
+
+    fn fits(&self, ixs: &[Instruction]) -> bool {
+        if self.lookup_tables.is_empty() {
+            return message_size(ixs, &self.payer) <= PACKET_DATA_SIZE;
+        }
+        // The blockhash doesn't change the size
+        let Ok(message) = v0::Message::try_compile(
+            &self.payer,
+            ixs,
+            &self.lookup_tables,
+            Hash::default(),
+        ) else {
+            return false;
+        };
+        let accounts = message.account_keys.len()
+            + message
+                .address_table_lookups
+                .iter()
+                .map(|lookup| {
+                    lookup.writable_indexes.len()
+                        + lookup.readonly_indexes.len()
+                })
+                .sum::<usize>();
+        let signatures = message.header.num_required_signatures as usize;
+        // Compact length prefix, signatures, then the version prefixed
+        // message
+        let size = 1
+            + 64 * signatures
+            + VersionedMessage::V0(message)
+                .serialize()
+                .len();
+        size <= PACKET_DATA_SIZE && accounts <= MAX_TRANSACTION_ACCOUNTS
+    }
+
+    fn combine(&self, ops: &[BatchOp]) -> Instruction {
+        self.combine_with(ops, false)
+    }
+
+    /// With `dedup`, an optional account is dropped if it's found after the
+    /// own accounts of the last op that looks for it anyway
+    fn combine_with(&self, ops: &[BatchOp], dedup: bool) -> Instruction {
+        let mut accounts = vec![];
+        // Each optional account and the last op that looks for it
+        let mut optional: Vec<(AccountMeta, usize)> = vec![];
+        // Where each op's own accounts end
+        let mut ends = vec![];
+        let mut data = vec![];
+        let mut config_writable = false;
+        for (i, op) in ops.iter().enumerate() {
+            accounts.extend_from_slice(&op.accounts);
+            ends.push(accounts.len());
+            for meta in &op.optional {
+                match optional
+                    .iter_mut()
+                    .find(|(a, _)| a.pubkey == meta.pubkey)
+                {
+                    Some((existing, last)) => {
+                        existing.is_signer |= meta.is_signer;
+                        existing.is_writable |= meta.is_writable;
+                        *last = i;
+                    }
+                    None => optional.push((meta.clone(), i)),
+                }
+            }
+            data.extend_from_slice(&op.data);
+            config_writable |= op.config_writable;
+        }
+        let mut tail = [
+            AccountMeta {
+                pubkey: CONFIG_ACCOUNT,
+                is_signer: false,
+                is_writable: config_writable,
+            },
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new(self.payer, true),
+        ];
+
+        let num_own = accounts.len();
+        for (meta, last) in optional {
+            let later = accounts[ends[last]..num_own]
+                .iter_mut()
+                .chain(&mut tail)
+                .find(|a| a.pubkey == meta.pubkey);
+            match later {
+                Some(existing) if dedup => {
+                    existing.is_signer |= meta.is_signer;
+                    existing.is_writable |= meta.is_writable;
+                }
+                _ => accounts.push(meta),
+            }
+        }
+        accounts.extend(tail);
+
+        Instruction {
+            program_id: nanotoken::ID,
+            accounts,
+            data,
+        }
+    }
+}
+
+/// Serialized size of a transaction holding only `ix`, paid for by `payer`
+pub fn transaction_size(ix: &Instruction, payer: &Pubkey) -> usize {
+    message_size(std::slice::from_ref(ix), payer)
+}
+
+/// Serialized size of a transaction holding `ixs`, paid for by `payer`
+fn message_size(ixs: &[Instruction], payer: &Pubkey) -> usize {
+    let message = Message::new(ixs, Some(payer));
+    let signatures = message.header.num_required_signatures as usize;
+    // Compact length prefix (one byte below 128 signatures), then signatures
+    1 + 64 * signatures + message.serialize().len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions;
+
+    #[test]
+    fn dedup_keeps_optional_accounts_after_every_op_that_needs_them() {
+        let [payer, from, to, owner, mint, recipient] =
+            [(); 6].map(|_| Pubkey::new_unique());
+        let royalty = [
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new(recipient, false),
+        ];
+        let count = |ix: &Instruction, key: Pubkey| {
+            ix.accounts
+                .iter()
+                .filter(|meta| meta.pubkey == key)
+                .count()
+        };
+
+        // The mint of the later mint_to is after the first transfer
+        let mut batch = BatchBuilder::new(&payer);
+        batch
+            .push_with_optional(
+                instructions::transfer(&from, &to, &owner, 1),
+                &royalty,
+            )
+            .push(instructions::mint_to(&from, &mint, &payer, 1));
+        let ix = batch.build_deduplicated();
+        assert_eq!(count(&ix, mint), 1);
+        assert_eq!(count(&ix, recipient), 1);
+        assert_eq!(ix.accounts.len(), batch.build().accounts.len() - 1);
+
+        // but not after a transfer following it
+        batch.push_with_optional(
+            instructions::transfer(&from, &to, &owner, 1),
+            &royalty,
+        );
+        let ix = batch.build_deduplicated();
+        assert_eq!(ix.accounts, batch.build().accounts);
+    }
+
+    #[test]
+    fn transactions_cover_every_op_in_order() {
+        let [payer, from, owner] = [(); 3].map(|_| Pubkey::new_unique());
+        let mut batch = BatchBuilder::new(&payer);
+        for amount in 0..100 {
+            batch.push(instructions::transfer(
+                &from,
+                &Pubkey::new_unique(),
+                &owner,
+                amount,
+            ));
+        }
+        let transactions = batch.build_transactions_with_ops();
+        assert!(transactions.len() > 1);
+        let mut next = 0;
+        for (ops, ixs) in &transactions {
+            assert_eq!(ops.start, next);
+            next = ops.end;
+            assert!(message_size(ixs, &payer) <= PACKET_DATA_SIZE);
+        }
+        assert_eq!(next, 100);
+    }
+}
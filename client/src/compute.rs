@@ -0,0 +1,309 @@
+//! Compute budgets for nanotoken transactions.
+//!
+//! Op costs vary with batch size, optional accounts, and program builds, so
+//! hardcoded limits are either wasteful or too tight. [ComputeBudget] sizes
+//! the compute unit and loaded account data limits from the ops in a batch,
+//! which is what [crate::batch::BatchBuilder] attaches by default. With the
+//! `rpc` feature, [estimate_compute_units] instead simulates the transaction
+//! and sizes the limit from what it actually consumed.
+
+use nanotoken::{
+    consts::CONFIG_ACCOUNT, ix::Tag, Mint, MintMetadata, ProgramConfig,
+    TokenAccount, TransferIntent, VaultInfo,
+};
+use nanotoken_core::inspect;
+#[cfg(feature = "rpc")]
+use solana_client::{
+    nonblocking::rpc_client::RpcClient,
+    rpc_config::RpcSimulateTransactionConfig,
+};
+#[cfg(feature = "rpc")]
+use solana_sdk::{compute_budget, transaction::Transaction};
+use solana_sdk::{
+    compute_budget::ComputeBudgetInstruction, instruction::Instruction,
+    pubkey::Pubkey, system_program,
+};
+
+#[cfg(feature = "rpc")]
+use crate::rpc::{RpcError, RpcResult};
+
+/// Cost of each compute budget instruction
+pub const COMPUTE_BUDGET_IX_COST: u32 = 150;
+
+/// Entrypoint cost of a nanotoken instruction, before any op runs
+pub const BASE_COST: u32 = 300;
+
+/// Upper bound on the compute units one op consumes. These are conservative
+/// figures for the default build; check them against the `cu-meter` feature
+/// when ops change. Batch ops are per entry.
+pub const fn op_cost(tag: Tag) -> u32 {
+    match tag {
+        Tag::Mint
+        | Tag::Burn
+        | Tag::BurnChecked
+        | Tag::Transfer
+        | Tag::TransferAll => 400,
+        Tag::BatchTransfer => 300,
+        // Create a pda through the system program
+        Tag::InitializeConfig
+        | Tag::InitializeMint
+        | Tag::InitializeAccount
+        | Tag::InitializeMintPda
+        | Tag::InitializeMetadata
+        | Tag::InitializeList
+        | Tag::ProposeTransfer => 6_000,
+        Tag::InitializeMintWithMetadata => 10_000,
+        // Searches for the canonical bump
+        Tag::CreateAssociatedAccount => 20_000,
+        // Resize or move lamports through the system program
+        Tag::WrapSol
+        | Tag::Realloc
+        | Tag::MigrateAccount
+        | Tag::MigrateState
+        | Tag::AddToList
+        | Tag::GarbageCollect => 4_000,
+        Tag::PermitTransfer => 3_000,
+        // CPI the token program
+        Tag::InitializeVault => 15_000,
+        Tag::Transmute | Tag::TransmuteBatch => 15_000,
+        Tag::Packed | Tag::Noop | Tag::AccountIndices => 50,
+        _ => 1_000,
+    }
+}
+
+/// Compute units of every op in nanotoken instruction data, excluding
+/// [BASE_COST]. Batch ops are charged per entry.
+pub fn data_cost(data: &[u8]) -> u32 {
+    let listing = inspect::inspect(data);
+    let ends = listing
+        .ops
+        .iter()
+        .skip(1)
+        .map(|op| op.offset)
+        .chain([data.len() - listing.trailing]);
+    listing
+        .ops
+        .iter()
+        .zip(ends)
+        .map(|(op, end)| {
+            let len = end - op.offset;
+            let entries = match op.tag {
+                // [tag][num_transfers][amounts]
+                Tag::BatchTransfer => len.saturating_sub(16) / 8,
+                // [tag][num_entries][TransmuteArgs entries]
+                Tag::TransmuteBatch => len.saturating_sub(16) / 16,
+                _ => 1,
+            };
+            op_cost(op.tag).saturating_mul(entries as u32)
+        })
+        .fold(0, u32::saturating_add)
+}
+
+/// Largest fixed-size nanotoken account. Mint lists grow with their entries
+/// and are budgeted as this size.
+fn largest_account_space() -> usize {
+    [
+        Mint::space(),
+        TokenAccount::space(),
+        VaultInfo::space(),
+        TransferIntent::space(),
+        MintMetadata::space(),
+    ]
+    .into_iter()
+    .max()
+    .unwrap()
+}
+
+/// Limits attached to each batch transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ComputeBudget {
+    /// Headroom over the estimates, in percent
+    pub margin_percent: u64,
+    /// Upper bound on the size of the deployed nanotoken program, which
+    /// every transaction loads
+    pub program_data_size: u32,
+}
+
+impl Default for ComputeBudget {
+    fn default() -> Self {
+        Self {
+            margin_percent: DEFAULT_MARGIN_PERCENT,
+            program_data_size: 64 * 1024,
+        }
+    }
+}
+
+impl ComputeBudget {
+    fn with_margin(&self, value: u64) -> u64 {
+        value
+            .saturating_mul(100 + self.margin_percent)
+            .div_ceil(100)
+    }
+
+    /// Compute unit limit for a transaction with the compute budget
+    /// instructions (including a compute unit price) and `ix`
+    pub fn compute_unit_limit(&self, ix: &Instruction) -> u32 {
+        let cost = (3 * COMPUTE_BUDGET_IX_COST)
+            .saturating_add(BASE_COST)
+            .saturating_add(data_cost(&ix.data));
+        self.with_margin(cost as u64)
+            .min(MAX_COMPUTE_UNIT_LIMIT as u64) as u32
+    }
+
+    /// Loaded account data limit for a transaction with `ix`, paid for by
+    /// a wallet. Signers are assumed to be wallets, and every other account
+    /// the largest nanotoken account. None for ops that CPI the token
+    /// program, whose size isn't known here.
+    pub fn loaded_accounts_data_size_limit(
+        &self,
+        ix: &Instruction,
+    ) -> Option<u32> {
+        let cpis_token_program = inspect::inspect(&ix.data)
+            .ops
+            .iter()
+            .any(|op| {
+                matches!(
+                    op.tag,
+                    Tag::Transmute | Tag::TransmuteBatch | Tag::InitializeVault
+                )
+            });
+        if cpis_token_program {
+            return None;
+        }
+
+        let mut seen: Vec<&Pubkey> = vec![];
+        let mut size = self.program_data_size as u64;
+        for meta in &ix.accounts {
+            if seen.contains(&&meta.pubkey) {
+                continue;
+            }
+            seen.push(&meta.pubkey);
+            size += if meta.pubkey == CONFIG_ACCOUNT {
+                ProgramConfig::space() as u64
+            } else if meta.pubkey == system_program::ID || meta.is_signer {
+                0
+            } else {
+                largest_account_space() as u64
+            };
+        }
+        Some(
+            self.with_margin(size)
+                .min(u32::MAX as u64) as u32,
+        )
+    }
+
+    /// The compute budget instructions to put in front of `ix`
+    pub fn instructions(&self, ix: &Instruction) -> Vec<Instruction> {
+        let mut ixs = vec![ComputeBudgetInstruction::set_compute_unit_limit(
+            self.compute_unit_limit(ix),
+        )];
+        if let Some(limit) = self.loaded_accounts_data_size_limit(ix) {
+            ixs.push(
+                ComputeBudgetInstruction::set_loaded_accounts_data_size_limit(
+                    limit,
+                ),
+            );
+        }
+        ixs
+    }
+}
+
+/// Headroom over estimated or simulated costs, in percent
+pub const DEFAULT_MARGIN_PERCENT: u64 = 10;
+
+/// The most a transaction may request
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+/// Tag of ComputeBudgetInstruction::SetComputeUnitLimit
+#[cfg(feature = "rpc")]
+const SET_COMPUTE_UNIT_LIMIT_TAG: u8 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ComputeUnitEstimate {
+    /// Compute units the simulation consumed, including the compute unit
+    /// limit instruction itself
+    pub consumed: u64,
+    /// `consumed` plus the margin, capped at [MAX_COMPUTE_UNIT_LIMIT]
+    pub limit: u32,
+}
+
+impl ComputeUnitEstimate {
+    pub fn new(consumed: u64, margin_percent: u64) -> Self {
+        let limit = consumed
+            .saturating_mul(100 + margin_percent)
+            .div_ceil(100)
+            .min(MAX_COMPUTE_UNIT_LIMIT as u64) as u32;
+        Self { consumed, limit }
+    }
+
+    /// The `set_compute_unit_limit` instruction to put in front of the
+    /// simulated instructions
+    pub fn limit_instruction(&self) -> Instruction {
+        ComputeBudgetInstruction::set_compute_unit_limit(self.limit)
+    }
+}
+
+#[cfg(feature = "rpc")]
+fn is_compute_unit_limit(ix: &Instruction) -> bool {
+    ix.program_id == compute_budget::id()
+        && ix.data.first() == Some(&SET_COMPUTE_UNIT_LIMIT_TAG)
+}
+
+/// [estimate_compute_units_with_margin] with [DEFAULT_MARGIN_PERCENT]
+#[cfg(feature = "rpc")]
+pub async fn estimate_compute_units(
+    client: &RpcClient,
+    instructions: &[Instruction],
+    payer: &Pubkey,
+) -> RpcResult<ComputeUnitEstimate> {
+    estimate_compute_units_with_margin(
+        client,
+        instructions,
+        payer,
+        DEFAULT_MARGIN_PERCENT,
+    )
+    .await
+}
+
+/// Simulates `instructions` paid for by `payer` and sizes a compute unit
+/// limit for them. Any compute unit limit among `instructions` is replaced
+/// by the max for the simulation, so a limit that is too low doesn't fail
+/// it. Signatures are not verified, so nothing needs to be signed.
+#[cfg(feature = "rpc")]
+pub async fn estimate_compute_units_with_margin(
+    client: &RpcClient,
+    instructions: &[Instruction],
+    payer: &Pubkey,
+    margin_percent: u64,
+) -> RpcResult<ComputeUnitEstimate> {
+    let mut simulated = vec![ComputeBudgetInstruction::set_compute_unit_limit(
+        MAX_COMPUTE_UNIT_LIMIT,
+    )];
+    simulated.extend(
+        instructions
+            .iter()
+            .filter(|ix| !is_compute_unit_limit(ix))
+            .cloned(),
+    );
+    let transaction = Transaction::new_with_payer(&simulated, Some(payer));
+
+    let config = RpcSimulateTransactionConfig {
+        sig_verify: false,
+        replace_recent_blockhash: true,
+        commitment: Some(client.commitment()),
+        ..Default::default()
+    };
+    let result = client
+        .simulate_transaction_with_config(&transaction, config)
+        .await?
+        .value;
+    match (result.err, result.units_consumed) {
+        (None, Some(consumed)) => {
+            Ok(ComputeUnitEstimate::new(consumed, margin_percent))
+        }
+        (err, _) => Err(RpcError::Simulation {
+            err,
+            logs: result.logs.unwrap_or_default(),
+        }),
+    }
+}
@@ -0,0 +1,106 @@
+//! getProgramAccounts filters for common queries, behind the `rpc` feature.
+//!
+//! Each query matches the discriminator and current layout version, and the
+//! account size, so results can be parsed with the typed `try_unpack`
+//! decoders. Accounts that still need MigrateAccount are not matched.
+
+use nanotoken::{
+    consts::{MINT_VERSION, TOKEN_ACCOUNT_VERSION},
+    offsets::{
+        DISCRIMINATOR_OFFSET, MINT_AUTHORITY_OFFSET, MINT_DISCRIMINATOR,
+        MINT_INDEX_OFFSET, TOKEN_ACCOUNT_MINT_OFFSET,
+        TOKEN_ACCOUNT_OWNER_OFFSET, TOKEN_DISCRIMINATOR,
+    },
+    Mint, TokenAccount,
+};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::{
+    account::Account, program_error::ProgramError, pubkey::Pubkey,
+};
+
+fn memcmp(offset: usize, bytes: &[u8]) -> RpcFilterType {
+    RpcFilterType::Memcmp(Memcmp::new_base58_encoded(offset, bytes))
+}
+
+/// Every token account
+pub fn token_accounts() -> Vec<RpcFilterType> {
+    vec![
+        memcmp(
+            DISCRIMINATOR_OFFSET,
+            &[TOKEN_DISCRIMINATOR, TOKEN_ACCOUNT_VERSION],
+        ),
+        RpcFilterType::DataSize(TokenAccount::space() as u64),
+    ]
+}
+
+/// Token accounts of the mint, including empty ones
+pub fn token_accounts_by_mint(mint_index: u64) -> Vec<RpcFilterType> {
+    let mut filters = token_accounts();
+    filters.push(memcmp(TOKEN_ACCOUNT_MINT_OFFSET, &mint_index.to_le_bytes()));
+    filters
+}
+
+/// Token accounts of the mint whose owner starts with the byte `page`. The
+/// 256 pages split [token_accounts_by_mint] for nodes that cap response
+/// sizes.
+pub fn token_accounts_by_mint_page(
+    mint_index: u64,
+    page: u8,
+) -> Vec<RpcFilterType> {
+    let mut filters = token_accounts_by_mint(mint_index);
+    filters.push(memcmp(TOKEN_ACCOUNT_OWNER_OFFSET, &[page]));
+    filters
+}
+
+/// Token accounts of the owner, of any mint, pda or keypair
+pub fn token_accounts_by_owner(owner: &Pubkey) -> Vec<RpcFilterType> {
+    let mut filters = token_accounts();
+    filters.push(memcmp(TOKEN_ACCOUNT_OWNER_OFFSET, owner.as_ref()));
+    filters
+}
+
+/// Every mint
+pub fn mints() -> Vec<RpcFilterType> {
+    vec![
+        memcmp(DISCRIMINATOR_OFFSET, &[MINT_DISCRIMINATOR, MINT_VERSION]),
+        RpcFilterType::DataSize(Mint::space() as u64),
+    ]
+}
+
+/// The mint at `mint_index`, pda or keypair
+pub fn mint_by_index(mint_index: u64) -> Vec<RpcFilterType> {
+    let mut filters = mints();
+    filters.push(memcmp(MINT_INDEX_OFFSET, &mint_index.to_le_bytes()));
+    filters
+}
+
+/// Mints whose mint authority is `authority`
+pub fn mints_by_authority(authority: &Pubkey) -> Vec<RpcFilterType> {
+    let mut filters = mints();
+    filters.push(memcmp(MINT_AUTHORITY_OFFSET, authority.as_ref()));
+    filters
+}
+
+/// Decodes getProgramAccounts results for [token_accounts] and friends
+pub fn parse_token_accounts(
+    accounts: Vec<(Pubkey, Account)>,
+) -> Result<Vec<(Pubkey, TokenAccount)>, ProgramError> {
+    accounts
+        .into_iter()
+        .map(|(address, account)| {
+            Ok((address, TokenAccount::try_unpack(&account.data)?))
+        })
+        .collect()
+}
+
+/// Decodes getProgramAccounts results for [mints] and friends
+pub fn parse_mints(
+    accounts: Vec<(Pubkey, Account)>,
+) -> Result<Vec<(Pubkey, Mint)>, ProgramError> {
+    accounts
+        .into_iter()
+        .map(|(address, account)| {
+            Ok((address, Mint::try_unpack(&account.data)?))
+        })
+        .collect()
+}
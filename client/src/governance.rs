@@ -0,0 +1,189 @@
+//! Helpers for handing nanotoken authorities to a DAO.
+//!
+//! SPL Governance executes proposal transactions via `invoke_signed`, signing
+//! as the governance account and as its native treasury. Either PDA can hold a
+//! nanotoken mint authority: the program's authority checks only look at
+//! `is_signer` and the key, so a PDA signer is indistinguishable from a
+//! keypair signer.
+//!
+//! The typical flow is
+//! 1. the current authority calls [set_authority_to_governance],
+//! 2. DAO members insert nanotoken instructions (e.g. [mint_to_via_dao]) into
+//!    a proposal with [insert_transaction],
+//! 3. once the proposal passes, anyone calls [execute_transaction].
+//!
+//! Instructions are encoded by hand against the SPL Governance v3 layout so
+//! this crate does not pull in `spl-governance` and its dependency tree.
+
+use nanotoken::ix::AuthorityType;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program, sysvar,
+};
+
+use crate::instructions;
+
+/// `GovernanceInstruction::InsertTransaction` variant index
+const INSERT_TRANSACTION: u8 = 9;
+/// `GovernanceInstruction::ExecuteTransaction` variant index
+const EXECUTE_TRANSACTION: u8 = 16;
+
+/// The governance native treasury, `["native-treasury", governance]`.
+pub fn native_treasury_address(
+    governance_program_id: &Pubkey,
+    governance: &Pubkey,
+) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"native-treasury", governance.as_ref()],
+        governance_program_id,
+    )
+    .0
+}
+
+/// The proposal transaction account,
+/// `["governance", proposal, option_index, transaction_index]`.
+pub fn proposal_transaction_address(
+    governance_program_id: &Pubkey,
+    proposal: &Pubkey,
+    option_index: u8,
+    transaction_index: u16,
+) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            b"governance",
+            proposal.as_ref(),
+            &option_index.to_le_bytes(),
+            &transaction_index.to_le_bytes(),
+        ],
+        governance_program_id,
+    )
+    .0
+}
+
+/// Hands the mint authority of `mint` to a governance-controlled PDA
+/// (usually the governance account or its native treasury).
+pub fn set_authority_to_governance(
+    mint: &Pubkey,
+    current_authority: &Pubkey,
+    governance_signer: &Pubkey,
+) -> Instruction {
+    instructions::set_authority(
+        mint,
+        current_authority,
+        Some(governance_signer),
+        AuthorityType::MintAuthority,
+    )
+}
+
+/// A nanotoken mint with the governance PDA as the authority. Meant to be
+/// passed to [insert_transaction].
+pub fn mint_to_via_dao(
+    to: &Pubkey,
+    mint: &Pubkey,
+    governance_signer: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    instructions::mint_to(to, mint, governance_signer, amount)
+}
+
+/// Accounts needed to add a transaction to an existing proposal.
+pub struct ProposalContext {
+    pub governance_program_id: Pubkey,
+    pub governance: Pubkey,
+    pub proposal: Pubkey,
+    /// TokenOwnerRecord of the proposal owner
+    pub token_owner_record: Pubkey,
+    /// Token owner or governance delegate
+    pub governance_authority: Pubkey,
+    pub payer: Pubkey,
+}
+
+/// Builds `InsertTransaction`, storing `instructions` in a proposal
+/// transaction account to be executed with [execute_transaction] after the
+/// vote passes and `hold_up_time` seconds elapse.
+pub fn insert_transaction(
+    ctx: &ProposalContext,
+    option_index: u8,
+    transaction_index: u16,
+    hold_up_time: u32,
+    instructions: &[Instruction],
+) -> Instruction {
+    let proposal_transaction = proposal_transaction_address(
+        &ctx.governance_program_id,
+        &ctx.proposal,
+        option_index,
+        transaction_index,
+    );
+
+    let mut data = vec![INSERT_TRANSACTION, option_index];
+    data.extend_from_slice(&transaction_index.to_le_bytes());
+    data.extend_from_slice(&hold_up_time.to_le_bytes());
+    write_instructions(&mut data, instructions);
+
+    Instruction {
+        program_id: ctx.governance_program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(ctx.governance, false),
+            AccountMeta::new(ctx.proposal, false),
+            AccountMeta::new_readonly(ctx.token_owner_record, false),
+            AccountMeta::new_readonly(ctx.governance_authority, true),
+            AccountMeta::new(proposal_transaction, false),
+            AccountMeta::new(ctx.payer, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(sysvar::rent::ID, false),
+        ],
+        data,
+    }
+}
+
+/// Builds `ExecuteTransaction` for a proposal transaction that stores
+/// `instructions`. The governance program signs for its PDAs, so every
+/// forwarded account is passed as a non-signer.
+pub fn execute_transaction(
+    governance_program_id: &Pubkey,
+    governance: &Pubkey,
+    proposal: &Pubkey,
+    proposal_transaction: &Pubkey,
+    instructions: &[Instruction],
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*governance, false),
+        AccountMeta::new(*proposal, false),
+        AccountMeta::new(*proposal_transaction, false),
+    ];
+    for ix in instructions {
+        accounts.push(AccountMeta::new_readonly(ix.program_id, false));
+        accounts.extend(
+            ix.accounts
+                .iter()
+                .map(|meta| AccountMeta {
+                    pubkey: meta.pubkey,
+                    is_signer: false,
+                    is_writable: meta.is_writable,
+                }),
+        );
+    }
+
+    Instruction {
+        program_id: *governance_program_id,
+        accounts,
+        data: vec![EXECUTE_TRANSACTION],
+    }
+}
+
+/// Borsh encoding of `Vec<InstructionData>`
+fn write_instructions(data: &mut Vec<u8>, instructions: &[Instruction]) {
+    data.extend_from_slice(&(instructions.len() as u32).to_le_bytes());
+    for ix in instructions {
+        data.extend_from_slice(ix.program_id.as_ref());
+        data.extend_from_slice(&(ix.accounts.len() as u32).to_le_bytes());
+        for meta in &ix.accounts {
+            data.extend_from_slice(meta.pubkey.as_ref());
+            data.push(meta.is_signer as u8);
+            data.push(meta.is_writable as u8);
+        }
+        data.extend_from_slice(&(ix.data.len() as u32).to_le_bytes());
+        data.extend_from_slice(&ix.data);
+    }
+}
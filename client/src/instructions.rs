@@ -0,0 +1,1519 @@
+//! Single-op instruction builders.
+//!
+//! Every builder returns a standalone nanotoken [Instruction] containing one
+//! op. Ops that need the config, system program, or payer get them appended
+//! as the trailing three accounts, matching what the entrypoint expects.
+
+use nanotoken::{
+    consts::{
+        ASSOCIATED_TOKEN_PROGRAM, CONFIG_ACCOUNT, MAX_BALANCE_QUERY,
+        MAX_INDEXED_ACCOUNTS, MAX_INTENT_APPROVERS, NATIVE_MINT_INDEX,
+        NOOP_PROGRAM,
+    },
+    ix::{
+        AcceptConfigAuthorityArgs, AccountIndicesArgs, AddToListArgs,
+        ApproveArgs, ApproveTransferArgs, AuthorityType, BalanceEntry,
+        BatchTransferArgs, BurnArgs, BurnCheckedArgs, CancelTransferArgs,
+        ClawbackArgs, CloseMintArgs, CreateAssociatedAccountArgs,
+        ExecuteTransferArgs, FreezeAccountArgs, GarbageCollectArgs,
+        GetAccountDataSizeArgs, GetBalancesArgs, InitConfigArgs,
+        InitializeAccountArgs, InitializeListArgs, InitializeMetadataArgs,
+        InitializeMintArgs, InitializeMintPdaArgs,
+        InitializeMintWithMetadataArgs, InitializeVaultArgs,
+        MigrateAccountArgs, MigrateStateArgs, MintArgs, NoopArgs, PauseArgs,
+        PermitMessage, PermitTransferArgs, ProposeTransferArgs, ReallocArgs,
+        RemoveFromListArgs, RevokeArgs, SetAuthorityArgs,
+        SetConfigAuthorityArgs, SetFeatureArgs, SetGcConfigArgs,
+        SetMemoRequiredArgs, SetRentDestinationArgs, SetRoyaltyArgs,
+        SyncNativeArgs, Tag, ThawAccountArgs, TimelockArgs, TransferAllArgs,
+        TransferArgs, TransferFeeConfig, TransmuteArgs, UnpauseArgs,
+        UnwrapSolArgs, UpdateMetadataArgs, WithdrawExcessLamportsArgs,
+        WithdrawWithheldFeesArgs, WrapSolArgs,
+    },
+    ListMode, Mint, MintList, MintMetadata, ProgramFeature, RentDestination,
+    TransferIntent,
+};
+use solana_sdk::{
+    ed25519_program,
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    signature::Signature,
+    system_instruction, system_program, sysvar,
+};
+
+use crate::pda;
+
+pub use nanotoken_core::encode::{op_data, packed_mode_data, packed_op_data};
+
+/// The entrypoint expects at least three accounts (the trailing config,
+/// system program, payer slots), even when no op uses them. Short account
+/// lists are padded with the read-only system program.
+const MIN_ACCOUNTS: usize = 3;
+
+fn instruction(mut accounts: Vec<AccountMeta>, data: Vec<u8>) -> Instruction {
+    while accounts.len() < MIN_ACCOUNTS {
+        accounts.push(AccountMeta::new_readonly(system_program::ID, false));
+    }
+    Instruction {
+        program_id: nanotoken::ID,
+        accounts,
+        data,
+    }
+}
+
+fn config_system_payer(payer: &Pubkey) -> [AccountMeta; 3] {
+    [
+        AccountMeta::new(CONFIG_ACCOUNT, false),
+        AccountMeta::new_readonly(system_program::ID, false),
+        AccountMeta::new(*payer, true),
+    ]
+}
+
+/// Combines single-op instructions into one instruction whose ops reference
+/// accounts by index, so accounts shared between ops are passed only once.
+/// Each op sees exactly its own accounts, so optional accounts (mint,
+/// instructions sysvar, noop program) must be appended to the op itself.
+///
+/// Panics if an op references more than MAX_INDEXED_ACCOUNTS accounts.
+pub fn batch_indexed(ops: &[Instruction], payer: &Pubkey) -> Instruction {
+    let tail = config_system_payer(payer);
+    let mut accounts: Vec<AccountMeta> = vec![];
+    let mut data = vec![];
+    for op in ops {
+        // The trailing config, system program, and payer are appended to
+        // every indexed op
+        let mut op_accounts = op.accounts.as_slice();
+        if op_accounts.len() >= 3
+            && op_accounts[op_accounts.len() - 3..]
+                .iter()
+                .zip(&tail)
+                .all(|(a, b)| a.pubkey == b.pubkey)
+        {
+            op_accounts = &op_accounts[..op_accounts.len() - 3];
+        }
+        assert!(op_accounts.len() <= MAX_INDEXED_ACCOUNTS);
+
+        let indices: Vec<u8> = op_accounts
+            .iter()
+            .map(|meta| {
+                let index = match accounts
+                    .iter()
+                    .position(|a| a.pubkey == meta.pubkey)
+                {
+                    Some(index) => {
+                        accounts[index].is_signer |= meta.is_signer;
+                        accounts[index].is_writable |= meta.is_writable;
+                        index
+                    }
+                    None => {
+                        accounts.push(meta.clone());
+                        accounts.len() - 1
+                    }
+                };
+                u8::try_from(index).expect("too many accounts")
+            })
+            .collect();
+
+        data.extend(op_data(
+            Tag::AccountIndices,
+            &AccountIndicesArgs {
+                num_accounts: indices.len() as u64,
+            },
+        ));
+        data.extend(&indices);
+        data.resize(
+            data.len() + AccountIndicesArgs::padded_len(indices.len())
+                - indices.len(),
+            0,
+        );
+        data.extend(&op.data);
+    }
+    accounts.extend(tail);
+
+    instruction(accounts, data)
+}
+
+/// Creates the config pda. Anyone can bootstrap a deployment, and whoever
+/// does sets the config authority.
+pub fn initialize_config(authority: &Pubkey, payer: &Pubkey) -> Instruction {
+    instruction(
+        config_system_payer(payer).to_vec(),
+        op_data(
+            Tag::InitializeConfig,
+            &InitConfigArgs {
+                authority: *authority,
+            },
+        ),
+    )
+}
+
+/// The mint account must already be allocated (`Mint::space()` bytes) and
+/// owned by the nanotoken program. Accounts of a mint without a freeze
+/// authority can never be frozen. Pass `TransferFeeConfig::default()` for no
+/// transfer fee.
+pub fn initialize_mint(
+    mint: &Pubkey,
+    authority: &Pubkey,
+    decimals: u8,
+    freeze_authority: Option<&Pubkey>,
+    transfer_fee: TransferFeeConfig,
+    pause_authority: Option<&Pubkey>,
+    max_supply: Option<u64>,
+    clawback_authority: Option<&Pubkey>,
+    payer: &Pubkey,
+) -> Instruction {
+    let mut accounts = vec![AccountMeta::new(*mint, false)];
+    accounts.extend(config_system_payer(payer));
+    instruction(
+        accounts,
+        op_data(
+            Tag::InitializeMint,
+            &InitializeMintArgs {
+                authority: *authority,
+                decimals: decimals as u64,
+                freeze_authority: freeze_authority
+                    .copied()
+                    .unwrap_or_default(),
+                transfer_fee,
+                pause_authority: pause_authority
+                    .copied()
+                    .unwrap_or_default(),
+                max_supply: max_supply.unwrap_or_default(),
+                clawback_authority: clawback_authority
+                    .copied()
+                    .unwrap_or_default(),
+            },
+        ),
+    )
+}
+
+/// Creates a mint at the ["mint", mint_index] pda. `mint_index` must be the
+/// next index in the config.
+pub fn initialize_mint_pda(
+    mint_index: u64,
+    mint_args: InitializeMintArgs,
+    payer: &Pubkey,
+) -> Instruction {
+    let (mint, bump) = pda::mint_address(mint_index);
+    let mut accounts = vec![AccountMeta::new(mint, false)];
+    accounts.extend(config_system_payer(payer));
+    instruction(
+        accounts,
+        op_data(
+            Tag::InitializeMintPda,
+            &InitializeMintPdaArgs {
+                mint: mint_args,
+                bump: bump as u64,
+            },
+        ),
+    )
+}
+
+/// Creates the canonical (owner, mint_index) token account. The bump is
+/// derived off-chain.
+///
+/// With `owner_must_sign`, the owner must also sign the transaction.
+pub fn initialize_account(
+    owner: &Pubkey,
+    mint_index: u64,
+    immutable_owner: bool,
+    owner_must_sign: bool,
+    payer: &Pubkey,
+) -> Instruction {
+    let (token_account, bump) = pda::token_account_address(owner, mint_index);
+    let mut accounts = vec![AccountMeta::new(token_account, false)];
+    if owner_must_sign {
+        accounts.push(AccountMeta::new_readonly(*owner, true));
+    }
+    accounts.extend(config_system_payer(payer));
+    instruction(
+        accounts,
+        op_data(
+            Tag::InitializeAccount,
+            &InitializeAccountArgs {
+                owner: *owner,
+                mint: mint_index,
+                bump: bump as u64,
+                immutable_owner: immutable_owner as u64,
+                owner_must_sign: owner_must_sign as u64,
+            },
+        ),
+    )
+}
+
+/// Creates the (owner, mint) token account, deriving the bump on-chain. Does
+/// nothing if it already exists. The owner of these accounts is immutable.
+pub fn create_associated_account(
+    owner: &Pubkey,
+    mint_index: u64,
+    payer: &Pubkey,
+) -> Instruction {
+    let (token_account, _bump) = pda::token_account_address(owner, mint_index);
+    let mut accounts = vec![AccountMeta::new(token_account, false)];
+    accounts.extend(config_system_payer(payer));
+    instruction(
+        accounts,
+        op_data(
+            Tag::CreateAssociatedAccount,
+            &CreateAssociatedAccountArgs {
+                owner: *owner,
+                mint: mint_index,
+            },
+        ),
+    )
+}
+
+/// Initializes a token account at a keypair address instead of the
+/// (owner, mint) pda. `token_account` must sign the transaction.
+pub fn initialize_keypair_account(
+    token_account: &Pubkey,
+    owner: &Pubkey,
+    mint_index: u64,
+    immutable_owner: bool,
+    owner_must_sign: bool,
+    payer: &Pubkey,
+) -> Instruction {
+    let mut accounts = vec![AccountMeta::new(*token_account, true)];
+    if owner_must_sign {
+        accounts.push(AccountMeta::new_readonly(*owner, true));
+    }
+    accounts.extend(config_system_payer(payer));
+    instruction(
+        accounts,
+        op_data(
+            Tag::InitializeAccount,
+            &InitializeAccountArgs {
+                owner: *owner,
+                mint: mint_index,
+                bump: 0,
+                immutable_owner: immutable_owner as u64,
+                owner_must_sign: owner_must_sign as u64,
+            },
+        ),
+    )
+}
+
+pub fn mint_to(
+    to: &Pubkey,
+    mint: &Pubkey,
+    authority: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    instruction(
+        vec![
+            AccountMeta::new(*to, false),
+            AccountMeta::new(*mint, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        op_data(Tag::Mint, &MintArgs { amount }),
+    )
+}
+
+pub fn burn(
+    from: &Pubkey,
+    mint: &Pubkey,
+    owner: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    instruction(
+        vec![
+            AccountMeta::new(*from, false),
+            AccountMeta::new(*mint, false),
+            AccountMeta::new_readonly(*owner, true),
+        ],
+        op_data(Tag::Burn, &BurnArgs { amount }),
+    )
+}
+
+/// Fails unless `decimals` matches the mint.
+pub fn burn_checked(
+    from: &Pubkey,
+    mint: &Pubkey,
+    owner: &Pubkey,
+    amount: u64,
+    decimals: u8,
+) -> Instruction {
+    instruction(
+        vec![
+            AccountMeta::new(*from, false),
+            AccountMeta::new(*mint, false),
+            AccountMeta::new_readonly(*owner, true),
+        ],
+        op_data(
+            Tag::BurnChecked,
+            &BurnCheckedArgs {
+                amount,
+                decimals: decimals as u64,
+            },
+        ),
+    )
+}
+
+/// The mint supply must be zero. With [RentDestination::Config],
+/// `destination` must be the config rent destination.
+pub fn close_mint(
+    mint: &Pubkey,
+    authority: &Pubkey,
+    destination: &Pubkey,
+    rent_destination: RentDestination,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(*mint, false),
+        AccountMeta::new_readonly(*authority, true),
+        AccountMeta::new(*destination, false),
+    ];
+    let rent_destination = rent_destination as u64;
+    if rent_destination == RentDestination::Config as u64 {
+        // The authority already signs, so it can fill the payer slot
+        accounts.extend(config_system_payer(authority));
+    }
+    instruction(
+        accounts,
+        op_data(Tag::CloseMint, &CloseMintArgs { rent_destination }),
+    )
+}
+
+/// `authority` is either the owner of `from` or its delegate. A delegate
+/// spends from its allowance.
+pub fn transfer(
+    from: &Pubkey,
+    to: &Pubkey,
+    authority: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    instruction(
+        vec![
+            AccountMeta::new(*from, false),
+            AccountMeta::new(*to, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        op_data(Tag::Transfer, &TransferArgs { amount }),
+    )
+}
+
+/// Sweeps the entire balance of `from`, whatever it is when the transaction
+/// lands.
+pub fn transfer_all(
+    from: &Pubkey,
+    to: &Pubkey,
+    authority: &Pubkey,
+) -> Instruction {
+    instruction(
+        vec![
+            AccountMeta::new(*from, false),
+            AccountMeta::new(*to, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        op_data(Tag::TransferAll, &TransferAllArgs {}),
+    )
+}
+
+/// Sends each `(to, amount)` from `from` in a single op. Destinations are
+/// passed as accounts and amounts trail the op args.
+pub fn batch_transfer(
+    from: &Pubkey,
+    owner: &Pubkey,
+    transfers: &[(Pubkey, u64)],
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(*from, false),
+        AccountMeta::new_readonly(*owner, true),
+    ];
+    accounts.extend(
+        transfers
+            .iter()
+            .map(|(to, _)| AccountMeta::new(*to, false)),
+    );
+
+    let mut data = op_data(
+        Tag::BatchTransfer,
+        &BatchTransferArgs {
+            num_transfers: transfers.len() as u64,
+        },
+    );
+    for (_, amount) in transfers {
+        data.extend_from_slice(&amount.to_le_bytes());
+    }
+    instruction(accounts, data)
+}
+
+/// Sweeps fees withheld in `sources` into `destination`.
+pub fn withdraw_withheld_fees(
+    mint: &Pubkey,
+    fee_authority: &Pubkey,
+    destination: &Pubkey,
+    sources: &[Pubkey],
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new_readonly(*fee_authority, true),
+        AccountMeta::new(*destination, false),
+    ];
+    accounts.extend(
+        sources
+            .iter()
+            .map(|source| AccountMeta::new(*source, false)),
+    );
+    instruction(
+        accounts,
+        op_data(
+            Tag::WithdrawWithheldFees,
+            &WithdrawWithheldFeesArgs {
+                num_sources: sources.len() as u64,
+            },
+        ),
+    )
+}
+
+/// When enabled, incoming transfers must be in a transaction with an SPL memo
+/// instruction, and must pass the instructions sysvar (see
+/// [with_instructions_sysvar]).
+pub fn set_memo_required(
+    token_account: &Pubkey,
+    owner: &Pubkey,
+    required: bool,
+) -> Instruction {
+    instruction(
+        vec![
+            AccountMeta::new(*token_account, false),
+            AccountMeta::new_readonly(*owner, true),
+        ],
+        op_data(
+            Tag::SetMemoRequired,
+            &SetMemoRequiredArgs {
+                required: required as u64,
+            },
+        ),
+    )
+}
+
+/// Locks `token_account` until `unlock_at` (unix timestamp). Locks can only
+/// be extended.
+pub fn timelock(
+    token_account: &Pubkey,
+    owner: &Pubkey,
+    unlock_at: i64,
+) -> Instruction {
+    instruction(
+        vec![
+            AccountMeta::new(*token_account, false),
+            AccountMeta::new_readonly(*owner, true),
+        ],
+        op_data(Tag::Timelock, &TimelockArgs { unlock_at }),
+    )
+}
+
+/// Appends the mint, which transfers out of accounts of a pausable mint read
+/// to check the pause state.
+pub fn with_mint(mut ix: Instruction, mint: &Pubkey) -> Instruction {
+    ix.accounts
+        .push(AccountMeta::new_readonly(*mint, false));
+    ix
+}
+
+/// Appends the noop program, which programs built with `noop-events` CPI to
+/// emit transfer, mint, and burn events.
+pub fn with_noop_program(mut ix: Instruction) -> Instruction {
+    ix.accounts
+        .push(AccountMeta::new_readonly(NOOP_PROGRAM, false));
+    ix
+}
+
+/// Appends a Noop op carrying `nonce`, which de-duplicates otherwise
+/// identical transactions without touching any accounts.
+pub fn with_nonce(mut ix: Instruction, nonce: u64) -> Instruction {
+    ix.data
+        .extend(op_data(Tag::Noop, &NoopArgs { nonce }));
+    ix
+}
+
+/// Grows a token account to `new_len` bytes (including the discriminator) to
+/// make room for extensions. Payer tops up rent.
+pub fn realloc(
+    token_account: &Pubkey,
+    owner: &Pubkey,
+    new_len: u64,
+    payer: &Pubkey,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(*token_account, false),
+        AccountMeta::new_readonly(*owner, true),
+    ];
+    accounts.extend(config_system_payer(payer));
+    instruction(accounts, op_data(Tag::Realloc, &ReallocArgs { new_len }))
+}
+
+/// Upgrades a mint or token account to the latest layout. Payer tops up
+/// rent for the larger account.
+pub fn migrate_account(account: &Pubkey, payer: &Pubkey) -> Instruction {
+    let mut accounts = vec![AccountMeta::new(*account, false)];
+    accounts.extend(config_system_payer(payer));
+    instruction(
+        accounts,
+        op_data(Tag::MigrateAccount, &MigrateAccountArgs {}),
+    )
+}
+
+/// Upgrades program state (the config or a vault info) to the latest
+/// layout. Signed by the config authority; payer tops up rent.
+pub fn migrate_state(
+    account: &Pubkey,
+    authority: &Pubkey,
+    payer: &Pubkey,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(*account, false),
+        AccountMeta::new_readonly(*authority, true),
+    ];
+    accounts.extend(config_system_payer(payer));
+    instruction(accounts, op_data(Tag::MigrateState, &MigrateStateArgs {}))
+}
+
+/// Configures garbage collection. A `min_age` of 0 disables it.
+pub fn set_gc_config(
+    authority: &Pubkey,
+    min_age: i64,
+    bounty: u64,
+    payer: &Pubkey,
+) -> Instruction {
+    let mut accounts = vec![AccountMeta::new_readonly(*authority, true)];
+    accounts.extend(config_system_payer(payer));
+    instruction(
+        accounts,
+        op_data(Tag::SetGcConfig, &SetGcConfigArgs { min_age, bounty }),
+    )
+}
+
+/// Sets where closes selecting [RentDestination::Config] send rent. `None`
+/// removes it.
+pub fn set_rent_destination(
+    authority: &Pubkey,
+    rent_destination: Option<&Pubkey>,
+    payer: &Pubkey,
+) -> Instruction {
+    let mut accounts = vec![AccountMeta::new_readonly(*authority, true)];
+    accounts.extend(config_system_payer(payer));
+    instruction(
+        accounts,
+        op_data(
+            Tag::SetRentDestination,
+            &SetRentDestinationArgs {
+                rent_destination: rent_destination
+                    .copied()
+                    .unwrap_or_default(),
+            },
+        ),
+    )
+}
+
+/// Enables or disables a program feature gate. Signed by the config
+/// authority.
+pub fn set_feature(
+    authority: &Pubkey,
+    feature: ProgramFeature,
+    enabled: bool,
+    payer: &Pubkey,
+) -> Instruction {
+    let mut accounts = vec![AccountMeta::new_readonly(*authority, true)];
+    accounts.extend(config_system_payer(payer));
+    instruction(
+        accounts,
+        op_data(
+            Tag::SetFeature,
+            &SetFeatureArgs {
+                feature: feature as u64,
+                enabled: enabled as u64,
+            },
+        ),
+    )
+}
+
+/// Proposes a new config authority, which takes over once it signs
+/// [accept_config_authority]. `None` cancels a pending transfer.
+pub fn set_config_authority(
+    authority: &Pubkey,
+    new_authority: Option<&Pubkey>,
+    payer: &Pubkey,
+) -> Instruction {
+    let mut accounts = vec![AccountMeta::new_readonly(*authority, true)];
+    accounts.extend(config_system_payer(payer));
+    instruction(
+        accounts,
+        op_data(
+            Tag::SetConfigAuthority,
+            &SetConfigAuthorityArgs {
+                new_authority: new_authority
+                    .copied()
+                    .unwrap_or_default(),
+            },
+        ),
+    )
+}
+
+pub fn accept_config_authority(
+    new_authority: &Pubkey,
+    payer: &Pubkey,
+) -> Instruction {
+    let mut accounts = vec![AccountMeta::new_readonly(*new_authority, true)];
+    accounts.extend(config_system_payer(payer));
+    instruction(
+        accounts,
+        op_data(Tag::AcceptConfigAuthority, &AcceptConfigAuthorityArgs {}),
+    )
+}
+
+/// Permissionlessly closes an old empty token account, paying the bounty to
+/// `collector` and the rest of the rent to `rent_payer`. This is the
+/// account's rent payer, or the config rent destination with
+/// [RentDestination::Config].
+pub fn garbage_collect(
+    token_account: &Pubkey,
+    rent_payer: &Pubkey,
+    collector: &Pubkey,
+    rent_destination: RentDestination,
+    payer: &Pubkey,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(*token_account, false),
+        AccountMeta::new(*rent_payer, false),
+        AccountMeta::new(*collector, false),
+    ];
+    accounts.extend(config_system_payer(payer));
+    instruction(
+        accounts,
+        op_data(
+            Tag::GarbageCollect,
+            &GarbageCollectArgs {
+                rent_destination: rent_destination as u64,
+            },
+        ),
+    )
+}
+
+pub fn clawback(
+    from: &Pubkey,
+    to: &Pubkey,
+    mint: &Pubkey,
+    clawback_authority: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    instruction(
+        vec![
+            AccountMeta::new(*from, false),
+            AccountMeta::new(*to, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(*clawback_authority, true),
+        ],
+        op_data(Tag::Clawback, &ClawbackArgs { amount }),
+    )
+}
+
+pub fn pause(mint: &Pubkey, pause_authority: &Pubkey) -> Instruction {
+    instruction(
+        vec![
+            AccountMeta::new(*mint, false),
+            AccountMeta::new_readonly(*pause_authority, true),
+        ],
+        op_data(Tag::Pause, &PauseArgs {}),
+    )
+}
+
+pub fn unpause(mint: &Pubkey, pause_authority: &Pubkey) -> Instruction {
+    instruction(
+        vec![
+            AccountMeta::new(*mint, false),
+            AccountMeta::new_readonly(*pause_authority, true),
+        ],
+        op_data(Tag::Unpause, &UnpauseArgs {}),
+    )
+}
+
+/// Appends the instructions sysvar, which transfers into memo-required
+/// accounts read to find the memo.
+pub fn with_instructions_sysvar(mut ix: Instruction) -> Instruction {
+    ix.accounts
+        .push(AccountMeta::new_readonly(sysvar::instructions::ID, false));
+    ix
+}
+
+/// The message the from account owner signs off-chain for [permit_transfer].
+pub fn permit_message(
+    from: &Pubkey,
+    to: &Pubkey,
+    amount: u64,
+    nonce: u64,
+    expiry: i64,
+) -> PermitMessage {
+    PermitMessage {
+        from: *from,
+        to: *to,
+        amount,
+        nonce,
+        expiry,
+    }
+}
+
+/// Executes a transfer the from account owner signed offline. The transaction
+/// must also include [ed25519_verify] over the same [permit_message]. `nonce`
+/// must be the from account's current `permit_nonce`.
+pub fn permit_transfer(
+    from: &Pubkey,
+    to: &Pubkey,
+    amount: u64,
+    nonce: u64,
+    expiry: i64,
+) -> Instruction {
+    instruction(
+        vec![
+            AccountMeta::new(*from, false),
+            AccountMeta::new(*to, false),
+            AccountMeta::new_readonly(sysvar::instructions::ID, false),
+        ],
+        op_data(
+            Tag::PermitTransfer,
+            &PermitTransferArgs {
+                amount,
+                nonce,
+                expiry,
+            },
+        ),
+    )
+}
+
+/// ed25519 program instruction verifying `signature` by `signer` over
+/// `message`, with all three stored inline as nanotoken requires.
+pub fn ed25519_verify(
+    signer: &Pubkey,
+    signature: &Signature,
+    message: &[u8],
+) -> Instruction {
+    const HEADER_LEN: u16 = 2 + 14;
+    const PUBKEY_OFFSET: u16 = HEADER_LEN;
+    const SIGNATURE_OFFSET: u16 = PUBKEY_OFFSET + 32;
+    const MESSAGE_OFFSET: u16 = SIGNATURE_OFFSET + 64;
+    const CURRENT_IX: u16 = u16::MAX;
+
+    let mut data = Vec::with_capacity(MESSAGE_OFFSET as usize + message.len());
+    data.extend_from_slice(&[1, 0]);
+    for field in [
+        SIGNATURE_OFFSET,
+        CURRENT_IX,
+        PUBKEY_OFFSET,
+        CURRENT_IX,
+        MESSAGE_OFFSET,
+        message.len() as u16,
+        CURRENT_IX,
+    ] {
+        data.extend_from_slice(&field.to_le_bytes());
+    }
+    data.extend_from_slice(signer.as_ref());
+    data.extend_from_slice(signature.as_ref());
+    data.extend_from_slice(message);
+    Instruction {
+        program_id: ed25519_program::ID,
+        accounts: vec![],
+        data,
+    }
+}
+
+/// Replaces any existing delegate and allowance.
+pub fn approve(
+    token_account: &Pubkey,
+    owner: &Pubkey,
+    delegate: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    instruction(
+        vec![
+            AccountMeta::new(*token_account, false),
+            AccountMeta::new_readonly(*owner, true),
+        ],
+        op_data(
+            Tag::Approve,
+            &ApproveArgs {
+                delegate: *delegate,
+                amount,
+            },
+        ),
+    )
+}
+
+pub fn revoke(token_account: &Pubkey, owner: &Pubkey) -> Instruction {
+    instruction(
+        vec![
+            AccountMeta::new(*token_account, false),
+            AccountMeta::new_readonly(*owner, true),
+        ],
+        op_data(Tag::Revoke, &RevokeArgs {}),
+    )
+}
+
+pub fn freeze_account(
+    token_account: &Pubkey,
+    mint: &Pubkey,
+    freeze_authority: &Pubkey,
+) -> Instruction {
+    instruction(
+        vec![
+            AccountMeta::new(*token_account, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(*freeze_authority, true),
+        ],
+        op_data(Tag::FreezeAccount, &FreezeAccountArgs {}),
+    )
+}
+
+pub fn thaw_account(
+    token_account: &Pubkey,
+    mint: &Pubkey,
+    freeze_authority: &Pubkey,
+) -> Instruction {
+    instruction(
+        vec![
+            AccountMeta::new(*token_account, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(*freeze_authority, true),
+        ],
+        op_data(Tag::ThawAccount, &ThawAccountArgs {}),
+    )
+}
+
+/// `new_authority = None` permanently disables the authority.
+pub fn set_authority(
+    mint: &Pubkey,
+    current_authority: &Pubkey,
+    new_authority: Option<&Pubkey>,
+    authority_type: AuthorityType,
+) -> Instruction {
+    instruction(
+        vec![
+            AccountMeta::new(*mint, false),
+            AccountMeta::new_readonly(*current_authority, true),
+        ],
+        op_data(
+            Tag::SetAuthority,
+            &SetAuthorityArgs {
+                new_authority: new_authority
+                    .copied()
+                    .unwrap_or_default(),
+                authority_type: authority_type as u64,
+            },
+        ),
+    )
+}
+
+/// Transfers ownership of a token account. Fails if the account was created
+/// with an immutable owner.
+pub fn set_owner(
+    token_account: &Pubkey,
+    owner: &Pubkey,
+    new_owner: &Pubkey,
+) -> Instruction {
+    set_authority(
+        token_account,
+        owner,
+        Some(new_owner),
+        AuthorityType::AccountOwner,
+    )
+}
+
+/// Sweeps lamports above rent exemption from `account` to `destination`.
+/// `authority` is the token account owner, the mint authority for mints, or
+/// the config authority for vault_info, config, and mints without a mint
+/// authority.
+pub fn withdraw_excess_lamports(
+    account: &Pubkey,
+    authority: &Pubkey,
+    destination: &Pubkey,
+    payer: &Pubkey,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(*account, false),
+        AccountMeta::new_readonly(*authority, true),
+        AccountMeta::new(*destination, false),
+    ];
+    accounts.extend(config_system_payer(payer));
+    instruction(
+        accounts,
+        op_data(Tag::WithdrawExcessLamports, &WithdrawExcessLamportsArgs {}),
+    )
+}
+
+/// Deposits `lamports` from `funder` into the native token account of
+/// `owner`, which must already be initialized with [NATIVE_MINT_INDEX].
+pub fn wrap_sol(
+    owner: &Pubkey,
+    funder: &Pubkey,
+    lamports: u64,
+    payer: &Pubkey,
+) -> Instruction {
+    let (token_account, _bump) =
+        pda::token_account_address(owner, NATIVE_MINT_INDEX);
+    let mut accounts = vec![
+        AccountMeta::new(token_account, false),
+        AccountMeta::new(*funder, true),
+    ];
+    accounts.extend(config_system_payer(payer));
+    instruction(
+        accounts,
+        op_data(Tag::WrapSol, &WrapSolArgs { amount: lamports }),
+    )
+}
+
+pub fn unwrap_sol(
+    owner: &Pubkey,
+    destination: &Pubkey,
+    lamports: u64,
+) -> Instruction {
+    let (token_account, _bump) =
+        pda::token_account_address(owner, NATIVE_MINT_INDEX);
+    instruction(
+        vec![
+            AccountMeta::new(token_account, false),
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new(*destination, false),
+        ],
+        op_data(Tag::UnwrapSol, &UnwrapSolArgs { amount: lamports }),
+    )
+}
+
+/// Permissionless. Credits lamports sent directly to a native token account.
+pub fn sync_native(token_account: &Pubkey) -> Instruction {
+    instruction(
+        vec![AccountMeta::new(*token_account, false)],
+        op_data(Tag::SyncNative, &SyncNativeArgs {}),
+    )
+}
+
+/// Creates the vault for `tokenkeg_mint` and its nanotoken mint. The
+/// nanotoken mint must already be allocated, see [create_vault].
+pub fn initialize_vault(
+    tokenkeg_mint: &Pubkey,
+    tokenkeg_program: &Pubkey,
+    nanotoken_mint: &Pubkey,
+    payer: &Pubkey,
+) -> Instruction {
+    let (vault_info, info_bump) = pda::vault_info(tokenkeg_mint);
+    let (vault, vault_bump) = pda::vault(tokenkeg_mint);
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*tokenkeg_mint, false),
+        AccountMeta::new(vault, false),
+        AccountMeta::new_readonly(*tokenkeg_program, false),
+        AccountMeta::new(vault_info, false),
+        AccountMeta::new(*nanotoken_mint, false),
+    ];
+    accounts.extend(config_system_payer(payer));
+    instruction(
+        accounts,
+        op_data(
+            Tag::InitializeVault,
+            &InitializeVaultArgs {
+                info_bump: info_bump as u32,
+                vault_bump: vault_bump as u32,
+            },
+        ),
+    )
+}
+
+/// Allocates the keypair `nanotoken_mint` with `mint_lamports` (rent
+/// exemption for [Mint::space]) and initializes the vault. `nanotoken_mint`
+/// must sign the transaction.
+pub fn create_vault(
+    tokenkeg_mint: &Pubkey,
+    tokenkeg_program: &Pubkey,
+    nanotoken_mint: &Pubkey,
+    mint_lamports: u64,
+    payer: &Pubkey,
+) -> [Instruction; 2] {
+    [
+        system_instruction::create_account(
+            payer,
+            nanotoken_mint,
+            mint_lamports,
+            Mint::space() as u64,
+            &nanotoken::ID,
+        ),
+        initialize_vault(
+            tokenkeg_mint,
+            tokenkeg_program,
+            nanotoken_mint,
+            payer,
+        ),
+    ]
+}
+
+/// Moves `amount` between a tokenkeg account and a nanotoken account of the
+/// vault for `tokenkeg_mint`, in whichever direction `from` is. `bump` is
+/// that of the (owner, mint) destination when it has to be created, or zero
+/// to derive it on-chain.
+///
+/// Prefer [wrap_spl_to_nanotoken] and [unwrap_nanotoken_to_spl], which fill
+/// in the user accounts.
+#[allow(clippy::too_many_arguments)]
+pub fn transmute(
+    from: &Pubkey,
+    to: &Pubkey,
+    owner: &Pubkey,
+    tokenkeg_mint: &Pubkey,
+    tokenkeg_program: &Pubkey,
+    nanotoken_mint: &Pubkey,
+    amount: u64,
+    bump: u8,
+    payer: &Pubkey,
+) -> Instruction {
+    let (vault_info, _bump) = pda::vault_info(tokenkeg_mint);
+    let (vault, _bump) = pda::vault(tokenkeg_mint);
+    let mut accounts = vec![
+        AccountMeta::new(*from, false),
+        AccountMeta::new(*to, false),
+        AccountMeta::new_readonly(*owner, true),
+        AccountMeta::new(*tokenkeg_mint, false),
+        AccountMeta::new(*nanotoken_mint, false),
+        AccountMeta::new_readonly(vault_info, false),
+        AccountMeta::new(vault, false),
+        AccountMeta::new_readonly(*tokenkeg_program, false),
+    ];
+    accounts.extend(config_system_payer(payer));
+    instruction(
+        accounts,
+        op_data(
+            Tag::Transmute,
+            &TransmuteArgs {
+                amount,
+                bump: bump as u64,
+            },
+        ),
+    )
+}
+
+/// Wraps `amount` from the owner's associated tokenkeg account into their
+/// (owner, mint) nanotoken account, creating the latter if needed.
+/// `nanotoken_mint` and `mint_index` are the vault's mint.
+pub fn wrap_spl_to_nanotoken(
+    tokenkeg_mint: &Pubkey,
+    tokenkeg_program: &Pubkey,
+    nanotoken_mint: &Pubkey,
+    mint_index: u64,
+    owner: &Pubkey,
+    amount: u64,
+    payer: &Pubkey,
+) -> Instruction {
+    let (from, _bump) =
+        pda::associated_token_address(owner, tokenkeg_mint, tokenkeg_program);
+    let (to, bump) = pda::token_account_address(owner, mint_index);
+    transmute(
+        &from,
+        &to,
+        owner,
+        tokenkeg_mint,
+        tokenkeg_program,
+        nanotoken_mint,
+        amount,
+        bump,
+        payer,
+    )
+}
+
+/// Unwraps `amount` from the owner's (owner, mint) nanotoken account into
+/// their associated tokenkeg account, creating the latter if needed.
+/// `nanotoken_mint` and `mint_index` are the vault's mint.
+pub fn unwrap_nanotoken_to_spl(
+    tokenkeg_mint: &Pubkey,
+    tokenkeg_program: &Pubkey,
+    nanotoken_mint: &Pubkey,
+    mint_index: u64,
+    owner: &Pubkey,
+    amount: u64,
+    payer: &Pubkey,
+) -> Instruction {
+    let (from, _bump) = pda::token_account_address(owner, mint_index);
+    let (to, _bump) =
+        pda::associated_token_address(owner, tokenkeg_mint, tokenkeg_program);
+    let mut ix = transmute(
+        &from,
+        &to,
+        owner,
+        tokenkeg_mint,
+        tokenkeg_program,
+        nanotoken_mint,
+        amount,
+        0,
+        payer,
+    );
+    // Scanned for when the associated account has to be created
+    ix.accounts.insert(
+        8,
+        AccountMeta::new_readonly(ASSOCIATED_TOKEN_PROGRAM, false),
+    );
+    ix
+}
+
+/// Proposes a transfer from `from` to `to` recorded in the intent PDA for
+/// (`from`, `nonce`). Unused approver slots are left empty. `authority` is
+/// either the owner of `from` or its delegate, which spends from its
+/// allowance, so `from` is writable.
+#[allow(clippy::too_many_arguments)]
+pub fn propose_transfer(
+    from: &Pubkey,
+    authority: &Pubkey,
+    to: &Pubkey,
+    amount: u64,
+    nonce: u64,
+    execute_after: i64,
+    approvers: &[Pubkey],
+    threshold: u8,
+    payer: &Pubkey,
+) -> Instruction {
+    assert!(
+        approvers.len() <= MAX_INTENT_APPROVERS,
+        "too many approvers"
+    );
+    let (intent, bump) = TransferIntent::address(from, nonce);
+    let mut args = ProposeTransferArgs {
+        amount,
+        nonce,
+        execute_after,
+        threshold: threshold as u64,
+        bump: bump as u64,
+        approvers: [Pubkey::default(); MAX_INTENT_APPROVERS],
+    };
+    args.approvers[..approvers.len()].copy_from_slice(approvers);
+
+    let mut accounts = vec![
+        AccountMeta::new(intent, false),
+        AccountMeta::new(*from, false),
+        AccountMeta::new_readonly(*authority, true),
+        AccountMeta::new_readonly(*to, false),
+    ];
+    accounts.extend(config_system_payer(payer));
+    instruction(accounts, op_data(Tag::ProposeTransfer, &args))
+}
+
+pub fn approve_transfer(intent: &Pubkey, approver: &Pubkey) -> Instruction {
+    instruction(
+        vec![
+            AccountMeta::new(*intent, false),
+            AccountMeta::new_readonly(*approver, true),
+        ],
+        op_data(Tag::ApproveTransfer, &ApproveTransferArgs {}),
+    )
+}
+
+/// Closes an intent, signed by its proposer. With [RentDestination::Config],
+/// `destination` must be the config rent destination.
+pub fn cancel_transfer(
+    intent: &Pubkey,
+    proposer: &Pubkey,
+    destination: &Pubkey,
+    rent_destination: RentDestination,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(*intent, false),
+        AccountMeta::new_readonly(*proposer, true),
+        AccountMeta::new(*destination, false),
+    ];
+    let rent_destination = rent_destination as u64;
+    if rent_destination == RentDestination::Config as u64 {
+        // The proposer already signs, so it can fill the payer slot
+        accounts.extend(config_system_payer(proposer));
+    }
+    instruction(
+        accounts,
+        op_data(
+            Tag::CancelTransfer,
+            &CancelTransferArgs { rent_destination },
+        ),
+    )
+}
+
+/// Permissionless once the intent is approved and its timelock has passed.
+pub fn execute_transfer(
+    intent: &Pubkey,
+    from: &Pubkey,
+    to: &Pubkey,
+) -> Instruction {
+    instruction(
+        vec![
+            AccountMeta::new(*intent, false),
+            AccountMeta::new(*from, false),
+            AccountMeta::new(*to, false),
+        ],
+        op_data(Tag::ExecuteTransfer, &ExecuteTransferArgs {}),
+    )
+}
+
+/// Read-only balance query, meant to be simulated. Config and system program
+/// are appended read-only since the entrypoint expects at least three
+/// accounts. Decode the return data with [parse_balances].
+pub fn get_balances(token_accounts: &[Pubkey], payer: &Pubkey) -> Instruction {
+    assert!(
+        token_accounts.len() <= MAX_BALANCE_QUERY,
+        "too many token accounts"
+    );
+    let mut accounts: Vec<AccountMeta> = token_accounts
+        .iter()
+        .map(|k| AccountMeta::new_readonly(*k, false))
+        .collect();
+    accounts.extend([
+        AccountMeta::new_readonly(CONFIG_ACCOUNT, false),
+        AccountMeta::new_readonly(system_program::ID, false),
+        AccountMeta::new(*payer, true),
+    ]);
+    instruction(
+        accounts,
+        op_data(
+            Tag::GetBalances,
+            &GetBalancesArgs {
+                num_accounts: token_accounts.len() as u64,
+            },
+        ),
+    )
+}
+
+/// Decodes GetBalances return data. Returns None if the length is not a
+/// multiple of the entry size.
+pub fn parse_balances(return_data: &[u8]) -> Option<Vec<BalanceEntry>> {
+    const ENTRY_LEN: usize = core::mem::size_of::<BalanceEntry>();
+    (return_data.len() % ENTRY_LEN == 0).then(|| {
+        return_data
+            .chunks_exact(ENTRY_LEN)
+            .map(bytemuck::pod_read_unaligned)
+            .collect()
+    })
+}
+
+/// Read-only token account size query, meant to be simulated. Decode the
+/// return data with [parse_account_data_size].
+pub fn get_account_data_size(extension_len: u64) -> Instruction {
+    // No accounts are read; instruction pads to the minimum
+    instruction(
+        vec![],
+        op_data(
+            Tag::GetAccountDataSize,
+            &GetAccountDataSizeArgs { extension_len },
+        ),
+    )
+}
+
+/// Decodes GetAccountDataSize return data
+pub fn parse_account_data_size(return_data: &[u8]) -> Option<u64> {
+    Some(u64::from_le_bytes(return_data.try_into().ok()?))
+}
+
+/// Zero-pads `s` into a fixed-size metadata field.
+///
+/// Panics if `s` is longer than `N` bytes.
+pub fn pad_str<const N: usize>(s: &str) -> [u8; N] {
+    assert!(s.len() <= N, "{s:?} is longer than {N} bytes");
+    let mut out = [0; N];
+    out[..s.len()].copy_from_slice(s.as_bytes());
+    out
+}
+
+/// Inverse of [pad_str]. Returns None if the bytes are not utf-8.
+pub fn unpad_str(bytes: &[u8]) -> Option<&str> {
+    let len = bytes
+        .iter()
+        .position(|b| *b == 0)
+        .unwrap_or(bytes.len());
+    core::str::from_utf8(&bytes[..len]).ok()
+}
+
+/// Creates the metadata PDA for `mint`. Panics if any string is too long.
+pub fn initialize_metadata(
+    mint: &Pubkey,
+    mint_authority: &Pubkey,
+    name: &str,
+    symbol: &str,
+    uri: &str,
+    payer: &Pubkey,
+) -> Instruction {
+    let (metadata, bump) = MintMetadata::address(mint);
+    let mut accounts = vec![
+        AccountMeta::new(metadata, false),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new_readonly(*mint_authority, true),
+    ];
+    accounts.extend(config_system_payer(payer));
+    instruction(
+        accounts,
+        op_data(
+            Tag::InitializeMetadata,
+            &InitializeMetadataArgs {
+                name: pad_str(name),
+                symbol: pad_str(symbol),
+                uri: pad_str(uri),
+                bump: bump as u64,
+            },
+        ),
+    )
+}
+
+/// Creates and initializes a keypair mint along with its metadata. The mint
+/// must sign. Panics if any string is too long.
+pub fn initialize_mint_with_metadata(
+    mint: &Pubkey,
+    mint_args: InitializeMintArgs,
+    name: &str,
+    symbol: &str,
+    uri: &str,
+    payer: &Pubkey,
+) -> Instruction {
+    let (metadata, bump) = MintMetadata::address(mint);
+    let mut accounts = vec![
+        AccountMeta::new(*mint, true),
+        AccountMeta::new(metadata, false),
+    ];
+    accounts.extend(config_system_payer(payer));
+    instruction(
+        accounts,
+        op_data(
+            Tag::InitializeMintWithMetadata,
+            &InitializeMintWithMetadataArgs {
+                mint: mint_args,
+                metadata: InitializeMetadataArgs {
+                    name: pad_str(name),
+                    symbol: pad_str(symbol),
+                    uri: pad_str(uri),
+                    bump: bump as u64,
+                },
+            },
+        ),
+    )
+}
+
+/// Overwrites all fields. Panics if any string is too long.
+pub fn update_metadata(
+    mint: &Pubkey,
+    mint_authority: &Pubkey,
+    name: &str,
+    symbol: &str,
+    uri: &str,
+) -> Instruction {
+    let (metadata, _bump) = MintMetadata::address(mint);
+    instruction(
+        vec![
+            AccountMeta::new(metadata, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(*mint_authority, true),
+        ],
+        op_data(
+            Tag::UpdateMetadata,
+            &UpdateMetadataArgs {
+                name: pad_str(name),
+                symbol: pad_str(symbol),
+                uri: pad_str(uri),
+            },
+        ),
+    )
+}
+
+/// Gates all transfers of `mint` by an allowlist or denylist administered by
+/// `list_authority`. Must be sent before anything is minted.
+pub fn initialize_list(
+    mint: &Pubkey,
+    mint_authority: &Pubkey,
+    list_authority: &Pubkey,
+    mode: ListMode,
+    payer: &Pubkey,
+) -> Instruction {
+    let (list, bump) = MintList::address(mint);
+    let mut accounts = vec![
+        AccountMeta::new(list, false),
+        AccountMeta::new(*mint, false),
+        AccountMeta::new_readonly(*mint_authority, true),
+    ];
+    accounts.extend(config_system_payer(payer));
+    instruction(
+        accounts,
+        op_data(
+            Tag::InitializeList,
+            &InitializeListArgs {
+                authority: *list_authority,
+                mode: mode as u64,
+                bump: bump as u64,
+            },
+        ),
+    )
+}
+
+/// Payer tops up rent when the list has to grow.
+pub fn add_to_list(
+    mint: &Pubkey,
+    list_authority: &Pubkey,
+    owner: &Pubkey,
+    payer: &Pubkey,
+) -> Instruction {
+    let (list, _bump) = MintList::address(mint);
+    let mut accounts = vec![
+        AccountMeta::new(list, false),
+        AccountMeta::new_readonly(*list_authority, true),
+    ];
+    accounts.extend(config_system_payer(payer));
+    instruction(
+        accounts,
+        op_data(Tag::AddToList, &AddToListArgs { owner: *owner }),
+    )
+}
+
+pub fn remove_from_list(
+    mint: &Pubkey,
+    list_authority: &Pubkey,
+    owner: &Pubkey,
+) -> Instruction {
+    let (list, _bump) = MintList::address(mint);
+    instruction(
+        vec![
+            AccountMeta::new(list, false),
+            AccountMeta::new_readonly(*list_authority, true),
+        ],
+        op_data(Tag::RemoveFromList, &RemoveFromListArgs { owner: *owner }),
+    )
+}
+
+/// Appends the mint's list, which transfers of listed mints read to check
+/// both owners.
+pub fn with_mint_list(mut ix: Instruction, mint: &Pubkey) -> Instruction {
+    ix.accounts
+        .push(AccountMeta::new_readonly(MintList::address(mint).0, false));
+    ix
+}
+
+/// Skims `bps` of every transfer into `recipient`, a token account of `mint`.
+/// Use 0 bps to turn the royalty off. It can only be turned on before
+/// anything is minted.
+pub fn set_royalty(
+    mint: &Pubkey,
+    mint_authority: &Pubkey,
+    bps: u64,
+    recipient: &Pubkey,
+) -> Instruction {
+    instruction(
+        vec![
+            AccountMeta::new(*mint, false),
+            AccountMeta::new_readonly(*mint_authority, true),
+            AccountMeta::new_readonly(*recipient, false),
+        ],
+        op_data(
+            Tag::SetRoyalty,
+            &SetRoyaltyArgs {
+                bps,
+                recipient: *recipient,
+            },
+        ),
+    )
+}
+
+/// Appends the mint and its royalty recipient, which transfers of royalty
+/// mints need to pay the royalty.
+pub fn with_royalty(
+    mut ix: Instruction,
+    mint: &Pubkey,
+    recipient: &Pubkey,
+) -> Instruction {
+    ix.accounts.extend([
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new(*recipient, false),
+    ]);
+    ix
+}
@@ -0,0 +1,13 @@
+//! Off-chain helpers for building nanotoken instructions.
+
+pub mod batch;
+pub mod compute;
+#[cfg(feature = "rpc")]
+pub mod filters;
+pub mod governance;
+pub mod instructions;
+pub mod lookup_table;
+pub mod pda;
+pub mod priority_fee;
+#[cfg(feature = "rpc")]
+pub mod rpc;
@@ -0,0 +1,108 @@
+//! Address lookup tables for nanotoken transactions.
+//!
+//! A legacy transaction spends 32 bytes on every account it references, so
+//! a batch runs out of packet space after a couple dozen transfers. A v0
+//! transaction references accounts in a lookup table by one byte instead.
+//! Put the accounts shared by most batches (config, system program, mints,
+//! vaults) in a table with [create] and [extend], then pass the table to
+//! [crate::batch::BatchBuilder::lookup_tables] or compile with
+//! [v0_message].
+
+use nanotoken::consts::CONFIG_ACCOUNT;
+use solana_sdk::{
+    address_lookup_table,
+    address_lookup_table_account::AddressLookupTableAccount,
+    hash::Hash,
+    instruction::Instruction,
+    message::{v0, CompileError, VersionedMessage},
+    pubkey::Pubkey,
+    system_program,
+};
+
+use crate::pda;
+
+/// Addresses per extend instruction, so that it fits in a transaction with
+/// the authority and payer signatures
+pub const MAX_EXTEND_ADDRESSES: usize = 20;
+
+/// Account lock limit of a transaction. Lookup tables lift the packet size
+/// limit on accounts, but not this one.
+pub const MAX_TRANSACTION_ACCOUNTS: usize = 64;
+
+/// Accounts most nanotoken transactions with these mints reference: the
+/// config, system program, nanotoken `mints`, and the tokenkeg mint, vault
+/// info, and vault of each of `tokenkeg_mints`.
+///
+/// The nanotoken program itself can't be looked up since it's invoked, and
+/// token accounts are usually too many to be worth it.
+pub fn hot_addresses(
+    mints: &[Pubkey],
+    tokenkeg_mints: &[Pubkey],
+) -> Vec<Pubkey> {
+    let mut addresses = vec![CONFIG_ACCOUNT, system_program::ID];
+    addresses.extend(mints);
+    for tokenkeg_mint in tokenkeg_mints {
+        addresses.extend([
+            *tokenkeg_mint,
+            pda::vault_info(tokenkeg_mint).0,
+            pda::vault(tokenkeg_mint).0,
+        ]);
+    }
+    let mut seen = vec![];
+    addresses.retain(|address| {
+        let new = !seen.contains(address);
+        seen.push(*address);
+        new
+    });
+    addresses
+}
+
+/// Creates a lookup table owned by `authority`, returning the instruction
+/// and the table address. `recent_slot` must be a recent finalized slot.
+pub fn create(
+    authority: &Pubkey,
+    payer: &Pubkey,
+    recent_slot: u64,
+) -> (Instruction, Pubkey) {
+    address_lookup_table::instruction::create_lookup_table(
+        *authority,
+        *payer,
+        recent_slot,
+    )
+}
+
+/// Appends `addresses` to `table`, one instruction per
+/// [MAX_EXTEND_ADDRESSES]. Send each in its own transaction. Tables hold at
+/// most 256 addresses.
+///
+/// New addresses can only be looked up from the slot after they're added.
+pub fn extend(
+    table: &Pubkey,
+    authority: &Pubkey,
+    payer: &Pubkey,
+    addresses: &[Pubkey],
+) -> Vec<Instruction> {
+    addresses
+        .chunks(MAX_EXTEND_ADDRESSES)
+        .map(|chunk| {
+            address_lookup_table::instruction::extend_lookup_table(
+                *table,
+                *authority,
+                Some(*payer),
+                chunk.to_vec(),
+            )
+        })
+        .collect()
+}
+
+/// Compiles `ixs` into a v0 message paid for by `payer`, looking up whatever
+/// accounts `tables` hold
+pub fn v0_message(
+    ixs: &[Instruction],
+    payer: &Pubkey,
+    tables: &[AddressLookupTableAccount],
+    recent_blockhash: Hash,
+) -> Result<VersionedMessage, CompileError> {
+    v0::Message::try_compile(payer, ixs, tables, recent_blockhash)
+        .map(VersionedMessage::V0)
+}
@@ -0,0 +1,125 @@
+//! Program derived addresses, memoized process-wide.
+//!
+//! `find_program_address` grinds bumps down from 255 and costs a sha256 per
+//! try, which adds up in hot loops that rebuild the same instructions (e.g.
+//! hammer). Every derivation here is cached by seeds for the life of the
+//! process; use [clear_cache] to bound memory when deriving for many
+//! distinct owners.
+
+use std::{
+    collections::HashMap,
+    sync::{Mutex, OnceLock},
+};
+
+use nanotoken::consts::ASSOCIATED_TOKEN_PROGRAM;
+use nanotoken_core::pda;
+use solana_sdk::pubkey::Pubkey;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum Seeds {
+    TokenAccount {
+        owner: Pubkey,
+        mint_index: u64,
+    },
+    Mint {
+        mint_index: u64,
+    },
+    VaultInfo {
+        tokenkeg_mint: Pubkey,
+    },
+    Vault {
+        tokenkeg_mint: Pubkey,
+    },
+    AssociatedTokenAccount {
+        owner: Pubkey,
+        tokenkeg_mint: Pubkey,
+        tokenkeg_program: Pubkey,
+    },
+}
+
+fn cache() -> &'static Mutex<HashMap<Seeds, (Pubkey, u8)>> {
+    static CACHE: OnceLock<Mutex<HashMap<Seeds, (Pubkey, u8)>>> =
+        OnceLock::new();
+    CACHE.get_or_init(Default::default)
+}
+
+fn cached(seeds: Seeds, derive: impl FnOnce() -> (Pubkey, u8)) -> (Pubkey, u8) {
+    if let Some(address) = cache().lock().unwrap().get(&seeds) {
+        return *address;
+    }
+    // Derive without holding the lock; a racing thread derives the same
+    // address
+    let address = derive();
+    cache()
+        .lock()
+        .unwrap()
+        .insert(seeds, address);
+    address
+}
+
+/// The canonical (owner, mint_index) token account and its bump
+pub fn token_account_address(owner: &Pubkey, mint_index: u64) -> (Pubkey, u8) {
+    cached(
+        Seeds::TokenAccount {
+            owner: *owner,
+            mint_index,
+        },
+        || pda::token_account(owner, mint_index),
+    )
+}
+
+/// The ["mint", mint_index] mint and its bump
+pub fn mint_address(mint_index: u64) -> (Pubkey, u8) {
+    cached(Seeds::Mint { mint_index }, || pda::mint(mint_index))
+}
+
+/// The vault info of a tokenkeg mint and its bump
+pub fn vault_info(tokenkeg_mint: &Pubkey) -> (Pubkey, u8) {
+    cached(
+        Seeds::VaultInfo {
+            tokenkeg_mint: *tokenkeg_mint,
+        },
+        || pda::vault_info(tokenkeg_mint),
+    )
+}
+
+/// The tokenkeg vault of a tokenkeg mint and its bump
+pub fn vault(tokenkeg_mint: &Pubkey) -> (Pubkey, u8) {
+    cached(
+        Seeds::Vault {
+            tokenkeg_mint: *tokenkeg_mint,
+        },
+        || pda::vault(tokenkeg_mint),
+    )
+}
+
+/// The owner's associated tokenkeg account, owned by `tokenkeg_program`
+/// (Tokenkeg or Token-2022), and its bump
+pub fn associated_token_address(
+    owner: &Pubkey,
+    tokenkeg_mint: &Pubkey,
+    tokenkeg_program: &Pubkey,
+) -> (Pubkey, u8) {
+    cached(
+        Seeds::AssociatedTokenAccount {
+            owner: *owner,
+            tokenkeg_mint: *tokenkeg_mint,
+            tokenkeg_program: *tokenkeg_program,
+        },
+        || {
+            Pubkey::find_program_address(
+                &[
+                    owner.as_ref(),
+                    tokenkeg_program.as_ref(),
+                    tokenkeg_mint.as_ref(),
+                ],
+                &ASSOCIATED_TOKEN_PROGRAM,
+            )
+        },
+    )
+}
+
+/// Drops every cached derivation
+pub fn clear_cache() {
+    cache().lock().unwrap().clear();
+}
@@ -0,0 +1,147 @@
+//! Compute unit prices for landing transactions under congestion.
+//!
+//! A [PriorityFeeStrategy] prices a transaction from the accounts it write
+//! locks, since that's what it contends on. [StaticFee] always pays the same,
+//! [PercentileFee] follows what recent transactions touching those accounts
+//! paid, and [OracleFee] defers to any external source. Pass one to
+//! [crate::batch::BatchBuilder::priority_fee], or put
+//! [PriorityFeeStrategy::price_instruction] in front of a transaction.
+
+use std::sync::{Arc, Mutex};
+
+#[cfg(feature = "rpc")]
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    compute_budget::ComputeBudgetInstruction, instruction::Instruction,
+    pubkey::Pubkey,
+};
+
+#[cfg(feature = "rpc")]
+use crate::rpc::RpcResult;
+
+pub trait PriorityFeeStrategy {
+    /// Price in micro-lamports per compute unit for a transaction that write
+    /// locks `writable`
+    fn compute_unit_price(&self, writable: &[Pubkey]) -> u64;
+
+    /// The `set_compute_unit_price` instruction for a transaction with
+    /// `ixs`, or None if the price is zero
+    fn price_instruction(&self, ixs: &[Instruction]) -> Option<Instruction> {
+        let mut writable: Vec<Pubkey> = vec![];
+        for meta in ixs.iter().flat_map(|ix| &ix.accounts) {
+            if meta.is_writable && !writable.contains(&meta.pubkey) {
+                writable.push(meta.pubkey);
+            }
+        }
+        match self.compute_unit_price(&writable) {
+            0 => None,
+            price => {
+                Some(ComputeBudgetInstruction::set_compute_unit_price(price))
+            }
+        }
+    }
+}
+
+impl<T: PriorityFeeStrategy + ?Sized> PriorityFeeStrategy for Arc<T> {
+    fn compute_unit_price(&self, writable: &[Pubkey]) -> u64 {
+        (**self).compute_unit_price(writable)
+    }
+}
+
+/// The same price for every transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StaticFee(pub u64);
+
+impl PriorityFeeStrategy for StaticFee {
+    fn compute_unit_price(&self, _writable: &[Pubkey]) -> u64 {
+        self.0
+    }
+}
+
+/// A percentile of recently paid prices, capped at `max`. Samples are
+/// replaced with [PercentileFee::update], or with the `rpc` feature
+/// [PercentileFee::refresh], so the accounts they are for are whichever were
+/// last sampled, not the ones passed when pricing. Zero until sampled.
+#[derive(Debug)]
+pub struct PercentileFee {
+    percentile: u8,
+    max: u64,
+    /// Sorted ascending
+    samples: Mutex<Vec<u64>>,
+}
+
+impl PercentileFee {
+    /// Panics if `percentile` is over 100
+    pub fn new(percentile: u8, max: u64) -> Self {
+        assert!(percentile <= 100, "percentile over 100");
+        Self {
+            percentile,
+            max,
+            samples: Mutex::default(),
+        }
+    }
+
+    /// Replaces the samples with `prices`
+    pub fn update(&self, prices: impl IntoIterator<Item = u64>) {
+        let mut samples: Vec<u64> = prices.into_iter().collect();
+        samples.sort_unstable();
+        *self.samples.lock().unwrap() = samples;
+    }
+
+    /// Samples the prices paid in recent slots by transactions that write
+    /// locked any of `writable`
+    #[cfg(feature = "rpc")]
+    pub async fn refresh(
+        &self,
+        client: &RpcClient,
+        writable: &[Pubkey],
+    ) -> RpcResult<()> {
+        let fees = client
+            .get_recent_prioritization_fees(writable)
+            .await?;
+        self.update(
+            fees.into_iter()
+                .map(|fee| fee.prioritization_fee),
+        );
+        Ok(())
+    }
+}
+
+impl PriorityFeeStrategy for PercentileFee {
+    fn compute_unit_price(&self, _writable: &[Pubkey]) -> u64 {
+        let samples = self.samples.lock().unwrap();
+        if samples.is_empty() {
+            return 0;
+        }
+        // Nearest rank
+        let rank = (samples.len() * self.percentile as usize).div_ceil(100);
+        samples[rank.saturating_sub(1)].min(self.max)
+    }
+}
+
+/// Prices from an external source, e.g. a fee estimation API polled in the
+/// background
+pub struct OracleFee<F>(pub F);
+
+impl<F: Fn(&[Pubkey]) -> u64> PriorityFeeStrategy for OracleFee<F> {
+    fn compute_unit_price(&self, writable: &[Pubkey]) -> u64 {
+        (self.0)(writable)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentile_fee_uses_nearest_rank() {
+        let fee = PercentileFee::new(75, 1_000);
+        assert_eq!(fee.compute_unit_price(&[]), 0);
+
+        fee.update([40, 10, 30, 20]);
+        assert_eq!(fee.compute_unit_price(&[]), 30);
+
+        fee.update([0, 5_000]);
+        assert_eq!(fee.compute_unit_price(&[]), 1_000);
+    }
+}
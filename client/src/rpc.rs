@@ -0,0 +1,469 @@
+//! Async RPC queries for nanotoken accounts, behind the `rpc` feature.
+//!
+//! Wraps a nonblocking [RpcClient] with the getProgramAccounts queries and
+//! decoding that dashboards and indexers otherwise reimplement, using the
+//! filters in [crate::filters].
+
+use std::fmt;
+
+use nanotoken::{
+    consts::CONFIG_ACCOUNT, Mint, ProgramConfig, TokenAccount, VaultInfo,
+};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    client_error::ClientError,
+    nonblocking::rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::RpcFilterType,
+};
+use solana_sdk::{
+    account::Account,
+    address_lookup_table::state::AddressLookupTable,
+    address_lookup_table_account::AddressLookupTableAccount,
+    instruction::Instruction,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+};
+
+use crate::{
+    batch::BatchBuilder,
+    compute::{self, ComputeUnitEstimate},
+    filters, instructions, pda,
+};
+
+#[derive(Debug)]
+pub enum RpcError {
+    Client(ClientError),
+    /// An account the request depends on does not exist
+    AccountNotFound(Pubkey),
+    /// The account exists but is not a valid nanotoken account of the
+    /// requested type
+    Decode(ProgramError),
+    /// The simulated transaction failed, or the node did not report the
+    /// compute units it consumed (`err` is None)
+    Simulation {
+        err: Option<TransactionError>,
+        logs: Vec<String>,
+    },
+}
+
+impl fmt::Display for RpcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RpcError::Client(e) => write!(f, "rpc error: {e}"),
+            RpcError::AccountNotFound(address) => {
+                write!(f, "account {address} does not exist")
+            }
+            RpcError::Decode(e) => write!(f, "could not decode account: {e}"),
+            RpcError::Simulation { err: Some(e), .. } => {
+                write!(f, "simulation failed: {e}")
+            }
+            RpcError::Simulation { err: None, .. } => {
+                write!(f, "simulation did not report compute units")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RpcError {}
+
+impl From<ClientError> for RpcError {
+    fn from(e: ClientError) -> Self {
+        RpcError::Client(e)
+    }
+}
+
+impl From<ProgramError> for RpcError {
+    fn from(e: ProgramError) -> Self {
+        RpcError::Decode(e)
+    }
+}
+
+pub type RpcResult<T> = Result<T, RpcError>;
+
+/// Instructions returned by [NanotokenRpc::wrap_spl_to_nanotoken]
+pub struct Wrap {
+    pub instructions: Vec<Instruction>,
+    /// The new nanotoken mint, if the vault had to be created. It must sign
+    /// the transaction.
+    pub nanotoken_mint: Option<Keypair>,
+}
+
+pub struct NanotokenRpc {
+    client: RpcClient,
+}
+
+impl NanotokenRpc {
+    pub fn new(client: RpcClient) -> Self {
+        Self { client }
+    }
+
+    pub fn new_with_url(url: &str) -> Self {
+        Self::new(RpcClient::new(url.to_string()))
+    }
+
+    pub fn client(&self) -> &RpcClient {
+        &self.client
+    }
+
+    /// None if the account does not exist
+    pub async fn get_token_account(
+        &self,
+        address: &Pubkey,
+    ) -> RpcResult<Option<TokenAccount>> {
+        let Some(account) = self.get_account(address).await? else {
+            return Ok(None);
+        };
+        Ok(Some(TokenAccount::try_unpack(&account)?))
+    }
+
+    /// Balance of the owner's (owner, mint) pda token account, zero if it
+    /// does not exist. Keypair token accounts are not included, see
+    /// [NanotokenRpc::get_all_token_accounts_by_owner].
+    pub async fn get_token_balance(
+        &self,
+        owner: &Pubkey,
+        mint_index: u64,
+    ) -> RpcResult<u64> {
+        let (address, _bump) = pda::token_account_address(owner, mint_index);
+        Ok(self
+            .get_token_account(&address)
+            .await?
+            .map_or(0, |account| account.balance))
+    }
+
+    /// The InitializeAccount instruction for the owner's (owner, mint) pda
+    /// token account, or None if it already exists. Prepend it to a payment
+    /// so the recipient's account is created only when needed.
+    ///
+    /// Another transaction can create the account between this check and
+    /// the payment landing, in which case the InitializeAccount op fails.
+    /// Senders that can't retry should prepend the idempotent (but pricier)
+    /// [instructions::create_associated_account] instead.
+    pub async fn initialize_account_if_needed(
+        &self,
+        owner: &Pubkey,
+        mint_index: u64,
+        payer: &Pubkey,
+    ) -> RpcResult<Option<Instruction>> {
+        let (address, _bump) = pda::token_account_address(owner, mint_index);
+        if self
+            .get_token_account(&address)
+            .await?
+            .is_some()
+        {
+            return Ok(None);
+        }
+        Ok(Some(instructions::initialize_account(
+            owner, mint_index, false, false, payer,
+        )))
+    }
+
+    /// The lookup table at `address` in the form v0 messages compile with,
+    /// see [crate::lookup_table]. None if the account does not exist.
+    pub async fn get_lookup_table(
+        &self,
+        address: &Pubkey,
+    ) -> RpcResult<Option<AddressLookupTableAccount>> {
+        let Some(account) = self.get_account(address).await? else {
+            return Ok(None);
+        };
+        let table = AddressLookupTable::deserialize(&account)
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+        Ok(Some(AddressLookupTableAccount {
+            key: *address,
+            addresses: table.addresses.to_vec(),
+        }))
+    }
+
+    /// Fails with [RpcError::AccountNotFound] if the program hasn't been
+    /// initialized
+    pub async fn get_config(&self) -> RpcResult<ProgramConfig> {
+        let config = self
+            .get_account(&CONFIG_ACCOUNT)
+            .await?
+            .ok_or(RpcError::AccountNotFound(CONFIG_ACCOUNT))?;
+        Ok(ProgramConfig::try_unpack(&config)?)
+    }
+
+    /// None if the tokenkeg mint has no vault
+    pub async fn get_vault_info(
+        &self,
+        tokenkeg_mint: &Pubkey,
+    ) -> RpcResult<Option<VaultInfo>> {
+        let (address, _bump) = pda::vault_info(tokenkeg_mint);
+        let Some(account) = self.get_account(&address).await? else {
+            return Ok(None);
+        };
+        Ok(Some(VaultInfo::try_unpack(&account)?))
+    }
+
+    /// Wraps `amount` from the owner's associated tokenkeg account into
+    /// their (owner, mint) nanotoken account, see
+    /// [instructions::wrap_spl_to_nanotoken]. The vault is created first if
+    /// the tokenkeg mint doesn't have one yet.
+    ///
+    /// A new vault mint gets the next mint index, which the destination is
+    /// derived from. If another mint is initialized before the transaction
+    /// lands, the transaction fails and can be rebuilt.
+    pub async fn wrap_spl_to_nanotoken(
+        &self,
+        tokenkeg_mint: &Pubkey,
+        owner: &Pubkey,
+        amount: u64,
+        payer: &Pubkey,
+    ) -> RpcResult<Wrap> {
+        let tokenkeg_program = self.get_owner(tokenkeg_mint).await?;
+        let mut wrap = Wrap {
+            instructions: vec![],
+            nanotoken_mint: None,
+        };
+        let (nanotoken_mint, mint_index) = match self
+            .get_vault_info(tokenkeg_mint)
+            .await?
+        {
+            Some(info) => {
+                let nanotoken_mint = *info.nanotoken_mint();
+                let mint = self
+                    .get_mint(&nanotoken_mint)
+                    .await?
+                    .ok_or(RpcError::AccountNotFound(nanotoken_mint))?;
+                (nanotoken_mint, mint.mint_index)
+            }
+            None => {
+                let mint_index = self
+                    .get_config()
+                    .await?
+                    .next_mint_index();
+                let mint_lamports = self
+                    .client
+                    .get_minimum_balance_for_rent_exemption(Mint::space())
+                    .await?;
+                let keypair = Keypair::new();
+                let nanotoken_mint = keypair.pubkey();
+                wrap.instructions
+                    .extend(instructions::create_vault(
+                        tokenkeg_mint,
+                        &tokenkeg_program,
+                        &nanotoken_mint,
+                        mint_lamports,
+                        payer,
+                    ));
+                wrap.nanotoken_mint = Some(keypair);
+                (nanotoken_mint, mint_index)
+            }
+        };
+        wrap.instructions
+            .push(instructions::wrap_spl_to_nanotoken(
+                tokenkeg_mint,
+                &tokenkeg_program,
+                &nanotoken_mint,
+                mint_index,
+                owner,
+                amount,
+                payer,
+            ));
+        Ok(wrap)
+    }
+
+    /// Unwraps `amount` from the owner's (owner, mint) nanotoken account
+    /// into their associated tokenkeg account, see
+    /// [instructions::unwrap_nanotoken_to_spl]. The vault must exist.
+    pub async fn unwrap_nanotoken_to_spl(
+        &self,
+        tokenkeg_mint: &Pubkey,
+        owner: &Pubkey,
+        amount: u64,
+        payer: &Pubkey,
+    ) -> RpcResult<Instruction> {
+        let tokenkeg_program = self.get_owner(tokenkeg_mint).await?;
+        let info = self
+            .get_vault_info(tokenkeg_mint)
+            .await?
+            .ok_or(RpcError::AccountNotFound(
+                pda::vault_info(tokenkeg_mint).0,
+            ))?;
+        let nanotoken_mint = *info.nanotoken_mint();
+        let mint = self
+            .get_mint(&nanotoken_mint)
+            .await?
+            .ok_or(RpcError::AccountNotFound(nanotoken_mint))?;
+        Ok(instructions::unwrap_nanotoken_to_spl(
+            tokenkeg_mint,
+            &tokenkeg_program,
+            &nanotoken_mint,
+            mint.mint_index,
+            owner,
+            amount,
+            payer,
+        ))
+    }
+
+    /// None if the account does not exist
+    pub async fn get_mint(&self, mint: &Pubkey) -> RpcResult<Option<Mint>> {
+        let Some(account) = self.get_account(mint).await? else {
+            return Ok(None);
+        };
+        Ok(Some(Mint::try_unpack(&account)?))
+    }
+
+    /// Every token account of `owner`, of any mint, pda or keypair
+    pub async fn get_all_token_accounts_by_owner(
+        &self,
+        owner: &Pubkey,
+    ) -> RpcResult<Vec<(Pubkey, TokenAccount)>> {
+        let accounts = self
+            .get_program_accounts(filters::token_accounts_by_owner(owner))
+            .await?;
+        Ok(filters::parse_token_accounts(accounts)?)
+    }
+
+    /// Token accounts of the mint with a nonzero balance
+    pub async fn get_holders(
+        &self,
+        mint_index: u64,
+    ) -> RpcResult<Vec<(Pubkey, TokenAccount)>> {
+        let accounts = self
+            .get_program_accounts(filters::token_accounts_by_mint(mint_index))
+            .await?;
+        let mut holders = filters::parse_token_accounts(accounts)?;
+        holders.retain(|(_, account)| account.balance > 0);
+        Ok(holders)
+    }
+
+    /// Like [NanotokenRpc::get_holders], one page of
+    /// [filters::token_accounts_by_mint_page] at a time, each read at
+    /// `min_context_slot` or later
+    pub async fn get_holders_paged(
+        &self,
+        mint_index: u64,
+        min_context_slot: Option<u64>,
+    ) -> RpcResult<Vec<(Pubkey, TokenAccount)>> {
+        let mut holders = vec![];
+        for page in 0..=u8::MAX {
+            let accounts = self
+                .get_program_accounts_at(
+                    filters::token_accounts_by_mint_page(mint_index, page),
+                    min_context_slot,
+                )
+                .await?;
+            holders.extend(filters::parse_token_accounts(accounts)?);
+        }
+        holders.retain(|(_, account)| account.balance > 0);
+        Ok(holders)
+    }
+
+    /// The mint at `mint_index`, for mints that may not be pdas. None if
+    /// there is none.
+    pub async fn get_mint_by_index(
+        &self,
+        mint_index: u64,
+    ) -> RpcResult<Option<(Pubkey, Mint)>> {
+        let accounts = self
+            .get_program_accounts(filters::mint_by_index(mint_index))
+            .await?;
+        Ok(filters::parse_mints(accounts)?
+            .into_iter()
+            .next())
+    }
+
+    /// Every mint
+    pub async fn get_all_mints(&self) -> RpcResult<Vec<(Pubkey, Mint)>> {
+        let accounts = self
+            .get_program_accounts(filters::mints())
+            .await?;
+        Ok(filters::parse_mints(accounts)?)
+    }
+
+    /// See [compute::estimate_compute_units]
+    pub async fn estimate_compute_units(
+        &self,
+        instructions: &[Instruction],
+        payer: &Pubkey,
+    ) -> RpcResult<ComputeUnitEstimate> {
+        compute::estimate_compute_units(&self.client, instructions, payer).await
+    }
+
+    /// [BatchBuilder::build_deduplicated], if a simulation of it succeeds.
+    /// Otherwise the plain [BatchBuilder::build] layout, if that succeeds
+    /// instead, or the simulation error of the deduplicated layout.
+    pub async fn build_deduplicated(
+        &self,
+        batch: &BatchBuilder,
+    ) -> RpcResult<Instruction> {
+        let deduplicated = batch.build_deduplicated();
+        let Err(err) = self
+            .estimate_compute_units(
+                std::slice::from_ref(&deduplicated),
+                batch.payer(),
+            )
+            .await
+        else {
+            return Ok(deduplicated);
+        };
+        let plain = batch.build();
+        if plain.accounts != deduplicated.accounts
+            && self
+                .estimate_compute_units(
+                    std::slice::from_ref(&plain),
+                    batch.payer(),
+                )
+                .await
+                .is_ok()
+        {
+            return Ok(plain);
+        }
+        Err(err)
+    }
+
+    async fn get_account(
+        &self,
+        address: &Pubkey,
+    ) -> RpcResult<Option<Vec<u8>>> {
+        Ok(self
+            .client
+            .get_account_with_commitment(address, self.client.commitment())
+            .await?
+            .value
+            .map(|account| account.data))
+    }
+
+    async fn get_owner(&self, address: &Pubkey) -> RpcResult<Pubkey> {
+        self.client
+            .get_account_with_commitment(address, self.client.commitment())
+            .await?
+            .value
+            .map(|account| account.owner)
+            .ok_or(RpcError::AccountNotFound(*address))
+    }
+
+    async fn get_program_accounts(
+        &self,
+        filters: Vec<RpcFilterType>,
+    ) -> RpcResult<Vec<(Pubkey, Account)>> {
+        self.get_program_accounts_at(filters, None)
+            .await
+    }
+
+    async fn get_program_accounts_at(
+        &self,
+        filters: Vec<RpcFilterType>,
+        min_context_slot: Option<u64>,
+    ) -> RpcResult<Vec<(Pubkey, Account)>> {
+        let config = RpcProgramAccountsConfig {
+            filters: Some(filters),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                min_context_slot,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        Ok(self
+            .client
+            .get_program_accounts_with_config(&nanotoken::ID, config)
+            .await?)
+    }
+}
@@ -0,0 +1,44 @@
+//! Prints the ops in a nanotoken instruction's data, see
+//! [nanotoken_core::inspect].
+//!
+//! ```text
+//! nanotoken-inspect 0600000000000000e803000000000000
+//! ```
+//!
+//! Reads the hex data from stdin if no argument is given.
+
+use std::io::Read;
+
+fn parse_hex(hex: &str) -> Option<Vec<u8>> {
+    let hex = hex.trim().trim_start_matches("0x");
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+fn main() {
+    let hex = match std::env::args().nth(1) {
+        Some(hex) => hex,
+        None => {
+            let mut hex = String::new();
+            std::io::stdin()
+                .read_to_string(&mut hex)
+                .expect("failed to read stdin");
+            hex
+        }
+    };
+    let Some(data) = parse_hex(&hex) else {
+        eprintln!("usage: nanotoken-inspect <instruction data as hex>");
+        std::process::exit(1);
+    };
+
+    let listing = nanotoken_core::inspect::inspect(&data);
+    print!("{listing}");
+    if listing.error.is_some() {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,74 @@
+//! Account decoding by discriminator, for indexers that see arbitrary
+//! nanotoken accounts.
+
+use nanotoken::{
+    loader::{AccountLoader, Discriminated},
+    offsets::{
+        CONFIG_DISCRIMINATOR, DISCRIMINATOR_OFFSET, MINT_DISCRIMINATOR,
+        MINT_LIST_DISCRIMINATOR, MINT_METADATA_DISCRIMINATOR,
+        TOKEN_DISCRIMINATOR, TRANSFER_INTENT_DISCRIMINATOR,
+        VAULT_INFO_DISCRIMINATOR,
+    },
+    Mint, MintList, MintMetadata, ProgramConfig, TokenAccount, TransferIntent,
+    VaultInfo,
+};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "wasm", derive(serde::Serialize))]
+pub enum NanotokenAccount {
+    Config(ProgramConfig),
+    Mint(Mint),
+    Token(TokenAccount),
+    VaultInfo(VaultInfo),
+    TransferIntent(TransferIntent),
+    MintMetadata(MintMetadata),
+    /// The list and its `len` entries
+    MintList(MintList, Vec<Pubkey>),
+}
+
+/// Decodes any nanotoken account. Accounts on an older layout fail with
+/// [nanotoken::error::NanoTokenError::AccountNeedsMigration].
+pub fn decode_account(data: &[u8]) -> Result<NanotokenAccount, ProgramError> {
+    let Some(&discriminator) = data.get(DISCRIMINATOR_OFFSET) else {
+        return Err(ProgramError::InvalidAccountData);
+    };
+    Ok(match discriminator {
+        CONFIG_DISCRIMINATOR => NanotokenAccount::Config(unpack(data)?),
+        MINT_DISCRIMINATOR => NanotokenAccount::Mint(unpack(data)?),
+        TOKEN_DISCRIMINATOR => NanotokenAccount::Token(unpack(data)?),
+        VAULT_INFO_DISCRIMINATOR => NanotokenAccount::VaultInfo(unpack(data)?),
+        TRANSFER_INTENT_DISCRIMINATOR => {
+            NanotokenAccount::TransferIntent(unpack(data)?)
+        }
+        MINT_METADATA_DISCRIMINATOR => {
+            NanotokenAccount::MintMetadata(unpack(data)?)
+        }
+        MINT_LIST_DISCRIMINATOR => {
+            let list: MintList = unpack(data)?;
+            let entries = list_entries(&list, data)?;
+            NanotokenAccount::MintList(list, entries)
+        }
+        _ => return Err(ProgramError::InvalidAccountData),
+    })
+}
+
+fn unpack<T: Discriminated>(data: &[u8]) -> Result<T, ProgramError> {
+    AccountLoader::<T>::try_unpack(data)
+}
+
+/// The in-use entries of a list account
+fn list_entries(
+    list: &MintList,
+    data: &[u8],
+) -> Result<Vec<Pubkey>, ProgramError> {
+    let len = usize::try_from(list.len)
+        .ok()
+        .filter(|&len| len <= MintList::capacity(data.len()))
+        .ok_or(ProgramError::InvalidAccountData)?;
+    Ok(data[MintList::space()..]
+        .chunks_exact(32)
+        .take(len)
+        .map(bytemuck::pod_read_unaligned)
+        .collect())
+}
@@ -0,0 +1,104 @@
+//! Instruction data encoding. An instruction is a batch of ops, each
+//! `[tag as u64][args]` followed by any trailing data the op takes (e.g.
+//! AddToList entries).
+
+use bytemuck::Pod;
+use nanotoken::ix::{PackedArgs, ProgramInstruction, Tag};
+
+/// Writes `[tag as u64][args]`, the encoding of a single op.
+pub fn op_data<T: Pod>(tag: Tag, args: &T) -> Vec<u8> {
+    let mut data = Vec::with_capacity(Tag::size() + core::mem::size_of::<T>());
+    data.extend_from_slice(&(tag as u64).to_le_bytes());
+    data.extend_from_slice(bytemuck::bytes_of(args));
+    data
+}
+
+/// Writes `[tag as u8][args]`, the encoding of a single op in packed mode.
+/// Only valid after [packed_mode_data]; see [nanotoken::ix::packed] for the
+/// ops that have a packed encoding and their args.
+pub fn packed_op_data<T: Pod>(tag: Tag, args: &T) -> Vec<u8> {
+    let mut data = Vec::with_capacity(1 + core::mem::size_of::<T>());
+    data.push(tag as u8);
+    data.extend_from_slice(bytemuck::bytes_of(args));
+    data
+}
+
+/// Switches the rest of the batch to packed mode
+pub fn packed_mode_data() -> Vec<u8> {
+    op_data(Tag::Packed, &PackedArgs {})
+}
+
+macro_rules! instruction_data {
+    ($ix:expr, [$($variant:ident),* $(,)?]) => {
+        match $ix {
+            $(ProgramInstruction::$variant(args) => {
+                op_data(Tag::$variant, args)
+            })*
+        }
+    };
+}
+
+/// Encodes one op, without any trailing data
+pub fn instruction_data(ix: &ProgramInstruction) -> Vec<u8> {
+    instruction_data!(
+        ix,
+        [
+            InitializeConfig,
+            InitializeMint,
+            InitializeAccount,
+            InitializeVault,
+            Mint,
+            Burn,
+            Transfer,
+            Transmute,
+            SetAuthority,
+            WithdrawExcessLamports,
+            ProposeTransfer,
+            ApproveTransfer,
+            ExecuteTransfer,
+            GetBalances,
+            Approve,
+            Revoke,
+            FreezeAccount,
+            ThawAccount,
+            BatchTransfer,
+            TransferAll,
+            BurnChecked,
+            WrapSol,
+            UnwrapSol,
+            SyncNative,
+            InitializeMetadata,
+            UpdateMetadata,
+            WithdrawWithheldFees,
+            CloseMint,
+            SetMemoRequired,
+            CreateAssociatedAccount,
+            PermitTransfer,
+            Pause,
+            Unpause,
+            Clawback,
+            SetGcConfig,
+            GarbageCollect,
+            MigrateAccount,
+            InitializeMintWithMetadata,
+            AccountIndices,
+            Realloc,
+            SetConfigAuthority,
+            AcceptConfigAuthority,
+            InitializeMintPda,
+            GetAccountDataSize,
+            TransmuteBatch,
+            InitializeList,
+            AddToList,
+            RemoveFromList,
+            SetRoyalty,
+            SetRentDestination,
+            Noop,
+            Timelock,
+            SetFeature,
+            Packed,
+            MigrateState,
+            CancelTransfer,
+        ]
+    )
+}
@@ -0,0 +1,442 @@
+//! Instruction data pretty-printer, for debugging failed transactions.
+//!
+//! [inspect] walks a batch the way the program does, switching encodings at
+//! the same ops, and lists each op with its args and the accounts it expects.
+//! Decoding stops at the first op the program would reject, which is then
+//! the op to look at.
+//!
+//! ```ignore
+//! print!("{}", nanotoken_core::inspect::inspect(&ix.data));
+//! ```
+
+use std::fmt;
+
+use nanotoken::ix::*;
+use solana_program::program_error::ProgramError;
+
+/// How an op's tag and args are laid out. Every batch starts out
+/// [Encoding::Aligned]; some ops switch the rest of the batch to another
+/// encoding, see [Encoding::after].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Encoding {
+    /// `[u64 tag][args]`
+    Aligned,
+    /// `[u8 tag][packed args]`, see [nanotoken::ix::packed]
+    Packed,
+}
+
+impl Encoding {
+    pub const fn tag_len(self) -> usize {
+        match self {
+            Encoding::Aligned => Tag::size(),
+            Encoding::Packed => 1,
+        }
+    }
+
+    /// The encoding of the ops following a `tag` op
+    pub const fn after(self, tag: Tag) -> Encoding {
+        match tag {
+            Tag::Packed => Encoding::Packed,
+            _ => self,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedOp {
+    /// Byte offset of the op's tag in the instruction data
+    pub offset: usize,
+    pub encoding: Encoding,
+    pub tag: Tag,
+    /// The args, plus trailing entries for ops that take them
+    pub args: String,
+}
+
+impl DecodedOp {
+    /// See [account_roles]
+    pub const fn accounts(&self) -> &'static str {
+        account_roles(self.tag)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InspectError {
+    /// No op has this tag in this encoding
+    UnknownTag {
+        offset: usize,
+        encoding: Encoding,
+        tag: u8,
+    },
+    /// The data ends before the op's args do
+    Truncated {
+        offset: usize,
+        encoding: Encoding,
+        tag: Tag,
+    },
+}
+
+impl fmt::Display for InspectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InspectError::UnknownTag {
+                offset,
+                encoding,
+                tag,
+            } => write!(f, "@{offset}: unknown {encoding:?} tag {tag}"),
+            InspectError::Truncated {
+                offset,
+                encoding,
+                tag,
+            } => {
+                write!(f, "@{offset}: {encoding:?} {tag:?} args are truncated")
+            }
+        }
+    }
+}
+
+impl std::error::Error for InspectError {}
+
+/// Every op decoded before `error`, if any
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Listing {
+    pub ops: Vec<DecodedOp>,
+    pub error: Option<InspectError>,
+    /// Bytes after the last op too short to hold a tag, which the program
+    /// ignores
+    pub trailing: usize,
+}
+
+impl fmt::Display for Listing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, op) in self.ops.iter().enumerate() {
+            writeln!(
+                f,
+                "#{i} @{} {:?} ({:?})",
+                op.offset, op.tag, op.encoding
+            )?;
+            writeln!(f, "    args: {}", op.args)?;
+            writeln!(f, "    accounts: {}", op.accounts())?;
+        }
+        if let Some(error) = &self.error {
+            writeln!(f, "error: {error}")?;
+        }
+        if self.trailing != 0 {
+            writeln!(f, "ignored {} trailing bytes", self.trailing)?;
+        }
+        Ok(())
+    }
+}
+
+macro_rules! decode_aligned {
+    (
+        $tag:expr, $data:expr,
+        args { $($variant:ident => $args:ty),* $(,)? }
+        entries { $($entries_variant:ident => $entries_args:ty),* $(,)? }
+    ) => {
+        match $tag {
+            $(t if t == Tag::$variant as u8 => Some((
+                Tag::$variant,
+                <$args>::from_data($data).map(|args| format!("{args:?}")),
+            )),)*
+            $(t if t == Tag::$entries_variant as u8 => Some((
+                Tag::$entries_variant,
+                <$entries_args>::from_data($data)
+                    .map(|(args, entries)| format!("{args:?} {entries:?}")),
+            )),)*
+            _ => None,
+        }
+    };
+}
+
+macro_rules! decode_packed {
+    ($tag:expr, $data:expr, { $($variant:ident => $args:ty),* $(,)? }) => {
+        match $tag {
+            $(t if t == Tag::$variant as u8 => Some((
+                Tag::$variant,
+                read_unaligned::<$args>($data).map(|args| format!("{args:?}")),
+            )),)*
+            _ => None,
+        }
+    };
+}
+
+/// Decodes one op's args, advancing `data` past them. None for unknown tags.
+/// Aligned `data` must be 8-byte aligned, like it is on-chain.
+fn decode_op(
+    encoding: Encoding,
+    tag: u8,
+    data: &mut &[u8],
+) -> Option<(Tag, Result<String, ProgramError>)> {
+    match encoding {
+        Encoding::Aligned => decode_aligned!(tag, data,
+            args {
+                InitializeConfig => InitConfigArgs,
+                InitializeMint => InitializeMintArgs,
+                InitializeAccount => InitializeAccountArgs,
+                InitializeVault => InitializeVaultArgs,
+                Mint => MintArgs,
+                Burn => BurnArgs,
+                Transfer => TransferArgs,
+                Transmute => TransmuteArgs,
+                SetAuthority => SetAuthorityArgs,
+                WithdrawExcessLamports => WithdrawExcessLamportsArgs,
+                ProposeTransfer => ProposeTransferArgs,
+                ApproveTransfer => ApproveTransferArgs,
+                ExecuteTransfer => ExecuteTransferArgs,
+                GetBalances => GetBalancesArgs,
+                Approve => ApproveArgs,
+                Revoke => RevokeArgs,
+                FreezeAccount => FreezeAccountArgs,
+                ThawAccount => ThawAccountArgs,
+                TransferAll => TransferAllArgs,
+                BurnChecked => BurnCheckedArgs,
+                WrapSol => WrapSolArgs,
+                UnwrapSol => UnwrapSolArgs,
+                SyncNative => SyncNativeArgs,
+                InitializeMetadata => InitializeMetadataArgs,
+                UpdateMetadata => UpdateMetadataArgs,
+                WithdrawWithheldFees => WithdrawWithheldFeesArgs,
+                CloseMint => CloseMintArgs,
+                SetMemoRequired => SetMemoRequiredArgs,
+                CreateAssociatedAccount => CreateAssociatedAccountArgs,
+                PermitTransfer => PermitTransferArgs,
+                Pause => PauseArgs,
+                Unpause => UnpauseArgs,
+                Clawback => ClawbackArgs,
+                SetGcConfig => SetGcConfigArgs,
+                GarbageCollect => GarbageCollectArgs,
+                MigrateAccount => MigrateAccountArgs,
+                InitializeMintWithMetadata => InitializeMintWithMetadataArgs,
+                Realloc => ReallocArgs,
+                SetConfigAuthority => SetConfigAuthorityArgs,
+                AcceptConfigAuthority => AcceptConfigAuthorityArgs,
+                InitializeMintPda => InitializeMintPdaArgs,
+                GetAccountDataSize => GetAccountDataSizeArgs,
+                InitializeList => InitializeListArgs,
+                AddToList => AddToListArgs,
+                RemoveFromList => RemoveFromListArgs,
+                SetRoyalty => SetRoyaltyArgs,
+                SetRentDestination => SetRentDestinationArgs,
+                Noop => NoopArgs,
+                Timelock => TimelockArgs,
+                SetFeature => SetFeatureArgs,
+                Packed => PackedArgs,
+                MigrateState => MigrateStateArgs,
+                CancelTransfer => CancelTransferArgs,
+            }
+            entries {
+                BatchTransfer => BatchTransferArgs,
+                AccountIndices => AccountIndicesArgs,
+                TransmuteBatch => TransmuteBatchArgs,
+            }
+        ),
+        Encoding::Packed => decode_packed!(tag, data, {
+            Mint => MintArgs,
+            Burn => BurnArgs,
+            Transfer => TransferArgs,
+            Noop => NoopArgs,
+            InitializeAccount => PackedInitializeAccountArgs,
+            InitializeMint => PackedInitializeMintArgs,
+            Transmute => PackedTransmuteArgs,
+        }),
+    }
+}
+
+/// Decodes a batch's instruction data into a listing of its ops
+pub fn inspect(data: &[u8]) -> Listing {
+    // The program reads aligned args in place, which needs the 8-byte
+    // alignment instruction data has on-chain
+    let mut aligned = vec![0u64; data.len().div_ceil(8)];
+    let aligned =
+        &mut bytemuck::cast_slice_mut::<u64, u8>(&mut aligned)[..data.len()];
+    aligned.copy_from_slice(data);
+
+    let mut listing = Listing {
+        ops: vec![],
+        error: None,
+        trailing: 0,
+    };
+    let mut encoding = Encoding::Aligned;
+    let mut rest: &[u8] = aligned;
+    while !rest.is_empty() {
+        let offset = data.len() - rest.len();
+        if rest.len() < encoding.tag_len() {
+            listing.trailing = rest.len();
+            break;
+        }
+        // Aligned tags are little endian u64s, of which the program only
+        // reads the low byte
+        let tag = rest[0];
+        rest = &rest[encoding.tag_len()..];
+
+        match decode_op(encoding, tag, &mut rest) {
+            Some((tag, Ok(args))) => {
+                listing.ops.push(DecodedOp {
+                    offset,
+                    encoding,
+                    tag,
+                    args,
+                });
+                encoding = encoding.after(tag);
+            }
+            Some((tag, Err(_))) => {
+                listing.error = Some(InspectError::Truncated {
+                    offset,
+                    encoding,
+                    tag,
+                });
+                break;
+            }
+            None => {
+                listing.error = Some(InspectError::UnknownTag {
+                    offset,
+                    encoding,
+                    tag,
+                });
+                break;
+            }
+        }
+    }
+    listing
+}
+
+/// The accounts an op expects, in order. `..` is where optional accounts go,
+/// and ops that create or pay for accounts end with the trailing config,
+/// system program, and payer.
+pub const fn account_roles(tag: Tag) -> &'static str {
+    match tag {
+        Tag::InitializeConfig => "[.. config, system_program, payer]",
+        Tag::InitializeMint => "[mint, .. config, system_program, payer]",
+        Tag::InitializeAccount => {
+            "[token_account, owner if owner_must_sign, .. config, \
+             system_program, payer]"
+        }
+        Tag::InitializeVault => {
+            "[tokenkeg_mint, tokenkeg_vault, tokenkeg_program, vault_info, \
+             nanotoken_mint, .. config, system_program, payer]"
+        }
+        Tag::Mint => "[to, mint, authority, ..]",
+        Tag::Burn => "[from, mint, owner, ..]",
+        Tag::Transfer => "[from, to, authority, ..]",
+        Tag::Transmute => {
+            "[from, to, owner, tokenkeg_mint, nanotoken_mint, vault_info, \
+             tokenkeg_vault, tokenkeg_program, .. config, system_program, \
+             payer]"
+        }
+        Tag::SetAuthority => "[mint, authority, ..]",
+        Tag::WithdrawExcessLamports => {
+            "[account, authority, destination, .. config, system_program, \
+             payer]"
+        }
+        Tag::ProposeTransfer => {
+            "[intent, from, authority, to, .. config, system_program, payer]"
+        }
+        Tag::ApproveTransfer => "[intent, approver, ..]",
+        Tag::ExecuteTransfer => "[intent, from, to, ..]",
+        Tag::GetBalances => "[token_accounts.., ..]",
+        Tag::Approve => "[token_account, owner, ..]",
+        Tag::Revoke => "[token_account, owner, ..]",
+        Tag::FreezeAccount => "[token_account, mint, freeze_authority, ..]",
+        Tag::ThawAccount => "[token_account, mint, freeze_authority, ..]",
+        Tag::BatchTransfer => "[from, owner, to.., ..]",
+        Tag::TransferAll => "[from, to, authority, ..]",
+        Tag::BurnChecked => "[from, mint, owner, ..]",
+        Tag::WrapSol => {
+            "[token_account, funder, .. config, system_program, payer]"
+        }
+        Tag::UnwrapSol => "[token_account, owner, destination, ..]",
+        Tag::SyncNative => "[token_account, ..]",
+        Tag::InitializeMetadata => {
+            "[metadata, mint, mint_authority, .. config, system_program, \
+             payer]"
+        }
+        Tag::UpdateMetadata => "[metadata, mint, mint_authority, ..]",
+        Tag::WithdrawWithheldFees => {
+            "[mint, fee_authority, destination, sources.., ..]"
+        }
+        Tag::CloseMint => "[mint, authority, destination, ..]",
+        Tag::SetMemoRequired => "[token_account, owner, ..]",
+        Tag::CreateAssociatedAccount => {
+            "[token_account, .. config, system_program, payer]"
+        }
+        Tag::PermitTransfer => "[from, to, instructions_sysvar, ..]",
+        Tag::Pause => "[mint, pause_authority, ..]",
+        Tag::Unpause => "[mint, pause_authority, ..]",
+        Tag::Clawback => "[from, to, mint, clawback_authority, ..]",
+        Tag::SetGcConfig => "[authority, .. config, system_program, payer]",
+        Tag::GarbageCollect => {
+            "[token_account, rent_payer, collector, .. config, \
+             system_program, payer]"
+        }
+        Tag::MigrateAccount => "[account, .. config, system_program, payer]",
+        Tag::InitializeMintWithMetadata => {
+            "[mint, metadata, .. config, system_program, payer]"
+        }
+        Tag::AccountIndices => "[] (picks the next op's accounts by index)",
+        Tag::Realloc => {
+            "[token_account, owner, .. config, system_program, payer]"
+        }
+        Tag::SetConfigAuthority => {
+            "[authority, .. config, system_program, payer]"
+        }
+        Tag::AcceptConfigAuthority => {
+            "[new_authority, .. config, system_program, payer]"
+        }
+        Tag::InitializeMintPda => "[mint, .. config, system_program, payer]",
+        Tag::GetAccountDataSize => "[]",
+        Tag::TransmuteBatch => "[Transmute accounts for each entry..]",
+        Tag::InitializeList => {
+            "[list, mint, mint_authority, .. config, system_program, payer]"
+        }
+        Tag::AddToList => {
+            "[list, list_authority, .. config, system_program, payer]"
+        }
+        Tag::RemoveFromList => "[list, list_authority, ..]",
+        Tag::SetRoyalty => "[mint, mint_authority, recipient, ..]",
+        Tag::SetRentDestination => {
+            "[authority, .. config, system_program, payer]"
+        }
+        Tag::Noop => "[]",
+        Tag::Timelock => "[token_account, owner, ..]",
+        Tag::SetFeature => "[authority, .. config, system_program, payer]",
+        Tag::Packed => "[]",
+        Tag::MigrateState => {
+            "[account, authority, .. config, system_program, payer]"
+        }
+        Tag::CancelTransfer => "[intent, proposer, destination, ..]",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encode::{op_data, packed_mode_data, packed_op_data};
+
+    #[test]
+    fn lists_aligned_and_packed_ops() {
+        let mut data = op_data(Tag::Mint, &MintArgs { amount: 7 });
+        data.extend(packed_mode_data());
+        data.extend(packed_op_data(Tag::Transfer, &TransferArgs { amount: 3 }));
+        data.push(Tag::SetFeature as u8);
+
+        let listing = inspect(&data);
+        let tags: Vec<_> = listing
+            .ops
+            .iter()
+            .map(|op| op.tag)
+            .collect();
+        assert_eq!(tags, [Tag::Mint, Tag::Packed, Tag::Transfer]);
+        assert_eq!(listing.ops[2].offset, 24);
+        assert_eq!(listing.ops[2].encoding, Encoding::Packed);
+        assert_eq!(listing.ops[2].args, "TransferArgs { amount: 3 }");
+        assert_eq!(
+            listing.error,
+            Some(InspectError::UnknownTag {
+                offset: 33,
+                encoding: Encoding::Packed,
+                tag: Tag::SetFeature as u8,
+            })
+        );
+    }
+}
@@ -0,0 +1,18 @@
+//! Off-chain nanotoken layouts without the program: account decoding, address
+//! derivation, and instruction encoding and inspection.
+//!
+//! Everything here is plain Rust over the program's own types, so it builds
+//! for `wasm32-unknown-unknown` as well as native targets. With the `wasm`
+//! feature, [wasm] exports the same via wasm-bindgen.
+
+pub mod decode;
+pub mod encode;
+pub mod inspect;
+pub mod pda;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use nanotoken::{
+    consts, ix, offsets, ListMode, Mint, MintList, MintMetadata, ProgramConfig,
+    TokenAccount, TransferIntent, VaultInfo, ID,
+};
@@ -0,0 +1,45 @@
+//! Program derived addresses and their bumps
+
+use nanotoken::{
+    Mint, MintList, MintMetadata, ProgramConfig, TokenAccount, TransferIntent,
+    VaultInfo,
+};
+use solana_program::pubkey::Pubkey;
+
+/// The program config
+pub fn config() -> (Pubkey, u8) {
+    ProgramConfig::address()
+}
+
+/// The ["mint", mint_index] mint
+pub fn mint(mint_index: u64) -> (Pubkey, u8) {
+    Mint::address(mint_index)
+}
+
+/// The canonical (owner, mint_index) token account
+pub fn token_account(owner: &Pubkey, mint_index: u64) -> (Pubkey, u8) {
+    TokenAccount::address(mint_index, owner)
+}
+
+/// The vault info of a tokenkeg mint
+pub fn vault_info(tokenkeg_mint: &Pubkey) -> (Pubkey, u8) {
+    VaultInfo::info(tokenkeg_mint)
+}
+
+/// The tokenkeg vault of a tokenkeg mint
+pub fn vault(tokenkeg_mint: &Pubkey) -> (Pubkey, u8) {
+    VaultInfo::vault(tokenkeg_mint)
+}
+
+pub fn metadata(mint: &Pubkey) -> (Pubkey, u8) {
+    MintMetadata::address(mint)
+}
+
+pub fn list(mint: &Pubkey) -> (Pubkey, u8) {
+    MintList::address(mint)
+}
+
+/// The `nonce`th transfer intent proposed by `from`
+pub fn transfer_intent(from: &Pubkey, nonce: u64) -> (Pubkey, u8) {
+    TransferIntent::address(from, nonce)
+}
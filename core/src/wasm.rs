@@ -0,0 +1,82 @@
+//! wasm-bindgen exports, behind the `wasm` feature. Pubkeys are passed as 32
+//! bytes, accounts and ops as json.
+
+use nanotoken::ix::ProgramInstruction;
+use solana_program::pubkey::Pubkey;
+use wasm_bindgen::prelude::*;
+
+use crate::{decode, encode, pda};
+
+#[wasm_bindgen(getter_with_clone)]
+pub struct Pda {
+    pub address: Vec<u8>,
+    pub bump: u8,
+}
+
+impl From<(Pubkey, u8)> for Pda {
+    fn from((address, bump): (Pubkey, u8)) -> Self {
+        Pda {
+            address: address.to_bytes().to_vec(),
+            bump,
+        }
+    }
+}
+
+fn pubkey(bytes: &[u8]) -> Result<Pubkey, JsError> {
+    Pubkey::try_from(bytes).map_err(|_| JsError::new("pubkey must be 32 bytes"))
+}
+
+#[wasm_bindgen(js_name = configAddress)]
+pub fn config_address() -> Pda {
+    pda::config().into()
+}
+
+#[wasm_bindgen(js_name = mintAddress)]
+pub fn mint_address(mint_index: u64) -> Pda {
+    pda::mint(mint_index).into()
+}
+
+#[wasm_bindgen(js_name = tokenAccountAddress)]
+pub fn token_account_address(
+    owner: &[u8],
+    mint_index: u64,
+) -> Result<Pda, JsError> {
+    Ok(pda::token_account(&pubkey(owner)?, mint_index).into())
+}
+
+#[wasm_bindgen(js_name = vaultInfoAddress)]
+pub fn vault_info_address(tokenkeg_mint: &[u8]) -> Result<Pda, JsError> {
+    Ok(pda::vault_info(&pubkey(tokenkeg_mint)?).into())
+}
+
+#[wasm_bindgen(js_name = vaultAddress)]
+pub fn vault_address(tokenkeg_mint: &[u8]) -> Result<Pda, JsError> {
+    Ok(pda::vault(&pubkey(tokenkeg_mint)?).into())
+}
+
+#[wasm_bindgen(js_name = metadataAddress)]
+pub fn metadata_address(mint: &[u8]) -> Result<Pda, JsError> {
+    Ok(pda::metadata(&pubkey(mint)?).into())
+}
+
+#[wasm_bindgen(js_name = listAddress)]
+pub fn list_address(mint: &[u8]) -> Result<Pda, JsError> {
+    Ok(pda::list(&pubkey(mint)?).into())
+}
+
+/// Decodes any nanotoken account to json, e.g. `{"Token":{"owner":..}}`
+#[wasm_bindgen(js_name = decodeAccount)]
+pub fn decode_account(data: &[u8]) -> Result<String, JsError> {
+    let account = decode::decode_account(data)
+        .map_err(|e| JsError::new(&e.to_string()))?;
+    serde_json::to_string(&account).map_err(|e| JsError::new(&e.to_string()))
+}
+
+/// Encodes one op from json, e.g. `{"Transfer":{"amount":5}}`. Trailing
+/// data (e.g. AddToList entries) must be appended by the caller.
+#[wasm_bindgen(js_name = encodeOp)]
+pub fn encode_op(op: &str) -> Result<Vec<u8>, JsError> {
+    let op: ProgramInstruction =
+        serde_json::from_str(op).map_err(|e| JsError::new(&e.to_string()))?;
+    Ok(encode::instruction_data(&op))
+}
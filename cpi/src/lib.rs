@@ -0,0 +1,222 @@
+//! Zero-copy CPI helpers for programs that call nanotoken.
+//!
+//! Each helper writes the op's `[tag as u64][args]` data on the stack and
+//! invokes nanotoken via `sol_invoke_signed_c` with the caller's
+//! [NoStdAccountInfo]s, the same way nanotoken CPIs the token program in
+//! transmute. The `_signed` variants sign with the caller's pda seeds, for
+//! pda owners, authorities, and payers.
+//!
+//! ```ignore
+//! use nanotoken_cpi as cpi;
+//!
+//! cpi::transfer(from, to, owner, amount)?;
+//! cpi::transfer_signed(from, to, vault, amount, &[&[b"vault", &[bump]]])?;
+//! ```
+//!
+//! The nanotoken program account must be passed to the calling instruction.
+//! Transfers on mints with transfer fees, royalties, or mint lists need their
+//! optional accounts; use [invoke_signed] with the full account list.
+#![no_std]
+
+use core::mem::size_of;
+
+use bytemuck::Pod;
+use nanotoken::{
+    ix::{InitializeAccountArgs, MintArgs, Tag, TransferArgs},
+    solana_nostd_entrypoint::{
+        AccountInfoC, AccountMetaC, InstructionC, NoStdAccountInfo,
+    },
+};
+use solana_program::{entrypoint::ProgramResult, pubkey::Pubkey};
+
+pub use nanotoken::ID;
+
+/// Writes `[tag as u64][args]` into `data`, which must be exactly
+/// `8 + size_of::<T>()` bytes long.
+#[inline(always)]
+fn write_op<T: Pod>(data: &mut [u8], tag: Tag, args: &T) {
+    data[..8].copy_from_slice(&(tag as u64).to_le_bytes());
+    data[8..].copy_from_slice(bytemuck::bytes_of(args));
+}
+
+/// Invokes nanotoken with raw instruction data, signing with
+/// `signers_seeds`. `metas` and `infos` must describe the same accounts in
+/// the same order.
+#[inline(always)]
+pub fn invoke_signed(
+    data: &[u8],
+    metas: &[AccountMetaC],
+    infos: &[AccountInfoC],
+    signers_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let ix = InstructionC {
+        program_id: &ID,
+        accounts: metas.as_ptr(),
+        accounts_len: metas.len() as u64,
+        data: data.as_ptr(),
+        data_len: data.len() as u64,
+    };
+    #[cfg(target_os = "solana")]
+    {
+        let result = unsafe {
+            solana_program::syscalls::sol_invoke_signed_c(
+                &ix as *const InstructionC as *const u8,
+                infos.as_ptr() as *const u8,
+                infos.len() as u64,
+                signers_seeds.as_ptr() as *const u8,
+                signers_seeds.len() as u64,
+            )
+        };
+        match result {
+            solana_program::entrypoint::SUCCESS => Ok(()),
+            _ => Err(result.into()),
+        }
+    }
+    #[cfg(not(target_os = "solana"))]
+    {
+        core::hint::black_box((&ix, infos, signers_seeds));
+        Ok(())
+    }
+}
+
+/// Transfers `amount` from `from` to `to`. `authority` is the owner or
+/// delegate of `from` and must sign.
+#[inline(always)]
+pub fn transfer(
+    from: &NoStdAccountInfo,
+    to: &NoStdAccountInfo,
+    authority: &NoStdAccountInfo,
+    amount: u64,
+) -> ProgramResult {
+    transfer_signed(from, to, authority, amount, &[])
+}
+
+/// [transfer] where `authority` is a pda of the calling program
+#[inline(always)]
+pub fn transfer_signed(
+    from: &NoStdAccountInfo,
+    to: &NoStdAccountInfo,
+    authority: &NoStdAccountInfo,
+    amount: u64,
+    signers_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let mut data = [0; 8 + size_of::<TransferArgs>()];
+    write_op(&mut data, Tag::Transfer, &TransferArgs { amount });
+    invoke_signed(
+        &data,
+        &[
+            from.to_meta_c(),
+            to.to_meta_c(),
+            authority.to_meta_c_signer(),
+        ],
+        &[from.to_info_c(), to.to_info_c(), authority.to_info_c()],
+        signers_seeds,
+    )
+}
+
+/// Mints `amount` to `to`. `authority` is the mint authority and must sign.
+#[inline(always)]
+pub fn mint_to(
+    to: &NoStdAccountInfo,
+    mint: &NoStdAccountInfo,
+    authority: &NoStdAccountInfo,
+    amount: u64,
+) -> ProgramResult {
+    mint_to_signed(to, mint, authority, amount, &[])
+}
+
+/// [mint_to] where `authority` is a pda of the calling program
+#[inline(always)]
+pub fn mint_to_signed(
+    to: &NoStdAccountInfo,
+    mint: &NoStdAccountInfo,
+    authority: &NoStdAccountInfo,
+    amount: u64,
+    signers_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let mut data = [0; 8 + size_of::<MintArgs>()];
+    write_op(&mut data, Tag::Mint, &MintArgs { amount });
+    invoke_signed(
+        &data,
+        &[
+            to.to_meta_c(),
+            mint.to_meta_c(),
+            authority.to_meta_c_signer(),
+        ],
+        &[to.to_info_c(), mint.to_info_c(), authority.to_info_c()],
+        signers_seeds,
+    )
+}
+
+/// Creates the (owner, mint) token account, or a keypair token account if
+/// `token_account` signs. `bump` is the (owner, mint) pda bump, which is
+/// cheaper to pass in than to derive on-chain; it is ignored for keypair
+/// accounts. `payer` must sign and funds the account's rent.
+#[allow(clippy::too_many_arguments)]
+#[inline(always)]
+pub fn initialize_account(
+    token_account: &NoStdAccountInfo,
+    config: &NoStdAccountInfo,
+    system_program: &NoStdAccountInfo,
+    payer: &NoStdAccountInfo,
+    owner: &Pubkey,
+    mint_index: u64,
+    bump: u8,
+    immutable_owner: bool,
+) -> ProgramResult {
+    initialize_account_signed(
+        token_account,
+        config,
+        system_program,
+        payer,
+        owner,
+        mint_index,
+        bump,
+        immutable_owner,
+        &[],
+    )
+}
+
+/// [initialize_account] where `payer` is a pda of the calling program
+#[allow(clippy::too_many_arguments)]
+#[inline(always)]
+pub fn initialize_account_signed(
+    token_account: &NoStdAccountInfo,
+    config: &NoStdAccountInfo,
+    system_program: &NoStdAccountInfo,
+    payer: &NoStdAccountInfo,
+    owner: &Pubkey,
+    mint_index: u64,
+    bump: u8,
+    immutable_owner: bool,
+    signers_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let mut data = [0; 8 + size_of::<InitializeAccountArgs>()];
+    write_op(
+        &mut data,
+        Tag::InitializeAccount,
+        &InitializeAccountArgs {
+            owner: *owner,
+            mint: mint_index,
+            bump: bump as u64,
+            immutable_owner: immutable_owner as u64,
+            owner_must_sign: 0,
+        },
+    );
+    invoke_signed(
+        &data,
+        &[
+            token_account.to_meta_c(),
+            config.to_meta_c(),
+            system_program.to_meta_c(),
+            payer.to_meta_c_signer(),
+        ],
+        &[
+            token_account.to_info_c(),
+            config.to_info_c(),
+            system_program.to_info_c(),
+            payer.to_info_c(),
+        ],
+        signers_seeds,
+    )
+}
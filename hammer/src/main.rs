@@ -12,10 +12,16 @@ use std::{
 use clap::{Parser, Subcommand};
 use indicatif::{ProgressBar, ProgressStyle};
 use nanotoken::{
+    consts::CONFIG_ACCOUNT,
     ix::{
-        InitializeAccountArgs, InitializeMintArgs, MintArgs, Tag, TransferArgs,
+        InitConfigArgs, InitializeAccountArgs, InitializeMintArgs, MintArgs,
+        NoopArgs, Tag, TransferArgs,
     },
-    Mint, ProgramConfig, TokenAccount,
+    Mint, TokenAccount,
+};
+use nanotoken_client::{
+    compute,
+    priority_fee::{PercentileFee, PriorityFeeStrategy, StaticFee},
 };
 use solana_client::{
     nonblocking::{rpc_client::RpcClient, tpu_client::TpuClient}, tpu_client::TpuClientConfig
@@ -63,6 +69,19 @@ enum Commands {
 
         #[clap(long, default_value_t = 1)]
         num_pairs: usize,
+
+        /// Fixed compute unit price in micro-lamports
+        #[clap(long, conflicts_with = "fee_percentile")]
+        priority_fee: Option<u64>,
+
+        /// Pay this percentile of recent prices for the transfer accounts,
+        /// refreshed with the blockhash
+        #[clap(long, value_parser = clap::value_parser!(u8).range(0..=100))]
+        fee_percentile: Option<u8>,
+
+        /// Cap on the percentile price in micro-lamports
+        #[clap(long, default_value_t = 100_000)]
+        max_priority_fee: u64,
     },
 
     /// Single Transfer
@@ -72,6 +91,56 @@ enum Commands {
     TransferCost,
 }
 
+/// Two transfers back and forth between a pair of token accounts, and a noop
+/// op whose `nonce` makes each tx unique
+fn transfer_pair_instruction(
+    (chad1, chad1_ta): (Pubkey, Pubkey),
+    (chad2, chad2_ta): (Pubkey, Pubkey),
+    nonce: u64,
+) -> Instruction {
+    let num_transfers = 2;
+    let mut ix_data =
+        vec![0; num_transfers * (8 + TransferArgs::size()) + 8 + NoopArgs::size()];
+    let mut accounts = vec![];
+    for n in 0..num_transfers {
+        let disc_offset = 8 * n + n * TransferArgs::size();
+        ix_data[disc_offset..8 + disc_offset]
+            .copy_from_slice(&(Tag::Transfer as u64).to_le_bytes());
+        let TransferArgs { amount } = bytemuck::try_from_bytes_mut(
+            &mut ix_data
+                [disc_offset + 8..disc_offset + 8 + TransferArgs::size()],
+        )
+        .unwrap();
+        *amount = 1;
+
+        if n % 2 == 0 {
+            accounts.extend([
+                AccountMeta::new(chad1_ta, false),
+                AccountMeta::new(chad2_ta, false),
+                AccountMeta::new_readonly(chad1, true),
+            ])
+        } else {
+            accounts.extend([
+                AccountMeta::new(chad2_ta, false),
+                AccountMeta::new(chad1_ta, false),
+                AccountMeta::new_readonly(chad2, true),
+            ])
+        }
+    }
+
+    // this acts as nonce
+    let noop_offset = num_transfers * (8 + TransferArgs::size());
+    ix_data[noop_offset..noop_offset + 8]
+        .copy_from_slice(&(Tag::Noop as u64).to_le_bytes());
+    ix_data[noop_offset + 8..].copy_from_slice(&nonce.to_le_bytes());
+
+    Instruction {
+        program_id: nanotoken::ID,
+        accounts,
+        data: ix_data,
+    }
+}
+
 fn main() -> Result<(), Box<dyn Error>> {
     const RPC_ENDPOINT: &'static str = "http://localhost:8899";
     const PS_ENDPOINT: &'static str = "ws://localhost:8900/";
@@ -99,8 +168,6 @@ fn main() -> Result<(), Box<dyn Error>> {
             let cargo_manifest_path =
                 PathBuf::from(&env::var("CARGO_MANIFEST_DIR")?);
             let cargo_workspace_path = cargo_manifest_path.parent().unwrap();
-            let config_keypair =
-                read_keypair_file(cargo_workspace_path.join("config.json"))?;
             let payer: &_ = Box::leak(Box::new(read_keypair_file(
                 cargo_workspace_path.join("payer.json"),
             )?));
@@ -117,20 +184,7 @@ fn main() -> Result<(), Box<dyn Error>> {
 
             match args.command {
                 Commands::Initialize => {
-                    let config = config_keypair.pubkey();
-                    let create_config = system_transaction::create_account(
-                        &payer,
-                        &config_keypair,
-                        client.get_latest_blockhash().await?,
-                        Rent::default().minimum_balance(ProgramConfig::space()),
-                        ProgramConfig::space() as u64,
-                        &nanotoken::ID,
-                    );
-                    client
-                        .send_and_confirm_transaction(&create_config)
-                        .await
-                        .unwrap();
-                    println!("system_transaction::create_account config");
+                    let config = CONFIG_ACCOUNT;
 
                     // Initialize mint
                     let create_mint = system_transaction::create_account(
@@ -148,15 +202,20 @@ fn main() -> Result<(), Box<dyn Error>> {
                     println!("system_transaction::create_account mint");
 
                     // Initialize config and mint
-                    let ix_data = (Tag::InitializeConfig as u64)
+                    let mut ix_data = (Tag::InitializeConfig as u64)
                         .to_le_bytes()
                         .to_vec();
+                    ix_data.extend_from_slice(bytemuck::bytes_of(
+                        &InitConfigArgs {
+                            authority: payer.pubkey(),
+                        },
+                    ));
 
                     let accounts = vec![
                         // init config
                         AccountMeta::new(config, false),
                         AccountMeta::new_readonly(system_program::ID, false),
-                        AccountMeta::new_readonly(payer.pubkey(), false),
+                        AccountMeta::new(payer.pubkey(), true),
                     ];
                     let instruction = Instruction {
                         program_id: nanotoken::ID,
@@ -189,6 +248,11 @@ fn main() -> Result<(), Box<dyn Error>> {
                     );
                     ix_data.extend_from_slice(payer.pubkey().as_ref());
                     ix_data.extend_from_slice(&[0; 8]); // decimals
+                    ix_data.extend_from_slice(&[0; 32]); // no freeze authority
+                    ix_data.extend_from_slice(&[0; 48]); // no transfer fee
+                    ix_data.extend_from_slice(&[0; 32]); // no pause authority
+                    ix_data.extend_from_slice(&[0; 8]); // no supply cap
+                    ix_data.extend_from_slice(&[0; 32]); // no clawback authority
 
                     let accounts = vec![
                         // init mint
@@ -234,6 +298,8 @@ fn main() -> Result<(), Box<dyn Error>> {
                                 owner: chad1.pubkey(),
                                 mint: 0,
                                 bump: chad1_ta_bump as u64,
+                                immutable_owner: 0,
+                                owner_must_sign: 0,
                             },
                         ));
 
@@ -246,6 +312,8 @@ fn main() -> Result<(), Box<dyn Error>> {
                                 owner: chad2.pubkey(),
                                 mint: 0,
                                 bump: chad2_ta_bump as u64,
+                                immutable_owner: 0,
+                                owner_must_sign: 0,
                             },
                         ));
 
@@ -269,7 +337,7 @@ fn main() -> Result<(), Box<dyn Error>> {
                         AccountMeta::new(mint_keypair.pubkey(), false),
                         AccountMeta::new_readonly(payer.pubkey(), true),
                         // remainder
-                        AccountMeta::new(config_keypair.pubkey(), false),
+                        AccountMeta::new(CONFIG_ACCOUNT, false),
                         AccountMeta::new_readonly(system_program::ID, false),
                         AccountMeta::new(payer.pubkey(), true),
                     ];
@@ -292,7 +360,14 @@ fn main() -> Result<(), Box<dyn Error>> {
 
 
                 }
-                Commands::Hammer { tps, time, num_pairs } => {
+                Commands::Hammer {
+                    tps,
+                    time,
+                    num_pairs,
+                    priority_fee,
+                    fee_percentile,
+                    max_priority_fee,
+                } => {
                     struct User {
                         kp: &'static Keypair,
                         ta: Pubkey,
@@ -322,6 +397,8 @@ fn main() -> Result<(), Box<dyn Error>> {
                                             owner: user1.pubkey(),
                                             mint: 0,
                                             bump: user1_ta_bump as u64,
+                                            immutable_owner: 0,
+                                            owner_must_sign: 0,
                                         },
                                     ));
 
@@ -334,6 +411,8 @@ fn main() -> Result<(), Box<dyn Error>> {
                                             owner: user2.pubkey(),
                                             mint: 0,
                                             bump: user2_ta_bump as u64,
+                                            immutable_owner: 0,
+                                            owner_must_sign: 0,
                                         },
                                     ));
                                     // Mint to user 1
@@ -358,7 +437,7 @@ fn main() -> Result<(), Box<dyn Error>> {
                                     AccountMeta::new(mint_keypair.pubkey(), false),
                                     AccountMeta::new_readonly(payer.pubkey(), true),
                                     // remainder
-                                    AccountMeta::new(config_keypair.pubkey(), false),
+                                    AccountMeta::new(CONFIG_ACCOUNT, false),
                                     AccountMeta::new_readonly(system_program::ID, false),
                                     AccountMeta::new(payer.pubkey(), true),
                                 ];
@@ -439,6 +518,48 @@ fn main() -> Result<(), Box<dyn Error>> {
                     let blockhash: &RwLock<_> = Box::leak(Box::new(RwLock::new(client.get_latest_blockhash().await?)));
                     let mut idx: u32 = 0;
 
+                    // Size the compute unit limit once, every tx has the same
+                    // ops (and at most a compute unit price)
+                    let cu_limit = compute::estimate_compute_units(
+                        &client,
+                        &[
+                            ComputeBudgetInstruction::set_compute_unit_price(1),
+                            transfer_pair_instruction(
+                                (pairs[0][0].kp.pubkey(), pairs[0][0].ta),
+                                (pairs[0][1].kp.pubkey(), pairs[0][1].ta),
+                                0,
+                            ),
+                        ],
+                        &pairs[0][0].kp.pubkey(),
+                    )
+                    .await?
+                    .limit;
+
+                    // Price every tx the same way, refreshing percentile
+                    // samples along with the blockhash
+                    let writable: &'static [Pubkey] = pairs
+                        .iter()
+                        .flat_map(|pair| [pair[0].ta, pair[1].ta])
+                        .collect::<Vec<_>>()
+                        .leak();
+                    let percentile_fee: Option<&'static PercentileFee> =
+                        fee_percentile.map(|percentile| {
+                            &*Box::leak(Box::new(PercentileFee::new(
+                                percentile,
+                                max_priority_fee,
+                            )))
+                        });
+                    if let Some(fee) = percentile_fee {
+                        fee.refresh(&client, writable).await?;
+                    }
+                    let fee: &'static (dyn PriorityFeeStrategy + Send + Sync) =
+                        match percentile_fee {
+                            Some(fee) => fee,
+                            None => Box::leak(Box::new(StaticFee(
+                                priority_fee.unwrap_or(0),
+                            ))),
+                        };
+
                     // Switch to tpu
                     let client: &'static TpuClient<_, _, _> =
                         Box::leak(Box::new(
@@ -464,6 +585,11 @@ fn main() -> Result<(), Box<dyn Error>> {
                             .rpc_client()
                             .get_new_latest_blockhash(&current).await {
                                 *blockhash.write().unwrap() = bh;
+                            }
+                            if let Some(fee) = percentile_fee {
+                                let _ = fee
+                                    .refresh(client.rpc_client(), writable)
+                                    .await;
                             }});
                         }
 
@@ -473,75 +599,24 @@ fn main() -> Result<(), Box<dyn Error>> {
                         let chad2_ta = pairs[iteration%num_pairs][1].ta;
 
                         tokio::task::spawn(async move {
-                            let num_transfers = 2;
-                            let mut ix_data =
-                                vec![
-                                    0;
-                                    num_transfers * (8 + TransferArgs::size())
-                                ];
-                            let mut accounts = vec![];
-                            for n in 0..num_transfers {
-                                let disc_offset =
-                                    8 * n + n * TransferArgs::size();
-                                ix_data[disc_offset..8 + disc_offset]
-                                    .copy_from_slice(
-                                        &(Tag::Transfer as u64).to_le_bytes(),
-                                    );
-                                let TransferArgs { amount } =
-                                    bytemuck::try_from_bytes_mut(
-                                        &mut ix_data[disc_offset + 8
-                                            ..disc_offset
-                                                + 8
-                                                + TransferArgs::size()],
-                                    )
-                                    .unwrap();
-                                *amount = 1;
-
-                                if n % 2 == 0 {
-                                    accounts.extend([
-                                        AccountMeta::new(chad1_ta, false),
-                                        AccountMeta::new(chad2_ta, false),
-                                        AccountMeta::new_readonly(
-                                            chad1.pubkey(),
-                                            true,
-                                        ),
-                                    ])
-                                } else {
-                                    accounts.extend([
-                                        AccountMeta::new(chad2_ta, false),
-                                        AccountMeta::new(chad1_ta, false),
-                                        AccountMeta::new_readonly(
-                                            chad2.pubkey(),
-                                            true,
-                                        ),
-                                    ])
-                                }
-                            }
-                            let request_cus =
-                            ComputeBudgetInstruction::set_compute_unit_limit(
-                                800,
+                            let instruction = transfer_pair_instruction(
+                                (chad1.pubkey(), chad1_ta),
+                                (chad2.pubkey(), chad2_ta),
+                                idx as u64,
                             );
-                            // this acts as nonce
-                            let ix_account_size = ComputeBudgetInstruction::set_loaded_accounts_data_size_limit(56 * 1024 + (idx % (fetch_every)));
-                            // let noop_nonce_ix = Instruction {
-                            //     program_id: noop_program::ID.into(),
-                            //     accounts: vec![],
-                            //     data: idx.to_le_bytes().to_vec(),
-                            // };
-
-                            let instruction = Instruction {
-                                program_id: nanotoken::ID,
-                                accounts,
-                                data: ix_data,
-                            };
+                            let request_cus =
+                                ComputeBudgetInstruction::set_compute_unit_limit(
+                                    cu_limit,
+                                );
+                            let ix_account_size = ComputeBudgetInstruction::set_loaded_accounts_data_size_limit(56 * 1024);
+                            let mut ixs = vec![request_cus, ix_account_size];
+                            ixs.extend(fee.price_instruction(
+                                std::slice::from_ref(&instruction),
+                            ));
+                            ixs.push(instruction);
                             let transaction =
                                 Transaction::new_signed_with_payer(
-                                    &[
-                                        request_cus, 
-                                        ix_account_size, 
-                                        // noop_nonce_ix, 
-                                        instruction
-                                    ],
+                                    &ixs,
                                     Some(&chad1.pubkey()),
                                     &[&chad1, &chad2],
                                     *blockhash.read().unwrap(),
@@ -626,8 +701,6 @@ fn main() -> Result<(), Box<dyn Error>> {
                         AccountMeta::new_readonly(chad1.pubkey(), true),
                     ]);
 
-                    let request_cus =
-                        ComputeBudgetInstruction::set_compute_unit_limit(600);
                     let ix_account_size = ComputeBudgetInstruction::set_loaded_accounts_data_size_limit(64 * 1024);
 
                     let instruction = Instruction {
@@ -635,6 +708,13 @@ fn main() -> Result<(), Box<dyn Error>> {
                         accounts,
                         data: ix_data,
                     };
+                    let request_cus = compute::estimate_compute_units(
+                        &client,
+                        &[ix_account_size.clone(), instruction.clone()],
+                        &chad1.pubkey(),
+                    )
+                    .await?
+                    .limit_instruction();
                     let transaction = Transaction::new_signed_with_payer(
                         &[request_cus, ix_account_size, instruction],
                         Some(&chad1.pubkey()),
@@ -680,8 +760,6 @@ fn main() -> Result<(), Box<dyn Error>> {
                             ])
                         }
                     }
-                    let request_cus =
-                        ComputeBudgetInstruction::set_compute_unit_limit(650);
                     let ix_account_size = ComputeBudgetInstruction::set_loaded_accounts_data_size_limit(64 * 1024);
                     let noop_nonce_ix = Instruction {
                         program_id: noop_program::ID.into(),
@@ -694,6 +772,13 @@ fn main() -> Result<(), Box<dyn Error>> {
                         accounts,
                         data: ix_data,
                     };
+                    let request_cus = compute::estimate_compute_units(
+                        &client,
+                        &[ix_account_size.clone(), noop_nonce_ix.clone(), instruction.clone()],
+                        &chad1.pubkey(),
+                    )
+                    .await?
+                    .limit_instruction();
                     let transaction = Transaction::new_signed_with_payer(
                         &[request_cus, ix_account_size, noop_nonce_ix, instruction],
                         Some(&chad1.pubkey()),
@@ -740,8 +825,6 @@ fn main() -> Result<(), Box<dyn Error>> {
                             AccountMeta::new_readonly(chad1.pubkey(), true),
                         ])
                 }
-                let request_cus =
-                    ComputeBudgetInstruction::set_compute_unit_limit(650);
                     let ix_account_size = ComputeBudgetInstruction::set_loaded_accounts_data_size_limit(64 * 1024);
                     let noop_nonce_ix = Instruction {
                     program_id: noop_program::ID.into(),
@@ -754,6 +837,13 @@ fn main() -> Result<(), Box<dyn Error>> {
                     accounts,
                     data: ix_data,
                 };
+                let request_cus = compute::estimate_compute_units(
+                    &client,
+                    &[ix_account_size.clone(), noop_nonce_ix.clone(), instruction.clone()],
+                    &chad1.pubkey(),
+                )
+                .await?
+                .limit_instruction();
                 let transaction = Transaction::new_signed_with_payer(
                     &[request_cus,ix_account_size, noop_nonce_ix, instruction],
                     Some(&chad1.pubkey()),
@@ -0,0 +1,59 @@
+-- Amounts are u64 and stored as NUMERIC(20, 0), which BIGINT can't hold.
+-- Addresses are base58.
+
+CREATE TABLE IF NOT EXISTS mints (
+    address TEXT PRIMARY KEY,
+    mint_index BIGINT NOT NULL UNIQUE,
+    authority TEXT,
+    supply NUMERIC(20, 0) NOT NULL,
+    decimals SMALLINT NOT NULL,
+    slot BIGINT NOT NULL
+);
+
+-- Closed accounts are kept with a zero balance
+CREATE TABLE IF NOT EXISTS token_accounts (
+    address TEXT PRIMARY KEY,
+    owner TEXT NOT NULL,
+    mint_index BIGINT NOT NULL,
+    balance NUMERIC(20, 0) NOT NULL,
+    closed BOOLEAN NOT NULL DEFAULT FALSE,
+    slot BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS token_accounts_holders
+    ON token_accounts (mint_index, balance DESC)
+    WHERE balance > 0;
+CREATE INDEX IF NOT EXISTS token_accounts_owner ON token_accounts (owner);
+
+-- A row per slot in which the indexer saw a mint's supply change
+CREATE TABLE IF NOT EXISTS supply_history (
+    mint_index BIGINT NOT NULL,
+    slot BIGINT NOT NULL,
+    supply NUMERIC(20, 0) NOT NULL,
+    PRIMARY KEY (mint_index, slot)
+);
+
+-- Transfer, mint, and burn events. Mints have no source and burns no
+-- destination; `mint` is set for those, and is the mint address.
+CREATE TABLE IF NOT EXISTS transfers (
+    signature TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    slot BIGINT NOT NULL,
+    block_time BIGINT,
+    kind TEXT NOT NULL CHECK (kind IN ('transfer', 'mint', 'burn')),
+    source TEXT,
+    destination TEXT,
+    mint TEXT,
+    amount NUMERIC(20, 0) NOT NULL,
+    PRIMARY KEY (signature, event_index)
+);
+
+CREATE INDEX IF NOT EXISTS transfers_source ON transfers (source, slot DESC);
+CREATE INDEX IF NOT EXISTS transfers_destination
+    ON transfers (destination, slot DESC);
+
+-- Newest transaction signature whose events have been ingested
+CREATE TABLE IF NOT EXISTS cursor (
+    id BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK (id),
+    signature TEXT NOT NULL
+);
@@ -0,0 +1,117 @@
+//! Read-only HTTP API over the indexed tables.
+//!
+//! - `GET /mints/:mint_index/holders?limit&offset`
+//! - `GET /mints/:mint_index/supply?limit`
+//! - `GET /accounts/:address`
+//! - `GET /accounts/:address/transfers?limit&offset`
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use serde::Deserialize;
+
+use crate::db::{AccountRow, Db, Holder, Supply, TransferRow};
+
+/// Largest page any route returns
+pub const MAX_LIMIT: i64 = 1_000;
+
+pub fn router(db: Arc<Db>) -> Router {
+    Router::new()
+        .route("/mints/:mint_index/holders", get(holders))
+        .route("/mints/:mint_index/supply", get(supply))
+        .route("/accounts/:address", get(account))
+        .route("/accounts/:address/transfers", get(transfers))
+        .with_state(db)
+}
+
+#[derive(Debug, Deserialize)]
+struct Page {
+    limit: Option<i64>,
+    offset: Option<i64>,
+}
+
+impl Page {
+    fn limit(&self) -> i64 {
+        self.limit
+            .unwrap_or(100)
+            .clamp(0, MAX_LIMIT)
+    }
+
+    fn offset(&self) -> i64 {
+        self.offset.unwrap_or(0).max(0)
+    }
+}
+
+enum ApiError {
+    NotFound,
+    Db(tokio_postgres::Error),
+}
+
+impl From<tokio_postgres::Error> for ApiError {
+    fn from(err: tokio_postgres::Error) -> Self {
+        ApiError::Db(err)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        match self {
+            ApiError::NotFound => StatusCode::NOT_FOUND.into_response(),
+            ApiError::Db(err) => {
+                eprintln!("query failed: {err}");
+                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            }
+        }
+    }
+}
+
+type ApiResult<T> = Result<Json<T>, ApiError>;
+
+async fn holders(
+    State(db): State<Arc<Db>>,
+    Path(mint_index): Path<u64>,
+    Query(page): Query<Page>,
+) -> ApiResult<Vec<Holder>> {
+    Ok(Json(
+        db.holders(mint_index, page.limit(), page.offset())
+            .await?,
+    ))
+}
+
+async fn supply(
+    State(db): State<Arc<Db>>,
+    Path(mint_index): Path<u64>,
+    Query(page): Query<Page>,
+) -> ApiResult<Supply> {
+    db.supply(mint_index, page.limit())
+        .await?
+        .map(Json)
+        .ok_or(ApiError::NotFound)
+}
+
+async fn account(
+    State(db): State<Arc<Db>>,
+    Path(address): Path<String>,
+) -> ApiResult<AccountRow> {
+    db.account(&address)
+        .await?
+        .map(Json)
+        .ok_or(ApiError::NotFound)
+}
+
+async fn transfers(
+    State(db): State<Arc<Db>>,
+    Path(address): Path<String>,
+    Query(page): Query<Page>,
+) -> ApiResult<Vec<TransferRow>> {
+    Ok(Json(
+        db.transfers(&address, page.limit(), page.offset())
+            .await?,
+    ))
+}
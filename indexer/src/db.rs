@@ -0,0 +1,371 @@
+//! Postgres storage, see `migrations/` for the schema.
+//!
+//! u64 amounts go through NUMERIC as text, since BIGINT can't hold them.
+//! Account writes are guarded by slot, so updates may arrive out of order.
+
+use nanotoken::{events::Event, Mint, TokenAccount};
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+use tokio_postgres::{Client, Error, NoTls, Row};
+
+const MIGRATIONS: &[&str] = &[include_str!("../migrations/0001_init.sql")];
+
+pub struct Db {
+    client: Client,
+}
+
+impl Db {
+    /// Connects and drives the connection in the background
+    pub async fn connect(url: &str) -> Result<Self, Error> {
+        let (client, connection) = tokio_postgres::connect(url, NoTls).await?;
+        tokio::spawn(async move {
+            if let Err(err) = connection.await {
+                eprintln!("postgres connection closed: {err}");
+            }
+        });
+        Ok(Self { client })
+    }
+
+    /// Creates any missing tables. Migrations are idempotent.
+    pub async fn migrate(&self) -> Result<(), Error> {
+        for migration in MIGRATIONS {
+            self.client
+                .batch_execute(migration)
+                .await?;
+        }
+        Ok(())
+    }
+
+    pub async fn upsert_token_account(
+        &self,
+        address: &Pubkey,
+        account: &TokenAccount,
+        slot: u64,
+    ) -> Result<(), Error> {
+        self.client
+            .execute(
+                "INSERT INTO token_accounts
+                    (address, owner, mint_index, balance, closed, slot)
+                 VALUES ($1, $2, $3, $4::TEXT::NUMERIC, FALSE, $5)
+                 ON CONFLICT (address) DO UPDATE SET
+                    owner = EXCLUDED.owner,
+                    mint_index = EXCLUDED.mint_index,
+                    balance = EXCLUDED.balance,
+                    closed = FALSE,
+                    slot = EXCLUDED.slot
+                 WHERE token_accounts.slot <= EXCLUDED.slot",
+                &[
+                    &address.to_string(),
+                    &account.owner.to_string(),
+                    &(account.mint as i64),
+                    &account.balance.to_string(),
+                    &(slot as i64),
+                ],
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Zeroes the balance of a closed token account. Does nothing for other
+    /// addresses.
+    pub async fn close_token_account(
+        &self,
+        address: &Pubkey,
+        slot: u64,
+    ) -> Result<(), Error> {
+        self.client
+            .execute(
+                "UPDATE token_accounts SET balance = 0, closed = TRUE, slot = $2
+                 WHERE address = $1 AND slot <= $2",
+                &[&address.to_string(), &(slot as i64)],
+            )
+            .await?;
+        Ok(())
+    }
+
+    pub async fn upsert_mint(
+        &self,
+        address: &Pubkey,
+        mint: &Mint,
+        slot: u64,
+    ) -> Result<(), Error> {
+        let authority = (mint.authority != Pubkey::default())
+            .then(|| mint.authority.to_string());
+        self.client
+            .execute(
+                "INSERT INTO mints
+                    (address, mint_index, authority, supply, decimals, slot)
+                 VALUES ($1, $2, $3, $4::TEXT::NUMERIC, $5, $6)
+                 ON CONFLICT (address) DO UPDATE SET
+                    authority = EXCLUDED.authority,
+                    supply = EXCLUDED.supply,
+                    slot = EXCLUDED.slot
+                 WHERE mints.slot <= EXCLUDED.slot",
+                &[
+                    &address.to_string(),
+                    &(mint.mint_index as i64),
+                    &authority,
+                    &mint.supply.to_string(),
+                    &(mint.decimals as i16),
+                    &(slot as i64),
+                ],
+            )
+            .await?;
+        Ok(())
+    }
+
+    pub async fn record_supply(
+        &self,
+        mint_index: u64,
+        slot: u64,
+        supply: u64,
+    ) -> Result<(), Error> {
+        self.client
+            .execute(
+                "INSERT INTO supply_history (mint_index, slot, supply)
+                 VALUES ($1, $2, $3::TEXT::NUMERIC)
+                 ON CONFLICT (mint_index, slot) DO UPDATE
+                    SET supply = EXCLUDED.supply",
+                &[&(mint_index as i64), &(slot as i64), &supply.to_string()],
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Newest transaction whose events have been ingested
+    pub async fn cursor(&self) -> Result<Option<String>, Error> {
+        Ok(self
+            .client
+            .query_opt("SELECT signature FROM cursor", &[])
+            .await?
+            .map(|row| row.get(0)))
+    }
+
+    /// Records the events of a transaction and advances the cursor past it,
+    /// atomically
+    pub async fn ingest_transaction(
+        &mut self,
+        signature: &str,
+        slot: u64,
+        block_time: Option<i64>,
+        events: &[Event],
+    ) -> Result<(), Error> {
+        let transaction = self.client.transaction().await?;
+        for (event_index, event) in events.iter().enumerate() {
+            let (kind, source, destination, mint, amount) = match event {
+                Event::Transfer(e) => {
+                    ("transfer", Some(e.from), Some(e.to), None, e.amount)
+                }
+                Event::Mint(e) => {
+                    ("mint", None, Some(e.to), Some(e.mint), e.amount)
+                }
+                Event::Burn(e) => {
+                    ("burn", Some(e.from), None, Some(e.mint), e.amount)
+                }
+            };
+            transaction
+                .execute(
+                    "INSERT INTO transfers (signature, event_index, slot,
+                        block_time, kind, source, destination, mint, amount)
+                     VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9::TEXT::NUMERIC)
+                     ON CONFLICT DO NOTHING",
+                    &[
+                        &signature,
+                        &(event_index as i32),
+                        &(slot as i64),
+                        &block_time,
+                        &kind,
+                        &source.map(|key| key.to_string()),
+                        &destination.map(|key| key.to_string()),
+                        &mint.map(|key| key.to_string()),
+                        &amount.to_string(),
+                    ],
+                )
+                .await?;
+        }
+        transaction
+            .execute(
+                "INSERT INTO cursor (signature) VALUES ($1)
+                 ON CONFLICT (id) DO UPDATE SET signature = EXCLUDED.signature",
+                &[&signature],
+            )
+            .await?;
+        transaction.commit().await
+    }
+
+    /// Token accounts of `mint_index` with a balance, largest first
+    pub async fn holders(
+        &self,
+        mint_index: u64,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<Holder>, Error> {
+        let rows = self
+            .client
+            .query(
+                "SELECT address, owner, balance::TEXT FROM token_accounts
+                 WHERE mint_index = $1 AND balance > 0
+                 ORDER BY balance DESC, address
+                 LIMIT $2 OFFSET $3",
+                &[&(mint_index as i64), &limit, &offset],
+            )
+            .await?;
+        Ok(rows
+            .iter()
+            .map(|row| Holder {
+                address: row.get(0),
+                owner: row.get(1),
+                balance: row.get(2),
+            })
+            .collect())
+    }
+
+    /// Current supply of `mint_index` and its recorded history, newest
+    /// first. None if the mint hasn't been seen.
+    pub async fn supply(
+        &self,
+        mint_index: u64,
+        limit: i64,
+    ) -> Result<Option<Supply>, Error> {
+        let Some(mint) = self
+            .client
+            .query_opt(
+                "SELECT address, supply::TEXT, decimals FROM mints
+                 WHERE mint_index = $1",
+                &[&(mint_index as i64)],
+            )
+            .await?
+        else {
+            return Ok(None);
+        };
+        let history = self
+            .client
+            .query(
+                "SELECT slot, supply::TEXT FROM supply_history
+                 WHERE mint_index = $1 ORDER BY slot DESC LIMIT $2",
+                &[&(mint_index as i64), &limit],
+            )
+            .await?;
+        Ok(Some(Supply {
+            mint: mint.get(0),
+            mint_index,
+            supply: mint.get(1),
+            decimals: mint.get(2),
+            history: history
+                .iter()
+                .map(|row| SupplyPoint {
+                    slot: row.get(0),
+                    supply: row.get(1),
+                })
+                .collect(),
+        }))
+    }
+
+    pub async fn account(
+        &self,
+        address: &str,
+    ) -> Result<Option<AccountRow>, Error> {
+        Ok(self
+            .client
+            .query_opt(
+                "SELECT address, owner, mint_index, balance::TEXT, closed, slot
+                 FROM token_accounts WHERE address = $1",
+                &[&address],
+            )
+            .await?
+            .map(|row| AccountRow {
+                address: row.get(0),
+                owner: row.get(1),
+                mint_index: row.get(2),
+                balance: row.get(3),
+                closed: row.get(4),
+                slot: row.get(5),
+            }))
+    }
+
+    /// Events to or from `address`, newest first
+    pub async fn transfers(
+        &self,
+        address: &str,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<TransferRow>, Error> {
+        let rows = self
+            .client
+            .query(
+                "SELECT signature, event_index, slot, block_time, kind, source,
+                    destination, mint, amount::TEXT
+                 FROM transfers WHERE source = $1 OR destination = $1
+                 ORDER BY slot DESC, signature, event_index
+                 LIMIT $2 OFFSET $3",
+                &[&address, &limit, &offset],
+            )
+            .await?;
+        Ok(rows
+            .iter()
+            .map(TransferRow::from)
+            .collect())
+    }
+}
+
+/// Amounts are strings since JSON numbers lose u64 precision
+#[derive(Debug, Serialize)]
+pub struct Holder {
+    pub address: String,
+    pub owner: String,
+    pub balance: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Supply {
+    pub mint: String,
+    pub mint_index: u64,
+    pub supply: String,
+    pub decimals: i16,
+    pub history: Vec<SupplyPoint>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SupplyPoint {
+    pub slot: i64,
+    pub supply: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AccountRow {
+    pub address: String,
+    pub owner: String,
+    pub mint_index: i64,
+    pub balance: String,
+    pub closed: bool,
+    pub slot: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TransferRow {
+    pub signature: String,
+    pub event_index: i32,
+    pub slot: i64,
+    pub block_time: Option<i64>,
+    pub kind: String,
+    pub source: Option<String>,
+    pub destination: Option<String>,
+    pub mint: Option<String>,
+    pub amount: String,
+}
+
+impl From<&Row> for TransferRow {
+    fn from(row: &Row) -> Self {
+        Self {
+            signature: row.get(0),
+            event_index: row.get(1),
+            slot: row.get(2),
+            block_time: row.get(3),
+            kind: row.get(4),
+            source: row.get(5),
+            destination: row.get(6),
+            mint: row.get(7),
+            amount: row.get(8),
+        }
+    }
+}
@@ -0,0 +1,228 @@
+//! Ingestion of account state and events.
+//!
+//! Account state streams in from a websocket `programSubscribe` on top of a
+//! getProgramAccounts snapshot. Events are read from the noop inner
+//! instructions of program transactions, which requires the program to be
+//! built with `noop-events`.
+
+use std::{error::Error, time::Duration};
+
+use futures_util::StreamExt;
+use nanotoken::{consts::NOOP_PROGRAM, events::Event};
+use nanotoken_subscriber::{ws::Subscriber, AccountState, StateDiff, Tracker};
+use solana_client::{
+    nonblocking::rpc_client::RpcClient,
+    rpc_client::GetConfirmedSignaturesForAddress2Config,
+    rpc_config::RpcTransactionConfig,
+};
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use solana_transaction_status::{
+    EncodedConfirmedTransactionWithStatusMeta, UiInstruction,
+    UiLoadedAddresses, UiTransactionEncoding,
+};
+
+use crate::db::Db;
+
+pub type IngestResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
+
+/// Streams account updates into `db` until the subscription ends
+pub async fn accounts(
+    db: &Db,
+    rpc: &RpcClient,
+    ws_url: &str,
+) -> IngestResult<()> {
+    let subscriber = Subscriber::new(ws_url).await?;
+    let mut diffs = subscriber
+        .state_diffs(Tracker::default(), vec![], rpc.commitment())
+        .await?;
+
+    // Snapshot after subscribing so that no update falls in between. It is
+    // labeled with a slot from before it was taken, so streamed updates
+    // replace it even if they are slightly older, and any change after them
+    // streams in too.
+    let slot = rpc.get_slot().await?;
+    for (address, account) in rpc
+        .get_program_accounts(&nanotoken::ID)
+        .await?
+    {
+        if let Some(state) = AccountState::decode(&account.data) {
+            write(
+                db,
+                &StateDiff {
+                    address,
+                    slot,
+                    before: None,
+                    after: state,
+                },
+            )
+            .await?;
+        }
+    }
+
+    while let Some(diff) = diffs.next().await {
+        write(db, &diff).await?;
+    }
+    Err("account subscription closed".into())
+}
+
+async fn write(db: &Db, diff: &StateDiff) -> IngestResult<()> {
+    match &diff.after {
+        AccountState::TokenAccount(account) => {
+            db.upsert_token_account(&diff.address, account, diff.slot)
+                .await?
+        }
+        AccountState::Mint(mint) => {
+            db.upsert_mint(&diff.address, mint, diff.slot)
+                .await?;
+            let supply_changed = match &diff.before {
+                Some(AccountState::Mint(before)) => {
+                    before.supply != mint.supply
+                }
+                _ => true,
+            };
+            if supply_changed {
+                db.record_supply(mint.mint_index, diff.slot, mint.supply)
+                    .await?;
+            }
+        }
+        AccountState::Closed => {
+            db.close_token_account(&diff.address, diff.slot)
+                .await?
+        }
+    }
+    Ok(())
+}
+
+/// Polls for new program transactions every `interval` and records their
+/// events, resuming from the cursor in `db`. Without a cursor, the whole
+/// history of the program is ingested first.
+pub async fn events(
+    db: &mut Db,
+    rpc: &RpcClient,
+    interval: Duration,
+) -> IngestResult<()> {
+    loop {
+        let cursor = match db.cursor().await? {
+            Some(signature) => Some(signature.parse::<Signature>()?),
+            None => None,
+        };
+        for signature in new_signatures(rpc, cursor).await? {
+            let config = RpcTransactionConfig {
+                encoding: Some(UiTransactionEncoding::Base64),
+                commitment: Some(rpc.commitment()),
+                max_supported_transaction_version: Some(0),
+            };
+            let transaction = rpc
+                .get_transaction_with_config(&signature, config)
+                .await?;
+            db.ingest_transaction(
+                &signature.to_string(),
+                transaction.slot,
+                transaction.block_time,
+                &decode_events(&transaction),
+            )
+            .await?;
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// Signatures of program transactions after `until`, oldest first
+async fn new_signatures(
+    rpc: &RpcClient,
+    until: Option<Signature>,
+) -> IngestResult<Vec<Signature>> {
+    let mut signatures: Vec<Signature> = vec![];
+    loop {
+        let config = GetConfirmedSignaturesForAddress2Config {
+            before: signatures.last().copied(),
+            until,
+            limit: None,
+            commitment: Some(rpc.commitment()),
+        };
+        let page = rpc
+            .get_signatures_for_address_with_config(&nanotoken::ID, config)
+            .await?;
+        if page.is_empty() {
+            break;
+        }
+        for status in page {
+            signatures.push(status.signature.parse()?);
+        }
+    }
+    signatures.reverse();
+    Ok(signatures)
+}
+
+/// Events emitted by the nanotoken program in a transaction, in order.
+///
+/// Anyone can invoke the noop program with event-shaped data, so only noop
+/// instructions invoked by nanotoken itself count. Failed transactions have
+/// no events.
+pub fn decode_events(
+    transaction: &EncodedConfirmedTransactionWithStatusMeta,
+) -> Vec<Event> {
+    let transaction = &transaction.transaction;
+    let (Some(meta), Some(decoded)) =
+        (&transaction.meta, transaction.transaction.decode())
+    else {
+        return vec![];
+    };
+    if meta.err.is_some() {
+        return vec![];
+    }
+
+    let mut keys = decoded
+        .message
+        .static_account_keys()
+        .to_vec();
+    let loaded: Option<&UiLoadedAddresses> =
+        meta.loaded_addresses.as_ref().into();
+    if let Some(loaded) = loaded {
+        keys.extend(
+            loaded
+                .writable
+                .iter()
+                .chain(&loaded.readonly)
+                .filter_map(|key| key.parse::<Pubkey>().ok()),
+        );
+    }
+    let key = |index: u8| keys.get(index as usize).copied();
+
+    let inner: Option<&Vec<_>> = meta.inner_instructions.as_ref().into();
+    let mut events = vec![];
+    for inner in inner.into_iter().flatten() {
+        let Some(outer) = decoded
+            .message
+            .instructions()
+            .get(inner.index as usize)
+        else {
+            continue;
+        };
+        // Program invoked at each stack height, from 1
+        let mut stack: Vec<Option<Pubkey>> = vec![key(outer.program_id_index)];
+        for ix in &inner.instructions {
+            let UiInstruction::Compiled(ix) = ix else {
+                continue;
+            };
+            // Transactions from before stack heights were recorded can't be
+            // attributed
+            let Some(height) = ix.stack_height else {
+                continue;
+            };
+            let program = key(ix.program_id_index);
+            stack.resize(height.saturating_sub(1) as usize, None);
+            let invoker = stack.last().copied().flatten();
+            if invoker == Some(nanotoken::ID) && program == Some(NOOP_PROGRAM) {
+                events.extend(
+                    bs58::decode(&ix.data)
+                        .into_vec()
+                        .ok()
+                        .and_then(|data| Event::decode(&data)),
+                );
+            }
+            stack.push(program);
+        }
+    }
+    events
+}
@@ -0,0 +1,102 @@
+//! Reference indexer: nanotoken holders, balances, supply history, and
+//! transfers in Postgres, served over HTTP.
+//!
+//! ```text
+//! nanotoken-indexer \
+//!     --rpc-url http://localhost:8899 \
+//!     --ws-url ws://localhost:8900 \
+//!     --database-url postgres://localhost/nanotoken
+//! curl localhost:3000/mints/0/holders
+//! ```
+//!
+//! Transfers are only indexed for programs built with `noop-events`. Each
+//! ingestion task reconnects on failure, and resumes where it left off.
+
+mod api;
+mod db;
+mod ingest;
+
+use std::{error::Error, net::SocketAddr, sync::Arc, time::Duration};
+
+use clap::Parser;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+
+use crate::db::Db;
+
+#[derive(Parser)]
+struct Indexer {
+    #[clap(long, default_value = "http://localhost:8899")]
+    rpc_url: String,
+
+    #[clap(long, default_value = "ws://localhost:8900")]
+    ws_url: String,
+
+    #[clap(long, env = "DATABASE_URL")]
+    database_url: String,
+
+    /// Address the HTTP API listens on
+    #[clap(long, default_value = "127.0.0.1:3000")]
+    listen: SocketAddr,
+
+    /// Seconds between polls for new transactions
+    #[clap(long, default_value_t = 2)]
+    poll_interval: u64,
+}
+
+/// Delay before reconnecting a failed ingestion task
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
+    let args = Indexer::parse();
+
+    let db = Db::connect(&args.database_url).await?;
+    db.migrate().await?;
+
+    // Finalized, so that nothing indexed is rolled back
+    let rpc = Arc::new(RpcClient::new_with_commitment(
+        args.rpc_url.clone(),
+        CommitmentConfig::finalized(),
+    ));
+
+    tokio::spawn({
+        let (rpc, database_url) = (rpc.clone(), args.database_url.clone());
+        async move {
+            loop {
+                let result = async {
+                    let db = Db::connect(&database_url).await?;
+                    ingest::accounts(&db, &rpc, &args.ws_url).await
+                }
+                .await;
+                if let Err(err) = result {
+                    eprintln!("account ingestion failed: {err}");
+                }
+                tokio::time::sleep(RETRY_DELAY).await;
+            }
+        }
+    });
+
+    tokio::spawn({
+        let database_url = args.database_url.clone();
+        let interval = Duration::from_secs(args.poll_interval);
+        async move {
+            loop {
+                let result = async {
+                    let mut db = Db::connect(&database_url).await?;
+                    ingest::events(&mut db, &rpc, interval).await
+                }
+                .await;
+                if let Err(err) = result {
+                    eprintln!("event ingestion failed: {err}");
+                }
+                tokio::time::sleep(RETRY_DELAY).await;
+            }
+        }
+    });
+
+    let listener = tokio::net::TcpListener::bind(args.listen).await?;
+    println!("listening on {}", args.listen);
+    axum::serve(listener, api::router(Arc::new(db))).await?;
+    Ok(())
+}
@@ -0,0 +1,90 @@
+//! Anchor account traits, behind the `anchor` feature, so Anchor programs can
+//! take nanotoken accounts directly, e.g. `Account<'info, TokenAccount>` and
+//! `Program<'info, Nanotoken>`.
+//!
+//! Accounts are copied out with the same checks as
+//! [AccountLoader::try_unpack]. Only nanotoken can write its accounts, so
+//! serialization exists for trait bounds and tests only.
+
+extern crate std;
+
+use anchor_lang::{
+    AccountDeserialize, AccountSerialize, Discriminator, Id, Owner,
+};
+use solana_program::pubkey::Pubkey;
+use std::io::Write;
+
+use crate::{
+    loader::{AccountLoader, Discriminated},
+    Mint, TokenAccount, VaultInfo,
+};
+
+/// The nanotoken program, for `Program<'info, Nanotoken>`
+#[derive(Clone)]
+pub struct Nanotoken;
+
+impl Id for Nanotoken {
+    fn id() -> Pubkey {
+        crate::ID
+    }
+}
+
+/// `[discriminator, version, 0, ...]`, the 8 bytes every account starts with
+const fn anchor_discriminator<T: Discriminated>() -> [u8; 8] {
+    let version = match T::VERSION {
+        Some(version) => version,
+        None => 0,
+    };
+    [T::DISCRIMINATOR as u8, version, 0, 0, 0, 0, 0, 0]
+}
+
+macro_rules! impl_anchor {
+    ($($ty:ty),*) => {$(
+        impl Discriminator for $ty {
+            const DISCRIMINATOR: [u8; 8] = anchor_discriminator::<$ty>();
+        }
+
+        impl Owner for $ty {
+            fn owner() -> Pubkey {
+                crate::ID
+            }
+        }
+
+        impl AccountDeserialize for $ty {
+            fn try_deserialize(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+                Ok(AccountLoader::<$ty>::try_unpack(buf)?)
+            }
+
+            /// Skips the discriminator and version checks, not the length
+            fn try_deserialize_unchecked(
+                buf: &mut &[u8],
+            ) -> anchor_lang::Result<Self> {
+                let Some(data) = buf.get(8..8 + core::mem::size_of::<$ty>())
+                else {
+                    return Err(
+                        anchor_lang::error::ErrorCode::AccountDidNotDeserialize
+                            .into(),
+                    );
+                };
+                Ok(bytemuck::pod_read_unaligned(data))
+            }
+        }
+
+        impl AccountSerialize for $ty {
+            fn try_serialize<W: Write>(
+                &self,
+                writer: &mut W,
+            ) -> anchor_lang::Result<()> {
+                writer
+                    .write_all(&<$ty as Discriminator>::DISCRIMINATOR)
+                    .and_then(|()| writer.write_all(bytemuck::bytes_of(self)))
+                    .map_err(|_| {
+                        anchor_lang::error::ErrorCode::AccountDidNotSerialize
+                            .into()
+                    })
+            }
+        }
+    )*};
+}
+
+impl_anchor!(Mint, TokenAccount, VaultInfo);
@@ -0,0 +1,109 @@
+//! Borsh adapters for instruction args, behind the `borsh` feature.
+//!
+//! Args are [bytemuck::Pod], so these write and read exactly the bytes the
+//! program parses, rather than deriving borsh's field-by-field encoding (the
+//! same for these types, but only by coincidence of the layouts). Use
+//! [BorshOp] to emit a whole op, `[tag as u64][args]`.
+
+use borsh::{
+    io::{Read, Result, Write},
+    BorshDeserialize, BorshSerialize,
+};
+use bytemuck::Zeroable;
+
+use crate::ix::*;
+
+/// One op of an instruction, serialized as `[tag as u64][args]`
+pub struct BorshOp<T> {
+    pub tag: Tag,
+    pub args: T,
+}
+
+impl<T: BorshSerialize> BorshSerialize for BorshOp<T> {
+    fn serialize<W: Write>(&self, writer: &mut W) -> Result<()> {
+        writer.write_all(&(self.tag as u64).to_le_bytes())?;
+        self.args.serialize(writer)
+    }
+}
+
+macro_rules! impl_borsh {
+    ($($ty:ty),* $(,)?) => {$(
+        impl BorshSerialize for $ty {
+            fn serialize<W: Write>(&self, writer: &mut W) -> Result<()> {
+                writer.write_all(bytemuck::bytes_of(self))
+            }
+        }
+
+        impl BorshDeserialize for $ty {
+            fn deserialize_reader<R: Read>(reader: &mut R) -> Result<Self> {
+                let mut args = <$ty>::zeroed();
+                reader.read_exact(bytemuck::bytes_of_mut(&mut args))?;
+                Ok(args)
+            }
+        }
+    )*};
+}
+
+impl_borsh!(
+    AcceptConfigAuthorityArgs,
+    AccountIndicesArgs,
+    AddToListArgs,
+    ApproveArgs,
+    ApproveTransferArgs,
+    BatchTransferArgs,
+    BurnArgs,
+    BurnCheckedArgs,
+    CancelTransferArgs,
+    ClawbackArgs,
+    CloseMintArgs,
+    CreateAssociatedAccountArgs,
+    ExecuteTransferArgs,
+    FreezeAccountArgs,
+    GarbageCollectArgs,
+    GetAccountDataSizeArgs,
+    GetBalancesArgs,
+    InitConfigArgs,
+    InitializeAccountArgs,
+    InitializeListArgs,
+    InitializeMetadataArgs,
+    InitializeMintArgs,
+    InitializeMintPdaArgs,
+    InitializeMintWithMetadataArgs,
+    InitializeVaultArgs,
+    MigrateAccountArgs,
+    MigrateStateArgs,
+    MintArgs,
+    NoopArgs,
+    PackedArgs,
+    PackedInitializeAccountArgs,
+    PackedInitializeMintArgs,
+    PackedTransmuteArgs,
+    PauseArgs,
+    PermitTransferArgs,
+    ProposeTransferArgs,
+    ReallocArgs,
+    RemoveFromListArgs,
+    RevokeArgs,
+    SetAuthorityArgs,
+    SetConfigAuthorityArgs,
+    SetFeatureArgs,
+    SetGcConfigArgs,
+    SetMemoRequiredArgs,
+    SetRentDestinationArgs,
+    SetRoyaltyArgs,
+    SyncNativeArgs,
+    ThawAccountArgs,
+    TimelockArgs,
+    TransferAllArgs,
+    TransferArgs,
+    TransferFeeConfig,
+    TransmuteArgs,
+    TransmuteBatchArgs,
+    UnpauseArgs,
+    UnwrapSolArgs,
+    UpdateMetadataArgs,
+    WithdrawExcessLamportsArgs,
+    WithdrawWithheldFeesArgs,
+    WrapSolArgs,
+    PermitMessage,
+);
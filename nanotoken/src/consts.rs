@@ -1,8 +1,72 @@
 use solana_program::pubkey::Pubkey;
 
+/// Program config pda, derived from ["config"]. See
+/// [crate::ProgramConfig::address].
 pub const CONFIG_ACCOUNT: Pubkey =
-    solana_program::pubkey!("CdPnCtvjY3TVQEXHYy8uyV8MNbnirpDc6En9BjAb6bS8");
+    solana_program::pubkey!("G3EgueUXCSiV8E6VzsRRVX1NzJsxoJFCKZa62Dt8GkNf");
+pub const CONFIG_BUMP: u8 = 253;
 
 pub const MAX_PDA_INIT_ALLOC: u64 = 10240;
 
 pub const MAX_DECIMALS: u64 = 9;
+
+/// SPL memo program ids (v2 and v1). Memo-required accounts accept incoming
+/// transfers only if the transaction includes one of these.
+pub const SPL_MEMO_PROGRAMS: [Pubkey; 2] = [
+    solana_program::pubkey!("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr"),
+    solana_program::pubkey!("Memo1UhkJRfHyvLMcVucJwxXeuD728EqVDDwQDxFMNo"),
+];
+
+/// Workspace noop program (see `noop/`), CPI'd with event bytes when the
+/// `noop-events` feature is enabled
+pub const NOOP_PROGRAM: Pubkey =
+    solana_program::pubkey!("noop8ytexvkpCuqbf6FB89BSuNemHtPRqaNC31GWivW");
+
+/// Token programs a vault can hold, and the associated token account program
+/// that transmute creates missing tokenkeg destinations with
+pub use crate::utils::spl_token_utils::{
+    ASSOCIATED_TOKEN_PROGRAM, SPL_TOKEN_2022_PROGRAM, SPL_TOKEN_PROGRAM,
+};
+
+/// Transfer fees are in basis points, so 10_000 is a 100% fee
+pub const MAX_FEE_BPS: u64 = 10_000;
+
+/// Reserved mint index for wrapped SOL. There is no mint account for it; the
+/// balance of a native token account is backed by the lamports it holds above
+/// rent exemption.
+pub const NATIVE_MINT_INDEX: u64 = u64::MAX;
+
+/// Wrapped SOL uses lamports as base units
+pub const NATIVE_DECIMALS: u8 = 9;
+
+/// Max number of approvers on a transfer intent
+pub const MAX_INTENT_APPROVERS: usize = 3;
+
+/// Max number of token accounts read by a single GetBalances. Each entry is
+/// 16 bytes, so 64 entries fill the 1024 byte return data limit.
+pub const MAX_BALANCE_QUERY: usize = 64;
+
+/// Max byte lengths of mint metadata strings
+pub const MAX_NAME_LEN: usize = 32;
+pub const MAX_SYMBOL_LEN: usize = 16;
+pub const MAX_URI_LEN: usize = 256;
+
+/// Layout versions, stored in the byte after the discriminator. Accounts
+/// created before versioning have version 0 and are upgraded by
+/// MigrateAccount.
+pub const MINT_VERSION: u8 = 2;
+pub const TOKEN_ACCOUNT_VERSION: u8 = 2;
+/// Layout versions of program state, upgraded by MigrateState
+pub const CONFIG_VERSION: u8 = 0;
+pub const VAULT_INFO_VERSION: u8 = 1;
+
+/// Sizes (excluding the 8 byte discriminator) of older layouts
+pub const MINT_V0_SIZE: usize = 56;
+pub const MINT_V1_SIZE: usize = 208;
+pub const TOKEN_ACCOUNT_V0_SIZE: usize = 48;
+pub const TOKEN_ACCOUNT_V1_SIZE: usize = 168;
+pub const VAULT_INFO_V0_SIZE: usize = 97;
+
+/// Max number of accounts an AccountIndices op can reference. The trailing
+/// config, system program, and payer are always appended.
+pub const MAX_INDEXED_ACCOUNTS: usize = 16;
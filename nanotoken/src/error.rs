@@ -1,6 +1,16 @@
-use solana_program::program_error::ProgramError;
+// The program is no_std, but solana_program's error traits are bound on
+// std::error::Error
+extern crate std;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+use num_derive::FromPrimitive;
+use solana_program::{
+    decode_error::DecodeError,
+    log,
+    program_error::{PrintProgramError, ProgramError},
+};
+
+/// Custom error codes. Variants are only ever appended, so codes are stable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromPrimitive)]
 #[repr(u32)]
 pub enum NanoTokenError {
     DuplicateAccount,
@@ -8,6 +18,187 @@ pub enum NanoTokenError {
     InvalidDecimals,
     IncorrectMint,
     SupplyOverflow,
+    IntentAlreadyExecuted,
+    IntentThresholdNotMet,
+    IntentTimelocked,
+    InsufficientDelegatedAmount,
+    AccountFrozen,
+    MintDecimalsMismatch,
+    InvalidTransferFee,
+    NonZeroSupply,
+    MemoRequired,
+    UnsupportedMintExtension,
+    InvalidPermit,
+    PermitExpired,
+    MintPaused,
+    SupplyCapExceeded,
+    NonZeroBalance,
+    AccountNeedsMigration,
+    ImmutableOwner,
+    OwnerNotAllowed,
+    BalanceOverflow,
+    AccountTimelocked,
+    FeatureDisabled,
+    SupplyUnderflow,
+    InvalidConfigAccount,
+    InvalidSystemProgram,
+    NoRentDestination,
+    RentDestinationMismatch,
+    TransmuteMintMismatch,
+    TransmuteVaultMismatch,
+    InvalidTokenProgram,
+    OwnerMismatch,
+    InvalidTransmuteAccounts,
+    TimelockNotExtended,
+    IntentMismatch,
+    GarbageCollectionDisabled,
+    AccountTooYoung,
+    ExcessLamports,
+    RoyaltyRecipientMismatch,
+    UnknownTag,
+    MissingAccounts,
+    TransmuteSameAccount,
+    TransmuteVaultAsUserAccount,
+    TransmuteDestinationMintMismatch,
+    TokenAccountAddressMismatch,
+    TransmuteNativeAccount,
+}
+
+impl NanoTokenError {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            NanoTokenError::DuplicateAccount => {
+                "account was passed more than once"
+            }
+            NanoTokenError::InsufficientTokenBalance => {
+                "insufficient token balance"
+            }
+            NanoTokenError::InvalidDecimals => "invalid decimals",
+            NanoTokenError::IncorrectMint => "account mint does not match",
+            NanoTokenError::SupplyOverflow => "supply would exceed u64::MAX",
+            NanoTokenError::IntentAlreadyExecuted => {
+                "transfer intent was already executed"
+            }
+            NanoTokenError::IntentThresholdNotMet => {
+                "transfer intent does not have enough approvals"
+            }
+            NanoTokenError::IntentTimelocked => {
+                "transfer intent is still timelocked"
+            }
+            NanoTokenError::InsufficientDelegatedAmount => {
+                "insufficient delegated amount"
+            }
+            NanoTokenError::AccountFrozen => "account is frozen",
+            NanoTokenError::MintDecimalsMismatch => {
+                "mint decimals do not match"
+            }
+            NanoTokenError::InvalidTransferFee => "invalid transfer fee",
+            NanoTokenError::NonZeroSupply => "mint supply is nonzero",
+            NanoTokenError::MemoRequired => "transfer requires a memo",
+            NanoTokenError::UnsupportedMintExtension => {
+                "tokenkeg mint has an unsupported extension"
+            }
+            NanoTokenError::InvalidPermit => "invalid permit",
+            NanoTokenError::PermitExpired => "permit has expired",
+            NanoTokenError::MintPaused => "mint is paused",
+            NanoTokenError::SupplyCapExceeded => {
+                "supply would exceed max supply"
+            }
+            NanoTokenError::NonZeroBalance => "account balance is nonzero",
+            NanoTokenError::AccountNeedsMigration => "account must be migrated",
+            NanoTokenError::ImmutableOwner => "account owner is immutable",
+            NanoTokenError::OwnerNotAllowed => {
+                "owner is not allowed by the mint list"
+            }
+            NanoTokenError::BalanceOverflow => "balance would exceed u64::MAX",
+            NanoTokenError::AccountTimelocked => "account is timelocked",
+            NanoTokenError::FeatureDisabled => "program feature is not enabled",
+            NanoTokenError::SupplyUnderflow => "supply would go below zero",
+            NanoTokenError::InvalidConfigAccount => {
+                "config does not have expected pubkey"
+            }
+            NanoTokenError::InvalidSystemProgram => {
+                "system_program does not have expected pubkey"
+            }
+            NanoTokenError::NoRentDestination => {
+                "config has no rent destination"
+            }
+            NanoTokenError::RentDestinationMismatch => {
+                "incorrect rent destination"
+            }
+            NanoTokenError::TransmuteMintMismatch => {
+                "transmute mint does not match vault info"
+            }
+            NanoTokenError::TransmuteVaultMismatch => {
+                "transmute vault does not match vault info"
+            }
+            NanoTokenError::InvalidTokenProgram => "incorrect token program",
+            NanoTokenError::OwnerMismatch => "account owner does not match",
+            NanoTokenError::InvalidTransmuteAccounts => {
+                "invalid tokenkeg/nanotoken account configuration"
+            }
+            NanoTokenError::TimelockNotExtended => {
+                "timelock can only be extended"
+            }
+            NanoTokenError::IntentMismatch => {
+                "accounts do not match transfer intent"
+            }
+            NanoTokenError::GarbageCollectionDisabled => {
+                "garbage collection is disabled"
+            }
+            NanoTokenError::AccountTooYoung => {
+                "token account is too young to collect"
+            }
+            NanoTokenError::ExcessLamports => "withdraw excess lamports first",
+            NanoTokenError::RoyaltyRecipientMismatch => {
+                "royalty recipient does not match"
+            }
+            NanoTokenError::UnknownTag => "unknown op tag",
+            NanoTokenError::MissingAccounts => "op is missing accounts",
+            NanoTokenError::TransmuteSameAccount => {
+                "transmute from and to are the same account"
+            }
+            NanoTokenError::TransmuteVaultAsUserAccount => {
+                "transmute vault passed as from or to"
+            }
+            NanoTokenError::TransmuteDestinationMintMismatch => {
+                "transmute destination has a different mint"
+            }
+            NanoTokenError::TokenAccountAddressMismatch => {
+                "token account is not at its derived address"
+            }
+            NanoTokenError::TransmuteNativeAccount => {
+                "native token accounts can't be transmuted"
+            }
+        }
+    }
+}
+
+impl core::fmt::Display for NanoTokenError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::error::Error for NanoTokenError {}
+
+impl<T> DecodeError<T> for NanoTokenError {
+    fn type_of() -> &'static str {
+        "NanoTokenError"
+    }
+}
+
+impl PrintProgramError for NanoTokenError {
+    fn print<E>(&self)
+    where
+        E: 'static
+            + std::error::Error
+            + DecodeError<E>
+            + PrintProgramError
+            + num_traits::FromPrimitive,
+    {
+        log::sol_log(self.as_str());
+    }
 }
 
 impl From<NanoTokenError> for ProgramError {
@@ -15,3 +206,42 @@ impl From<NanoTokenError> for ProgramError {
         ProgramError::Custom(e as u32)
     }
 }
+
+/// Op indices are stored plus one in the top byte of returned custom error
+/// codes, so zero means the error did not come from an op.
+pub const OP_INDEX_SHIFT: u32 = 24;
+/// Set in the low bits when the error is a builtin [ProgramError] rather than
+/// a custom code. The builtin's index (`u64::from(e) >> 32`) is stored below.
+pub const BUILTIN_ERROR_FLAG: u32 = 1 << 23;
+const INNER_MASK: u32 = (1 << OP_INDEX_SHIFT) - 1;
+
+/// Encodes the index of the failing op in a batch into `e`. Builtin errors
+/// become custom errors so they can carry the index; use [decode_op_error] to
+/// get the original error back. Indices past 254 saturate.
+pub fn encode_op_error(op_index: usize, e: ProgramError) -> ProgramError {
+    let inner = match e {
+        ProgramError::Custom(code) => code & !BUILTIN_ERROR_FLAG & INNER_MASK,
+        builtin => {
+            BUILTIN_ERROR_FLAG
+                | ((u64::from(builtin) >> 32) as u32 & INNER_MASK)
+        }
+    };
+    let op = (op_index as u32).min(u8::MAX as u32 - 1) + 1;
+    ProgramError::Custom(op << OP_INDEX_SHIFT | inner)
+}
+
+/// Decodes a custom error code returned by the program into the index of the
+/// failing op (if any) and the error it raised.
+pub fn decode_op_error(code: u32) -> (Option<usize>, ProgramError) {
+    let op = code >> OP_INDEX_SHIFT;
+    if op == 0 {
+        return (None, ProgramError::Custom(code));
+    }
+    let inner = code & INNER_MASK;
+    let e = if inner & BUILTIN_ERROR_FLAG != 0 {
+        ProgramError::from(((inner & !BUILTIN_ERROR_FLAG) as u64) << 32)
+    } else {
+        ProgramError::Custom(inner)
+    };
+    (Some(op as usize - 1), e)
+}
@@ -0,0 +1,135 @@
+//! Self-CPI events through the workspace noop program.
+//!
+//! With the `noop-events` feature, transfer, mint, and burn CPI the noop
+//! program with the serialized event as instruction data. Indexers read
+//! events from inner instructions, which unlike logs are never truncated.
+//! The noop program must be passed somewhere after each op's accounts.
+//!
+//! Event data is `[u8 EventTag][event bytes]`.
+
+use bytemuck::{Pod, Zeroable};
+use solana_program::pubkey::Pubkey;
+
+#[cfg(feature = "noop-events")]
+use crate::consts::NOOP_PROGRAM;
+#[cfg(feature = "noop-events")]
+use crate::solana_nostd_entrypoint::{InstructionC, NoStdAccountInfo};
+#[cfg(feature = "noop-events")]
+use solana_program::{
+    entrypoint::ProgramResult, log, program_error::ProgramError,
+};
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EventTag {
+    Transfer = 0,
+    Mint = 1,
+    Burn = 2,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable)]
+#[repr(C)]
+pub struct TransferEvent {
+    pub from: Pubkey,
+    pub to: Pubkey,
+    /// Includes any fee withheld in `to`
+    pub amount: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable)]
+#[repr(C)]
+pub struct MintEvent {
+    pub mint: Pubkey,
+    pub to: Pubkey,
+    pub amount: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable)]
+#[repr(C)]
+pub struct BurnEvent {
+    pub mint: Pubkey,
+    pub from: Pubkey,
+    pub amount: u64,
+}
+
+/// An event decoded from noop instruction data, e.g. by an indexer
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Event {
+    Transfer(TransferEvent),
+    Mint(MintEvent),
+    Burn(BurnEvent),
+}
+
+impl Event {
+    /// Decodes `[u8 EventTag][event bytes]`. None for unknown tags or
+    /// lengths.
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        let (&tag, bytes) = data.split_first()?;
+        Some(match tag {
+            t if t == EventTag::Transfer as u8 => {
+                Event::Transfer(bytemuck::try_pod_read_unaligned(bytes).ok()?)
+            }
+            t if t == EventTag::Mint as u8 => {
+                Event::Mint(bytemuck::try_pod_read_unaligned(bytes).ok()?)
+            }
+            t if t == EventTag::Burn as u8 => {
+                Event::Burn(bytemuck::try_pod_read_unaligned(bytes).ok()?)
+            }
+            _ => return None,
+        })
+    }
+}
+
+/// Largest event, plus the tag
+#[cfg(feature = "noop-events")]
+const MAX_EVENT_LEN: usize = 1 + 72;
+
+/// CPIs the noop program with `[tag][event]`. The noop program may be passed
+/// anywhere in `accounts`.
+#[cfg(feature = "noop-events")]
+pub(crate) fn emit<E: Pod>(
+    accounts: &[NoStdAccountInfo],
+    tag: EventTag,
+    event: &E,
+) -> ProgramResult {
+    let Some(noop) = accounts.iter().find(|a| {
+        solana_program::program_memory::sol_memcmp(
+            a.key().as_ref(),
+            NOOP_PROGRAM.as_ref(),
+            32,
+        ) == 0
+    }) else {
+        log::sol_log("noop program must be passed to emit events");
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    let event_bytes = bytemuck::bytes_of(event);
+    let len = 1 + event_bytes.len();
+    let mut data = [0; MAX_EVENT_LEN];
+    data[0] = tag as u8;
+    data[1..len].copy_from_slice(event_bytes);
+
+    let event_ix = InstructionC {
+        program_id: &NOOP_PROGRAM,
+        accounts: [].as_ptr(),
+        accounts_len: 0,
+        data: data.as_ptr(),
+        data_len: len as u64,
+    };
+    let infos = [noop.to_info_c()];
+    let cpi_seeds: &[&[&[u8]]] = &[];
+    #[cfg(target_os = "solana")]
+    unsafe {
+        solana_program::syscalls::sol_invoke_signed_c(
+            &event_ix as *const InstructionC as *const u8,
+            infos.as_ptr() as *const u8,
+            1,
+            cpi_seeds.as_ptr() as *const u8,
+            0,
+        );
+    }
+    #[cfg(not(target_os = "solana"))]
+    core::hint::black_box((&event_ix, &infos, cpi_seeds));
+
+    Ok(())
+}
@@ -0,0 +1,54 @@
+use crate::solana_nostd_entrypoint::NoStdAccountInfo;
+use bytemuck::{Pod, Zeroable};
+use solana_program::{log, program_error::ProgramError, pubkey::Pubkey};
+
+use crate::{utils::check_authority, ProgramConfig};
+
+#[derive(PartialEq, Debug, Clone, Pod, Zeroable, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct AcceptConfigAuthorityArgs {
+    // Keeping this scaffolded just in case...
+}
+
+impl AcceptConfigAuthorityArgs {
+    pub fn from_data<'a>(
+        _data: &mut &'a [u8],
+    ) -> Result<&'a AcceptConfigAuthorityArgs, ProgramError> {
+        // Zero-sized, so there is nothing to consume
+        Ok(&AcceptConfigAuthorityArgs {})
+    }
+
+    pub const fn size() -> usize {
+        core::mem::size_of::<Self>()
+    }
+}
+
+/// Second step of a config authority transfer
+pub fn accept_config_authority(
+    accounts: &[NoStdAccountInfo],
+    _args: &AcceptConfigAuthorityArgs,
+) -> Result<usize, ProgramError> {
+    // Unpack accounts
+    //
+    // 1) new_authority must be the pending authority and must be signer
+    // 2) config is checked by memoized validator closure
+    let [new_authority, _rem @ .., config, _system_program, _payer] = accounts
+    else {
+        log::sol_log(
+            "accept_config_authority expecting [new_authority, .. config, system_program, payer]",
+        );
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // SAFETY: no one else has a view into config's data
+    let config_account = unsafe { ProgramConfig::unchecked_load_mut(config)? };
+
+    // No one can sign for [0; 32], so this fails if no transfer is pending
+    check_authority(new_authority, &config_account.pending_authority)?;
+
+    config_account.authority = config_account.pending_authority;
+    config_account.pending_authority = Pubkey::default();
+
+    Ok(1)
+}
@@ -0,0 +1,98 @@
+use crate::solana_nostd_entrypoint::NoStdAccountInfo;
+use arrayvec::ArrayVec;
+use bytemuck::{Pod, Zeroable};
+use solana_program::{
+    entrypoint::ProgramResult, log, program_error::ProgramError,
+};
+
+use crate::{consts::MAX_INDEXED_ACCOUNTS, utils::split_at_unchecked};
+
+/// Followed by `num_accounts` u8 indices into the transaction's accounts,
+/// zero-padded to a multiple of 8 bytes.
+///
+/// The op after this one sees the referenced accounts followed by the
+/// trailing config, system program, and payer, instead of the next accounts
+/// in line. It does not consume any accounts, so batches can reference the
+/// same accounts many times without passing them again.
+#[derive(PartialEq, Debug, Clone, Copy, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct AccountIndicesArgs {
+    pub num_accounts: u64,
+}
+
+impl AccountIndicesArgs {
+    pub fn from_data<'a>(
+        data: &mut &'a [u8],
+    ) -> Result<(&'a AccountIndicesArgs, &'a [u8]), ProgramError> {
+        const IX_LEN: usize = core::mem::size_of::<AccountIndicesArgs>();
+        if data.len() >= IX_LEN {
+            // SAFETY:
+            // We do the length check ourselves instead of via
+            // core::slice::split_at so we can return an error
+            // instead of panicking.
+            let (ix_data, rem) = unsafe { split_at_unchecked(data, IX_LEN) };
+
+            // This is always aligned and all bit patterns are valid
+            let args =
+                unsafe { &*(ix_data.as_ptr() as *const AccountIndicesArgs) };
+
+            // Indices, padded so the next op stays aligned
+            let num_accounts = args.num_accounts as usize;
+            if num_accounts > MAX_INDEXED_ACCOUNTS {
+                log::sol_log("too many account indices");
+                return Err(ProgramError::InvalidInstructionData);
+            }
+            let padded_len = Self::padded_len(num_accounts);
+            if rem.len() < padded_len {
+                return Err(ProgramError::InvalidInstructionData);
+            }
+            // SAFETY: length was checked above
+            let (indices, rem) = unsafe { split_at_unchecked(rem, padded_len) };
+            *data = rem;
+
+            // SAFETY: num_accounts <= padded_len
+            Ok((args, unsafe { indices.get_unchecked(..num_accounts) }))
+        } else {
+            Err(ProgramError::InvalidInstructionData)
+        }
+    }
+
+    pub const fn size() -> usize {
+        core::mem::size_of::<Self>()
+    }
+
+    /// Length of the index bytes including padding
+    pub const fn padded_len(num_accounts: usize) -> usize {
+        (num_accounts + 7) & !7
+    }
+}
+
+/// Collects the accounts referenced by `indices`, followed by the trailing
+/// config, system program, and payer, into `out`.
+///
+/// Repeated indices are the same account, so handlers still catch them as
+/// duplicates when borrowing.
+pub(crate) fn gather_indexed_accounts(
+    accounts: &[NoStdAccountInfo],
+    indices: &[u8],
+    out: &mut ArrayVec<NoStdAccountInfo, { MAX_INDEXED_ACCOUNTS + 3 }>,
+) -> ProgramResult {
+    out.clear();
+    for &index in indices {
+        let Some(account) = accounts.get(index as usize) else {
+            log::sol_log("account index out of bounds");
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        out.push(account.clone());
+    }
+
+    // The entrypoint checks there are at least three accounts
+    out.extend(
+        accounts[accounts.len() - 3..]
+            .iter()
+            .cloned(),
+    );
+
+    Ok(())
+}
@@ -0,0 +1,96 @@
+use crate::solana_nostd_entrypoint::NoStdAccountInfo;
+use bytemuck::{Pod, Zeroable};
+use solana_program::{log, program_error::ProgramError, pubkey::Pubkey};
+
+use crate::{
+    error::NanoTokenError,
+    utils::{check_authority, realloc_funded_by_payer, split_at_unchecked},
+    MintList,
+};
+
+#[derive(PartialEq, Debug, Clone, Copy, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct AddToListArgs {
+    pub owner: Pubkey,
+}
+
+impl AddToListArgs {
+    pub fn from_data<'a>(
+        data: &mut &'a [u8],
+    ) -> Result<&'a AddToListArgs, ProgramError> {
+        const IX_LEN: usize = core::mem::size_of::<AddToListArgs>();
+        if data.len() >= IX_LEN {
+            // SAFETY:
+            // We do the length check ourselves instead of via
+            // core::slice::split_at so we can return an error
+            // instead of panicking.
+            let (ix_data, rem) = unsafe { split_at_unchecked(data, IX_LEN) };
+            *data = rem;
+
+            // This is always aligned and all bit patterns are valid
+            Ok(unsafe { &*(ix_data.as_ptr() as *const AddToListArgs) })
+        } else {
+            Err(ProgramError::InvalidInstructionData)
+        }
+    }
+
+    pub const fn size() -> usize {
+        core::mem::size_of::<Self>()
+    }
+}
+
+/// Inserts owner into the list, growing it (funded by payer) when it is full.
+/// Adding an owner that is already listed is a no-op.
+pub fn add_to_list(
+    accounts: &[NoStdAccountInfo],
+    args: &AddToListArgs,
+) -> Result<usize, ProgramError> {
+    // Unpack accounts
+    //
+    // 1) list is mutated, which is an implicit owner check
+    // 2) list_authority must be the list authority and must be signer
+    // 3) system program is checked by memoized validator closure
+    // 4) payer will be checked by the sol transfer if necessary
+    let [list, list_authority, _rem @ .., _config, _system_program, payer] =
+        accounts
+    else {
+        log::sol_log(
+            "add_to_list expecting [list, list_authority, .. config, system_program, payer]",
+        );
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // Check list authority and find insertion point
+    let (len, index) = {
+        let mut list_data = list
+            .try_borrow_mut_data()
+            .ok_or(NanoTokenError::DuplicateAccount)?;
+        let (list_account, entries) =
+            MintList::checked_load_mut(&mut list_data)?;
+        check_authority(list_authority, &list_account.authority)?;
+
+        let len = list_account.len as usize;
+        match entries[..len].binary_search(&args.owner) {
+            Ok(_) => return Ok(2),
+            Err(index) => (len, index),
+        }
+    };
+
+    // Grow by one entry if full
+    if len == MintList::capacity(list.data_len()) {
+        // SAFETY: no one holds a view into list data or lamports
+        unsafe { realloc_funded_by_payer(list, payer, list.data_len() + 32)? };
+    }
+
+    // Insert
+    let mut list_data = list
+        .try_borrow_mut_data()
+        .ok_or(NanoTokenError::DuplicateAccount)?;
+    let (list_account, entries) = MintList::checked_load_mut(&mut list_data)?;
+    entries.copy_within(index..len, index + 1);
+    entries[index] = args.owner;
+    list_account.len += 1;
+
+    Ok(2)
+}
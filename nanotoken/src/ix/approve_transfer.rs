@@ -0,0 +1,76 @@
+use crate::solana_nostd_entrypoint::NoStdAccountInfo;
+use bytemuck::{Pod, Zeroable};
+use solana_program::{log, program_error::ProgramError};
+
+use crate::{error::NanoTokenError, TransferIntent};
+
+#[derive(PartialEq, Debug, Clone, Pod, Zeroable, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct ApproveTransferArgs {
+    // Keeping this scaffolded just in case...
+}
+
+impl ApproveTransferArgs {
+    pub fn from_data<'a>(
+        _data: &mut &'a [u8],
+    ) -> Result<&'a ApproveTransferArgs, ProgramError> {
+        // Zero-sized, so there is nothing to consume
+        Ok(&ApproveTransferArgs {})
+    }
+
+    pub const fn size() -> usize {
+        core::mem::size_of::<Self>()
+    }
+}
+
+pub fn approve_transfer(
+    accounts: &[NoStdAccountInfo],
+    _args: &ApproveTransferArgs,
+) -> Result<usize, ProgramError> {
+    // Unpack accounts
+    //
+    // 1) intent is checked by loader and mutated, which is an implicit owner
+    //    check
+    // 2) approver must be one of the intent approvers and must be signer
+    let [intent, approver, _rem @ ..] = accounts else {
+        log::sol_log("approve_transfer expecting [intent, approver, .. ]");
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !approver.is_signer() {
+        log::sol_log("approver must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut intent_data = intent
+        .try_borrow_mut_data()
+        .ok_or(NanoTokenError::DuplicateAccount)?;
+    let intent_account = TransferIntent::checked_load_mut(&mut intent_data)?;
+
+    if intent_account.executed != 0 {
+        log::sol_log("intent was already executed");
+        return Err(NanoTokenError::IntentAlreadyExecuted.into());
+    }
+
+    // Find approver slot. Empty slots are [0; 32], which can never sign.
+    let Some(slot) = intent_account
+        .approvers
+        .iter()
+        .position(|a| {
+            solana_program::program_memory::sol_memcmp(
+                a.as_ref(),
+                approver.key().as_ref(),
+                32,
+            ) == 0
+        })
+    else {
+        log::sol_log("signer is not an approver");
+        return Err(ProgramError::IllegalOwner);
+    };
+
+    // Approving twice is a no-op
+    intent_account.approvals |= 1 << slot;
+
+    Ok(2)
+}
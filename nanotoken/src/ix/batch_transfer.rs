@@ -0,0 +1,156 @@
+use crate::solana_nostd_entrypoint::NoStdAccountInfo;
+use bytemuck::{Pod, Zeroable};
+use solana_program::{log, program_error::ProgramError};
+
+use crate::{
+    consts::NATIVE_MINT_INDEX,
+    error::NanoTokenError,
+    utils::{
+        check_authority, check_memo_present, move_native_lamports,
+        split_at_unchecked,
+    },
+    TokenAccount,
+};
+
+/// Followed by `num_transfers` u64 amounts, one per destination account.
+#[derive(PartialEq, Debug, Clone, Copy, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct BatchTransferArgs {
+    pub num_transfers: u64,
+}
+
+impl BatchTransferArgs {
+    pub fn from_data<'a>(
+        data: &mut &'a [u8],
+    ) -> Result<(&'a BatchTransferArgs, &'a [u64]), ProgramError> {
+        const IX_LEN: usize = core::mem::size_of::<BatchTransferArgs>();
+        if data.len() >= IX_LEN {
+            // SAFETY:
+            // We do the length check ourselves instead of via
+            // core::slice::split_at so we can return an error
+            // instead of panicking.
+            let (ix_data, rem) = unsafe { split_at_unchecked(data, IX_LEN) };
+
+            // This is always aligned and all bit patterns are valid
+            let args =
+                unsafe { &*(ix_data.as_ptr() as *const BatchTransferArgs) };
+
+            // Amounts
+            let amounts_len = (args.num_transfers as usize)
+                .checked_mul(8)
+                .ok_or(ProgramError::InvalidInstructionData)?;
+            if rem.len() < amounts_len {
+                return Err(ProgramError::InvalidInstructionData);
+            }
+            // SAFETY: length was checked above
+            let (amounts, rem) =
+                unsafe { split_at_unchecked(rem, amounts_len) };
+            *data = rem;
+
+            // This is always aligned and all bit patterns are valid
+            let amounts = unsafe {
+                core::slice::from_raw_parts(
+                    amounts.as_ptr() as *const u64,
+                    args.num_transfers as usize,
+                )
+            };
+
+            Ok((args, amounts))
+        } else {
+            Err(ProgramError::InvalidInstructionData)
+        }
+    }
+
+    pub const fn size() -> usize {
+        core::mem::size_of::<Self>()
+    }
+}
+
+pub fn batch_transfer(
+    accounts: &[NoStdAccountInfo],
+    _args: &BatchTransferArgs,
+    amounts: &[u64],
+) -> Result<usize, ProgramError> {
+    // Unpack accounts
+    //
+    // 1) from needs an owner/disc check and an authority check
+    // 2) owner must be from authority and must be signer
+    // 3) each to needs to have the same mint as from, and requires an
+    //    owner/disc check
+    //
+    // Owner checks are implicit as in transfer: any account that receives a
+    // nonzero amount is mutated.
+    let [from, owner, rem @ ..] = accounts else {
+        log::sol_log("batch_transfer expecting [from, owner, to.., .. ]");
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+    let Some(tos) = rem.get(..amounts.len()) else {
+        log::sol_log("batch_transfer missing destination accounts");
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // Load from_account
+    let from_account = unsafe { TokenAccount::check_disc_raw(from)? };
+    unsafe {
+        (*from_account).check_not_frozen()?;
+        (*from_account).check_not_timelocked()?;
+    }
+
+    // Check mint is not paused. The mint may be passed anywhere after this
+    // op's accounts.
+    unsafe { (*from_account).check_not_paused(accounts)? };
+
+    // Check that the owner is correct and signed this
+    check_authority(owner, unsafe { &(*from_account).owner })?;
+
+    let mut memo_checked = false;
+    for (to, &amount) in tos.iter().zip(amounts) {
+        // Load to_account. Pointers are used since to may alias from or
+        // another destination.
+        let to_account = unsafe { TokenAccount::check_disc_raw(to)? };
+        unsafe {
+            (*to_account).check_not_frozen()?;
+
+            // Check both owners against the mint list. The list may be passed
+            // anywhere after this op's accounts.
+            (*from_account).check_list(&(*to_account).owner, accounts)?;
+
+            // Check memo, once per batch. The instructions sysvar may be
+            // passed anywhere after this op's accounts.
+            if !memo_checked && (*to_account).requires_memo() {
+                check_memo_present(accounts)?;
+                memo_checked = true;
+            }
+
+            // Check that the mints match
+            if (*from_account).mint != (*to_account).mint {
+                log::sol_log("from/to mint mismatch");
+                return Err(NanoTokenError::IncorrectMint.into());
+            }
+
+            // Check from_account balance
+            if (*from_account).balance < amount {
+                log::sol_log("insufficient balance");
+                return Err(NanoTokenError::InsufficientTokenBalance.into());
+            }
+
+            // Transfer, less royalty. The mint and royalty recipient may be
+            // passed anywhere after this op's accounts.
+            let royalty =
+                TokenAccount::pay_royalty(from_account, amount, accounts)?;
+            TokenAccount::move_balance(
+                from_account,
+                to_account,
+                amount - royalty,
+            )?;
+
+            // Native balances carry their lamports with them
+            if (*from_account).mint == NATIVE_MINT_INDEX {
+                move_native_lamports(from, to, amount);
+            }
+        }
+    }
+
+    Ok(2 + amounts.len())
+}
@@ -1,12 +1,19 @@
 use crate::solana_nostd_entrypoint::NoStdAccountInfo;
 use bytemuck::{Pod, Zeroable};
-use solana_program::{log, program_error::ProgramError};
+use solana_program::{log, program_error::ProgramError, pubkey::Pubkey};
 
 use crate::{
-    error::NanoTokenError, utils::split_at_unchecked, Mint, TokenAccount,
+    error::NanoTokenError,
+    math,
+    utils::{
+        check_authority, paranoid_check_owner,
+        paranoid_check_token_account_address, split_at_unchecked,
+    },
+    Mint, TokenAccount,
 };
 
 #[derive(PartialEq, Debug, Clone, Copy, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct BurnArgs {
     pub amount: u64,
@@ -40,9 +47,9 @@ pub fn burn(
     accounts: &[NoStdAccountInfo],
     args: &BurnArgs,
 ) -> Result<usize, ProgramError> {
-    log::sol_log("burn");
+    crate::nanolog!(trace, "burn");
     let [from, mint, owner, _rem @ ..] = accounts else {
-        log::sol_log("mint expecting [from, mint, owner, .. ]");
+        log::sol_log("burn expecting [from, mint, owner, .. ]");
         return Err(ProgramError::NotEnoughAccountKeys);
     };
 
@@ -57,25 +64,25 @@ pub fn burn(
 
     // Load mint account
     // we do not do an owner check since we will mutate (add nonzero amount to
-    // supply)
+    // supply), except in paranoid builds
+    paranoid_check_owner(mint)?;
     let mut mint_data = mint
         .try_borrow_mut_data()
         .expect("first borrow won't fail");
     let mint_account = Mint::checked_load_mut(&mut mint_data)?;
-
-    // Check if from is signer
-    if !from.is_signer() {
-        log::sol_log("authority must sign to mint");
-        return Err(ProgramError::MissingRequiredSignature);
-    }
+    mint_account.check_not_paused()?;
 
     // Load account
     // we do not do an owner check since we will mutate (sub nonzero amount from
-    // supply/balance)
+    // supply/balance), except in paranoid builds
+    paranoid_check_owner(from)?;
     let mut from_data = from
         .try_borrow_mut_data()
         .ok_or(NanoTokenError::DuplicateAccount)?;
     let from_account = TokenAccount::checked_load_mut(&mut from_data)?;
+    paranoid_check_token_account_address(from, from_account)?;
+    from_account.check_not_frozen()?;
+    from_account.check_not_timelocked()?;
 
     // Check mint
     if from_account.mint != mint_account.mint_index {
@@ -83,26 +90,56 @@ pub fn burn(
         return Err(NanoTokenError::IncorrectMint.into());
     }
 
-    // Check if owner is correct
+    // Check that the owner or delegate is correct and signed this. The
+    // delegate burns from its allowance, and is revoked once it is spent.
     if solana_program::program_memory::sol_memcmp(
-        from_account.owner.as_ref(),
         owner.key().as_ref(),
+        from_account.owner.as_ref(),
         32,
-    ) != 0
+    ) == 0
     {
-        log::sol_log("incorrect mint authority");
-        return Err(ProgramError::MissingRequiredSignature);
-    };
+        if !owner.is_signer() {
+            log::sol_log("owner must sign to burn");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+    } else {
+        check_authority(owner, &from_account.delegate)?;
+        if from_account.delegated_amount < args.amount {
+            log::sol_log("insufficient delegated amount");
+            return Err(NanoTokenError::InsufficientDelegatedAmount.into());
+        }
+        from_account.delegated_amount -= args.amount;
+
+        // Auto-revoke once the allowance is used up
+        if from_account.delegated_amount == 0 {
+            from_account.delegate = Pubkey::default();
+        }
+    }
 
     // Check balance
     if from_account.balance >= args.amount {
         // decrement supply, balance
-        mint_account.supply -= args.amount;
-        from_account.balance -= args.amount;
+        mint_account.supply =
+            math::sub_supply(mint_account.supply, args.amount)?;
+        from_account.balance =
+            math::sub_balance(from_account.balance, args.amount)?;
     } else {
         log::sol_log("insufficient token balance");
         return Err(NanoTokenError::InsufficientTokenBalance.into());
     }
 
+    // Emit event. The noop program may be passed anywhere after this op's
+    // accounts.
+    #[cfg(feature = "noop-events")]
+    crate::events::emit(
+        accounts,
+        crate::events::EventTag::Burn,
+        &crate::events::BurnEvent {
+            mint: *mint.key(),
+            from: *from.key(),
+            amount: args.amount,
+        },
+    )?;
+
     Ok(3)
 }
@@ -0,0 +1,73 @@
+use crate::solana_nostd_entrypoint::NoStdAccountInfo;
+use bytemuck::{Pod, Zeroable};
+use solana_program::{log, program_error::ProgramError};
+
+use crate::{error::NanoTokenError, utils::split_at_unchecked, Mint};
+
+use super::{burn, BurnArgs};
+
+#[derive(PartialEq, Debug, Clone, Copy, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct BurnCheckedArgs {
+    pub amount: u64,
+    /// u64 is used for alignment. Must match the mint decimals.
+    pub decimals: u64,
+}
+
+impl BurnCheckedArgs {
+    pub fn from_data<'a>(
+        data: &mut &'a [u8],
+    ) -> Result<&'a BurnCheckedArgs, ProgramError> {
+        const IX_LEN: usize = core::mem::size_of::<BurnCheckedArgs>();
+        if data.len() >= IX_LEN {
+            // SAFETY:
+            // We do the length check ourselves instead of via
+            // core::slice::split_at so we can return an error
+            // instead of panicking.
+            let (ix_data, rem) = unsafe { split_at_unchecked(data, IX_LEN) };
+            *data = rem;
+
+            // This is always aligned and all bit patterns are valid
+            Ok(unsafe { &*(ix_data.as_ptr() as *const BurnCheckedArgs) })
+        } else {
+            Err(ProgramError::InvalidInstructionData)
+        }
+    }
+
+    pub const fn size() -> usize {
+        core::mem::size_of::<Self>()
+    }
+}
+
+/// Burn, but the caller also asserts the mint decimals. Takes the same
+/// accounts as burn, which checks the mint against the token account.
+pub fn burn_checked(
+    accounts: &[NoStdAccountInfo],
+    args: &BurnCheckedArgs,
+) -> Result<usize, ProgramError> {
+    let [_from, mint, _rem @ ..] = accounts else {
+        log::sol_log("burn_checked expecting [from, mint, owner, .. ]");
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // Check decimals. Borrow is dropped before burn takes its own.
+    {
+        Mint::owner_check(mint)?;
+        let mut mint_data = mint
+            .try_borrow_mut_data()
+            .expect("first borrow won't fail");
+        let mint_account = Mint::checked_load_mut(&mut mint_data)?;
+        if mint_account.decimals as u64 != args.decimals {
+            log::sol_log("mint decimals mismatch");
+            return Err(NanoTokenError::MintDecimalsMismatch.into());
+        }
+    }
+
+    burn(
+        accounts,
+        &BurnArgs {
+            amount: args.amount,
+        },
+    )
+}
@@ -0,0 +1,101 @@
+use crate::solana_nostd_entrypoint::NoStdAccountInfo;
+use bytemuck::{Pod, Zeroable};
+use solana_program::{log, program_error::ProgramError};
+
+use crate::{
+    error::NanoTokenError,
+    utils::{check_authority, split_at_unchecked},
+    ProgramConfig, RentDestination, TransferIntent,
+};
+
+#[derive(PartialEq, Debug, Clone, Copy, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct CancelTransferArgs {
+    /// See [RentDestination]
+    pub rent_destination: u64,
+}
+
+impl CancelTransferArgs {
+    pub fn from_data<'a>(
+        data: &mut &'a [u8],
+    ) -> Result<&'a CancelTransferArgs, ProgramError> {
+        const IX_LEN: usize = core::mem::size_of::<CancelTransferArgs>();
+        if data.len() >= IX_LEN {
+            // SAFETY:
+            // We do the length check ourselves instead of via
+            // core::slice::split_at so we can return an error
+            // instead of panicking.
+            let (ix_data, rem) = unsafe { split_at_unchecked(data, IX_LEN) };
+            *data = rem;
+
+            // This is always aligned and all bit patterns are valid
+            Ok(unsafe { &*(ix_data.as_ptr() as *const CancelTransferArgs) })
+        } else {
+            Err(ProgramError::InvalidInstructionData)
+        }
+    }
+
+    pub const fn size() -> usize {
+        core::mem::size_of::<Self>()
+    }
+}
+
+/// Closes a transfer intent, pending or executed, sending its lamports to
+/// `destination`. Only its proposer may cancel it. A delegate's allowance
+/// spent by proposing is not restored.
+pub fn cancel_transfer(
+    accounts: &[NoStdAccountInfo],
+    args: &CancelTransferArgs,
+) -> Result<usize, ProgramError> {
+    // Unpack accounts
+    //
+    // 1) intent is checked by loader and zeroed, which is an implicit owner
+    //    check
+    // 2) proposer must be the intent proposer and must be signer
+    // 3) destination can be any writable account, unless the config rent
+    //    destination is selected
+    let [intent, proposer, destination, _rem @ ..] = accounts else {
+        log::sol_log(
+            "cancel_transfer expecting [intent, proposer, destination, .. ]",
+        );
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // Check destination. Config is checked by memoized validator closure.
+    if args.rent_destination == RentDestination::Config as u64 {
+        let [.., config, _system_program, _payer] = accounts else {
+            unreachable!()
+        };
+        // SAFETY: no one else has a view into config's data
+        let config_account =
+            unsafe { ProgramConfig::unchecked_load_mut(config)? };
+        config_account.check_rent_destination(destination)?;
+    }
+
+    // Check and zero intent
+    {
+        let mut intent_data = intent
+            .try_borrow_mut_data()
+            .expect("first borrow won't fail");
+        let intent_account =
+            TransferIntent::checked_load_mut(&mut intent_data)?;
+
+        // Check that the proposer is correct and signed this
+        check_authority(proposer, &intent_account.proposer)?;
+
+        intent_data.fill(0);
+    }
+
+    // Reclaim lamports. Runtime checks that destination is writable.
+    let mut intent_lamports = intent
+        .try_borrow_mut_lamports()
+        .expect("first borrow won't fail");
+    let mut destination_lamports = destination
+        .try_borrow_mut_lamports()
+        .ok_or(NanoTokenError::DuplicateAccount)?;
+    *destination_lamports += *intent_lamports;
+    *intent_lamports = 0;
+
+    Ok(3)
+}
@@ -0,0 +1,122 @@
+use crate::solana_nostd_entrypoint::NoStdAccountInfo;
+use bytemuck::{Pod, Zeroable};
+use solana_program::{log, program_error::ProgramError};
+
+use crate::{
+    error::NanoTokenError,
+    math,
+    utils::{check_authority, split_at_unchecked},
+    Mint, TokenAccount,
+};
+
+#[derive(PartialEq, Debug, Clone, Copy, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct ClawbackArgs {
+    pub amount: u64,
+}
+
+impl ClawbackArgs {
+    pub fn from_data<'a>(
+        data: &mut &'a [u8],
+    ) -> Result<&'a ClawbackArgs, ProgramError> {
+        const IX_LEN: usize = core::mem::size_of::<ClawbackArgs>();
+        if data.len() >= IX_LEN {
+            // SAFETY:
+            // We do the length check ourselves instead of via
+            // core::slice::split_at so we can return an error
+            // instead of panicking.
+            let (ix_data, rem) = unsafe { split_at_unchecked(data, IX_LEN) };
+            *data = rem;
+
+            // This is always aligned and all bit patterns are valid
+            Ok(unsafe { &*(ix_data.as_ptr() as *const ClawbackArgs) })
+        } else {
+            Err(ProgramError::InvalidInstructionData)
+        }
+    }
+
+    pub const fn size() -> usize {
+        core::mem::size_of::<Self>()
+    }
+}
+
+/// Moves tokens out of any account of a clawback-enabled mint into an
+/// account chosen by the clawback authority. Frozen accounts can be clawed
+/// back, no transfer fee is withheld, and the owner's delegate allowance is
+/// left untouched.
+///
+/// Emits a `clawback` event via sol_log_data: [from, to, amount le bytes].
+pub fn clawback(
+    accounts: &[NoStdAccountInfo],
+    args: &ClawbackArgs,
+) -> Result<usize, ProgramError> {
+    // Unpack accounts
+    //
+    // 1) from/to need a disc check and must have the mint's index. They are
+    //    mutated for nonzero amounts, which is an implicit owner check.
+    // 2) mint is not mutated, so it needs an explicit owner check
+    // 3) clawback_authority must be the mint clawback authority and must be
+    //    signer
+    let [from, to, mint, clawback_authority, _rem @ ..] = accounts else {
+        log::sol_log(
+            "clawback expecting [from, to, mint, clawback_authority, .. ]",
+        );
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // Return early if clawing back zero
+    //
+    // This is necessary!
+    // It is extremely cheap implicit owner check for from/to
+    if args.amount == 0 {
+        return Ok(4);
+    }
+
+    // Load mint
+    Mint::owner_check(mint)?;
+    let mut mint_data = mint
+        .try_borrow_mut_data()
+        .expect("first borrow won't fail");
+    let mint_account = Mint::checked_load_mut(&mut mint_data)?;
+
+    // Check that the clawback authority is correct and signed this.
+    // [0; 32] can never sign, so mints without one can't claw back.
+    check_authority(clawback_authority, &mint_account.clawback_authority)?;
+
+    // Load from/to. Pointers are used since they may alias.
+    let from_account = unsafe { TokenAccount::check_disc_raw(from)? };
+    let to_account = unsafe { TokenAccount::check_disc_raw(to)? };
+
+    // Check mints
+    if unsafe {
+        (*from_account).mint != mint_account.mint_index
+            || (*to_account).mint != mint_account.mint_index
+    } {
+        log::sol_log("invalid mint");
+        return Err(NanoTokenError::IncorrectMint.into());
+    }
+
+    // Check from_account balance
+    if unsafe { (*from_account).balance } < args.amount {
+        log::sol_log("insufficient balance");
+        return Err(NanoTokenError::InsufficientTokenBalance.into());
+    }
+
+    // Claw back
+    unsafe {
+        (*from_account).balance =
+            math::sub_balance((*from_account).balance, args.amount)?;
+        (*to_account).credit(args.amount)?;
+        (*to_account).inherit_mint_config(mint_account);
+    }
+
+    log::sol_log_data(&[
+        b"clawback",
+        from.key().as_ref(),
+        to.key().as_ref(),
+        args.amount.to_le_bytes().as_ref(),
+    ]);
+
+    Ok(4)
+}
@@ -0,0 +1,105 @@
+use crate::solana_nostd_entrypoint::NoStdAccountInfo;
+use bytemuck::{Pod, Zeroable};
+use solana_program::{log, program_error::ProgramError};
+
+use crate::{
+    error::NanoTokenError,
+    utils::{check_authority, split_at_unchecked},
+    Mint, ProgramConfig, RentDestination,
+};
+
+#[derive(PartialEq, Debug, Clone, Copy, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct CloseMintArgs {
+    /// See [RentDestination]
+    pub rent_destination: u64,
+}
+
+impl CloseMintArgs {
+    pub fn from_data<'a>(
+        data: &mut &'a [u8],
+    ) -> Result<&'a CloseMintArgs, ProgramError> {
+        const IX_LEN: usize = core::mem::size_of::<CloseMintArgs>();
+        if data.len() >= IX_LEN {
+            // SAFETY:
+            // We do the length check ourselves instead of via
+            // core::slice::split_at so we can return an error
+            // instead of panicking.
+            let (ix_data, rem) = unsafe { split_at_unchecked(data, IX_LEN) };
+            *data = rem;
+
+            // This is always aligned and all bit patterns are valid
+            Ok(unsafe { &*(ix_data.as_ptr() as *const CloseMintArgs) })
+        } else {
+            Err(ProgramError::InvalidInstructionData)
+        }
+    }
+
+    pub const fn size() -> usize {
+        core::mem::size_of::<Self>()
+    }
+}
+
+/// Closes a mint with zero supply, sending its lamports to `destination`.
+/// The mint index is never reused.
+pub fn close_mint(
+    accounts: &[NoStdAccountInfo],
+    args: &CloseMintArgs,
+) -> Result<usize, ProgramError> {
+    // Unpack accounts
+    //
+    // 1) mint is checked by loader and zeroed, which is an implicit owner
+    //    check
+    // 2) authority must be the mint authority and must be signer
+    // 3) destination can be any writable account, unless the config rent
+    //    destination is selected
+    let [mint, authority, destination, _rem @ ..] = accounts else {
+        log::sol_log(
+            "close_mint expecting [mint, authority, destination, .. ]",
+        );
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // Check destination. Config is checked by memoized validator closure.
+    if args.rent_destination == RentDestination::Config as u64 {
+        let [.., config, _system_program, _payer] = accounts else {
+            unreachable!()
+        };
+        // SAFETY: no one else has a view into config's data
+        let config_account =
+            unsafe { ProgramConfig::unchecked_load_mut(config)? };
+        config_account.check_rent_destination(destination)?;
+    }
+
+    // Check and zero mint
+    {
+        let mut mint_data = mint
+            .try_borrow_mut_data()
+            .expect("first borrow won't fail");
+        let mint_account = Mint::checked_load_mut(&mut mint_data)?;
+
+        // Check that the authority is correct and signed this
+        check_authority(authority, &mint_account.authority)?;
+
+        // Check supply
+        if mint_account.supply != 0 {
+            log::sol_log("mint supply is nonzero");
+            return Err(NanoTokenError::NonZeroSupply.into());
+        }
+
+        mint_data.fill(0);
+    }
+
+    // Reclaim lamports. Runtime checks that destination is writable.
+    let mut mint_lamports = mint
+        .try_borrow_mut_lamports()
+        .expect("first borrow won't fail");
+    let mut destination_lamports = destination
+        .try_borrow_mut_lamports()
+        .ok_or(NanoTokenError::DuplicateAccount)?;
+    *destination_lamports += *mint_lamports;
+    *mint_lamports = 0;
+
+    Ok(3)
+}
@@ -0,0 +1,123 @@
+use crate::solana_nostd_entrypoint::NoStdAccountInfo;
+use bytemuck::{Pod, Zeroable};
+use solana_program::{log, program_error::ProgramError, pubkey::Pubkey};
+
+use crate::{
+    error::NanoTokenError, utils::split_at_unchecked, AccountDiscriminator,
+    TokenAccount,
+};
+
+#[derive(PartialEq, Debug, Clone, Copy, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct CreateAssociatedAccountArgs {
+    pub owner: Pubkey,
+    pub mint: u64,
+}
+
+impl CreateAssociatedAccountArgs {
+    pub fn from_data<'a>(
+        data: &mut &'a [u8],
+    ) -> Result<&'a CreateAssociatedAccountArgs, ProgramError> {
+        const IX_LEN: usize =
+            core::mem::size_of::<CreateAssociatedAccountArgs>();
+        if data.len() >= IX_LEN {
+            // SAFETY:
+            // We do the length check ourselves instead of via
+            // core::slice::split_at so we can return an error
+            // instead of panicking.
+            let (ix_data, rem) = unsafe { split_at_unchecked(data, IX_LEN) };
+            *data = rem;
+
+            // This is always aligned and all bit patterns are valid
+            Ok(unsafe {
+                &*(ix_data.as_ptr() as *const CreateAssociatedAccountArgs)
+            })
+        } else {
+            Err(ProgramError::InvalidInstructionData)
+        }
+    }
+
+    pub const fn size() -> usize {
+        core::mem::size_of::<Self>()
+    }
+}
+
+/// Like InitializeAccount, but the (owner, mint) pda bump is derived on-chain
+/// so callers don't need to compute it. This is idempotent: if the account
+/// already exists for this owner and mint, nothing happens.
+///
+/// CU-sensitive callers should prefer InitializeAccount with an off-chain
+/// bump.
+pub fn create_associated_account(
+    accounts: &[NoStdAccountInfo],
+    args: &CreateAssociatedAccountArgs,
+) -> Result<usize, ProgramError> {
+    // Unpack accounts
+    //
+    // 1) Token account will be checked by checked_initialize_account, or
+    //    against the existing account data if already initialized
+    // 2) Config will be checked by memoized validator closure
+    // 3) system program will be checked by memoized validator closure
+    // 4) payer will be checked by the sol transfer if necessary
+    let [token_account, _rem @ .., config, system_program, payer] = accounts
+    else {
+        log::sol_log(
+            "create_associated_account expecting [token_account, .. config, system_program, payer]",
+        );
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // Derive address
+    //
+    // The target_os = "solana" impl is alloc-free
+    let (expected_address, bump) = Pubkey::find_program_address(
+        &[args.owner.as_ref(), args.mint.to_le_bytes().as_ref()],
+        &crate::ID,
+    );
+    if solana_program::program_memory::sol_memcmp(
+        token_account.key().as_ref(),
+        expected_address.as_ref(),
+        32,
+    ) != 0
+    {
+        log::sol_log("token account is not the associated account");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    // Already exists. The address pins owner and mint, so only the
+    // discriminator and program owner need checking.
+    if token_account.data_len() != 0 {
+        let data = token_account
+            .try_borrow_data()
+            .ok_or(NanoTokenError::DuplicateAccount)?;
+        if data[0] != AccountDiscriminator::Token as u8
+            || solana_program::program_memory::sol_memcmp(
+                token_account.owner().as_ref(),
+                crate::ID.as_ref(),
+                32,
+            ) != 0
+        {
+            log::sol_log(
+                "associated account exists but is not a token account",
+            );
+            return Err(ProgramError::InvalidAccountData);
+        }
+        return Ok(1);
+    }
+
+    super::initialize_account::checked_initialize_account(
+        payer,
+        config,
+        token_account,
+        system_program,
+        &args.owner,
+        args.mint,
+        bump,
+        // Like token-2022 associated accounts, so the owner -> address
+        // mapping can always be trusted
+        TokenAccount::IMMUTABLE_OWNER,
+    )?;
+
+    Ok(1)
+}
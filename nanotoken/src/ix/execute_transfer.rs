@@ -0,0 +1,160 @@
+use crate::solana_nostd_entrypoint::NoStdAccountInfo;
+use bytemuck::{Pod, Zeroable};
+use solana_program::{
+    clock::Clock, log, program_error::ProgramError, sysvar::Sysvar,
+};
+
+use crate::{
+    consts::NATIVE_MINT_INDEX,
+    error::NanoTokenError,
+    utils::{check_memo_present, move_native_lamports},
+    Mint, TokenAccount, TransferIntent,
+};
+
+#[derive(PartialEq, Debug, Clone, Pod, Zeroable, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct ExecuteTransferArgs {
+    // Keeping this scaffolded just in case...
+}
+
+impl ExecuteTransferArgs {
+    pub fn from_data<'a>(
+        _data: &mut &'a [u8],
+    ) -> Result<&'a ExecuteTransferArgs, ProgramError> {
+        // Zero-sized, so there is nothing to consume
+        Ok(&ExecuteTransferArgs {})
+    }
+
+    pub const fn size() -> usize {
+        core::mem::size_of::<Self>()
+    }
+}
+
+/// Permissionless once the intent has enough approvals and its timelock has
+/// passed.
+pub fn execute_transfer(
+    accounts: &[NoStdAccountInfo],
+    _args: &ExecuteTransferArgs,
+) -> Result<usize, ProgramError> {
+    // Unpack accounts
+    //
+    // 1) intent is checked by loader and mutated, which is an implicit owner
+    //    check
+    // 2) from/to must be the distinct accounts recorded in the intent, and are
+    //    mutated which is an implicit owner check
+    let [intent, from, to, _rem @ ..] = accounts else {
+        log::sol_log("execute_transfer expecting [intent, from, to, .. ]");
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    let mut intent_data = intent
+        .try_borrow_mut_data()
+        .ok_or(NanoTokenError::DuplicateAccount)?;
+    let intent_account = TransferIntent::checked_load_mut(&mut intent_data)?;
+
+    // Check intent state
+    if intent_account.executed != 0 {
+        log::sol_log("intent was already executed");
+        return Err(NanoTokenError::IntentAlreadyExecuted.into());
+    }
+    if intent_account.approval_count() < intent_account.threshold as u32 {
+        log::sol_log("intent does not have enough approvals");
+        return Err(NanoTokenError::IntentThresholdNotMet.into());
+    }
+    if intent_account.execute_after > 0
+        && Clock::get()?.unix_timestamp < intent_account.execute_after
+    {
+        log::sol_log("intent is still timelocked");
+        return Err(NanoTokenError::IntentTimelocked.into());
+    }
+
+    // Check from/to are the intended accounts
+    if solana_program::program_memory::sol_memcmp(
+        from.key().as_ref(),
+        intent_account.from.as_ref(),
+        32,
+    ) != 0
+        || solana_program::program_memory::sol_memcmp(
+            to.key().as_ref(),
+            intent_account.to.as_ref(),
+            32,
+        ) != 0
+    {
+        log::sol_log("from/to do not match intent");
+        return Err(NanoTokenError::IntentMismatch.into());
+    }
+
+    // from and to are borrowed for the rest of the op, so they must be
+    // distinct
+    if from.key() == to.key() {
+        log::sol_log("from and to must be distinct");
+        return Err(NanoTokenError::DuplicateAccount.into());
+    }
+
+    // Load from/to. Mints were checked at proposal and cannot change.
+    let mut from_data = from
+        .try_borrow_mut_data()
+        .ok_or(NanoTokenError::DuplicateAccount)?;
+    let from_account = TokenAccount::checked_load_mut(&mut from_data)?;
+    let mut to_data = to
+        .try_borrow_mut_data()
+        .ok_or(NanoTokenError::DuplicateAccount)?;
+    let to_account = TokenAccount::checked_load_mut(&mut to_data)?;
+    from_account.check_not_frozen()?;
+    to_account.check_not_frozen()?;
+    from_account.check_not_timelocked()?;
+    // The mint may be passed anywhere after this op's accounts
+    from_account.check_not_paused(accounts)?;
+    // Likewise for the mint list
+    from_account.check_list(&to_account.owner, accounts)?;
+    if to_account.requires_memo() {
+        // The instructions sysvar may be passed anywhere after this op's
+        // accounts
+        check_memo_present(accounts)?;
+    }
+
+    // pay_royalty loads the royalty recipient unchecked, so it must not be
+    // from or to either
+    if from_account.state & TokenAccount::ROYALTY != 0 {
+        // SAFETY: no one holds a mutable view into mint data
+        if let Some(mint) = unsafe { Mint::find(from_account.mint, accounts) } {
+            if mint.royalty_recipient == *from.key()
+                || mint.royalty_recipient == *to.key()
+            {
+                log::sol_log("royalty recipient must not be from or to");
+                return Err(NanoTokenError::DuplicateAccount.into());
+            }
+        }
+    }
+
+    // Check from_account balance
+    if from_account.balance < intent_account.amount {
+        log::sol_log("insufficient balance");
+        return Err(NanoTokenError::InsufficientTokenBalance.into());
+    }
+
+    // Transfer, less royalty. The mint and royalty recipient may be passed
+    // anywhere after this op's accounts.
+    // SAFETY: from and to are distinct borrows
+    unsafe {
+        let royalty = TokenAccount::pay_royalty(
+            from_account,
+            intent_account.amount,
+            accounts,
+        )?;
+        TokenAccount::move_balance(
+            from_account,
+            to_account,
+            intent_account.amount - royalty,
+        )?
+    };
+    if from_account.mint == NATIVE_MINT_INDEX {
+        // Native balances carry their lamports with them
+        // SAFETY: no one holds a view into from/to lamports
+        unsafe { move_native_lamports(from, to, intent_account.amount) };
+    }
+    intent_account.executed = 1;
+
+    Ok(3)
+}
@@ -0,0 +1,74 @@
+use crate::solana_nostd_entrypoint::NoStdAccountInfo;
+use bytemuck::{Pod, Zeroable};
+use solana_program::{log, program_error::ProgramError};
+
+use crate::{
+    error::NanoTokenError, utils::check_authority, Mint, TokenAccount,
+};
+
+#[derive(PartialEq, Debug, Clone, Pod, Zeroable, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct FreezeAccountArgs {
+    // Keeping this scaffolded just in case...
+}
+
+impl FreezeAccountArgs {
+    pub fn from_data<'a>(
+        _data: &mut &'a [u8],
+    ) -> Result<&'a FreezeAccountArgs, ProgramError> {
+        // Zero-sized, so there is nothing to consume
+        Ok(&FreezeAccountArgs {})
+    }
+
+    pub const fn size() -> usize {
+        core::mem::size_of::<Self>()
+    }
+}
+
+pub fn freeze_account(
+    accounts: &[NoStdAccountInfo],
+    _args: &FreezeAccountArgs,
+) -> Result<usize, ProgramError> {
+    // Unpack accounts
+    //
+    // 1) token_account is checked by loader and mutated, which is an implicit
+    //    owner check
+    // 2) mint is not mutated, so it needs an explicit owner check
+    // 3) freeze_authority must be the mint freeze authority and must be signer
+    let [token_account, mint, freeze_authority, _rem @ ..] = accounts else {
+        log::sol_log(
+            "freeze_account expecting [token_account, mint, freeze_authority, .. ]",
+        );
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // Load mint
+    Mint::owner_check(mint)?;
+    let mut mint_data = mint
+        .try_borrow_mut_data()
+        .expect("first borrow won't fail");
+    let mint_account = Mint::checked_load_mut(&mut mint_data)?;
+
+    // Check that the freeze authority is correct and signed this.
+    // [0; 32] can never sign, so mints without one can't freeze.
+    check_authority(freeze_authority, &mint_account.freeze_authority)?;
+
+    // Load token account
+    let mut token_account_data = token_account
+        .try_borrow_mut_data()
+        .ok_or(NanoTokenError::DuplicateAccount)?;
+    let token_account =
+        TokenAccount::checked_load_mut(&mut token_account_data)?;
+
+    // Check mint
+    if token_account.mint != mint_account.mint_index {
+        log::sol_log("invalid mint");
+        return Err(NanoTokenError::IncorrectMint.into());
+    }
+
+    // Freeze
+    token_account.state |= TokenAccount::FROZEN;
+
+    Ok(3)
+}
@@ -0,0 +1,150 @@
+use crate::solana_nostd_entrypoint::NoStdAccountInfo;
+use bytemuck::{Pod, Zeroable};
+use solana_program::{
+    clock::Clock, log, program_error::ProgramError, rent::Rent, sysvar::Sysvar,
+};
+
+use crate::{
+    error::NanoTokenError, utils::split_at_unchecked, ProgramConfig,
+    RentDestination, TokenAccount,
+};
+
+#[derive(PartialEq, Debug, Clone, Copy, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct GarbageCollectArgs {
+    /// See [RentDestination]
+    pub rent_destination: u64,
+}
+
+impl GarbageCollectArgs {
+    pub fn from_data<'a>(
+        data: &mut &'a [u8],
+    ) -> Result<&'a GarbageCollectArgs, ProgramError> {
+        const IX_LEN: usize = core::mem::size_of::<GarbageCollectArgs>();
+        if data.len() >= IX_LEN {
+            // SAFETY:
+            // We do the length check ourselves instead of via
+            // core::slice::split_at so we can return an error
+            // instead of panicking.
+            let (ix_data, rem) = unsafe { split_at_unchecked(data, IX_LEN) };
+            *data = rem;
+
+            // This is always aligned and all bit patterns are valid
+            Ok(unsafe { &*(ix_data.as_ptr() as *const GarbageCollectArgs) })
+        } else {
+            Err(ProgramError::InvalidInstructionData)
+        }
+    }
+
+    pub const fn size() -> usize {
+        core::mem::size_of::<Self>()
+    }
+}
+
+/// Permissionlessly closes an empty token account once it is older than the
+/// config's gc_min_age. The config's gc_bounty goes to the collector and the
+/// rest of the rent goes back to the account's rent payer, or to the config
+/// rent destination if selected.
+///
+/// Frozen accounts and accounts with withheld fees are never collected.
+pub fn garbage_collect(
+    accounts: &[NoStdAccountInfo],
+    args: &GarbageCollectArgs,
+) -> Result<usize, ProgramError> {
+    // Unpack accounts
+    //
+    // 1) token_account is checked by loader and zeroed, which is an implicit
+    //    owner check
+    // 2) rent_payer must be the account's recorded rent payer, or the config
+    //    rent destination if selected
+    // 3) collector can be any writable account
+    // 4) config is checked by memoized validator closure
+    let [token_account, rent_payer, collector, _rem @ .., config, _system_program, _payer] =
+        accounts
+    else {
+        log::sol_log(
+            "garbage_collect expecting [token_account, rent_payer, collector, .. config, system_program, payer]",
+        );
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // Load config
+    // SAFETY: no one else has a view into config's data
+    let config_account = unsafe { ProgramConfig::unchecked_load_mut(config)? };
+    let (min_age, bounty) =
+        (config_account.gc_min_age, config_account.gc_bounty);
+    let to_config_destination =
+        args.rent_destination == RentDestination::Config as u64;
+    if to_config_destination {
+        config_account.check_rent_destination(rent_payer)?;
+    }
+    if min_age == 0 {
+        log::sol_log("garbage collection is disabled");
+        return Err(NanoTokenError::GarbageCollectionDisabled.into());
+    }
+
+    // Check and zero token account
+    {
+        let mut token_account_data = token_account
+            .try_borrow_mut_data()
+            .ok_or(NanoTokenError::DuplicateAccount)?;
+        let token_account_state =
+            TokenAccount::checked_load_mut(&mut token_account_data)?;
+
+        if token_account_state.balance != 0
+            || token_account_state.withheld_fees != 0
+        {
+            log::sol_log("token account is not empty");
+            return Err(NanoTokenError::NonZeroBalance.into());
+        }
+        token_account_state.check_not_frozen()?;
+        if Clock::get()?.unix_timestamp
+            < token_account_state
+                .created_at
+                .saturating_add(min_age)
+        {
+            log::sol_log("token account is too young to collect");
+            return Err(NanoTokenError::AccountTooYoung.into());
+        }
+        if !to_config_destination
+            && solana_program::program_memory::sol_memcmp(
+                rent_payer.key().as_ref(),
+                token_account_state.rent_payer.as_ref(),
+                32,
+            ) != 0
+        {
+            log::sol_log("incorrect rent payer");
+            return Err(NanoTokenError::RentDestinationMismatch.into());
+        }
+
+        token_account_data.fill(0);
+    }
+
+    // Reclaim lamports. Runtime checks that rent_payer/collector are writable.
+    let mut token_account_lamports = token_account
+        .try_borrow_mut_lamports()
+        .expect("first borrow won't fail");
+
+    // Lamports beyond rent belong to the owner, not the rent payer
+    if *token_account_lamports
+        > Rent::get()?.minimum_balance(token_account.data_len())
+    {
+        log::sol_log("withdraw excess lamports before collecting");
+        return Err(NanoTokenError::ExcessLamports.into());
+    }
+    let bounty = bounty.min(*token_account_lamports);
+    {
+        let mut collector_lamports = collector
+            .try_borrow_mut_lamports()
+            .ok_or(NanoTokenError::DuplicateAccount)?;
+        *collector_lamports += bounty;
+    }
+    let mut rent_payer_lamports = rent_payer
+        .try_borrow_mut_lamports()
+        .ok_or(NanoTokenError::DuplicateAccount)?;
+    *rent_payer_lamports += *token_account_lamports - bounty;
+    *token_account_lamports = 0;
+
+    Ok(3)
+}
@@ -0,0 +1,64 @@
+use crate::solana_nostd_entrypoint::NoStdAccountInfo;
+use bytemuck::{Pod, Zeroable};
+use solana_program::{
+    log, program::set_return_data, program_error::ProgramError,
+};
+
+use crate::{utils::split_at_unchecked, TokenAccount};
+
+#[derive(PartialEq, Debug, Clone, Copy, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct GetAccountDataSizeArgs {
+    /// Bytes of extension space wanted after the TokenAccount, see Realloc
+    pub extension_len: u64,
+}
+
+impl GetAccountDataSizeArgs {
+    pub fn from_data<'a>(
+        data: &mut &'a [u8],
+    ) -> Result<&'a GetAccountDataSizeArgs, ProgramError> {
+        const IX_LEN: usize = core::mem::size_of::<GetAccountDataSizeArgs>();
+        if data.len() >= IX_LEN {
+            // SAFETY:
+            // We do the length check ourselves instead of via
+            // core::slice::split_at so we can return an error
+            // instead of panicking.
+            let (ix_data, rem) = unsafe { split_at_unchecked(data, IX_LEN) };
+            *data = rem;
+
+            // This is always aligned and all bit patterns are valid
+            Ok(
+                unsafe {
+                    &*(ix_data.as_ptr() as *const GetAccountDataSizeArgs)
+                },
+            )
+        } else {
+            Err(ProgramError::InvalidInstructionData)
+        }
+    }
+
+    pub const fn size() -> usize {
+        core::mem::size_of::<Self>()
+    }
+}
+
+/// Read-only view. Writes the data length (u64 le) of a token account with
+/// `extension_len` bytes of extensions into return data.
+///
+/// Return data is per transaction, so if this is batched with other return
+/// data ops only the last one is visible.
+pub fn get_account_data_size(
+    _accounts: &[NoStdAccountInfo],
+    args: &GetAccountDataSizeArgs,
+) -> Result<usize, ProgramError> {
+    let Some(data_size) =
+        (TokenAccount::space() as u64).checked_add(args.extension_len)
+    else {
+        log::sol_log("extension_len is too large");
+        return Err(ProgramError::InvalidInstructionData);
+    };
+    set_return_data(&data_size.to_le_bytes());
+
+    Ok(0)
+}
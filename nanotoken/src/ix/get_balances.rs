@@ -0,0 +1,113 @@
+use crate::solana_nostd_entrypoint::NoStdAccountInfo;
+use bytemuck::{Pod, Zeroable};
+use solana_program::{
+    log, program::set_return_data, program_error::ProgramError,
+};
+
+use crate::{
+    consts::MAX_BALANCE_QUERY, utils::split_at_unchecked, AccountDiscriminator,
+    TokenAccount,
+};
+
+#[derive(PartialEq, Debug, Clone, Copy, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct GetBalancesArgs {
+    /// Number of token accounts to read. At most MAX_BALANCE_QUERY.
+    pub num_accounts: u64,
+}
+
+impl GetBalancesArgs {
+    pub fn from_data<'a>(
+        data: &mut &'a [u8],
+    ) -> Result<&'a GetBalancesArgs, ProgramError> {
+        const IX_LEN: usize = core::mem::size_of::<GetBalancesArgs>();
+        if data.len() >= IX_LEN {
+            // SAFETY:
+            // We do the length check ourselves instead of via
+            // core::slice::split_at so we can return an error
+            // instead of panicking.
+            let (ix_data, rem) = unsafe { split_at_unchecked(data, IX_LEN) };
+            *data = rem;
+
+            // This is always aligned and all bit patterns are valid
+            Ok(unsafe { &*(ix_data.as_ptr() as *const GetBalancesArgs) })
+        } else {
+            Err(ProgramError::InvalidInstructionData)
+        }
+    }
+
+    pub const fn size() -> usize {
+        core::mem::size_of::<Self>()
+    }
+}
+
+/// One entry of the GetBalances return data, in account order.
+#[derive(PartialEq, Debug, Clone, Copy, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct BalanceEntry {
+    pub mint: u64,
+    pub balance: u64,
+}
+
+/// Read-only view. Writes one [BalanceEntry] per account into return data.
+///
+/// Return data is per transaction, so if this is batched with other
+/// GetBalances ops only the last one is visible.
+pub fn get_balances(
+    accounts: &[NoStdAccountInfo],
+    args: &GetBalancesArgs,
+) -> Result<usize, ProgramError> {
+    let num_accounts = args.num_accounts as usize;
+    if num_accounts > MAX_BALANCE_QUERY {
+        log::sol_log("too many accounts in balance query");
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    // Unpack accounts
+    //
+    // 1) token accounts are not mutated so they need an explicit owner check
+    //    along with the disc check
+    let Some(token_accounts) = accounts.get(..num_accounts) else {
+        log::sol_log("get_balances expecting [token_accounts.., ..]");
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    let mut entries = [BalanceEntry::zeroed(); MAX_BALANCE_QUERY];
+    for (token_account, entry) in token_accounts.iter().zip(&mut entries) {
+        // Check owner
+        if solana_program::program_memory::sol_memcmp(
+            token_account.owner().as_ref(),
+            crate::ID.as_ref(),
+            32,
+        ) != 0
+        {
+            log::sol_log("token_account has incorrect owner");
+            return Err(ProgramError::InvalidAccountOwner);
+        }
+
+        // Check disc. Duplicates are fine since nothing is mutated.
+        let data = token_account
+            .try_borrow_data()
+            .expect("no mutable borrows outstanding");
+        let (disc, token_account_bytes) = data.split_at(8);
+        if disc[0] != AccountDiscriminator::Token as u8 {
+            log::sol_log("token_account discriminator is incorrect");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        // SAFETY: disc was checked and token accounts are always allocated
+        // with at least TokenAccount::space()
+        let account =
+            unsafe { &*(token_account_bytes.as_ptr() as *const TokenAccount) };
+        *entry = BalanceEntry {
+            mint: account.mint,
+            balance: account.balance,
+        };
+    }
+
+    set_return_data(bytemuck::cast_slice(&entries[..num_accounts]));
+
+    Ok(num_accounts)
+}
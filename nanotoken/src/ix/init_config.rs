@@ -3,18 +3,24 @@ extern crate alloc; // needed for rkyv args
 use crate::solana_nostd_entrypoint::NoStdAccountInfo;
 use bytemuck::{Pod, Zeroable};
 use solana_program::{
-    entrypoint::ProgramResult, log, program_error::ProgramError,
+    entrypoint::ProgramResult, log, program_error::ProgramError, pubkey::Pubkey,
 };
 
 use crate::{
-    utils::{split_at_mut_unchecked, split_at_unchecked},
+    consts::{CONFIG_BUMP, CONFIG_VERSION},
+    utils::{
+        create_pda_funded_by_payer, split_at_mut_unchecked, split_at_unchecked,
+    },
     AccountDiscriminator, ProgramConfig,
 };
 
 #[derive(PartialEq, Debug, Clone, Pod, Zeroable, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct InitConfigArgs {
-    // Keeping this scaffolded just in case...
+    /// Admin for privileged operations on program-owned accounts (e.g.
+    /// sweeping excess lamports from vaults)
+    pub authority: Pubkey,
 }
 
 impl InitConfigArgs {
@@ -45,17 +51,31 @@ pub fn initialize_config(
     log::sol_log("initializing config");
     // Unpack accounts
     //
-    // 1) Config account needs a pubkey + owner + data_len check, which is done
-    //    in checked_initialize_config
-    // 2) unused
-    // 3) Anyone can be the initializer because initialization is determinstic
-    //    with no args, so they're basically just paying tx fee
-    let [_rem @ .., config, _system_program, _payer] = accounts else {
+    // 1) Config pubkey is checked in process_instruction. It is created here,
+    //    so anyone can bootstrap a fresh deployment. Whoever does sets the
+    //    config authority, so deployers should initialize in the same
+    //    transaction as (or right after) the program deploy.
+    // 2) system_program is checked in process_instruction
+    // 3) payer is checked by the system program when funding config
+    let [_rem @ .., config, system_program, payer] = accounts else {
         return Err(ProgramError::NotEnoughAccountKeys);
     };
 
-    // 3) Config account needs a pubkey + owner + data_len check, which is done
-    //    in checked_initialize_config
+    // Create config. This fails if it already exists.
+    // SAFETY: no one has any write access to any account at this stage.
+    unsafe {
+        create_pda_funded_by_payer(
+            config.to_info_c(),
+            &crate::ID,
+            ProgramConfig::space() as u64,
+            &[b"config", &[CONFIG_BUMP]],
+            system_program.to_info_c(),
+            payer.to_info_c(),
+        )?
+    };
+
+    // Config account needs a data_len and disc check, which is done in
+    // checked_initialize_config
     checked_initialize_config(config, args)?;
 
     Ok(0)
@@ -63,7 +83,7 @@ pub fn initialize_config(
 
 /// Checks program config and initializes it
 ///
-/// Check 1) Expecting a particular pubkey (done in process_instruction)
+/// Check 1) Expecting the config pda (done in process_instruction)
 /// Check 2) Expecting a particular data length
 /// Check 3) Expecting uninitialized disc
 ///
@@ -75,7 +95,7 @@ pub fn initialize_config(
 /// the runtime complains, then we were not the account owner.
 fn checked_initialize_config(
     config: &NoStdAccountInfo,
-    _args: &InitConfigArgs,
+    args: &InitConfigArgs,
 ) -> ProgramResult {
     // Get account data
     // SAFETY: this is the one and only time any account data is mutably
@@ -106,6 +126,7 @@ fn checked_initialize_config(
 
         // Init 1) Write initialized disc
         *padded_disc.get_unchecked_mut(0) = AccountDiscriminator::Config as u8;
+        *padded_disc.get_unchecked_mut(1) = CONFIG_VERSION;
 
         // Init 2) Write config
         // Note:
@@ -113,9 +134,25 @@ fn checked_initialize_config(
         // fields SAFETY: due to 8 byte disc and bpf alignment, size and
         // 8-byte alignment of bytes is checked
         const _: () = assert!(core::mem::align_of::<ProgramConfig>() == 8);
-        let ProgramConfig { mint_index } =
-            &mut *(config_data.as_mut_ptr() as *mut ProgramConfig);
+        let ProgramConfig {
+            mint_index,
+            authority,
+            pending_authority,
+            gc_min_age,
+            gc_bounty,
+            rent_destination,
+            features,
+        } = &mut *(config_data.as_mut_ptr() as *mut ProgramConfig);
         *mint_index = 0;
+        *authority = args.authority;
+        *pending_authority = Pubkey::default();
+        // Garbage collection is off until the authority configures it
+        *gc_min_age = 0;
+        *gc_bounty = 0;
+        // Closed accounts refund rent until the authority sets a destination
+        *rent_destination = Pubkey::default();
+        // New behavior is off until the authority enables it
+        *features = 0;
     }
     Ok(())
 }
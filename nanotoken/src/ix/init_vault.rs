@@ -5,19 +5,30 @@ use solana_program::{
 };
 
 use crate::{
+    consts::VAULT_INFO_VERSION,
+    offsets::{
+        VAULT_INFO_BUMP_OFFSET, VAULT_INFO_NANOTOKEN_MINT_OFFSET,
+        VAULT_INFO_TOKENKEG_MINT_OFFSET, VAULT_INFO_TOKENKEG_VAULT_OFFSET,
+        VAULT_INFO_VAULT_BUMP_OFFSET,
+    },
     utils::{
-        create_pda_funded_by_payer,
-        spl_token_utils::{MintAccountInfo, SPL_TOKEN_PROGRAM},
+        create_pda_funded_by_payer, paranoid_check_pda,
+        spl_token_utils::{token_account_len, MintAccountInfo},
         split_at_unchecked,
     },
     AccountDiscriminator, VaultInfo,
 };
 
+use super::{InitializeMintArgs, TransferFeeConfig};
+
 #[derive(PartialEq, Debug, Clone, Copy, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct InitializeVaultArgs {
-    info_bump: u32,
-    vault_bump: u32,
+    /// Bump of the vault info pda, see [VaultInfo::info]
+    pub info_bump: u32,
+    /// Bump of the tokenkeg vault pda, see [VaultInfo::vault]
+    pub vault_bump: u32,
 }
 
 impl InitializeVaultArgs {
@@ -53,7 +64,7 @@ pub fn initialize_vault(
     //
     // 1) tokenkeg_mint will be checked by spl token create_token_account
     // 2) tokenkeg_vault will be checked by spl token create_token_account
-    // 3) tokenkeg_program is validated by cpi, which expects &SPL_TOKEN_PROGRAM to own the pda being mutated
+    // 3) tokenkeg_program must be the token program (Tokenkeg or Token-2022) that owns tokenkeg_mint
     // 4) vault_info is validated by create_pda_funded_by_payer + mutation
     // 5) nanotoken_mint is validated by create mint handler
     // 6) Config will be checked by memoized validator closure
@@ -68,9 +79,21 @@ pub fn initialize_vault(
         return Err(ProgramError::NotEnoughAccountKeys);
     };
 
-    // Validate token mint
+    // Validate token mint. This also rejects incompatible token-2022
+    // extensions.
     let tokenkeg_mint_info = MintAccountInfo::new(&tokenkeg_mint)?;
 
+    // Validate token program
+    if solana_program::program_memory::sol_memcmp(
+        tokenkeg_program.key().as_ref(),
+        tokenkeg_mint.owner().as_ref(),
+        32,
+    ) != 0
+    {
+        log::sol_log("token program does not own mint");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
     // Create vault_info
     create_vault_info(
         tokenkeg_mint,
@@ -80,6 +103,7 @@ pub fn initialize_vault(
         tokenkeg_vault,
         nanotoken_mint.key(),
         args.info_bump as u8,
+        args.vault_bump as u8,
     )?;
 
     // Create spl token vault
@@ -91,14 +115,25 @@ pub fn initialize_vault(
         vault_info,
         system_program,
         args.vault_bump as u8,
+        token_account_len(&tokenkeg_mint_info.data),
     )?;
 
     // Create nano token mint
     super::initialize_mint::checked_initialized_mint(
         config,
         nanotoken_mint,
-        vault_info.key(),
-        &(tokenkeg_mint_info.mint.decimals as u64),
+        // Vault mints can't be frozen, paused, or clawed back, charge no
+        // transfer fee (so transmute never withholds anything), and are
+        // uncapped since supply is backed by the vault
+        &InitializeMintArgs {
+            authority: *vault_info.key(),
+            decimals: tokenkeg_mint_info.mint.decimals as u64,
+            freeze_authority: Pubkey::default(),
+            transfer_fee: TransferFeeConfig::default(),
+            pause_authority: Pubkey::default(),
+            max_supply: 0,
+            clawback_authority: Pubkey::default(),
+        },
     )?;
 
     Ok(5)
@@ -112,8 +147,10 @@ fn create_vault_info(
     tokenkeg_vault: &NoStdAccountInfo,
     nanotoken_mint: &Pubkey,
     info_bump: u8,
+    vault_bump: u8,
 ) -> Result<(), ProgramError> {
     // Create vault info account
+    paranoid_check_pda(&[b"info", tokenkeg_mint.key().as_ref()], vault_info)?;
     let pda_seeds = &[b"info", tokenkeg_mint.key().as_ref(), &[info_bump]];
     // SAFETY: no one has any write access to any account at this stage.
     unsafe {
@@ -135,34 +172,40 @@ fn create_vault_info(
 
         // Write discriminator
         *vault_info_account_data = AccountDiscriminator::VaultInfo as u8;
+        *vault_info_account_data.add(1) = VAULT_INFO_VERSION;
 
         // Write spl mint
         core::ptr::copy_nonoverlapping(
             tokenkeg_mint.key().as_ref().as_ptr(),
-            vault_info_account_data.add(8),
+            vault_info_account_data.add(VAULT_INFO_TOKENKEG_MINT_OFFSET),
             32,
         );
 
         // Write spl vault
         core::ptr::copy_nonoverlapping(
             tokenkeg_vault.key().as_ref().as_ptr(),
-            vault_info_account_data.add(40),
+            vault_info_account_data.add(VAULT_INFO_TOKENKEG_VAULT_OFFSET),
             32,
         );
 
         // Write nanotoken mint (will be initialized by end of ix)
         core::ptr::copy_nonoverlapping(
             nanotoken_mint.as_ref().as_ptr(),
-            vault_info_account_data.add(72),
+            vault_info_account_data.add(VAULT_INFO_NANOTOKEN_MINT_OFFSET),
             32,
         );
 
         // Write info bump
-        *vault_info_account_data.add(104) = info_bump;
+        *vault_info_account_data.add(VAULT_INFO_BUMP_OFFSET) = info_bump;
+
+        // Write vault bump. A wrong one fails vault creation below since the
+        // seeds won't sign for the vault.
+        *vault_info_account_data.add(VAULT_INFO_VAULT_BUMP_OFFSET) = vault_bump;
     };
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 fn initialize_program_owned_spl_vault(
     tokenkeg_mint: &NoStdAccountInfo,
     tokenkeg_vault: &NoStdAccountInfo,
@@ -171,15 +214,20 @@ fn initialize_program_owned_spl_vault(
     vault_info: &NoStdAccountInfo,
     system_program: &NoStdAccountInfo,
     vault_bump: u8,
+    vault_len: usize,
 ) -> ProgramResult {
     // Create account, initialize account
+    paranoid_check_pda(
+        &[b"vault", tokenkeg_mint.key().as_ref()],
+        tokenkeg_vault,
+    )?;
     let vault_seeds = [b"vault", tokenkeg_mint.key().as_ref(), &[vault_bump]];
     // SAFETY: no one has any write access to any account at this stage.
     unsafe {
         create_pda_funded_by_payer(
             tokenkeg_vault.to_info_c(),
             tokenkeg_program.key(),
-            165,
+            vault_len as u64,
             &vault_seeds,
             system_program.to_info_c(),
             payer.to_info_c(),
@@ -198,7 +246,7 @@ fn initialize_program_owned_spl_vault(
     }
     let accounts = [tokenkeg_vault.to_meta_c(), tokenkeg_mint.to_meta_c()];
     let init_account_ix = InstructionC {
-        program_id: &SPL_TOKEN_PROGRAM,
+        program_id: tokenkeg_program.key(),
         accounts: accounts.as_ptr(),
         accounts_len: 2,
         data: data.as_mut_ptr(),
@@ -1,17 +1,21 @@
 use crate::solana_nostd_entrypoint::NoStdAccountInfo;
 use bytemuck::{Pod, Zeroable};
 use solana_program::{
-    entrypoint::ProgramResult, log, program_error::ProgramError, pubkey::Pubkey,
+    clock::Clock, entrypoint::ProgramResult, log, program_error::ProgramError,
+    pubkey::Pubkey, sysvar::Sysvar,
 };
 
 use crate::{
+    consts::{NATIVE_MINT_INDEX, TOKEN_ACCOUNT_VERSION},
     utils::{
-        create_pda_funded_by_payer, split_at_mut_unchecked, split_at_unchecked,
+        create_pda_funded_by_payer, paranoid_check_pda, split_at_mut_unchecked,
+        split_at_unchecked,
     },
     AccountDiscriminator, ProgramConfig, TokenAccount,
 };
 
 #[derive(PartialEq, Debug, Clone, Copy, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct InitializeAccountArgs {
     pub owner: Pubkey,
@@ -21,7 +25,16 @@ pub struct InitializeAccountArgs {
     // This is provided as an argument to provide the option to do it off
     // chain. Otherwise, if we do it on-chain via a syscall, it will always
     // be done. The cpi client will abstract this away and do it internally
+    //
+    // Ignored when the token account is a keypair that signs.
     pub bump: u64,
+    /// u64 is used for alignment. Nonzero makes the owner permanent, see
+    /// [TokenAccount::IMMUTABLE_OWNER].
+    pub immutable_owner: u64,
+    /// u64 is used for alignment. Nonzero requires the owner to co-sign, so
+    /// no one can attach unsolicited accounts to the owner's key. The owner
+    /// may be passed anywhere after the token account.
+    pub owner_must_sign: u64,
 }
 
 impl InitializeAccountArgs {
@@ -56,7 +69,8 @@ pub fn initialize_account(
     // log::sol_log("init account");
     // Unpack accounts
     //
-    // 1) Token account will be checked by checked_initialize_account
+    // 1) Token account will be checked by checked_initialize_account. It is
+    //    either the (owner, mint) pda or any keypair that signs.
     // 2) Config will be checked
     // 4) payer will be checked by the sol transfer if necessary
     let [token_account, _rem @ .., config, system_program, payer] = accounts
@@ -67,6 +81,17 @@ pub fn initialize_account(
         return Err(ProgramError::NotEnoughAccountKeys);
     };
 
+    // Check owner co-signed, if required. The owner can be anywhere in
+    // _rem, so we scan rather than fix a position.
+    if args.owner_must_sign != 0
+        && !accounts
+            .iter()
+            .any(|account| account.is_signer() && *account.key() == args.owner)
+    {
+        log::sol_log("owner must sign to initialize this account");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
     checked_initialize_account(
         payer,
         config,
@@ -75,6 +100,11 @@ pub fn initialize_account(
         &args.owner,
         args.mint,
         args.bump as u8,
+        if args.immutable_owner != 0 {
+            TokenAccount::IMMUTABLE_OWNER
+        } else {
+            0
+        },
     )?;
 
     Ok(1)
@@ -82,6 +112,11 @@ pub fn initialize_account(
 
 /// Creates token account and initializes it
 ///
+/// If `token_account` is a signer it is created at its keypair address and
+/// `bump` is ignored, so an owner can hold many accounts for the same mint.
+/// Nothing downstream depends on the address: owner and mint are read from
+/// account data.
+///
 /// Check 1) Check seeds (valid index + checked by initialization)
 /// owner and data len need not be checked since we are allocating account
 ///
@@ -92,6 +127,7 @@ pub fn initialize_account(
 /// /// Note: owner check is done by the runtime after we validate data change.
 /// If we validate uninitialized disc, write initialized disc, and then
 /// the runtime complains, then we were not the account owner.
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn checked_initialize_account(
     payer: &NoStdAccountInfo,
     config: &NoStdAccountInfo,
@@ -101,6 +137,7 @@ pub(crate) fn checked_initialize_account(
     account_owner: &Pubkey,
     account_mint: u64,
     bump: u8,
+    initial_state: u8,
 ) -> ProgramResult {
     // Check 1) Check seeds (valid index + checked by initialization)
     let mint_index: [u8; 8] = {
@@ -109,16 +146,26 @@ pub(crate) fn checked_initialize_account(
             unsafe { ProgramConfig::unchecked_load_mut(config)? };
 
         // If the mint provided is not than the current mint_index, this is a
-        // valid mint
-        if account_mint >= config_account.mint_index {
+        // valid mint. The native mint is always valid.
+        if account_mint >= config_account.mint_index
+            && account_mint != NATIVE_MINT_INDEX
+        {
             log::sol_log("mint u64 provided for initialization is not valid");
             return Err(ProgramError::InvalidInstructionData);
         }
 
         account_mint.to_le_bytes()
     };
-    let token_account_seeds: &[&[u8]] =
-        &[account_owner.as_ref(), mint_index.as_ref(), &[bump]];
+    let bump = [bump];
+    let token_account_seeds: &[&[u8]] = if token_account.is_signer() {
+        &[]
+    } else {
+        paranoid_check_pda(
+            &[account_owner.as_ref(), mint_index.as_ref()],
+            token_account,
+        )?;
+        &[account_owner.as_ref(), mint_index.as_ref(), &bump]
+    };
 
     // Init 1) Create token account
     // SAFETY: no one has any write access to any account at this stage.
@@ -133,6 +180,8 @@ pub(crate) fn checked_initialize_account(
         )?
     };
 
+    let now = Clock::get()?.unix_timestamp;
+
     // Split data into discriminator and token account
     // SAFETY:
     // 1) no one holds a view into the token account
@@ -144,18 +193,47 @@ pub(crate) fn checked_initialize_account(
 
         // Init 2) Write initialized disc
         *(disc.as_mut_ptr() as *mut u8) = AccountDiscriminator::Token as u8;
+        *disc.get_unchecked_mut(1) = TOKEN_ACCOUNT_VERSION;
 
         // Init 3) Write initial state
         let TokenAccount {
             owner,
             mint,
             balance,
+            delegate,
+            delegated_amount,
+            state,
+            bump: account_bump,
+            _padding,
+            withheld_fees,
+            transfer_fee_bps,
+            max_transfer_fee,
+            permit_nonce,
+            rent_payer,
+            created_at,
+            unlock_at,
         } = &mut *(token_account_data.as_mut_ptr() as *mut TokenAccount);
         *owner = *account_owner;
         // SAFETY: little endian byte memcpy. alignment is correct due to
         // TokenAccount.
         *(mint as *mut u64 as *mut [u8; 8]) = mint_index;
         *balance = 0;
+        *delegate = Pubkey::default();
+        *delegated_amount = 0;
+        *state = initial_state;
+        // Keypair accounts aren't pdas
+        *account_bump = if token_account.is_signer() {
+            0
+        } else {
+            bump[0]
+        };
+        *withheld_fees = 0;
+        *transfer_fee_bps = 0;
+        *max_transfer_fee = 0;
+        *permit_nonce = 0;
+        *rent_payer = *payer.key();
+        *created_at = now;
+        *unlock_at = 0;
     }
 
     Ok(())
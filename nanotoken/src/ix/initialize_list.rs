@@ -0,0 +1,140 @@
+use crate::solana_nostd_entrypoint::NoStdAccountInfo;
+use bytemuck::{Pod, Zeroable};
+use solana_program::{log, program_error::ProgramError, pubkey::Pubkey};
+
+use crate::{
+    error::NanoTokenError,
+    utils::{
+        check_authority, create_pda_funded_by_payer, paranoid_check_pda,
+        split_at_mut_unchecked, split_at_unchecked,
+    },
+    AccountDiscriminator, ListMode, Mint, MintList,
+};
+
+#[derive(PartialEq, Debug, Clone, Copy, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct InitializeListArgs {
+    /// May add and remove owners. [0; 32] is used as None.
+    pub authority: Pubkey,
+    /// See [ListMode]. u64 is used for alignment.
+    pub mode: u64,
+    /// u64 is used for alignment
+    pub bump: u64,
+}
+
+impl InitializeListArgs {
+    pub fn from_data<'a>(
+        data: &mut &'a [u8],
+    ) -> Result<&'a InitializeListArgs, ProgramError> {
+        const IX_LEN: usize = core::mem::size_of::<InitializeListArgs>();
+        if data.len() >= IX_LEN {
+            // SAFETY:
+            // We do the length check ourselves instead of via
+            // core::slice::split_at so we can return an error
+            // instead of panicking.
+            let (ix_data, rem) = unsafe { split_at_unchecked(data, IX_LEN) };
+            *data = rem;
+
+            // This is always aligned and all bit patterns are valid
+            Ok(unsafe { &*(ix_data.as_ptr() as *const InitializeListArgs) })
+        } else {
+            Err(ProgramError::InvalidInstructionData)
+        }
+    }
+
+    pub const fn size() -> usize {
+        core::mem::size_of::<Self>()
+    }
+}
+
+/// Gates all transfers of mint by an (initially empty) allowlist or
+/// denylist. Must happen before anything is minted, so that every balance
+/// carries [crate::TokenAccount::LISTED].
+pub fn initialize_list(
+    accounts: &[NoStdAccountInfo],
+    args: &InitializeListArgs,
+) -> Result<usize, ProgramError> {
+    // Unpack accounts
+    //
+    // 1) list is validated by create_pda_funded_by_payer (seeds)
+    // 2) mint is mutated, which is an implicit owner check
+    // 3) mint_authority must be the mint authority and must be signer
+    // 4) system program is checked by memoized validator closure
+    // 5) payer will be checked by the sol transfer
+    let [list, mint, mint_authority, _rem @ .., _config, system_program, payer] =
+        accounts
+    else {
+        log::sol_log(
+            "initialize_list expecting [list, mint, mint_authority, .. config, system_program, payer]",
+        );
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if args.mode != ListMode::Allow as u64 && args.mode != ListMode::Deny as u64
+    {
+        log::sol_log("invalid list mode");
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    // Check mint authority and set list mode
+    let mint_index = {
+        let mut mint_data = mint
+            .try_borrow_mut_data()
+            .ok_or(NanoTokenError::DuplicateAccount)?;
+        let mint_account = Mint::checked_load_mut(&mut mint_data)?;
+        check_authority(mint_authority, &mint_account.authority)?;
+        if mint_account.supply != 0 {
+            log::sol_log("list must be initialized before minting");
+            return Err(NanoTokenError::NonZeroSupply.into());
+        }
+        if mint_account.list_mode != ListMode::None as u8 {
+            log::sol_log("mint already has a list");
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+        mint_account.list_mode = args.mode as u8;
+        mint_account.mint_index
+    };
+
+    // Create list
+    paranoid_check_pda(&[b"list", mint.key().as_ref()], list)?;
+    let list_seeds: &[&[u8]] =
+        &[b"list", mint.key().as_ref(), &[args.bump as u8]];
+    // SAFETY: no one has any write access to any account at this stage.
+    unsafe {
+        create_pda_funded_by_payer(
+            list.to_info_c(),
+            &crate::ID,
+            MintList::space() as u64,
+            list_seeds,
+            system_program.to_info_c(),
+            payer.to_info_c(),
+        )?
+    };
+
+    // Write initial state
+    // SAFETY:
+    // 1) no one holds a view into the list
+    // 2) we just validated data length by creating account
+    unsafe {
+        let (disc, list_data) =
+            split_at_mut_unchecked(list.unchecked_borrow_mut_data(), 8);
+        *disc.get_unchecked_mut(0) = AccountDiscriminator::MintList as u8;
+
+        let MintList {
+            mint_index: list_mint_index,
+            authority,
+            len,
+            mode,
+            bump,
+            _padding,
+        } = &mut *(list_data.as_mut_ptr() as *mut MintList);
+        *list_mint_index = mint_index;
+        *authority = args.authority;
+        *len = 0;
+        *mode = args.mode as u8;
+        *bump = args.bump as u8;
+    }
+
+    Ok(3)
+}
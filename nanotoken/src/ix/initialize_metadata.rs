@@ -0,0 +1,147 @@
+use crate::solana_nostd_entrypoint::NoStdAccountInfo;
+use bytemuck::{Pod, Zeroable};
+use solana_program::{
+    entrypoint::ProgramResult, log, program_error::ProgramError,
+};
+
+use crate::{
+    consts::{MAX_NAME_LEN, MAX_SYMBOL_LEN, MAX_URI_LEN},
+    utils::{
+        check_authority, create_pda_funded_by_payer, split_at_mut_unchecked,
+        split_at_unchecked,
+    },
+    AccountDiscriminator, Mint, MintMetadata,
+};
+
+#[derive(PartialEq, Debug, Clone, Copy, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct InitializeMetadataArgs {
+    /// Zero-padded utf-8
+    pub name: [u8; MAX_NAME_LEN],
+    /// Zero-padded utf-8
+    pub symbol: [u8; MAX_SYMBOL_LEN],
+    /// Zero-padded utf-8
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::utils::serde_byte_array")
+    )]
+    pub uri: [u8; MAX_URI_LEN],
+    /// u64 is used for alignment
+    pub bump: u64,
+}
+
+impl InitializeMetadataArgs {
+    pub fn from_data<'a>(
+        data: &mut &'a [u8],
+    ) -> Result<&'a InitializeMetadataArgs, ProgramError> {
+        const IX_LEN: usize = core::mem::size_of::<InitializeMetadataArgs>();
+        if data.len() >= IX_LEN {
+            // SAFETY:
+            // We do the length check ourselves instead of via
+            // core::slice::split_at so we can return an error
+            // instead of panicking.
+            let (ix_data, rem) = unsafe { split_at_unchecked(data, IX_LEN) };
+            *data = rem;
+
+            // This is always aligned and all bit patterns are valid
+            Ok(
+                unsafe {
+                    &*(ix_data.as_ptr() as *const InitializeMetadataArgs)
+                },
+            )
+        } else {
+            Err(ProgramError::InvalidInstructionData)
+        }
+    }
+
+    pub const fn size() -> usize {
+        core::mem::size_of::<Self>()
+    }
+}
+
+pub fn initialize_metadata(
+    accounts: &[NoStdAccountInfo],
+    args: &InitializeMetadataArgs,
+) -> Result<usize, ProgramError> {
+    // Unpack accounts
+    //
+    // 1) metadata is validated by create_pda_funded_by_payer (seeds)
+    // 2) mint is not mutated, so it needs an explicit owner check
+    // 3) mint_authority must be the mint authority and must be signer
+    // 4) system program is checked by memoized validator closure
+    // 5) payer will be checked by the sol transfer
+    let [metadata, mint, mint_authority, _rem @ .., _config, system_program, payer] =
+        accounts
+    else {
+        log::sol_log(
+            "initialize_metadata expecting [metadata, mint, mint_authority, .. config, system_program, payer]",
+        );
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // Check mint authority
+    {
+        Mint::owner_check(mint)?;
+        let mut mint_data = mint
+            .try_borrow_mut_data()
+            .expect("first borrow won't fail");
+        let mint_account = Mint::checked_load_mut(&mut mint_data)?;
+        check_authority(mint_authority, &mint_account.authority)?;
+    }
+
+    create_metadata(metadata, mint, args, system_program, payer)?;
+
+    Ok(3)
+}
+
+/// Creates the metadata PDA for mint and writes its initial state. Callers
+/// must have checked the mint authority.
+pub(crate) fn create_metadata(
+    metadata: &NoStdAccountInfo,
+    mint: &NoStdAccountInfo,
+    args: &InitializeMetadataArgs,
+    system_program: &NoStdAccountInfo,
+    payer: &NoStdAccountInfo,
+) -> ProgramResult {
+    // Create metadata
+    let metadata_seeds: &[&[u8]] =
+        &[b"metadata", mint.key().as_ref(), &[args.bump as u8]];
+    // SAFETY: no one has any write access to any account at this stage.
+    unsafe {
+        create_pda_funded_by_payer(
+            metadata.to_info_c(),
+            &crate::ID,
+            MintMetadata::space() as u64,
+            metadata_seeds,
+            system_program.to_info_c(),
+            payer.to_info_c(),
+        )?
+    };
+
+    // Write initial state
+    // SAFETY:
+    // 1) no one holds a view into the metadata
+    // 2) we just validated data length by creating account
+    unsafe {
+        let (disc, metadata_data) =
+            split_at_mut_unchecked(metadata.unchecked_borrow_mut_data(), 8);
+        *disc.get_unchecked_mut(0) = AccountDiscriminator::MintMetadata as u8;
+
+        let MintMetadata {
+            mint: metadata_mint,
+            name,
+            symbol,
+            uri,
+            bump,
+            _padding,
+        } = &mut *(metadata_data.as_mut_ptr() as *mut MintMetadata);
+        *metadata_mint = *mint.key();
+        *name = args.name;
+        *symbol = args.symbol;
+        *uri = args.uri;
+        *bump = args.bump as u8;
+    }
+
+    Ok(())
+}
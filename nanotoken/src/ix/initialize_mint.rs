@@ -5,17 +5,40 @@ use solana_program::{
 };
 
 use crate::{
+    consts::{MAX_FEE_BPS, MINT_VERSION},
     error::NanoTokenError,
     utils::{split_at_mut_unchecked, split_at_unchecked},
-    AccountDiscriminator, Mint, ProgramConfig,
+    AccountDiscriminator, ListMode, Mint, ProgramConfig, ProgramFeature,
 };
 
 #[derive(PartialEq, Debug, Clone, Copy, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct InitializeMintArgs {
     pub authority: Pubkey,
     /// u64 is used for alignment. Max value is 12
     pub decimals: u64,
+    /// [0; 32] is used as None, in which case accounts can never be frozen
+    pub freeze_authority: Pubkey,
+    pub transfer_fee: TransferFeeConfig,
+    /// [0; 32] is used as None, in which case the mint can never be paused
+    pub pause_authority: Pubkey,
+    /// Use 0 for no supply cap
+    pub max_supply: u64,
+    /// [0; 32] is used as None, in which case tokens can never be clawed back
+    pub clawback_authority: Pubkey,
+}
+
+/// Fixed for the lifetime of the mint. Use all zeros for no transfer fee.
+#[derive(PartialEq, Debug, Default, Clone, Copy, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct TransferFeeConfig {
+    /// May withdraw withheld fees. [0; 32] is used as None.
+    pub authority: Pubkey,
+    /// At most MAX_FEE_BPS
+    pub bps: u64,
+    pub max_fee: u64,
 }
 
 impl InitializeMintArgs {
@@ -47,7 +70,7 @@ pub fn initialize_mint(
     accounts: &[NoStdAccountInfo],
     args: &InitializeMintArgs,
 ) -> Result<usize, ProgramError> {
-    log::sol_log("init mint");
+    crate::nanolog!(trace, "init mint");
     // Unpack accounts
     //
     // 1) config is checked by ProgramConfig::unchecked_load
@@ -58,7 +81,7 @@ pub fn initialize_mint(
         return Err(ProgramError::NotEnoughAccountKeys);
     };
 
-    checked_initialized_mint(config, mint, &args.authority, &args.decimals)?;
+    checked_initialized_mint(config, mint, args)?;
 
     Ok(1)
 }
@@ -77,8 +100,7 @@ pub fn initialize_mint(
 pub(crate) fn checked_initialized_mint(
     config: &NoStdAccountInfo,
     mint: &NoStdAccountInfo,
-    mint_authority: &Pubkey,
-    mint_decimals: &u64,
+    args: &InitializeMintArgs,
 ) -> ProgramResult {
     // Get account data
     // SAFETY: this is the one and only time any account data is mutably
@@ -96,6 +118,15 @@ pub(crate) fn checked_initialized_mint(
         // 2) config is checked by ProgramConfig::unchecked_load
         let config_account =
             unsafe { ProgramConfig::unchecked_load_mut(config)? };
+
+        // Check feature gates
+        if args.transfer_fee.bps != 0 {
+            config_account.check_feature(ProgramFeature::TransferFees)?;
+        }
+        if args.freeze_authority != Pubkey::default() {
+            config_account.check_feature(ProgramFeature::Freezing)?;
+        }
+
         let idx = config_account.mint_index;
         config_account.mint_index += 1;
         idx
@@ -119,6 +150,7 @@ pub(crate) fn checked_initialized_mint(
 
         // Init 1) Write initialized disc
         *padded_disc.get_unchecked_mut(0) = AccountDiscriminator::Mint as u8;
+        *padded_disc.get_unchecked_mut(1) = MINT_VERSION;
 
         // Init 2) Write config
         // Note:
@@ -133,16 +165,42 @@ pub(crate) fn checked_initialized_mint(
             supply,
             decimals,
             mint_index,
+            paused,
+            list_mode,
             _padding,
+            freeze_authority,
+            transfer_fee_authority,
+            transfer_fee_bps,
+            max_transfer_fee,
+            pause_authority,
+            max_supply,
+            clawback_authority,
+            royalty_bps,
+            royalty_recipient,
         } = &mut *(config_data.as_mut_ptr() as *mut Mint);
         *mint_index = this_mint_index;
-        *authority = *mint_authority;
+        *authority = args.authority;
         *supply = 0;
-        if *mint_decimals > 12 {
+        *freeze_authority = args.freeze_authority;
+        *pause_authority = args.pause_authority;
+        *paused = 0;
+        *list_mode = ListMode::None as u8;
+        *max_supply = args.max_supply;
+        *clawback_authority = args.clawback_authority;
+        *royalty_bps = 0;
+        *royalty_recipient = Pubkey::default();
+        if args.transfer_fee.bps > MAX_FEE_BPS {
+            log::sol_log("max transfer fee is 10_000 bps");
+            return Err(NanoTokenError::InvalidTransferFee.into());
+        }
+        *transfer_fee_authority = args.transfer_fee.authority;
+        *transfer_fee_bps = args.transfer_fee.bps;
+        *max_transfer_fee = args.transfer_fee.max_fee;
+        if args.decimals > 12 {
             log::sol_log("max decimals is 12");
             return Err(NanoTokenError::InvalidDecimals.into());
         }
-        *decimals = *mint_decimals as u8;
+        *decimals = args.decimals as u8;
     }
 
     Ok(())
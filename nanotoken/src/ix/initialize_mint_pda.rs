@@ -0,0 +1,96 @@
+use crate::solana_nostd_entrypoint::NoStdAccountInfo;
+use bytemuck::{Pod, Zeroable};
+use solana_program::{log, program_error::ProgramError};
+
+use crate::{
+    utils::{
+        create_pda_funded_by_payer, paranoid_check_pda, split_at_unchecked,
+    },
+    Mint, ProgramConfig,
+};
+
+use super::{checked_initialized_mint, InitializeMintArgs};
+
+#[derive(PartialEq, Debug, Clone, Copy, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct InitializeMintPdaArgs {
+    pub mint: InitializeMintArgs,
+    /// u64 is used for alignment. Bump of the ["mint", mint_index] pda, where
+    /// mint_index is the next index in the config.
+    pub bump: u64,
+}
+
+impl InitializeMintPdaArgs {
+    pub fn from_data<'a>(
+        data: &mut &'a [u8],
+    ) -> Result<&'a InitializeMintPdaArgs, ProgramError> {
+        const IX_LEN: usize = core::mem::size_of::<InitializeMintPdaArgs>();
+        if data.len() >= IX_LEN {
+            // SAFETY:
+            // We do the length check ourselves instead of via
+            // core::slice::split_at so we can return an error
+            // instead of panicking.
+            let (ix_data, rem) = unsafe { split_at_unchecked(data, IX_LEN) };
+            *data = rem;
+
+            // This is always aligned and all bit patterns are valid
+            Ok(unsafe { &*(ix_data.as_ptr() as *const InitializeMintPdaArgs) })
+        } else {
+            Err(ProgramError::InvalidInstructionData)
+        }
+    }
+
+    pub const fn size() -> usize {
+        core::mem::size_of::<Self>()
+    }
+}
+
+/// Creates the mint at the ["mint", mint_index] pda instead of a pre-created
+/// keypair account, so mint addresses can be derived from the index alone.
+pub fn initialize_mint_pda(
+    accounts: &[NoStdAccountInfo],
+    args: &InitializeMintPdaArgs,
+) -> Result<usize, ProgramError> {
+    log::sol_log("init mint pda");
+    // Unpack accounts
+    //
+    // 1) mint is validated by create_pda_funded_by_payer (seeds)
+    // 2) config is checked by ProgramConfig::unchecked_load
+    // 3) system program is checked by memoized validator closure
+    // 4) payer will be checked by the sol transfer
+    let [mint, _rem @ .., config, system_program, payer] = accounts else {
+        log::sol_log(
+            "initialize_mint_pda expecting [mint, .. config, system_program, payer]",
+        );
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // The index this mint will be assigned by checked_initialized_mint
+    let mint_index = {
+        // SAFETY: no one else has a view into config data during this scope
+        let config_account =
+            unsafe { ProgramConfig::unchecked_load_mut(config)? };
+        config_account.mint_index.to_le_bytes()
+    };
+
+    // Create mint
+    paranoid_check_pda(&[b"mint", mint_index.as_ref()], mint)?;
+    let mint_seeds: &[&[u8]] =
+        &[b"mint", mint_index.as_ref(), &[args.bump as u8]];
+    // SAFETY: no one has any write access to any account at this stage.
+    unsafe {
+        create_pda_funded_by_payer(
+            mint.to_info_c(),
+            &crate::ID,
+            Mint::space() as u64,
+            mint_seeds,
+            system_program.to_info_c(),
+            payer.to_info_c(),
+        )?
+    };
+
+    checked_initialized_mint(config, mint, &args.mint)?;
+
+    Ok(1)
+}
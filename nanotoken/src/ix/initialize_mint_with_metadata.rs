@@ -0,0 +1,98 @@
+use crate::solana_nostd_entrypoint::NoStdAccountInfo;
+use bytemuck::{Pod, Zeroable};
+use solana_program::{log, program_error::ProgramError};
+
+use crate::{
+    utils::{create_pda_funded_by_payer, split_at_unchecked},
+    Mint,
+};
+
+use super::{
+    checked_initialized_mint, create_metadata, InitializeMetadataArgs,
+    InitializeMintArgs,
+};
+
+#[derive(PartialEq, Debug, Clone, Copy, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct InitializeMintWithMetadataArgs {
+    pub mint: InitializeMintArgs,
+    pub metadata: InitializeMetadataArgs,
+}
+
+impl InitializeMintWithMetadataArgs {
+    pub fn from_data<'a>(
+        data: &mut &'a [u8],
+    ) -> Result<&'a InitializeMintWithMetadataArgs, ProgramError> {
+        const IX_LEN: usize =
+            core::mem::size_of::<InitializeMintWithMetadataArgs>();
+        if data.len() >= IX_LEN {
+            // SAFETY:
+            // We do the length check ourselves instead of via
+            // core::slice::split_at so we can return an error
+            // instead of panicking.
+            let (ix_data, rem) = unsafe { split_at_unchecked(data, IX_LEN) };
+            *data = rem;
+
+            // This is always aligned and all bit patterns are valid
+            Ok(unsafe {
+                &*(ix_data.as_ptr() as *const InitializeMintWithMetadataArgs)
+            })
+        } else {
+            Err(ProgramError::InvalidInstructionData)
+        }
+    }
+
+    pub const fn size() -> usize {
+        core::mem::size_of::<Self>()
+    }
+}
+
+/// Creates the mint account, initializes it, and creates its metadata in one
+/// instruction. The mint is a keypair account, so it must sign.
+pub fn initialize_mint_with_metadata(
+    accounts: &[NoStdAccountInfo],
+    args: &InitializeMintWithMetadataArgs,
+) -> Result<usize, ProgramError> {
+    log::sol_log("init mint with metadata");
+    // Unpack accounts
+    //
+    // 1) mint must be signer, and is created here
+    // 2) metadata is validated by create_pda_funded_by_payer (seeds)
+    // 3) config is checked by ProgramConfig::unchecked_load
+    // 4) system program is checked by memoized validator closure
+    // 5) payer will be checked by the sol transfer
+    let [mint, metadata, _rem @ .., config, system_program, payer] = accounts
+    else {
+        log::sol_log(
+            "initialize_mint_with_metadata expecting [mint, metadata, .. config, system_program, payer]",
+        );
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !mint.is_signer() {
+        log::sol_log("mint must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Create mint. No seeds since the mint signs for itself.
+    // SAFETY: no one has any write access to any account at this stage.
+    unsafe {
+        create_pda_funded_by_payer(
+            mint.to_info_c(),
+            &crate::ID,
+            Mint::space() as u64,
+            &[],
+            system_program.to_info_c(),
+            payer.to_info_c(),
+        )?
+    };
+
+    // Initialize mint. We just set the authority, so there is no authority to
+    // check before creating the metadata.
+    checked_initialized_mint(config, mint, &args.mint)?;
+
+    create_metadata(metadata, mint, &args.metadata, system_program, payer)?;
+
+    Ok(2)
+}
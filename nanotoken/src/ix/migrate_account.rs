@@ -0,0 +1,48 @@
+use crate::solana_nostd_entrypoint::NoStdAccountInfo;
+use bytemuck::{Pod, Zeroable};
+use solana_program::{log, program_error::ProgramError};
+
+use crate::migrations;
+
+#[derive(PartialEq, Debug, Clone, Pod, Zeroable, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct MigrateAccountArgs {
+    // Keeping this scaffolded just in case...
+}
+
+impl MigrateAccountArgs {
+    pub fn from_data<'a>(
+        _data: &mut &'a [u8],
+    ) -> Result<&'a MigrateAccountArgs, ProgramError> {
+        // Zero-sized, so there is nothing to consume
+        Ok(&MigrateAccountArgs {})
+    }
+
+    pub const fn size() -> usize {
+        core::mem::size_of::<Self>()
+    }
+}
+
+/// Permissionlessly upgrades a mint or token account to the latest layout,
+/// reallocating it and topping up rent from payer. See [migrations].
+pub fn migrate_account(
+    accounts: &[NoStdAccountInfo],
+    _args: &MigrateAccountArgs,
+) -> Result<usize, ProgramError> {
+    // Unpack accounts
+    //
+    // 1) account is reallocated and mutated, which is an implicit owner check
+    // 2) system program is checked by memoized validator closure
+    // 3) payer will be checked by the sol transfer if necessary
+    let [account, _rem @ .., _config, _system_program, payer] = accounts else {
+        log::sol_log(
+            "migrate_account expecting [account, .. config, system_program, payer]",
+        );
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    migrations::migrate(account, payer, &migrations::USER_ACCOUNTS)?;
+
+    Ok(1)
+}
@@ -0,0 +1,76 @@
+use crate::solana_nostd_entrypoint::NoStdAccountInfo;
+use bytemuck::{Pod, Zeroable};
+use solana_program::{log, program_error::ProgramError, pubkey::Pubkey};
+
+use crate::{
+    migrations, offsets::CONFIG_AUTHORITY_OFFSET, utils::check_authority,
+    AccountDiscriminator,
+};
+
+#[derive(PartialEq, Debug, Clone, Pod, Zeroable, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct MigrateStateArgs {}
+
+impl MigrateStateArgs {
+    pub fn from_data<'a>(
+        _data: &mut &'a [u8],
+    ) -> Result<&'a MigrateStateArgs, ProgramError> {
+        // Zero-sized, so there is nothing to consume
+        Ok(&MigrateStateArgs {})
+    }
+
+    pub const fn size() -> usize {
+        core::mem::size_of::<Self>()
+    }
+}
+
+/// Upgrades program state (the config or a vault info) to the latest layout,
+/// reallocating it and topping up rent from payer. Only the config authority
+/// may do this. See [migrations].
+pub fn migrate_state(
+    accounts: &[NoStdAccountInfo],
+    _args: &MigrateStateArgs,
+) -> Result<usize, ProgramError> {
+    // Unpack accounts
+    //
+    // 1) account is reallocated and mutated, which is an implicit owner check
+    // 2) authority must be the config authority and must be signer
+    // 3) config is checked by memoized validator closure
+    // 4) system program is checked by memoized validator closure
+    // 5) payer will be checked by the sol transfer if necessary
+    let [account, authority, _rem @ .., config, _system_program, payer] =
+        accounts
+    else {
+        log::sol_log(
+            "migrate_state expecting [account, authority, .. config, system_program, payer]",
+        );
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // The config may itself be the account being migrated, and so be on an
+    // older (shorter) layout than ProgramConfig. Only the authority is read,
+    // which all layouts share since they only append fields.
+    let config_authority = {
+        let data = config
+            .try_borrow_data()
+            .expect("first borrow won't fail");
+        if data.first() != Some(&(AccountDiscriminator::Config as u8))
+            || data.len() < CONFIG_AUTHORITY_OFFSET + 32
+        {
+            log::sol_log("config discriminator is incorrect");
+            return Err(ProgramError::InvalidAccountData);
+        }
+        // SAFETY: length was checked and all bit patterns are valid
+        unsafe {
+            *(data
+                .as_ptr()
+                .add(CONFIG_AUTHORITY_OFFSET) as *const Pubkey)
+        }
+    };
+    check_authority(authority, &config_authority)?;
+
+    migrations::migrate(account, payer, &migrations::PROGRAM_STATE)?;
+
+    Ok(2)
+}
@@ -3,10 +3,14 @@ use bytemuck::{Pod, Zeroable};
 use solana_program::{log, program_error::ProgramError};
 
 use crate::{
-    error::NanoTokenError, utils::split_at_unchecked, Mint, TokenAccount,
+    error::NanoTokenError,
+    math,
+    utils::{check_authority, paranoid_check_owner, split_at_unchecked},
+    Mint, TokenAccount,
 };
 
 #[derive(PartialEq, Debug, Clone, Copy, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct MintArgs {
     pub amount: u64,
@@ -40,7 +44,7 @@ pub fn mint(
     accounts: &[NoStdAccountInfo],
     args: &MintArgs,
 ) -> Result<usize, ProgramError> {
-    log::sol_log("mint");
+    crate::nanolog!(trace, "mint");
     let [to, mint, auth, _rem @ ..] = accounts else {
         log::sol_log("mint expecting [to, mint, auth, .. ]");
         return Err(ProgramError::NotEnoughAccountKeys);
@@ -57,27 +61,21 @@ pub fn mint(
 
     // Load mint account
     // we do not do an owner check since we will mutate (add nonzero amount to
-    // supply)
+    // supply), except in paranoid builds
+    paranoid_check_owner(mint)?;
     let mut mint_data = mint
         .try_borrow_mut_data()
         .expect("first borrow won't fail");
     let mint_account = Mint::checked_load_mut(&mut mint_data)?;
+    mint_account.check_not_paused()?;
 
-    // Check if auth is signer
-    if !auth.is_signer() {
-        log::sol_log("authority must sign to mint");
-        return Err(ProgramError::MissingRequiredSignature);
-    }
-
-    // Check if auth is correct
-    if mint_account.authority != *auth.key() {
-        log::sol_log("incorrect mint authority");
-        return Err(ProgramError::MissingRequiredSignature);
-    };
+    // Check that auth is the mint authority and is signer
+    check_authority(auth, &mint_account.authority)?;
 
     // Load account
     // we do not do an owner check since we will mutate (add nonzero amount to
-    // supply)
+    // supply), except in paranoid builds
+    paranoid_check_owner(to)?;
     let mut to_data = to
         .try_borrow_mut_data()
         .ok_or(NanoTokenError::DuplicateAccount)?;
@@ -90,16 +88,32 @@ pub fn mint(
     }
 
     // Check max
-    if let Some(new_supply) = mint_account
-        .supply
-        .checked_add(args.amount)
-    {
+    if let Ok(new_supply) = math::add_supply(mint_account.supply, args.amount) {
+        if mint_account.max_supply != 0 && new_supply > mint_account.max_supply
+        {
+            log::sol_log("total supply would exceed max supply");
+            return Err(NanoTokenError::SupplyCapExceeded.into());
+        }
         mint_account.supply = new_supply;
-        to_account.balance += args.amount;
+        to_account.credit(args.amount)?;
+        to_account.inherit_mint_config(mint_account);
     } else {
         log::sol_log("total supply would exceed u64::MAX");
         return Err(NanoTokenError::SupplyOverflow.into());
     }
 
+    // Emit event. The noop program may be passed anywhere after this op's
+    // accounts.
+    #[cfg(feature = "noop-events")]
+    crate::events::emit(
+        accounts,
+        crate::events::EventTag::Mint,
+        &crate::events::MintEvent {
+            mint: *mint.key(),
+            to: *to.key(),
+            amount: args.amount,
+        },
+    )?;
+
     Ok(3)
 }
@@ -1,7 +1,7 @@
 use solana_program::program_error::ProgramError;
 use strum::EnumDiscriminants;
 
-use crate::utils::split_at_unchecked;
+use crate::{error::NanoTokenError, utils::split_at_unchecked};
 
 pub mod init_config;
 
@@ -28,7 +28,152 @@ pub use init_vault::*;
 pub use transmute::*;
 pub mod transmute;
 
+pub mod set_authority;
+pub use set_authority::*;
+
+pub mod withdraw_excess_lamports;
+pub use withdraw_excess_lamports::*;
+
+pub mod propose_transfer;
+pub use propose_transfer::*;
+
+pub mod approve_transfer;
+pub use approve_transfer::*;
+
+pub mod execute_transfer;
+pub use execute_transfer::*;
+
+pub mod get_balances;
+pub use get_balances::*;
+
+pub mod approve;
+pub use approve::*;
+
+pub mod revoke;
+pub use revoke::*;
+
+pub mod freeze_account;
+pub use freeze_account::*;
+
+pub mod thaw_account;
+pub use thaw_account::*;
+
+pub mod batch_transfer;
+pub use batch_transfer::*;
+
+pub mod transfer_all;
+pub use transfer_all::*;
+
+pub mod burn_checked;
+pub use burn_checked::*;
+
+pub mod wrap_sol;
+pub use wrap_sol::*;
+
+pub mod unwrap_sol;
+pub use unwrap_sol::*;
+
+pub mod sync_native;
+pub use sync_native::*;
+
+pub mod initialize_metadata;
+pub use initialize_metadata::*;
+
+pub mod update_metadata;
+pub use update_metadata::*;
+
+pub mod withdraw_withheld_fees;
+pub use withdraw_withheld_fees::*;
+
+pub mod close_mint;
+pub use close_mint::*;
+
+pub mod set_memo_required;
+pub use set_memo_required::*;
+
+pub mod create_associated_account;
+pub use create_associated_account::*;
+
+pub mod permit_transfer;
+pub use permit_transfer::*;
+
+pub mod pause;
+pub use pause::*;
+
+pub mod unpause;
+pub use unpause::*;
+
+pub mod clawback;
+pub use clawback::*;
+
+pub mod set_gc_config;
+pub use set_gc_config::*;
+
+pub mod garbage_collect;
+pub use garbage_collect::*;
+
+pub mod migrate_account;
+pub use migrate_account::*;
+
+pub mod initialize_mint_with_metadata;
+pub use initialize_mint_with_metadata::*;
+
+pub mod account_indices;
+pub use account_indices::*;
+
+pub mod realloc;
+pub use realloc::*;
+
+pub mod set_config_authority;
+pub use set_config_authority::*;
+
+pub mod accept_config_authority;
+pub use accept_config_authority::*;
+
+pub mod initialize_mint_pda;
+pub use initialize_mint_pda::*;
+
+pub mod get_account_data_size;
+pub use get_account_data_size::*;
+
+pub mod transmute_batch;
+pub use transmute_batch::*;
+
+pub mod initialize_list;
+pub use initialize_list::*;
+
+pub mod add_to_list;
+pub use add_to_list::*;
+
+pub mod remove_from_list;
+pub use remove_from_list::*;
+
+pub mod set_royalty;
+pub use set_royalty::*;
+
+pub mod set_rent_destination;
+pub use set_rent_destination::*;
+
+pub mod noop;
+pub use noop::*;
+
+pub mod timelock;
+pub use timelock::*;
+
+pub mod set_feature;
+pub use set_feature::*;
+
+pub mod packed;
+pub use packed::*;
+
+pub mod migrate_state;
+pub use migrate_state::*;
+
+pub mod cancel_transfer;
+pub use cancel_transfer::*;
+
 #[derive(PartialEq, Debug, Clone, Copy, EnumDiscriminants)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[strum_discriminants(name(Tag))]
 #[repr(u64)]
 pub enum ProgramInstruction {
@@ -42,39 +187,358 @@ pub enum ProgramInstruction {
     Burn(BurnArgs),
     Transfer(TransferArgs),
     Transmute(TransmuteArgs),
+    SetAuthority(SetAuthorityArgs),
+    WithdrawExcessLamports(WithdrawExcessLamportsArgs),
+    ProposeTransfer(ProposeTransferArgs),
+    ApproveTransfer(ApproveTransferArgs),
+    ExecuteTransfer(ExecuteTransferArgs),
+    GetBalances(GetBalancesArgs),
+    Approve(ApproveArgs),
+    Revoke(RevokeArgs),
+    FreezeAccount(FreezeAccountArgs),
+    ThawAccount(ThawAccountArgs),
+    BatchTransfer(BatchTransferArgs),
+    TransferAll(TransferAllArgs),
+    BurnChecked(BurnCheckedArgs),
+    WrapSol(WrapSolArgs),
+    UnwrapSol(UnwrapSolArgs),
+    SyncNative(SyncNativeArgs),
+    InitializeMetadata(InitializeMetadataArgs),
+    UpdateMetadata(UpdateMetadataArgs),
+    WithdrawWithheldFees(WithdrawWithheldFeesArgs),
+    CloseMint(CloseMintArgs),
+    SetMemoRequired(SetMemoRequiredArgs),
+    CreateAssociatedAccount(CreateAssociatedAccountArgs),
+    PermitTransfer(PermitTransferArgs),
+    Pause(PauseArgs),
+    Unpause(UnpauseArgs),
+    Clawback(ClawbackArgs),
+    SetGcConfig(SetGcConfigArgs),
+    GarbageCollect(GarbageCollectArgs),
+    MigrateAccount(MigrateAccountArgs),
+    InitializeMintWithMetadata(InitializeMintWithMetadataArgs),
+    AccountIndices(AccountIndicesArgs),
+    Realloc(ReallocArgs),
+    SetConfigAuthority(SetConfigAuthorityArgs),
+    AcceptConfigAuthority(AcceptConfigAuthorityArgs),
+    InitializeMintPda(InitializeMintPdaArgs),
+    GetAccountDataSize(GetAccountDataSizeArgs),
+    TransmuteBatch(TransmuteBatchArgs),
+    InitializeList(InitializeListArgs),
+    AddToList(AddToListArgs),
+    RemoveFromList(RemoveFromListArgs),
+    SetRoyalty(SetRoyaltyArgs),
+    SetRentDestination(SetRentDestinationArgs),
+    Noop(NoopArgs),
+    Timelock(TimelockArgs),
+    SetFeature(SetFeatureArgs),
+    /// Switches the rest of the batch to unaligned args, see [packed]
+    Packed(PackedArgs),
+    MigrateState(MigrateStateArgs),
+    CancelTransfer(CancelTransferArgs),
 }
 
 impl Tag {
     pub const fn size() -> usize {
         core::mem::size_of::<Self>()
     }
+
+    /// Minimum number of accounts each op needs, including the trailing
+    /// config, system program, and payer for ops that use them. Checked
+    /// before dispatch, so handlers never see fewer.
+    pub const fn min_accounts(self) -> usize {
+        match self {
+            Tag::InitializeConfig => 3,
+            Tag::InitializeMint => 4,
+            Tag::InitializeAccount => 4,
+            Tag::InitializeVault => 8,
+            Tag::Mint => 3,
+            Tag::Burn => 3,
+            Tag::Transfer => 3,
+            Tag::Transmute => 11,
+            Tag::SetAuthority => 2,
+            Tag::WithdrawExcessLamports => 6,
+            Tag::ProposeTransfer => 7,
+            Tag::ApproveTransfer => 2,
+            Tag::ExecuteTransfer => 3,
+            Tag::GetBalances => 0,
+            Tag::Approve => 2,
+            Tag::Revoke => 2,
+            Tag::FreezeAccount => 3,
+            Tag::ThawAccount => 3,
+            Tag::BatchTransfer => 2,
+            Tag::TransferAll => 3,
+            Tag::BurnChecked => 3,
+            Tag::WrapSol => 5,
+            Tag::UnwrapSol => 3,
+            Tag::SyncNative => 1,
+            Tag::InitializeMetadata => 6,
+            Tag::UpdateMetadata => 3,
+            Tag::WithdrawWithheldFees => 3,
+            Tag::CloseMint => 3,
+            Tag::SetMemoRequired => 2,
+            Tag::CreateAssociatedAccount => 4,
+            Tag::PermitTransfer => 3,
+            Tag::Pause => 2,
+            Tag::Unpause => 2,
+            Tag::Clawback => 4,
+            Tag::SetGcConfig => 4,
+            Tag::GarbageCollect => 6,
+            Tag::MigrateAccount => 4,
+            Tag::InitializeMintWithMetadata => 5,
+            Tag::AccountIndices => 0,
+            Tag::Realloc => 5,
+            Tag::SetConfigAuthority => 4,
+            Tag::AcceptConfigAuthority => 4,
+            Tag::InitializeMintPda => 4,
+            Tag::GetAccountDataSize => 0,
+            // Each entry is checked by transmute
+            Tag::TransmuteBatch => 0,
+            Tag::InitializeList => 6,
+            Tag::AddToList => 5,
+            Tag::RemoveFromList => 2,
+            Tag::SetRoyalty => 3,
+            Tag::SetRentDestination => 4,
+            Tag::Noop => 0,
+            Tag::Timelock => 2,
+            Tag::SetFeature => 4,
+            Tag::Packed => 0,
+            Tag::MigrateState => 5,
+            Tag::CancelTransfer => 3,
+        }
+    }
 }
 
+/// Ops with a packed encoding hold their args by value, since packed args
+/// are widened into a copy rather than borrowed from the instruction data.
 #[repr(u64)]
 pub(crate) enum ProgramInstructionRef<'a> {
     InitializeConfig(&'a InitConfigArgs),
-    InitializeAccount(&'a InitializeAccountArgs),
-    InitializeMint(&'a InitializeMintArgs),
+    InitializeAccount(InitializeAccountArgs),
+    InitializeMint(InitializeMintArgs),
     InitializeVault(&'a InitializeVaultArgs),
-    Mint(&'a MintArgs),
-    Burn(&'a BurnArgs),
-    Transfer(&'a TransferArgs),
-    Transmute(&'a TransmuteArgs),
+    Mint(MintArgs),
+    Burn(BurnArgs),
+    Transfer(TransferArgs),
+    Transmute(TransmuteArgs),
+    SetAuthority(&'a SetAuthorityArgs),
+    WithdrawExcessLamports(&'a WithdrawExcessLamportsArgs),
+    ProposeTransfer(&'a ProposeTransferArgs),
+    ApproveTransfer(&'a ApproveTransferArgs),
+    ExecuteTransfer(&'a ExecuteTransferArgs),
+    GetBalances(&'a GetBalancesArgs),
+    Approve(&'a ApproveArgs),
+    Revoke(&'a RevokeArgs),
+    FreezeAccount(&'a FreezeAccountArgs),
+    ThawAccount(&'a ThawAccountArgs),
+    BatchTransfer(&'a BatchTransferArgs, &'a [u64]),
+    TransferAll(&'a TransferAllArgs),
+    BurnChecked(&'a BurnCheckedArgs),
+    WrapSol(&'a WrapSolArgs),
+    UnwrapSol(&'a UnwrapSolArgs),
+    SyncNative(&'a SyncNativeArgs),
+    InitializeMetadata(&'a InitializeMetadataArgs),
+    UpdateMetadata(&'a UpdateMetadataArgs),
+    WithdrawWithheldFees(&'a WithdrawWithheldFeesArgs),
+    CloseMint(&'a CloseMintArgs),
+    SetMemoRequired(&'a SetMemoRequiredArgs),
+    CreateAssociatedAccount(&'a CreateAssociatedAccountArgs),
+    PermitTransfer(&'a PermitTransferArgs),
+    Pause(&'a PauseArgs),
+    Unpause(&'a UnpauseArgs),
+    Clawback(&'a ClawbackArgs),
+    SetGcConfig(&'a SetGcConfigArgs),
+    GarbageCollect(&'a GarbageCollectArgs),
+    MigrateAccount(&'a MigrateAccountArgs),
+    InitializeMintWithMetadata(&'a InitializeMintWithMetadataArgs),
+    AccountIndices(&'a AccountIndicesArgs, &'a [u8]),
+    Realloc(&'a ReallocArgs),
+    SetConfigAuthority(&'a SetConfigAuthorityArgs),
+    AcceptConfigAuthority(&'a AcceptConfigAuthorityArgs),
+    InitializeMintPda(&'a InitializeMintPdaArgs),
+    GetAccountDataSize(&'a GetAccountDataSizeArgs),
+    TransmuteBatch(&'a TransmuteBatchArgs, &'a [TransmuteArgs]),
+    InitializeList(&'a InitializeListArgs),
+    AddToList(&'a AddToListArgs),
+    RemoveFromList(&'a RemoveFromListArgs),
+    SetRoyalty(&'a SetRoyaltyArgs),
+    SetRentDestination(&'a SetRentDestinationArgs),
+    Noop(NoopArgs),
+    Timelock(&'a TimelockArgs),
+    SetFeature(&'a SetFeatureArgs),
+    Packed(&'a PackedArgs),
+    MigrateState(&'a MigrateStateArgs),
+    CancelTransfer(&'a CancelTransferArgs),
 }
 
 pub(crate) struct InstructionIter<'a> {
     data: &'a [u8],
+    /// Set by a Packed op, see [packed]
+    packed: bool,
 }
 
+/// Parses one op's args, advancing `data` past them
+type ParseFn = for<'a> fn(
+    &mut &'a [u8],
+) -> Result<ProgramInstructionRef<'a>, ProgramError>;
+
+pub(crate) const NUM_TAGS: usize = Tag::CancelTransfer as usize + 1;
+
+#[inline(always)]
+const fn parser(tag: Tag, parse: ParseFn) -> Option<(Tag, ParseFn)> {
+    Some((tag, parse))
+}
+
+/// Aligned parsers indexed by tag. Dispatching through a dense table costs
+/// the same for every tag, where a chain of tag comparisons costs more the
+/// later a tag appears in it, which shows up in long batches.
+static PARSERS: [Option<(Tag, ParseFn)>; NUM_TAGS] = {
+    let mut table: [Option<(Tag, ParseFn)>; NUM_TAGS] = [None; NUM_TAGS];
+    macro_rules! by_ref {
+        ($tag:ident, $args:ty) => {
+            table[Tag::$tag as usize] = parser(Tag::$tag, |data| {
+                <$args>::from_data(data).map(ProgramInstructionRef::$tag)
+            });
+        };
+    }
+    macro_rules! by_copy {
+        ($tag:ident, $args:ty) => {
+            table[Tag::$tag as usize] = parser(Tag::$tag, |data| {
+                <$args>::from_data(data)
+                    .map(|args| ProgramInstructionRef::$tag(*args))
+            });
+        };
+    }
+    macro_rules! with_slice {
+        ($tag:ident, $args:ty) => {
+            table[Tag::$tag as usize] = parser(Tag::$tag, |data| {
+                <$args>::from_data(data).map(|(args, entries)| {
+                    ProgramInstructionRef::$tag(args, entries)
+                })
+            });
+        };
+    }
+    by_ref!(InitializeConfig, InitConfigArgs);
+    by_copy!(InitializeMint, InitializeMintArgs);
+    by_copy!(InitializeAccount, InitializeAccountArgs);
+    by_ref!(InitializeVault, InitializeVaultArgs);
+    by_copy!(Mint, MintArgs);
+    by_copy!(Burn, BurnArgs);
+    by_copy!(Transfer, TransferArgs);
+    by_copy!(Transmute, TransmuteArgs);
+    by_ref!(SetAuthority, SetAuthorityArgs);
+    by_ref!(WithdrawExcessLamports, WithdrawExcessLamportsArgs);
+    by_ref!(ProposeTransfer, ProposeTransferArgs);
+    by_ref!(ApproveTransfer, ApproveTransferArgs);
+    by_ref!(ExecuteTransfer, ExecuteTransferArgs);
+    by_ref!(GetBalances, GetBalancesArgs);
+    by_ref!(Approve, ApproveArgs);
+    by_ref!(Revoke, RevokeArgs);
+    by_ref!(FreezeAccount, FreezeAccountArgs);
+    by_ref!(ThawAccount, ThawAccountArgs);
+    with_slice!(BatchTransfer, BatchTransferArgs);
+    by_ref!(TransferAll, TransferAllArgs);
+    by_ref!(BurnChecked, BurnCheckedArgs);
+    by_ref!(WrapSol, WrapSolArgs);
+    by_ref!(UnwrapSol, UnwrapSolArgs);
+    by_ref!(SyncNative, SyncNativeArgs);
+    by_ref!(InitializeMetadata, InitializeMetadataArgs);
+    by_ref!(UpdateMetadata, UpdateMetadataArgs);
+    by_ref!(WithdrawWithheldFees, WithdrawWithheldFeesArgs);
+    by_ref!(CloseMint, CloseMintArgs);
+    by_ref!(SetMemoRequired, SetMemoRequiredArgs);
+    by_ref!(CreateAssociatedAccount, CreateAssociatedAccountArgs);
+    by_ref!(PermitTransfer, PermitTransferArgs);
+    by_ref!(Pause, PauseArgs);
+    by_ref!(Unpause, UnpauseArgs);
+    by_ref!(Clawback, ClawbackArgs);
+    by_ref!(SetGcConfig, SetGcConfigArgs);
+    by_ref!(GarbageCollect, GarbageCollectArgs);
+    by_ref!(MigrateAccount, MigrateAccountArgs);
+    by_ref!(InitializeMintWithMetadata, InitializeMintWithMetadataArgs);
+    with_slice!(AccountIndices, AccountIndicesArgs);
+    by_ref!(Realloc, ReallocArgs);
+    by_ref!(SetConfigAuthority, SetConfigAuthorityArgs);
+    by_ref!(AcceptConfigAuthority, AcceptConfigAuthorityArgs);
+    by_ref!(InitializeMintPda, InitializeMintPdaArgs);
+    by_ref!(GetAccountDataSize, GetAccountDataSizeArgs);
+    with_slice!(TransmuteBatch, TransmuteBatchArgs);
+    by_ref!(InitializeList, InitializeListArgs);
+    by_ref!(AddToList, AddToListArgs);
+    by_ref!(RemoveFromList, RemoveFromListArgs);
+    by_ref!(SetRoyalty, SetRoyaltyArgs);
+    by_ref!(SetRentDestination, SetRentDestinationArgs);
+    by_copy!(Noop, NoopArgs);
+    by_ref!(Timelock, TimelockArgs);
+    by_ref!(SetFeature, SetFeatureArgs);
+    by_ref!(Packed, PackedArgs);
+    by_ref!(MigrateState, MigrateStateArgs);
+    by_ref!(CancelTransfer, CancelTransferArgs);
+
+    // Every tag must have a parser, otherwise its ops would fail as unknown
+    let mut i = 0;
+    while i < NUM_TAGS {
+        assert!(table[i].is_some());
+        i += 1;
+    }
+    table
+};
+
+/// Packed parsers indexed by tag, see [packed]. None for ops without a
+/// packed encoding.
+static PACKED_PARSERS: [Option<(Tag, ParseFn)>; NUM_TAGS] = {
+    let mut table: [Option<(Tag, ParseFn)>; NUM_TAGS] = [None; NUM_TAGS];
+    macro_rules! unaligned {
+        ($tag:ident, $args:ty) => {
+            table[Tag::$tag as usize] = parser(Tag::$tag, |data| {
+                read_unaligned::<$args>(data)
+                    .map(|args| ProgramInstructionRef::$tag(args.into()))
+            });
+        };
+    }
+    unaligned!(Mint, MintArgs);
+    unaligned!(Burn, BurnArgs);
+    unaligned!(Transfer, TransferArgs);
+    unaligned!(Noop, NoopArgs);
+    unaligned!(InitializeAccount, PackedInitializeAccountArgs);
+    unaligned!(InitializeMint, PackedInitializeMintArgs);
+    unaligned!(Transmute, PackedTransmuteArgs);
+    table
+};
+
 impl<'a> InstructionIter<'a> {
     pub fn new(data: &'a [u8]) -> InstructionIter<'a> {
-        InstructionIter { data }
+        InstructionIter {
+            data,
+            packed: false,
+        }
+    }
+
+    /// Parses `[u8 tag][packed args]`
+    fn next_packed(
+        &mut self,
+    ) -> Option<Result<(Tag, ProgramInstructionRef<'a>), ProgramError>> {
+        let (&tag, data) = self.data.split_first()?;
+        self.data = data;
+
+        match PACKED_PARSERS.get(tag as usize) {
+            Some(&Some((tag, parse))) => {
+                Some(parse(&mut self.data).map(|ix| (tag, ix)))
+            }
+            _ => {
+                solana_program::log::sol_log("op has no packed encoding");
+                Some(Err(NanoTokenError::UnknownTag.into()))
+            }
+        }
     }
 }
 
 impl<'a> Iterator for InstructionIter<'a> {
-    type Item = Result<ProgramInstructionRef<'a>, ProgramError>;
+    type Item = Result<(Tag, ProgramInstructionRef<'a>), ProgramError>;
     fn next(&mut self) -> Option<Self::Item> {
+        if self.packed {
+            return self.next_packed();
+        }
         if self.data.len() < Tag::size() {
             return None;
         }
@@ -86,48 +550,13 @@ impl<'a> Iterator for InstructionIter<'a> {
         let tag = unsafe { *(tag.as_ptr() as *const u8) }; // little endian
         self.data = data;
 
-        match tag {
-            x if x == Tag::InitializeConfig as u8 => Some(
-                InitConfigArgs::from_data(&mut self.data)
-                    .map(ProgramInstructionRef::InitializeConfig),
-            ),
-
-            x if x == Tag::InitializeMint as u8 => Some(
-                InitializeMintArgs::from_data(&mut self.data)
-                    .map(ProgramInstructionRef::InitializeMint),
-            ),
-
-            x if x == Tag::InitializeAccount as u8 => Some(
-                InitializeAccountArgs::from_data(&mut self.data)
-                    .map(ProgramInstructionRef::InitializeAccount),
-            ),
-
-            x if x == Tag::InitializeVault as u8 => Some(
-                InitializeVaultArgs::from_data(&mut self.data)
-                    .map(ProgramInstructionRef::InitializeVault),
-            ),
-
-            x if x == Tag::Mint as u8 => Some(
-                MintArgs::from_data(&mut self.data)
-                    .map(ProgramInstructionRef::Mint),
-            ),
-
-            x if x == Tag::Burn as u8 => Some(
-                BurnArgs::from_data(&mut self.data)
-                    .map(ProgramInstructionRef::Burn),
-            ),
-
-            x if x == Tag::Transfer as u8 => Some(
-                TransferArgs::from_data(&mut self.data)
-                    .map(ProgramInstructionRef::Transfer),
-            ),
-
-            x if x == Tag::Transmute as u8 => Some(
-                TransmuteArgs::from_data(&mut self.data)
-                    .map(ProgramInstructionRef::Transmute),
-            ),
-
-            _ => None,
+        let Some(&Some((tag, parse))) = PARSERS.get(tag as usize) else {
+            solana_program::log::sol_log("unknown op tag");
+            return Some(Err(NanoTokenError::UnknownTag.into()));
+        };
+        if tag == Tag::Packed {
+            self.packed = true;
         }
+        Some(parse(&mut self.data).map(|ix| (tag, ix)))
     }
 }
@@ -0,0 +1,48 @@
+use crate::solana_nostd_entrypoint::NoStdAccountInfo;
+use bytemuck::{Pod, Zeroable};
+use solana_program::program_error::ProgramError;
+
+use crate::utils::split_at_unchecked;
+
+#[derive(PartialEq, Debug, Clone, Copy, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct NoopArgs {
+    /// Arbitrary value, only used to make otherwise identical transactions
+    /// distinct
+    pub nonce: u64,
+}
+
+impl NoopArgs {
+    pub fn from_data<'a>(
+        data: &mut &'a [u8],
+    ) -> Result<&'a NoopArgs, ProgramError> {
+        const IX_LEN: usize = core::mem::size_of::<NoopArgs>();
+        if data.len() >= IX_LEN {
+            // SAFETY:
+            // We do the length check ourselves instead of via
+            // core::slice::split_at so we can return an error
+            // instead of panicking.
+            let (ix_data, rem) = unsafe { split_at_unchecked(data, IX_LEN) };
+            *data = rem;
+
+            // This is always aligned and all bit patterns are valid
+            Ok(unsafe { &*(ix_data.as_ptr() as *const NoopArgs) })
+        } else {
+            Err(ProgramError::InvalidInstructionData)
+        }
+    }
+
+    pub const fn size() -> usize {
+        core::mem::size_of::<Self>()
+    }
+}
+
+/// Does nothing and consumes no accounts. Batch one in with a unique nonce to
+/// de-duplicate otherwise identical transactions.
+pub fn noop(
+    _accounts: &[NoStdAccountInfo],
+    _args: &NoopArgs,
+) -> Result<usize, ProgramError> {
+    Ok(0)
+}
@@ -0,0 +1,150 @@
+//! Unaligned instruction data
+//!
+//! Aligned ops are `[u64 tag][args]` with 8-byte aligned args, which forces
+//! padding (e.g. `bump: u64`). A `Packed` op switches the rest of the batch
+//! to `[u8 tag][packed args]`, where args are read with `read_unaligned` and
+//! fields are only as wide as they need to be. Ops before the switch are
+//! unaffected, so existing clients keep working.
+//!
+//! Only the ops below have a packed encoding. The others fail with
+//! UnknownTag in packed mode.
+//!
+//! | op                | packed args                        |
+//! |-------------------|------------------------------------|
+//! | Mint              | [MintArgs]                         |
+//! | Burn              | [BurnArgs]                         |
+//! | Transfer          | [TransferArgs]                     |
+//! | Noop              | [NoopArgs]                         |
+//! | InitializeAccount | [PackedInitializeAccountArgs]      |
+//! | InitializeMint    | [PackedInitializeMintArgs]         |
+//! | Transmute         | [PackedTransmuteArgs]              |
+use crate::solana_nostd_entrypoint::NoStdAccountInfo;
+use bytemuck::{Pod, Zeroable};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+
+use crate::utils::split_at_unchecked;
+
+use super::{
+    InitializeAccountArgs, InitializeMintArgs, TransferFeeConfig, TransmuteArgs,
+};
+
+#[derive(PartialEq, Debug, Clone, Copy, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct PackedArgs {}
+
+impl PackedArgs {
+    pub fn from_data<'a>(
+        _data: &mut &'a [u8],
+    ) -> Result<&'a PackedArgs, ProgramError> {
+        Ok(&PackedArgs {})
+    }
+
+    pub const fn size() -> usize {
+        core::mem::size_of::<Self>()
+    }
+}
+
+/// Switches the rest of the batch to packed mode. The switch itself is done
+/// by the instruction iterator, so this consumes no accounts.
+pub fn packed(
+    _accounts: &[NoStdAccountInfo],
+    _args: &PackedArgs,
+) -> Result<usize, ProgramError> {
+    Ok(0)
+}
+
+/// Reads a `T` from the front of `data`, which need not be aligned
+#[inline(always)]
+pub fn read_unaligned<T: Pod>(data: &mut &[u8]) -> Result<T, ProgramError> {
+    let len = core::mem::size_of::<T>();
+    if data.len() >= len {
+        // SAFETY:
+        // We do the length check ourselves instead of via
+        // core::slice::split_at so we can return an error
+        // instead of panicking.
+        let (ix_data, rem) = unsafe { split_at_unchecked(data, len) };
+        *data = rem;
+
+        // All bit patterns are valid
+        Ok(unsafe { core::ptr::read_unaligned(ix_data.as_ptr() as *const T) })
+    } else {
+        Err(ProgramError::InvalidInstructionData)
+    }
+}
+
+/// Packed [InitializeAccountArgs]. 43 bytes instead of 64.
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C, packed)]
+pub struct PackedInitializeAccountArgs {
+    pub owner: Pubkey,
+    pub mint: u64,
+    pub bump: u8,
+    pub immutable_owner: u8,
+    pub owner_must_sign: u8,
+}
+
+impl From<PackedInitializeAccountArgs> for InitializeAccountArgs {
+    fn from(args: PackedInitializeAccountArgs) -> InitializeAccountArgs {
+        InitializeAccountArgs {
+            owner: args.owner,
+            mint: args.mint,
+            bump: args.bump as u64,
+            immutable_owner: args.immutable_owner as u64,
+            owner_must_sign: args.owner_must_sign as u64,
+        }
+    }
+}
+
+/// Packed [InitializeMintArgs]. 182 bytes instead of 192.
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C, packed)]
+pub struct PackedInitializeMintArgs {
+    pub authority: Pubkey,
+    pub decimals: u32,
+    pub freeze_authority: Pubkey,
+    pub transfer_fee_authority: Pubkey,
+    pub transfer_fee_bps: u16,
+    pub max_transfer_fee: u64,
+    pub pause_authority: Pubkey,
+    pub max_supply: u64,
+    pub clawback_authority: Pubkey,
+}
+
+impl From<PackedInitializeMintArgs> for InitializeMintArgs {
+    fn from(args: PackedInitializeMintArgs) -> InitializeMintArgs {
+        InitializeMintArgs {
+            authority: args.authority,
+            decimals: args.decimals as u64,
+            freeze_authority: args.freeze_authority,
+            transfer_fee: TransferFeeConfig {
+                authority: args.transfer_fee_authority,
+                bps: args.transfer_fee_bps as u64,
+                max_fee: args.max_transfer_fee,
+            },
+            pause_authority: args.pause_authority,
+            max_supply: args.max_supply,
+            clawback_authority: args.clawback_authority,
+        }
+    }
+}
+
+/// Packed [TransmuteArgs]. 9 bytes instead of 16.
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C, packed)]
+pub struct PackedTransmuteArgs {
+    pub amount: u64,
+    pub bump: u8,
+}
+
+impl From<PackedTransmuteArgs> for TransmuteArgs {
+    fn from(args: PackedTransmuteArgs) -> TransmuteArgs {
+        TransmuteArgs {
+            amount: args.amount,
+            bump: args.bump as u64,
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use crate::solana_nostd_entrypoint::NoStdAccountInfo;
+use bytemuck::{Pod, Zeroable};
+use solana_program::{log, program_error::ProgramError};
+
+use crate::{error::NanoTokenError, utils::check_authority, Mint};
+
+#[derive(PartialEq, Debug, Clone, Pod, Zeroable, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct PauseArgs {
+    // Keeping this scaffolded just in case...
+}
+
+impl PauseArgs {
+    pub fn from_data<'a>(
+        _data: &mut &'a [u8],
+    ) -> Result<&'a PauseArgs, ProgramError> {
+        // Zero-sized, so there is nothing to consume
+        Ok(&PauseArgs {})
+    }
+
+    pub const fn size() -> usize {
+        core::mem::size_of::<Self>()
+    }
+}
+
+/// While a mint is paused, transfer, mint, burn, and transmute involving it
+/// fail with [NanoTokenError::MintPaused].
+pub fn pause(
+    accounts: &[NoStdAccountInfo],
+    _args: &PauseArgs,
+) -> Result<usize, ProgramError> {
+    // Unpack accounts
+    //
+    // 1) mint is checked by loader and mutated, which is an implicit owner
+    //    check
+    // 2) pause_authority must be the mint pause authority and must be signer
+    let [mint, pause_authority, _rem @ ..] = accounts else {
+        log::sol_log("pause expecting [mint, pause_authority, .. ]");
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    let mut mint_data = mint
+        .try_borrow_mut_data()
+        .ok_or(NanoTokenError::DuplicateAccount)?;
+    let mint_account = Mint::checked_load_mut(&mut mint_data)?;
+
+    // Check that the pause authority is correct and signed this.
+    // [0; 32] can never sign, so mints without one can't be paused.
+    check_authority(pause_authority, &mint_account.pause_authority)?;
+
+    mint_account.paused = 1;
+
+    Ok(2)
+}
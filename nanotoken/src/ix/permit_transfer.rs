@@ -0,0 +1,192 @@
+use crate::solana_nostd_entrypoint::NoStdAccountInfo;
+use bytemuck::{Pod, Zeroable};
+use solana_program::{
+    clock::Clock, log, program_error::ProgramError, pubkey::Pubkey,
+    sysvar::instructions::ID as INSTRUCTIONS_SYSVAR, sysvar::Sysvar,
+};
+
+use crate::{
+    consts::NATIVE_MINT_INDEX,
+    error::NanoTokenError,
+    utils::{
+        check_ed25519_signed, check_memo_present, move_native_lamports,
+        split_at_unchecked,
+    },
+    TokenAccount,
+};
+
+#[derive(PartialEq, Debug, Clone, Copy, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct PermitTransferArgs {
+    pub amount: u64,
+    /// Must equal the from account's current permit_nonce
+    pub nonce: u64,
+    /// Unix timestamp after which the permit can no longer be used
+    pub expiry: i64,
+}
+
+impl PermitTransferArgs {
+    pub fn from_data<'a>(
+        data: &mut &'a [u8],
+    ) -> Result<&'a PermitTransferArgs, ProgramError> {
+        const IX_LEN: usize = core::mem::size_of::<PermitTransferArgs>();
+        if data.len() >= IX_LEN {
+            // SAFETY:
+            // We do the length check ourselves instead of via
+            // core::slice::split_at so we can return an error
+            // instead of panicking.
+            let (ix_data, rem) = unsafe { split_at_unchecked(data, IX_LEN) };
+            *data = rem;
+
+            // This is always aligned and all bit patterns are valid
+            Ok(unsafe { &*(ix_data.as_ptr() as *const PermitTransferArgs) })
+        } else {
+            Err(ProgramError::InvalidInstructionData)
+        }
+    }
+
+    pub const fn size() -> usize {
+        core::mem::size_of::<Self>()
+    }
+}
+
+/// The message the from account owner signs off-chain. The signed bytes are
+/// exactly `bytemuck::bytes_of(&PermitMessage { .. })`.
+#[derive(PartialEq, Debug, Clone, Copy, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct PermitMessage {
+    pub from: Pubkey,
+    pub to: Pubkey,
+    pub amount: u64,
+    pub nonce: u64,
+    pub expiry: i64,
+}
+
+/// Executes a transfer the from account owner signed offline. Anyone (e.g. a
+/// relayer) may submit it, as long as the transaction includes an ed25519
+/// program instruction verifying the owner's signature over the
+/// [PermitMessage].
+pub fn permit_transfer(
+    accounts: &[NoStdAccountInfo],
+    args: &PermitTransferArgs,
+) -> Result<usize, ProgramError> {
+    // Unpack accounts
+    //
+    // 1) from is mutated (nonce), which is an implicit owner check. Its owner
+    //    must have signed the permit.
+    // 2) to needs to have the same mint as from, and is mutated which is an
+    //    implicit owner check
+    // 3) instructions sysvar pubkey check is validated below
+    let [from, to, instructions_sysvar, _rem @ ..] = accounts else {
+        log::sol_log(
+            "permit_transfer expecting [from, to, instructions_sysvar, .. ]",
+        );
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // Check instructions sysvar
+    if solana_program::program_memory::sol_memcmp(
+        instructions_sysvar.key().as_ref(),
+        INSTRUCTIONS_SYSVAR.as_ref(),
+        32,
+    ) != 0
+    {
+        log::sol_log("incorrect instructions sysvar");
+        return Err(ProgramError::UnsupportedSysvar);
+    }
+
+    // Load from/to
+    let from_account = unsafe { TokenAccount::check_disc_raw(from)? };
+    let to_account = unsafe { TokenAccount::check_disc_raw(to)? };
+
+    // Check neither account is frozen
+    unsafe {
+        (*from_account).check_not_frozen()?;
+        (*to_account).check_not_frozen()?;
+        (*from_account).check_not_timelocked()?;
+    }
+
+    // Check mint is not paused. The mint may be passed anywhere after this
+    // op's accounts.
+    unsafe { (*from_account).check_not_paused(accounts)? };
+
+    // Check both owners against the mint list. The list may be passed
+    // anywhere after this op's accounts.
+    unsafe { (*from_account).check_list(&(*to_account).owner, accounts)? };
+
+    // Check memo
+    if unsafe { (*to_account).requires_memo() } {
+        check_memo_present(accounts)?;
+    }
+
+    // Check permit is live and not replayed
+    if Clock::get()?.unix_timestamp > args.expiry {
+        log::sol_log("permit expired");
+        return Err(NanoTokenError::PermitExpired.into());
+    }
+    if unsafe { (*from_account).permit_nonce } != args.nonce {
+        log::sol_log("incorrect permit nonce");
+        return Err(NanoTokenError::InvalidPermit.into());
+    }
+
+    // Check owner signed this exact permit
+    let message = PermitMessage {
+        from: *from.key(),
+        to: *to.key(),
+        amount: args.amount,
+        nonce: args.nonce,
+        expiry: args.expiry,
+    };
+    check_ed25519_signed(
+        instructions_sysvar,
+        unsafe { &(*from_account).owner },
+        bytemuck::bytes_of(&message),
+    )?;
+
+    // Check that the mints match
+    if unsafe { (*from_account).mint != (*to_account).mint } {
+        log::sol_log("from/to mint mismatch");
+        return Err(NanoTokenError::IncorrectMint.into());
+    }
+
+    // Check from_account balance
+    if unsafe { (*from_account).balance } < args.amount {
+        log::sol_log("insufficient balance");
+        return Err(NanoTokenError::InsufficientTokenBalance.into());
+    }
+
+    // Consume nonce and transfer. The nonce write is an implicit owner check
+    // on from, even when amount is zero.
+    unsafe {
+        (*from_account).permit_nonce += 1;
+        let royalty =
+            TokenAccount::pay_royalty(from_account, args.amount, accounts)?;
+        TokenAccount::move_balance(
+            from_account,
+            to_account,
+            args.amount - royalty,
+        )?;
+
+        // Native balances carry their lamports with them
+        if (*from_account).mint == NATIVE_MINT_INDEX {
+            move_native_lamports(from, to, args.amount);
+        }
+    }
+
+    // Emit event. The noop program may be passed anywhere after this op's
+    // accounts.
+    #[cfg(feature = "noop-events")]
+    crate::events::emit(
+        accounts,
+        crate::events::EventTag::Transfer,
+        &crate::events::TransferEvent {
+            from: *from.key(),
+            to: *to.key(),
+            amount: args.amount,
+        },
+    )?;
+
+    Ok(3)
+}
@@ -0,0 +1,206 @@
+use crate::solana_nostd_entrypoint::NoStdAccountInfo;
+use bytemuck::{Pod, Zeroable};
+use solana_program::{log, program_error::ProgramError, pubkey::Pubkey};
+
+use crate::{
+    consts::MAX_INTENT_APPROVERS,
+    error::NanoTokenError,
+    utils::{
+        check_authority, create_pda_funded_by_payer, paranoid_check_pda,
+        split_at_mut_unchecked, split_at_unchecked,
+    },
+    AccountDiscriminator, TokenAccount, TransferIntent,
+};
+
+#[derive(PartialEq, Debug, Clone, Copy, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct ProposeTransferArgs {
+    pub amount: u64,
+    /// Distinguishes intents from the same account. Part of the intent seeds.
+    pub nonce: u64,
+    /// Unix timestamp before which the intent cannot be executed. Use 0 for
+    /// no timelock.
+    pub execute_after: i64,
+    /// u64 is used for alignment. Must not exceed the number of approvers.
+    pub threshold: u64,
+    /// u64 is used for alignment
+    pub bump: u64,
+    /// [0; 32] is used as an empty slot
+    pub approvers: [Pubkey; MAX_INTENT_APPROVERS],
+}
+
+impl ProposeTransferArgs {
+    pub fn from_data<'a>(
+        data: &mut &'a [u8],
+    ) -> Result<&'a ProposeTransferArgs, ProgramError> {
+        const IX_LEN: usize = core::mem::size_of::<ProposeTransferArgs>();
+        if data.len() >= IX_LEN {
+            // SAFETY:
+            // We do the length check ourselves instead of via
+            // core::slice::split_at so we can return an error
+            // instead of panicking.
+            let (ix_data, rem) = unsafe { split_at_unchecked(data, IX_LEN) };
+            *data = rem;
+
+            // This is always aligned and all bit patterns are valid
+            Ok(unsafe { &*(ix_data.as_ptr() as *const ProposeTransferArgs) })
+        } else {
+            Err(ProgramError::InvalidInstructionData)
+        }
+    }
+
+    pub const fn size() -> usize {
+        core::mem::size_of::<Self>()
+    }
+}
+
+/// Records a transfer out of `from` in an intent PDA, to be executed once
+/// enough approvers sign off. The authority is either the owner of `from` or
+/// its delegate. A delegate spends from its allowance when proposing.
+pub fn propose_transfer(
+    accounts: &[NoStdAccountInfo],
+    args: &ProposeTransferArgs,
+) -> Result<usize, ProgramError> {
+    // Unpack accounts
+    //
+    // 1) intent is validated by create_pda_funded_by_payer (seeds)
+    // 2) from needs a disc check and an authority check. Owner check is done
+    //    implicitly when a delegate's allowance is debited, and otherwise at
+    //    execution, when from is mutated.
+    // 3) authority must be from owner or from delegate and must be signer
+    // 4) to needs a disc check and must have the same mint as from
+    // 5) system program is checked by memoized validator closure
+    // 6) payer will be checked by the sol transfer
+    let [intent, from, authority, to, _rem @ .., _config, system_program, payer] =
+        accounts
+    else {
+        log::sol_log(
+            "propose_transfer expecting [intent, from, authority, to, .. config, system_program, payer]",
+        );
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // Execution borrows from and to at once
+    if from.key() == to.key() {
+        log::sol_log("from and to must be distinct");
+        return Err(NanoTokenError::DuplicateAccount.into());
+    }
+
+    // Check from/to
+    // SAFETY: no one else has a view into from/to data
+    let from_account = unsafe { TokenAccount::check_disc_raw(from)? };
+    let to_account = unsafe { TokenAccount::check_disc_raw(to)? };
+    if unsafe { (*from_account).mint != (*to_account).mint } {
+        log::sol_log("from/to mint mismatch");
+        return Err(NanoTokenError::IncorrectMint.into());
+    }
+
+    // Check threshold is reachable by distinct approvers
+    let mut num_approvers = 0;
+    for (i, approver) in args.approvers.iter().enumerate() {
+        if *approver == Pubkey::default() {
+            continue;
+        }
+        if args.approvers[i + 1..].contains(approver) {
+            log::sol_log("duplicate approver");
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        num_approvers += 1;
+    }
+    if args.threshold > num_approvers {
+        log::sol_log("threshold exceeds number of approvers");
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    // Check that the owner or delegate is correct and signed this. The
+    // delegate spends from its allowance, and is revoked once it is spent.
+    // Cancelling the intent does not restore the allowance.
+    unsafe {
+        if solana_program::program_memory::sol_memcmp(
+            authority.key().as_ref(),
+            (*from_account).owner.as_ref(),
+            32,
+        ) == 0
+        {
+            if !authority.is_signer() {
+                log::sol_log("authority must sign");
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+        } else {
+            check_authority(authority, &(*from_account).delegate)?;
+            if (*from_account).delegated_amount < args.amount {
+                log::sol_log("insufficient delegated amount");
+                return Err(NanoTokenError::InsufficientDelegatedAmount.into());
+            }
+            (*from_account).delegated_amount -= args.amount;
+
+            // Auto-revoke once the allowance is used up
+            if (*from_account).delegated_amount == 0 {
+                (*from_account).delegate = Pubkey::default();
+            }
+        }
+    }
+
+    // Create intent
+    let nonce = args.nonce.to_le_bytes();
+    paranoid_check_pda(
+        &[b"intent", from.key().as_ref(), nonce.as_ref()],
+        intent,
+    )?;
+    let intent_seeds: &[&[u8]] = &[
+        b"intent",
+        from.key().as_ref(),
+        nonce.as_ref(),
+        &[args.bump as u8],
+    ];
+    // SAFETY: no one has any write access to any account at this stage.
+    unsafe {
+        create_pda_funded_by_payer(
+            intent.to_info_c(),
+            &crate::ID,
+            TransferIntent::space() as u64,
+            intent_seeds,
+            system_program.to_info_c(),
+            payer.to_info_c(),
+        )?
+    };
+
+    // Write initial state
+    // SAFETY:
+    // 1) no one holds a view into the intent
+    // 2) we just validated data length by creating account
+    unsafe {
+        let (disc, intent_data) =
+            split_at_mut_unchecked(intent.unchecked_borrow_mut_data(), 8);
+        *disc.get_unchecked_mut(0) = AccountDiscriminator::TransferIntent as u8;
+
+        let TransferIntent {
+            from: intent_from,
+            to: intent_to,
+            amount,
+            nonce,
+            execute_after,
+            approvers,
+            threshold,
+            approvals,
+            executed,
+            bump,
+            _padding,
+            proposer,
+        } = &mut *(intent_data.as_mut_ptr() as *mut TransferIntent);
+        *intent_from = *from.key();
+        *intent_to = *to.key();
+        *amount = args.amount;
+        *nonce = args.nonce;
+        *execute_after = args.execute_after;
+        *approvers = args.approvers;
+        *threshold = args.threshold as u8;
+        *approvals = 0;
+        *executed = 0;
+        *bump = args.bump as u8;
+        *proposer = *authority.key();
+    }
+
+    Ok(4)
+}
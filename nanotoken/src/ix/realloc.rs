@@ -0,0 +1,92 @@
+use crate::solana_nostd_entrypoint::NoStdAccountInfo;
+use bytemuck::{Pod, Zeroable};
+use solana_program::{
+    entrypoint::MAX_PERMITTED_DATA_INCREASE, log, program_error::ProgramError,
+};
+
+use crate::{
+    error::NanoTokenError,
+    utils::{check_authority, realloc_funded_by_payer, split_at_unchecked},
+    TokenAccount,
+};
+
+#[derive(PartialEq, Debug, Clone, Copy, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct ReallocArgs {
+    /// Total data length, including the 8 byte discriminator
+    pub new_len: u64,
+}
+
+impl ReallocArgs {
+    pub fn from_data<'a>(
+        data: &mut &'a [u8],
+    ) -> Result<&'a ReallocArgs, ProgramError> {
+        const IX_LEN: usize = core::mem::size_of::<ReallocArgs>();
+        if data.len() >= IX_LEN {
+            // SAFETY:
+            // We do the length check ourselves instead of via
+            // core::slice::split_at so we can return an error
+            // instead of panicking.
+            let (ix_data, rem) = unsafe { split_at_unchecked(data, IX_LEN) };
+            *data = rem;
+
+            // This is always aligned and all bit patterns are valid
+            Ok(unsafe { &*(ix_data.as_ptr() as *const ReallocArgs) })
+        } else {
+            Err(ProgramError::InvalidInstructionData)
+        }
+    }
+
+    pub const fn size() -> usize {
+        core::mem::size_of::<Self>()
+    }
+}
+
+/// Grows a token account so extensions can be added after creation. Payer
+/// tops up rent. Space after the TokenAccount is zeroed and reserved for
+/// extensions, and is kept past the TokenAccount when it is migrated to a
+/// newer layout.
+pub fn realloc(
+    accounts: &[NoStdAccountInfo],
+    args: &ReallocArgs,
+) -> Result<usize, ProgramError> {
+    // Unpack accounts
+    //
+    // 1) token_account is checked by loader and reallocated, which is an
+    //    implicit owner check
+    // 2) owner must be token_account owner and must be signer
+    // 3) system program is checked by memoized validator closure
+    // 4) payer will be checked by the sol transfer if necessary
+    let [token_account, owner, _rem @ .., _config, _system_program, payer] =
+        accounts
+    else {
+        log::sol_log(
+            "realloc expecting [token_account, owner, .. config, system_program, payer]",
+        );
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // Check owner
+    {
+        let mut token_account_data = token_account
+            .try_borrow_mut_data()
+            .ok_or(NanoTokenError::DuplicateAccount)?;
+        let token_account =
+            TokenAccount::checked_load_mut(&mut token_account_data)?;
+        check_authority(owner, &token_account.owner)?;
+    }
+
+    // Accounts only grow. The runtime also caps growth per instruction.
+    let new_len = args.new_len as usize;
+    let old_len = token_account.data_len();
+    if new_len < old_len || new_len - old_len > MAX_PERMITTED_DATA_INCREASE {
+        log::sol_log("invalid token_account data len");
+        return Err(ProgramError::InvalidRealloc);
+    }
+
+    // SAFETY: no one holds a view into token_account data or lamports
+    unsafe { realloc_funded_by_payer(token_account, payer, new_len)? };
+
+    Ok(2)
+}
@@ -0,0 +1,72 @@
+use crate::solana_nostd_entrypoint::NoStdAccountInfo;
+use bytemuck::{Pod, Zeroable};
+use solana_program::{log, program_error::ProgramError, pubkey::Pubkey};
+
+use crate::{
+    error::NanoTokenError,
+    utils::{check_authority, split_at_unchecked},
+    MintList,
+};
+
+#[derive(PartialEq, Debug, Clone, Copy, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct RemoveFromListArgs {
+    pub owner: Pubkey,
+}
+
+impl RemoveFromListArgs {
+    pub fn from_data<'a>(
+        data: &mut &'a [u8],
+    ) -> Result<&'a RemoveFromListArgs, ProgramError> {
+        const IX_LEN: usize = core::mem::size_of::<RemoveFromListArgs>();
+        if data.len() >= IX_LEN {
+            // SAFETY:
+            // We do the length check ourselves instead of via
+            // core::slice::split_at so we can return an error
+            // instead of panicking.
+            let (ix_data, rem) = unsafe { split_at_unchecked(data, IX_LEN) };
+            *data = rem;
+
+            // This is always aligned and all bit patterns are valid
+            Ok(unsafe { &*(ix_data.as_ptr() as *const RemoveFromListArgs) })
+        } else {
+            Err(ProgramError::InvalidInstructionData)
+        }
+    }
+
+    pub const fn size() -> usize {
+        core::mem::size_of::<Self>()
+    }
+}
+
+/// Removing an owner that is not listed is a no-op. The freed slot is kept
+/// for future additions.
+pub fn remove_from_list(
+    accounts: &[NoStdAccountInfo],
+    args: &RemoveFromListArgs,
+) -> Result<usize, ProgramError> {
+    // Unpack accounts
+    //
+    // 1) list is mutated, which is an implicit owner check
+    // 2) list_authority must be the list authority and must be signer
+    let [list, list_authority, _rem @ ..] = accounts else {
+        log::sol_log("remove_from_list expecting [list, list_authority, .. ]");
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    let mut list_data = list
+        .try_borrow_mut_data()
+        .ok_or(NanoTokenError::DuplicateAccount)?;
+    let (list_account, entries) = MintList::checked_load_mut(&mut list_data)?;
+    check_authority(list_authority, &list_account.authority)?;
+
+    let len = list_account.len as usize;
+    if let Ok(index) = entries[..len].binary_search(&args.owner) {
+        entries.copy_within(index + 1..len, index);
+        entries[len - 1] = Pubkey::default();
+        list_account.len -= 1;
+    }
+
+    Ok(2)
+}
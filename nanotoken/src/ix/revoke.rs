@@ -0,0 +1,55 @@
+use crate::solana_nostd_entrypoint::NoStdAccountInfo;
+use bytemuck::{Pod, Zeroable};
+use solana_program::{log, program_error::ProgramError, pubkey::Pubkey};
+
+use crate::{error::NanoTokenError, utils::check_authority, TokenAccount};
+
+#[derive(PartialEq, Debug, Clone, Pod, Zeroable, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct RevokeArgs {
+    // Keeping this scaffolded just in case...
+}
+
+impl RevokeArgs {
+    pub fn from_data<'a>(
+        _data: &mut &'a [u8],
+    ) -> Result<&'a RevokeArgs, ProgramError> {
+        // Zero-sized, so there is nothing to consume
+        Ok(&RevokeArgs {})
+    }
+
+    pub const fn size() -> usize {
+        core::mem::size_of::<Self>()
+    }
+}
+
+pub fn revoke(
+    accounts: &[NoStdAccountInfo],
+    _args: &RevokeArgs,
+) -> Result<usize, ProgramError> {
+    // Unpack accounts
+    //
+    // 1) token_account is checked by loader and mutated, which is an implicit
+    //    owner check
+    // 2) owner must be token_account owner and must be signer
+    let [token_account, owner, _rem @ ..] = accounts else {
+        log::sol_log("revoke expecting [token_account, owner, .. ]");
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    let mut token_account_data = token_account
+        .try_borrow_mut_data()
+        .ok_or(NanoTokenError::DuplicateAccount)?;
+    let token_account =
+        TokenAccount::checked_load_mut(&mut token_account_data)?;
+
+    // Check that the owner is correct and signed this
+    check_authority(owner, &token_account.owner)?;
+
+    // Clear delegate
+    token_account.delegate = Pubkey::default();
+    token_account.delegated_amount = 0;
+
+    Ok(2)
+}
@@ -0,0 +1,133 @@
+use crate::solana_nostd_entrypoint::NoStdAccountInfo;
+use bytemuck::{Pod, Zeroable};
+use solana_program::{log, program_error::ProgramError, pubkey::Pubkey};
+
+use crate::{
+    error::NanoTokenError,
+    utils::{check_authority, split_at_unchecked},
+    Mint, TokenAccount,
+};
+
+#[derive(PartialEq, Debug, Clone, Copy, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct SetAuthorityArgs {
+    /// [0; 32] is used as None
+    pub new_authority: Pubkey,
+    /// u64 is used for alignment. See [AuthorityType].
+    pub authority_type: u64,
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+#[repr(u64)]
+pub enum AuthorityType {
+    MintAuthority = 0,
+    FreezeAccount = 1,
+    PauseMint = 2,
+    ClawbackTokens = 3,
+    /// Owner of a token account rather than a mint authority. Fails for
+    /// accounts with an immutable owner.
+    AccountOwner = 4,
+}
+
+impl SetAuthorityArgs {
+    pub fn from_data<'a>(
+        data: &mut &'a [u8],
+    ) -> Result<&'a SetAuthorityArgs, ProgramError> {
+        const IX_LEN: usize = core::mem::size_of::<SetAuthorityArgs>();
+        if data.len() >= IX_LEN {
+            // SAFETY:
+            // We do the length check ourselves instead of via
+            // core::slice::split_at so we can return an error
+            // instead of panicking.
+            let (ix_data, rem) = unsafe { split_at_unchecked(data, IX_LEN) };
+            *data = rem;
+
+            // This is always aligned and all bit patterns are valid
+            Ok(unsafe { &*(ix_data.as_ptr() as *const SetAuthorityArgs) })
+        } else {
+            Err(ProgramError::InvalidInstructionData)
+        }
+    }
+
+    pub const fn size() -> usize {
+        core::mem::size_of::<Self>()
+    }
+}
+
+pub fn set_authority(
+    accounts: &[NoStdAccountInfo],
+    args: &SetAuthorityArgs,
+) -> Result<usize, ProgramError> {
+    // Unpack accounts
+    //
+    // 1) mint (token account for AccountOwner) is checked by loader and
+    //    mutated, which is an implicit owner check
+    // 2) authority must be the current authority and must be signer. This can
+    //    be a PDA (e.g. a governance program) signing via invoke_signed.
+    let [mint, authority, _rem @ ..] = accounts else {
+        log::sol_log("set_authority expecting [mint, authority, .. ]");
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if args.authority_type == AuthorityType::AccountOwner as u64 {
+        let token_account = mint;
+        let mut token_account_data = token_account
+            .try_borrow_mut_data()
+            .ok_or(NanoTokenError::DuplicateAccount)?;
+        let token_account =
+            TokenAccount::checked_load_mut(&mut token_account_data)?;
+        check_authority(authority, &token_account.owner)?;
+        if token_account.has_immutable_owner() {
+            log::sol_log("token_account owner is immutable");
+            return Err(NanoTokenError::ImmutableOwner.into());
+        }
+        if args.new_authority == Pubkey::default() {
+            log::sol_log("token_account must have an owner");
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        // Listed accounts can only move to allowed owners. The mint list may
+        // be passed anywhere after this op's accounts.
+        token_account.check_list(&args.new_authority, accounts)?;
+        token_account.owner = args.new_authority;
+
+        // The address no longer derives from the owner
+        token_account.bump = 0;
+
+        // The previous owner's approval does not carry over
+        token_account.delegate = Pubkey::default();
+        token_account.delegated_amount = 0;
+
+        return Ok(2);
+    }
+
+    let mut mint_data = mint
+        .try_borrow_mut_data()
+        .ok_or(NanoTokenError::DuplicateAccount)?;
+    let mint_account = Mint::checked_load_mut(&mut mint_data)?;
+
+    match args.authority_type {
+        x if x == AuthorityType::MintAuthority as u64 => {
+            check_authority(authority, &mint_account.authority)?;
+            mint_account.authority = args.new_authority;
+        }
+        x if x == AuthorityType::FreezeAccount as u64 => {
+            check_authority(authority, &mint_account.freeze_authority)?;
+            mint_account.freeze_authority = args.new_authority;
+        }
+        x if x == AuthorityType::PauseMint as u64 => {
+            check_authority(authority, &mint_account.pause_authority)?;
+            mint_account.pause_authority = args.new_authority;
+        }
+        x if x == AuthorityType::ClawbackTokens as u64 => {
+            check_authority(authority, &mint_account.clawback_authority)?;
+            mint_account.clawback_authority = args.new_authority;
+        }
+        _ => {
+            log::sol_log("invalid authority type");
+            return Err(ProgramError::InvalidInstructionData);
+        }
+    }
+
+    Ok(2)
+}
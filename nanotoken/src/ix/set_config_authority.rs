@@ -0,0 +1,70 @@
+use crate::solana_nostd_entrypoint::NoStdAccountInfo;
+use bytemuck::{Pod, Zeroable};
+use solana_program::{log, program_error::ProgramError, pubkey::Pubkey};
+
+use crate::{utils::split_at_unchecked, ProgramConfig};
+
+#[derive(PartialEq, Debug, Clone, Copy, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct SetConfigAuthorityArgs {
+    /// [0; 32] cancels a pending transfer
+    pub new_authority: Pubkey,
+}
+
+impl SetConfigAuthorityArgs {
+    pub fn from_data<'a>(
+        data: &mut &'a [u8],
+    ) -> Result<&'a SetConfigAuthorityArgs, ProgramError> {
+        const IX_LEN: usize = core::mem::size_of::<SetConfigAuthorityArgs>();
+        if data.len() >= IX_LEN {
+            // SAFETY:
+            // We do the length check ourselves instead of via
+            // core::slice::split_at so we can return an error
+            // instead of panicking.
+            let (ix_data, rem) = unsafe { split_at_unchecked(data, IX_LEN) };
+            *data = rem;
+
+            // This is always aligned and all bit patterns are valid
+            Ok(
+                unsafe {
+                    &*(ix_data.as_ptr() as *const SetConfigAuthorityArgs)
+                },
+            )
+        } else {
+            Err(ProgramError::InvalidInstructionData)
+        }
+    }
+
+    pub const fn size() -> usize {
+        core::mem::size_of::<Self>()
+    }
+}
+
+/// First step of a config authority transfer. The new authority only takes
+/// over once it signs AcceptConfigAuthority, so a mistyped key can't lock
+/// the config.
+pub fn set_config_authority(
+    accounts: &[NoStdAccountInfo],
+    args: &SetConfigAuthorityArgs,
+) -> Result<usize, ProgramError> {
+    // Unpack accounts
+    //
+    // 1) authority must be the config authority and must be signer
+    // 2) config is checked by memoized validator closure
+    let [authority, _rem @ .., config, _system_program, _payer] = accounts
+    else {
+        log::sol_log(
+            "set_config_authority expecting [authority, .. config, system_program, payer]",
+        );
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // SAFETY: no one else has a view into config's data
+    let config_account = unsafe { ProgramConfig::unchecked_load_mut(config)? };
+    config_account.check_authority(authority)?;
+
+    config_account.pending_authority = args.new_authority;
+
+    Ok(1)
+}
@@ -0,0 +1,76 @@
+use crate::solana_nostd_entrypoint::NoStdAccountInfo;
+use bytemuck::{Pod, Zeroable};
+use solana_program::{log, program_error::ProgramError};
+
+use crate::{utils::split_at_unchecked, ProgramConfig};
+
+#[derive(PartialEq, Debug, Clone, Copy, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct SetFeatureArgs {
+    /// Bit index, see [crate::ProgramFeature]. Must be less than 64.
+    pub feature: u64,
+    /// u64 is used for alignment. Nonzero enables, zero disables.
+    pub enabled: u64,
+}
+
+impl SetFeatureArgs {
+    pub fn from_data<'a>(
+        data: &mut &'a [u8],
+    ) -> Result<&'a SetFeatureArgs, ProgramError> {
+        const IX_LEN: usize = core::mem::size_of::<SetFeatureArgs>();
+        if data.len() >= IX_LEN {
+            // SAFETY:
+            // We do the length check ourselves instead of via
+            // core::slice::split_at so we can return an error
+            // instead of panicking.
+            let (ix_data, rem) = unsafe { split_at_unchecked(data, IX_LEN) };
+            *data = rem;
+
+            // This is always aligned and all bit patterns are valid
+            Ok(unsafe { &*(ix_data.as_ptr() as *const SetFeatureArgs) })
+        } else {
+            Err(ProgramError::InvalidInstructionData)
+        }
+    }
+
+    pub const fn size() -> usize {
+        core::mem::size_of::<Self>()
+    }
+}
+
+/// Enables or disables a program feature gate. Disabling a feature only stops
+/// new uses of it, e.g. existing mints keep their transfer fees.
+pub fn set_feature(
+    accounts: &[NoStdAccountInfo],
+    args: &SetFeatureArgs,
+) -> Result<usize, ProgramError> {
+    // Unpack accounts
+    //
+    // 1) authority must be the config authority and must be signer
+    // 2) config is checked by memoized validator closure
+    let [authority, _rem @ .., config, _system_program, _payer] = accounts
+    else {
+        log::sol_log(
+            "set_feature expecting [authority, .. config, system_program, payer]",
+        );
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if args.feature >= u64::BITS as u64 {
+        log::sol_log("feature index out of range");
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    // SAFETY: no one else has a view into config's data
+    let config_account = unsafe { ProgramConfig::unchecked_load_mut(config)? };
+    config_account.check_authority(authority)?;
+
+    if args.enabled != 0 {
+        config_account.features |= 1 << args.feature;
+    } else {
+        config_account.features &= !(1 << args.feature);
+    }
+
+    Ok(1)
+}
@@ -0,0 +1,67 @@
+use crate::solana_nostd_entrypoint::NoStdAccountInfo;
+use bytemuck::{Pod, Zeroable};
+use solana_program::{log, program_error::ProgramError};
+
+use crate::{utils::split_at_unchecked, ProgramConfig};
+
+#[derive(PartialEq, Debug, Clone, Copy, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct SetGcConfigArgs {
+    /// Minimum age in seconds of an empty token account before it may be
+    /// garbage collected. 0 disables garbage collection.
+    pub min_age: i64,
+    /// Lamports paid to whoever collects an account
+    pub bounty: u64,
+}
+
+impl SetGcConfigArgs {
+    pub fn from_data<'a>(
+        data: &mut &'a [u8],
+    ) -> Result<&'a SetGcConfigArgs, ProgramError> {
+        const IX_LEN: usize = core::mem::size_of::<SetGcConfigArgs>();
+        if data.len() >= IX_LEN {
+            // SAFETY:
+            // We do the length check ourselves instead of via
+            // core::slice::split_at so we can return an error
+            // instead of panicking.
+            let (ix_data, rem) = unsafe { split_at_unchecked(data, IX_LEN) };
+            *data = rem;
+
+            // This is always aligned and all bit patterns are valid
+            Ok(unsafe { &*(ix_data.as_ptr() as *const SetGcConfigArgs) })
+        } else {
+            Err(ProgramError::InvalidInstructionData)
+        }
+    }
+
+    pub const fn size() -> usize {
+        core::mem::size_of::<Self>()
+    }
+}
+
+pub fn set_gc_config(
+    accounts: &[NoStdAccountInfo],
+    args: &SetGcConfigArgs,
+) -> Result<usize, ProgramError> {
+    // Unpack accounts
+    //
+    // 1) authority must be the config authority and must be signer
+    // 2) config is checked by memoized validator closure
+    let [authority, _rem @ .., config, _system_program, _payer] = accounts
+    else {
+        log::sol_log(
+            "set_gc_config expecting [authority, .. config, system_program, payer]",
+        );
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // SAFETY: no one else has a view into config's data
+    let config_account = unsafe { ProgramConfig::unchecked_load_mut(config)? };
+    config_account.check_authority(authority)?;
+
+    config_account.gc_min_age = args.min_age;
+    config_account.gc_bounty = args.bounty;
+
+    Ok(1)
+}
@@ -0,0 +1,74 @@
+use crate::solana_nostd_entrypoint::NoStdAccountInfo;
+use bytemuck::{Pod, Zeroable};
+use solana_program::{log, program_error::ProgramError};
+
+use crate::{
+    error::NanoTokenError,
+    utils::{check_authority, split_at_unchecked},
+    TokenAccount,
+};
+
+#[derive(PartialEq, Debug, Clone, Copy, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct SetMemoRequiredArgs {
+    /// u64 is used for alignment. Nonzero enables, zero disables.
+    pub required: u64,
+}
+
+impl SetMemoRequiredArgs {
+    pub fn from_data<'a>(
+        data: &mut &'a [u8],
+    ) -> Result<&'a SetMemoRequiredArgs, ProgramError> {
+        const IX_LEN: usize = core::mem::size_of::<SetMemoRequiredArgs>();
+        if data.len() >= IX_LEN {
+            // SAFETY:
+            // We do the length check ourselves instead of via
+            // core::slice::split_at so we can return an error
+            // instead of panicking.
+            let (ix_data, rem) = unsafe { split_at_unchecked(data, IX_LEN) };
+            *data = rem;
+
+            // This is always aligned and all bit patterns are valid
+            Ok(unsafe { &*(ix_data.as_ptr() as *const SetMemoRequiredArgs) })
+        } else {
+            Err(ProgramError::InvalidInstructionData)
+        }
+    }
+
+    pub const fn size() -> usize {
+        core::mem::size_of::<Self>()
+    }
+}
+
+pub fn set_memo_required(
+    accounts: &[NoStdAccountInfo],
+    args: &SetMemoRequiredArgs,
+) -> Result<usize, ProgramError> {
+    // Unpack accounts
+    //
+    // 1) token_account is checked by loader and mutated, which is an implicit
+    //    owner check
+    // 2) owner must be token_account owner and must be signer
+    let [token_account, owner, _rem @ ..] = accounts else {
+        log::sol_log("set_memo_required expecting [token_account, owner, .. ]");
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    let mut token_account_data = token_account
+        .try_borrow_mut_data()
+        .ok_or(NanoTokenError::DuplicateAccount)?;
+    let token_account =
+        TokenAccount::checked_load_mut(&mut token_account_data)?;
+
+    // Check that the owner is correct and signed this
+    check_authority(owner, &token_account.owner)?;
+
+    if args.required != 0 {
+        token_account.state |= TokenAccount::MEMO_REQUIRED;
+    } else {
+        token_account.state &= !TokenAccount::MEMO_REQUIRED;
+    }
+
+    Ok(2)
+}
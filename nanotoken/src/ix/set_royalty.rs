@@ -0,0 +1,109 @@
+use crate::solana_nostd_entrypoint::NoStdAccountInfo;
+use bytemuck::{Pod, Zeroable};
+use solana_program::{log, program_error::ProgramError, pubkey::Pubkey};
+
+use crate::{
+    consts::MAX_FEE_BPS,
+    error::NanoTokenError,
+    utils::{check_authority, split_at_unchecked},
+    Mint, TokenAccount,
+};
+
+#[derive(PartialEq, Debug, Clone, Copy, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct SetRoyaltyArgs {
+    /// 0 turns the royalty off
+    pub bps: u64,
+    /// Token account of this mint that receives royalties
+    pub recipient: Pubkey,
+}
+
+impl SetRoyaltyArgs {
+    pub fn from_data<'a>(
+        data: &mut &'a [u8],
+    ) -> Result<&'a SetRoyaltyArgs, ProgramError> {
+        const IX_LEN: usize = core::mem::size_of::<SetRoyaltyArgs>();
+        if data.len() >= IX_LEN {
+            // SAFETY:
+            // We do the length check ourselves instead of via
+            // core::slice::split_at so we can return an error
+            // instead of panicking.
+            let (ix_data, rem) = unsafe { split_at_unchecked(data, IX_LEN) };
+            *data = rem;
+
+            // This is always aligned and all bit patterns are valid
+            Ok(unsafe { &*(ix_data.as_ptr() as *const SetRoyaltyArgs) })
+        } else {
+            Err(ProgramError::InvalidInstructionData)
+        }
+    }
+
+    pub const fn size() -> usize {
+        core::mem::size_of::<Self>()
+    }
+}
+
+/// Sets the royalty skimmed from every transfer. Turning a royalty on must
+/// happen before anything is minted, so that every balance carries
+/// [TokenAccount::ROYALTY]. After that it can be changed or turned off.
+pub fn set_royalty(
+    accounts: &[NoStdAccountInfo],
+    args: &SetRoyaltyArgs,
+) -> Result<usize, ProgramError> {
+    // Unpack accounts
+    //
+    // 1) mint is checked by loader and mutated, which is an implicit owner
+    //    check
+    // 2) mint_authority must be the mint authority and must be signer
+    // 3) recipient needs a disc check and must be an account of this mint.
+    //    Owner check is done implicitly when royalties are paid into it.
+    let [mint, mint_authority, recipient, _rem @ ..] = accounts else {
+        log::sol_log(
+            "set_royalty expecting [mint, mint_authority, recipient, .. ]",
+        );
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if args.bps > MAX_FEE_BPS {
+        log::sol_log("max royalty is 10_000 bps");
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let mut mint_data = mint
+        .try_borrow_mut_data()
+        .ok_or(NanoTokenError::DuplicateAccount)?;
+    let mint_account = Mint::checked_load_mut(&mut mint_data)?;
+    check_authority(mint_authority, &mint_account.authority)?;
+
+    if mint_account.royalty_bps == 0
+        && args.bps != 0
+        && mint_account.supply != 0
+    {
+        log::sol_log("royalty must be turned on before minting");
+        return Err(NanoTokenError::NonZeroSupply.into());
+    }
+
+    // Check recipient
+    if solana_program::program_memory::sol_memcmp(
+        recipient.key().as_ref(),
+        args.recipient.as_ref(),
+        32,
+    ) != 0
+    {
+        log::sol_log("recipient does not match args");
+        return Err(NanoTokenError::RoyaltyRecipientMismatch.into());
+    }
+    // SAFETY: no one else has a view into recipient data
+    let (_owner, recipient_mint, _balance) =
+        unsafe { TokenAccount::check_disc(recipient)? };
+    if recipient_mint != mint_account.mint_index {
+        log::sol_log("royalty recipient mint mismatch");
+        return Err(NanoTokenError::IncorrectMint.into());
+    }
+
+    mint_account.royalty_bps = args.bps;
+    mint_account.royalty_recipient = args.recipient;
+
+    Ok(3)
+}
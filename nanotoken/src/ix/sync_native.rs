@@ -0,0 +1,71 @@
+use crate::solana_nostd_entrypoint::NoStdAccountInfo;
+use bytemuck::{Pod, Zeroable};
+use solana_program::{
+    log, program_error::ProgramError, rent::Rent, sysvar::Sysvar,
+};
+
+use crate::{consts::NATIVE_MINT_INDEX, error::NanoTokenError, TokenAccount};
+
+#[derive(PartialEq, Debug, Clone, Pod, Zeroable, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct SyncNativeArgs {
+    // Keeping this scaffolded just in case...
+}
+
+impl SyncNativeArgs {
+    pub fn from_data<'a>(
+        _data: &mut &'a [u8],
+    ) -> Result<&'a SyncNativeArgs, ProgramError> {
+        // Zero-sized, so there is nothing to consume
+        Ok(&SyncNativeArgs {})
+    }
+
+    pub const fn size() -> usize {
+        core::mem::size_of::<Self>()
+    }
+}
+
+/// Credits lamports sent directly to a native token account (e.g. via a
+/// system transfer) to its balance. Permissionless.
+pub fn sync_native(
+    accounts: &[NoStdAccountInfo],
+    _args: &SyncNativeArgs,
+) -> Result<usize, ProgramError> {
+    // Unpack accounts
+    //
+    // 1) token_account is checked by loader and mutated if the balance
+    //    changes, which is an implicit owner check. Must be a native account.
+    let [token_account, _rem @ ..] = accounts else {
+        log::sol_log("sync_native expecting [token_account, .. ]");
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    let rent_exempt_minimum =
+        Rent::get()?.minimum_balance(token_account.data_len());
+    // SAFETY: no one else has a view into token_account lamports
+    let lamports = unsafe { *token_account.unchecked_borrow_lamports() };
+
+    let mut token_account_data = token_account
+        .try_borrow_mut_data()
+        .ok_or(NanoTokenError::DuplicateAccount)?;
+    let token_account =
+        TokenAccount::checked_load_mut(&mut token_account_data)?;
+    if token_account.mint != NATIVE_MINT_INDEX {
+        log::sol_log("token_account is not native");
+        return Err(NanoTokenError::IncorrectMint.into());
+    }
+
+    // Lamports can only leave a native account through the balance, so the
+    // balance can never exceed what is held above rent exemption. Like
+    // spl-token, a sync that would lower the balance means the account is in
+    // a bad state (e.g. rent increased), so it fails instead.
+    let synced_balance = lamports.saturating_sub(rent_exempt_minimum);
+    if synced_balance < token_account.balance {
+        log::sol_log("native balance exceeds lamports above rent exemption");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    token_account.balance = synced_balance;
+
+    Ok(1)
+}
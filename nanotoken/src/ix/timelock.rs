@@ -0,0 +1,78 @@
+use crate::solana_nostd_entrypoint::NoStdAccountInfo;
+use bytemuck::{Pod, Zeroable};
+use solana_program::{log, program_error::ProgramError};
+
+use crate::{
+    error::NanoTokenError,
+    utils::{check_authority, split_at_unchecked},
+    TokenAccount,
+};
+
+#[derive(PartialEq, Debug, Clone, Copy, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct TimelockArgs {
+    /// Unix timestamp before which the account can't send or burn. Must not
+    /// be earlier than the account's current `unlock_at`.
+    pub unlock_at: i64,
+}
+
+impl TimelockArgs {
+    pub fn from_data<'a>(
+        data: &mut &'a [u8],
+    ) -> Result<&'a TimelockArgs, ProgramError> {
+        const IX_LEN: usize = core::mem::size_of::<TimelockArgs>();
+        if data.len() >= IX_LEN {
+            // SAFETY:
+            // We do the length check ourselves instead of via
+            // core::slice::split_at so we can return an error
+            // instead of panicking.
+            let (ix_data, rem) = unsafe { split_at_unchecked(data, IX_LEN) };
+            *data = rem;
+
+            // This is always aligned and all bit patterns are valid
+            Ok(unsafe { &*(ix_data.as_ptr() as *const TimelockArgs) })
+        } else {
+            Err(ProgramError::InvalidInstructionData)
+        }
+    }
+
+    pub const fn size() -> usize {
+        core::mem::size_of::<Self>()
+    }
+}
+
+/// Locks a token account's balance until `unlock_at`. A lock can only be
+/// extended, never shortened, so the owner can commit to holding.
+pub fn timelock(
+    accounts: &[NoStdAccountInfo],
+    args: &TimelockArgs,
+) -> Result<usize, ProgramError> {
+    // Unpack accounts
+    //
+    // 1) token_account is checked by loader and mutated, which is an implicit
+    //    owner check
+    // 2) owner must be token_account owner and must be signer
+    let [token_account, owner, _rem @ ..] = accounts else {
+        log::sol_log("timelock expecting [token_account, owner, .. ]");
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    let mut token_account_data = token_account
+        .try_borrow_mut_data()
+        .ok_or(NanoTokenError::DuplicateAccount)?;
+    let token_account =
+        TokenAccount::checked_load_mut(&mut token_account_data)?;
+
+    // Check that the owner is correct and signed this
+    check_authority(owner, &token_account.owner)?;
+
+    if args.unlock_at < token_account.unlock_at {
+        log::sol_log("timelock can only be extended");
+        return Err(NanoTokenError::TimelockNotExtended.into());
+    }
+    token_account.unlock_at = args.unlock_at;
+    token_account.state |= TokenAccount::TIMELOCKED;
+
+    Ok(2)
+}
@@ -1,10 +1,19 @@
 use crate::solana_nostd_entrypoint::NoStdAccountInfo;
 use bytemuck::{Pod, Zeroable};
-use solana_program::{log, program_error::ProgramError};
+use solana_program::{log, program_error::ProgramError, pubkey::Pubkey};
 
-use crate::{error::NanoTokenError, utils::split_at_unchecked, TokenAccount};
+use crate::{
+    consts::NATIVE_MINT_INDEX,
+    error::NanoTokenError,
+    utils::{
+        check_authority, check_memo_present, move_native_lamports,
+        paranoid_check_token_account_address, split_at_unchecked,
+    },
+    TokenAccount,
+};
 
 #[derive(PartialEq, Debug, Clone, Copy, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct TransferArgs {
     pub amount: u64,
@@ -45,9 +54,9 @@ pub fn transfer(
     //
     // 1) from needs an owner/disc check and an authority check
     // 2) to needs to have the same mint as from, and requires an owner/disc check
-    // 3) owner must be from authority and must be signer
-    let [from, to, owner, _rem @ ..] = accounts else {
-        log::sol_log("transfer expecting [from, to, owner, .. ]");
+    // 3) authority must be from owner or from delegate and must be signer
+    let [from, to, authority, _rem @ ..] = accounts else {
+        log::sol_log("transfer expecting [from, to, authority, .. ]");
         return Err(ProgramError::NotEnoughAccountKeys);
     };
 
@@ -61,46 +70,107 @@ pub fn transfer(
         return Ok(3);
     }
 
-    // Check that owner signed this
-    if !owner.is_signer() {
-        log::sol_log("from account owner must sign to transfer");
-        return Err(ProgramError::MissingRequiredSignature);
+    // Load from_account
+    let from_account = unsafe { TokenAccount::check_disc_raw(from)? };
+    let to_account = unsafe { TokenAccount::check_disc_raw(to)? };
+
+    // With paranoid, check both accounts are at their (owner, mint) pda
+    unsafe {
+        paranoid_check_token_account_address(from, &*from_account)?;
+        paranoid_check_token_account_address(to, &*to_account)?;
     }
 
-    // Load from_account
-    let (from_owner, from_mint, from_balance) =
-        unsafe { TokenAccount::check_disc(from)? };
-    let (_to_owner, to_mint, to_balance) =
-        unsafe { TokenAccount::check_disc(to)? };
+    // Check neither account is frozen
+    unsafe {
+        (*from_account).check_not_frozen()?;
+        (*to_account).check_not_frozen()?;
+        (*from_account).check_not_timelocked()?;
+    }
+
+    // Check mint is not paused. The mint may be passed anywhere after this
+    // op's accounts.
+    unsafe { (*from_account).check_not_paused(accounts)? };
+
+    // Check both owners against the mint list. The list may be passed
+    // anywhere after this op's accounts.
+    unsafe { (*from_account).check_list(&(*to_account).owner, accounts)? };
+
+    // Check memo. The instructions sysvar may be passed anywhere after this
+    // op's accounts.
+    if unsafe { (*to_account).requires_memo() } {
+        check_memo_present(accounts)?;
+    }
 
     // Check from_account balance
-    if unsafe { *from_balance } < args.amount {
+    if unsafe { (*from_account).balance } < args.amount {
         log::sol_log("insufficient balance");
         return Err(NanoTokenError::InsufficientTokenBalance.into());
     }
 
-    // Check that the owner is correct
-    if solana_program::program_memory::sol_memcmp(
-        from_owner.as_ref(),
-        owner.key().as_ref(),
-        32,
-    ) != 0
-    {
-        log::sol_log("incorrect from_account owner");
-        return Err(ProgramError::IllegalOwner);
+    // Check that the owner or delegate is correct and signed this. The
+    // delegate spends from its allowance, and is revoked once it is spent.
+    unsafe {
+        if solana_program::program_memory::sol_memcmp(
+            authority.key().as_ref(),
+            (*from_account).owner.as_ref(),
+            32,
+        ) == 0
+        {
+            if !authority.is_signer() {
+                log::sol_log("authority must sign");
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+        } else {
+            check_authority(authority, &(*from_account).delegate)?;
+            if (*from_account).delegated_amount < args.amount {
+                log::sol_log("insufficient delegated amount");
+                return Err(NanoTokenError::InsufficientDelegatedAmount.into());
+            }
+            (*from_account).delegated_amount -= args.amount;
+
+            // Auto-revoke once the allowance is used up
+            if (*from_account).delegated_amount == 0 {
+                (*from_account).delegate = Pubkey::default();
+            }
+        }
     }
 
     // Check that the mints match
-    if from_mint != to_mint {
+    if unsafe { (*from_account).mint != (*to_account).mint } {
         log::sol_log("from/to mint mismatch");
         return Err(NanoTokenError::IncorrectMint.into());
     }
 
     // Transfer
     unsafe {
-        *from_balance -= args.amount;
-        *to_balance += args.amount;
+        // Royalty comes out of the amount. The mint and royalty recipient may
+        // be passed anywhere after this op's accounts.
+        let royalty =
+            TokenAccount::pay_royalty(from_account, args.amount, accounts)?;
+        TokenAccount::move_balance(
+            from_account,
+            to_account,
+            args.amount - royalty,
+        )?;
+
+        // Native balances carry their lamports with them
+        if (*from_account).mint == NATIVE_MINT_INDEX {
+            move_native_lamports(from, to, args.amount);
+        }
     }
 
+    // Emit event. The noop program may be passed anywhere after this op's
+    // accounts.
+    #[cfg(feature = "noop-events")]
+    crate::events::emit(
+        accounts,
+        crate::events::EventTag::Transfer,
+        &crate::events::TransferEvent {
+            from: *from.key(),
+            to: *to.key(),
+            amount: args.amount,
+        },
+    )?;
+
     Ok(3)
 }
@@ -0,0 +1,48 @@
+use crate::solana_nostd_entrypoint::NoStdAccountInfo;
+use bytemuck::{Pod, Zeroable};
+use solana_program::{log, program_error::ProgramError};
+
+use crate::TokenAccount;
+
+use super::{transfer, TransferArgs};
+
+#[derive(PartialEq, Debug, Clone, Pod, Zeroable, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct TransferAllArgs {
+    // Keeping this scaffolded just in case...
+}
+
+impl TransferAllArgs {
+    pub fn from_data<'a>(
+        _data: &mut &'a [u8],
+    ) -> Result<&'a TransferAllArgs, ProgramError> {
+        // Zero-sized, so there is nothing to consume
+        Ok(&TransferAllArgs {})
+    }
+
+    pub const fn size() -> usize {
+        core::mem::size_of::<Self>()
+    }
+}
+
+/// Transfers the entire balance of `from`, read at execution time. Takes the
+/// same accounts as transfer, and all of its checks apply.
+pub fn transfer_all(
+    accounts: &[NoStdAccountInfo],
+    _args: &TransferAllArgs,
+) -> Result<usize, ProgramError> {
+    let [from, _rem @ ..] = accounts else {
+        log::sol_log("transfer_all expecting [from, to, authority, .. ]");
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // Read balance. The disc is checked again by transfer.
+    // SAFETY: no one else has a view into from data, and the pointer is read
+    // before transfer takes its own view
+    let (_from_owner, _from_mint, from_balance) =
+        unsafe { TokenAccount::check_disc(from)? };
+    let amount = unsafe { *from_balance };
+
+    transfer(accounts, &TransferArgs { amount })
+}
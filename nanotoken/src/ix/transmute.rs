@@ -2,6 +2,7 @@ use crate::solana_nostd_entrypoint::{InstructionC, NoStdAccountInfo};
 
 use bytemuck::{Pod, Zeroable};
 use solana_program::{
+    entrypoint::ProgramResult,
     log::{self},
     program_error::ProgramError,
     pubkey::Pubkey,
@@ -9,17 +10,29 @@ use solana_program::{
 
 use crate::{
     error::NanoTokenError,
+    math,
     utils::{
-        spl_token_utils::{token::TokenAccountInfo, SPL_TOKEN_PROGRAM},
+        create_pda_funded_by_payer,
+        spl_token_utils::{
+            is_token_program,
+            token::{self, TokenAccountInfo, TokenAccountZC},
+            token_account_len, ASSOCIATED_TOKEN_PROGRAM,
+        },
         split_at_unchecked,
     },
     Mint, TokenAccount, VaultInfo,
 };
 
 #[derive(PartialEq, Debug, Clone, Copy, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct TransmuteArgs {
     pub amount: u64,
+    /// Bump of the (owner, mint) pda, used only when tokenkeg -> nanotoken
+    /// has to create the destination. Zero derives it on-chain, which costs
+    /// far more compute. (The canonical bump is zero with negligible
+    /// probability.)
+    pub bump: u64,
 }
 
 impl TransmuteArgs {
@@ -53,6 +66,7 @@ pub fn transmute(
 ) -> Result<usize, ProgramError> {
     // 1) from can be either a tokenkeg or nanotoken account.
     // 2) to must be whatever kind of account form is not. both require account-specific checks
+    //    A tokenkeg to that does not exist yet is created (keypair signer or ATA)
     // 3) owner must be from authority and must be signer (checked during spl transfer, we need to check if from is nanotoken)
     // 4) tokenkeg_mint is validated by tokenkeg program when creating token account
     // 5) nanotoken_mint is checked by loader, and is used to validate from/to account.
@@ -91,7 +105,7 @@ pub fn transmute(
     ) != 0
     {
         log::sol_log("nanotoken mint mismatch");
-        return Err(ProgramError::InvalidArgument);
+        return Err(NanoTokenError::TransmuteMintMismatch.into());
     }
 
     // Check tokenkeg mint
@@ -104,7 +118,7 @@ pub fn transmute(
     ) != 0
     {
         log::sol_log("tokenkeg mint mismatch");
-        return Err(ProgramError::InvalidArgument);
+        return Err(NanoTokenError::TransmuteMintMismatch.into());
     }
 
     // Check tokenkeg vault
@@ -117,18 +131,47 @@ pub fn transmute(
     ) != 0
     {
         log::sol_log("tokenkeg vault mismatch");
-        return Err(ProgramError::InvalidArgument);
+        return Err(NanoTokenError::TransmuteVaultMismatch.into());
     }
 
-    // Check tokenkeg program
+    // Check tokenkeg program. This is Tokenkeg or Token-2022, whichever owns
+    // the (vault-validated) tokenkeg mint.
+    if !is_token_program(tokenkeg_program.key())
+        || solana_program::program_memory::sol_memcmp(
+            tokenkeg_program.key().as_ref(),
+            tokenkeg_mint.owner().as_ref(),
+            32,
+        ) != 0
+    {
+        log::sol_log("tokenkeg program mismatch");
+        return Err(NanoTokenError::InvalidTokenProgram.into());
+    }
+
+    // Reject degenerate account configurations. Neither user account may
+    // alias the other or the vault, since the vault would then be paying
+    // itself (or the user) while supply changes.
     if solana_program::program_memory::sol_memcmp(
-        tokenkeg_program.key().as_ref(),
-        SPL_TOKEN_PROGRAM.as_ref(),
+        from.key().as_ref(),
+        to.key().as_ref(),
         32,
-    ) != 0
+    ) == 0
     {
-        log::sol_log("tokenkeg program mismatch");
-        return Err(ProgramError::InvalidArgument);
+        log::sol_log("transmute from and to must differ");
+        return Err(NanoTokenError::TransmuteSameAccount.into());
+    }
+    if solana_program::program_memory::sol_memcmp(
+        tokenkeg_vault.key().as_ref(),
+        from.key().as_ref(),
+        32,
+    ) == 0
+        || solana_program::program_memory::sol_memcmp(
+            tokenkeg_vault.key().as_ref(),
+            to.key().as_ref(),
+            32,
+        ) == 0
+    {
+        log::sol_log("tokenkeg vault cannot be transmute from or to");
+        return Err(NanoTokenError::TransmuteVaultAsUserAccount.into());
     }
 
     // We will need nanotoken mint to increase or decrease supply
@@ -137,6 +180,7 @@ pub fn transmute(
         .ok_or(NanoTokenError::DuplicateAccount)?;
     let nanotoken_mint_account =
         Mint::checked_load_mut(&mut nanotoken_mint_data)?;
+    nanotoken_mint_account.check_not_paused()?;
 
     // Try to go tokenkeg -> nanotoken.
     // Tokenkeg will do authority check and balance check
@@ -146,45 +190,104 @@ pub fn transmute(
         owner.key(),
         false,
     ) {
+        check_tokenkeg_user_account(tokenkeg_from.info)?;
+
+        // 1) Transfer from tokenkeg to vault
+        // 2) Credit what the vault actually received. Token-2022 transfer
+        //    fees are withheld in the vault account, so this can be less than
+        //    amount. Crediting more would under-collateralize the vault.
+        let vault_balance_before = token::amount(tokenkeg_vault)?;
+
+        // 1) Transfer from tokenkeg to vault
+        // transfer_checked has tag = 12, args = amount, decimals
+        let tokenkeg_transfer_data =
+            transfer_checked_data(args.amount, nanotoken_mint_account.decimals);
+
+        let infos = [
+            tokenkeg_from.info.to_info_c(),
+            tokenkeg_mint.to_info_c(),
+            tokenkeg_vault.to_info_c(),
+            owner.to_info_c(),
+        ];
+
+        let tokenkeg_transfer_metas = [
+            infos[0].to_meta_c(),
+            tokenkeg_mint.to_meta_c(),
+            tokenkeg_vault.to_meta_c(),
+            owner.to_meta_c(),
+        ];
+
+        let transfer_ix = InstructionC {
+            program_id: tokenkeg_program.key(),
+            accounts: tokenkeg_transfer_metas.as_ptr(),
+            accounts_len: 4,
+            data: tokenkeg_transfer_data.as_ptr(),
+            data_len: 10,
+        };
+
+        let cpi_seeds: &[&[&[u8]]] = &[];
+        #[cfg(target_os = "solana")]
+        unsafe {
+            solana_program::syscalls::sol_invoke_signed_c(
+                &transfer_ix as *const InstructionC as *const u8,
+                infos.as_ptr() as *const u8,
+                4,
+                cpi_seeds.as_ptr() as *const u8,
+                0,
+            );
+        }
+        #[cfg(not(target_os = "solana"))]
+        core::hint::black_box((&transfer_ix, &infos, cpi_seeds));
+
+        // 2) Credit what the vault received
+        let received =
+            token::amount(tokenkeg_vault)?.saturating_sub(vault_balance_before);
+
+        // Account owner check will be done implicitly by runtime
+        let mut nanotoken_to_data = to
+            .try_borrow_mut_data()
+            .ok_or(NanoTokenError::DuplicateAccount)?;
+        if let Ok(nanotoken_account) =
+            TokenAccount::checked_load_mut(&mut nanotoken_to_data)
         {
-            // Account owner check will be done implicitly by runtime
-            let mut nanotoken_to_data = to
-                .try_borrow_mut_data()
-                .ok_or(NanoTokenError::DuplicateAccount)?;
-            if let Ok(nanotoken_account) =
-                TokenAccount::checked_load_mut(&mut nanotoken_to_data)
-            {
-                // Account is already initialized. Check nanotoken mint
-                // 0) Check nanotoken account mint
-                // 1) Increment nanotoken balance
-                // 2) Increment nanotoken mint supply
-                // 3) Transfer from tokenkeg to vault (later)
-
-                // 0) Check nanotoken account mint
-                assert!(
-                    nanotoken_account.mint == nanotoken_mint_account.mint_index
+            // Account is already initialized. Check nanotoken mint
+            // 0) Check nanotoken account mint
+            // 1) Increment nanotoken balance
+            // 2) Increment nanotoken mint supply
+
+            // 0) Check nanotoken account mint
+            if nanotoken_account.mint != nanotoken_mint_account.mint_index {
+                log::sol_log("transmute destination mint mismatch");
+                return Err(
+                    NanoTokenError::TransmuteDestinationMintMismatch.into()
                 );
+            }
+            nanotoken_account.check_not_frozen()?;
 
-                // 1) Increment nanotoken balance
-                nanotoken_account.balance += args.amount;
+            // 1) Increment nanotoken balance
+            nanotoken_account.credit(received)?;
+            nanotoken_account.inherit_mint_config(nanotoken_mint_account);
 
-                // 2) Increment nanotoken mint supply
-                nanotoken_mint_account.supply += args.amount;
+            // 2) Increment nanotoken mint supply
+            nanotoken_mint_account.supply =
+                math::add_supply(nanotoken_mint_account.supply, received)?;
+        } else {
+            // Account is not initialized
+            // 1) initialize nanotoken account
+            // 2) update nanotoken balance from 0 to received
+            // 3) Increment nanotoken mint supply
+
+            // 1) initialize nanotoken account
+            // need to drop RefMut
+            drop(nanotoken_to_data);
+
+            // A wrong bump fails account creation since the seeds won't sign
+            // for `to`. Otherwise derive it on-chain, which is alloc-free for
+            // target_os = "solana" at least.
+            let account_bump = if args.bump != 0 {
+                args.bump as u8
             } else {
-                // Account is not initialized
-                // 1) initialize nanotoken account
-                // 2) update nanotoken balance from 0 to amount
-                // 3) Increment nanotoken mint supply
-                // 4) Transfer from tokenkeg to vault (later)
-
-                // 1) initialize nanotoken account
-                // need to drop RefMut
-                drop(nanotoken_to_data);
-
-                // TODO: I am sad that we are calculating this bump on-chain
-                //
-                // The target_os = "solana" impl is alloc-free at least
-                let account_bump = Pubkey::find_program_address(
+                Pubkey::find_program_address(
                     &[
                         owner.key().as_ref(),
                         nanotoken_mint_account
@@ -194,87 +297,48 @@ pub fn transmute(
                     ],
                     &crate::ID,
                 )
-                .1;
-
-                log::sol_log("transmute: initializing nanotoken account");
-                super::initialize_account::checked_initialize_account(
-                    payer,
-                    config,
-                    to,
-                    system_program,
-                    owner.key(),
-                    nanotoken_mint_account.mint_index,
-                    account_bump,
-                )?;
-
-                // 2) update nanotoken balance from 0 to amount
-                unsafe {
-                    core::ptr::copy_nonoverlapping(
-                        &args.amount as *const u64 as *const u8,
-                        to.unchecked_borrow_mut_data()
-                            .as_mut_ptr()
-                            .add(48),
-                        8,
-                    );
-                }
-
-                // 3) Increment nanotoken mint supply
-                nanotoken_mint_account.supply += args.amount;
-            }
-
-            // 4) Transfer from tokenkeg to vault
-            // transfer has tag = 3, args = amount
-            let mut tokenkeg_transfer_data = [3, 0, 0, 0, 0, 0, 0, 0, 0];
-            unsafe {
-                core::ptr::copy_nonoverlapping(
-                    &args.amount as *const u64 as *const u8,
-                    tokenkeg_transfer_data
-                        .as_mut_ptr()
-                        .add(1),
-                    8,
-                );
-            }
-
-            let infos = [
-                tokenkeg_from.info.to_info_c(),
-                tokenkeg_vault.to_info_c(),
-                owner.to_info_c(),
-            ];
-
-            let tokenkeg_transfer_metas = [
-                infos[0].to_meta_c(),
-                tokenkeg_vault.to_meta_c(),
-                owner.to_meta_c(),
-            ];
-
-            let transfer_ix = InstructionC {
-                program_id: &SPL_TOKEN_PROGRAM,
-                accounts: tokenkeg_transfer_metas.as_ptr(),
-                accounts_len: 3,
-                data: tokenkeg_transfer_data.as_ptr(),
-                data_len: 9,
+                .1
             };
 
-            let cpi_seeds: &[&[&[u8]]] = &[];
-            #[cfg(target_os = "solana")]
-            unsafe {
-                solana_program::syscalls::sol_invoke_signed_c(
-                    &transfer_ix as *const InstructionC as *const u8,
-                    infos.as_ptr() as *const u8,
-                    3,
-                    cpi_seeds.as_ptr() as *const u8,
-                    0,
-                );
-            }
-            #[cfg(not(target_os = "solana"))]
-            core::hint::black_box((&transfer_ix, &infos, cpi_seeds));
+            log::sol_log("transmute: initializing nanotoken account");
+            super::initialize_account::checked_initialize_account(
+                payer,
+                config,
+                to,
+                system_program,
+                owner.key(),
+                nanotoken_mint_account.mint_index,
+                account_bump,
+                0,
+            )?;
+
+            // 2) update nanotoken balance from 0 to received
+            let mut nanotoken_to_data = to
+                .try_borrow_mut_data()
+                .ok_or(NanoTokenError::DuplicateAccount)?;
+            let nanotoken_account =
+                TokenAccount::checked_load_mut(&mut nanotoken_to_data)?;
+            nanotoken_account.credit(received)?;
+            nanotoken_account.inherit_mint_config(nanotoken_mint_account);
+
+            // 3) Increment nanotoken mint supply
+            nanotoken_mint_account.supply =
+                math::add_supply(nanotoken_mint_account.supply, received)?;
         }
     } else {
         // Check to see if we can do nanotoken -> tokenkeg
         let mut from_data = from
             .try_borrow_mut_data()
-            .expect("first borrow won't fail");
+            .ok_or(NanoTokenError::DuplicateAccount)?;
         let nanotoken_from = TokenAccount::checked_load_mut(&mut from_data)?;
+        nanotoken_from.check_not_frozen()?;
+        nanotoken_from.check_not_timelocked()?;
+
+        // Check nanotoken account mint
+        if nanotoken_from.mint != nanotoken_mint_account.mint_index {
+            log::sol_log("invalid mint");
+            return Err(NanoTokenError::IncorrectMint.into());
+        }
 
         // Check for authority as signer
         if !owner.is_signer() {
@@ -288,7 +352,7 @@ pub fn transmute(
         ) != 0
         {
             log::sol_log("incorrect from account owner");
-            return Err(ProgramError::InvalidArgument);
+            return Err(NanoTokenError::OwnerMismatch.into());
         }
 
         // Check nanotoken balance
@@ -298,8 +362,24 @@ pub fn transmute(
         }
 
         // Reduce nanotoken balance, supply
-        nanotoken_from.balance -= args.amount;
-        nanotoken_mint_account.supply -= args.amount;
+        nanotoken_from.balance =
+            math::sub_balance(nanotoken_from.balance, args.amount)?;
+        nanotoken_mint_account.supply =
+            math::sub_supply(nanotoken_mint_account.supply, args.amount)?;
+
+        // Create and initialize destination if it doesn't exist yet, so users
+        // can always exit back to tokenkeg
+        if to.data_len() == 0 {
+            create_tokenkeg_destination(
+                accounts,
+                to,
+                owner,
+                tokenkeg_mint,
+                tokenkeg_program,
+                system_program,
+                payer,
+            )?;
+        }
 
         // Transfer from vault to tokenkeg account
         let ta_exists =
@@ -308,97 +388,48 @@ pub fn transmute(
             log::sol_log(
                 "invalid tokenkeg/nanotoken account configuration for transmute",
             );
-            return Err(ProgramError::InvalidArgument);
-        }
-        // Init if needed TODO
-        //     // To initialize token account, we need to create account and then initialize it
-
-        //     // CreateAccount {
-        //     //   /// Number of lamports to transfer to the new account
-        //     //   lamports: u64,
-
-        //     //   /// Number of bytes of memory to allocate
-        //     //   space: u64,
-
-        //     //   /// Address of program that will own the new account
-        //     //   owner: Pubkey,
-        //     // },
-
-        //     // discriminant is zero so we just need to write lamports, space, address
-        //     let mut create_account_data = [0; 4 + 8 + 8 + 32];
-        //     unsafe {
-        //         // lamports
-        //         let rent = Rent::get()?;
-        //         core::ptr::copy_nonoverlapping(
-        //             rent.minimum_balance(165)
-        //                 .to_le_bytes()
-        //                 .as_ptr(),
-        //             create_account_data.as_mut_ptr().add(4),
-        //             8,
-        //         );
-
-        //         // space
-        //         core::ptr::copy_nonoverlapping(
-        //             165_u64.to_le_bytes().as_ptr(),
-        //             create_account_data.as_mut_ptr().add(12),
-        //             8,
-        //         );
-
-        //         // owner
-        //         core::ptr::copy_nonoverlapping(
-        //             owner.key().as_ref().as_ptr(),
-        //             create_account_data.as_mut_ptr().add(20),
-        //             32,
-        //         );
-        //     }
-
-        //     let create_account_accounts =
-        // }
-
-        // 4) Transfer from tokenkeg to vault
-        // transfer has tag = 3, args = amount
-        let mut tokenkeg_transfer_data = [3, 0, 0, 0, 0, 0, 0, 0, 0];
-        unsafe {
-            core::ptr::copy_nonoverlapping(
-                &args.amount as *const u64 as *const u8,
-                tokenkeg_transfer_data
-                    .as_mut_ptr()
-                    .add(1),
-                8,
-            );
+            return Err(NanoTokenError::InvalidTransmuteAccounts.into());
         }
+        check_tokenkeg_user_account(to)?;
+
+        // 4) Transfer from vault to tokenkeg
+        // transfer_checked has tag = 12, args = amount, decimals
+        let tokenkeg_transfer_data =
+            transfer_checked_data(args.amount, nanotoken_mint_account.decimals);
 
         let infos = [
             tokenkeg_vault.to_info_c(),
+            tokenkeg_mint.to_info_c(),
             to.to_info_c(),
             vault_info.to_info_c(),
         ];
 
         let tokenkeg_transfer_metas = [
             infos[0].to_meta_c(),
+            tokenkeg_mint.to_meta_c(),
             to.to_meta_c(),
             vault_info.to_meta_c_signer(),
         ];
 
         let transfer_ix = InstructionC {
-            program_id: &SPL_TOKEN_PROGRAM,
+            program_id: tokenkeg_program.key(),
             accounts: tokenkeg_transfer_metas.as_ptr(),
-            accounts_len: 3,
+            accounts_len: 4,
             data: tokenkeg_transfer_data.as_ptr(),
-            data_len: 9,
+            data_len: 10,
         };
 
         let cpi_seeds: &[&[&[u8]]] = &[&[
             b"info",
             tokenkeg_mint.key().as_ref(),
-            &[vault_info_account.info_bump],
+            &[vault_info_account.info_bump()],
         ]];
         #[cfg(target_os = "solana")]
         unsafe {
             solana_program::syscalls::sol_invoke_signed_c(
                 &transfer_ix as *const InstructionC as *const u8,
                 infos.as_ptr() as *const u8,
-                3,
+                4,
                 cpi_seeds.as_ptr() as *const u8,
                 1,
             );
@@ -409,3 +440,160 @@ pub fn transmute(
 
     Ok(8)
 }
+
+/// Checks the user's tokenkeg account is neither frozen nor native before we
+/// CPI. The token program would reject a frozen account anyway, but this fails
+/// with a clear error. Native accounts should use wrap/unwrap instead.
+#[inline(always)]
+fn check_tokenkeg_user_account(info: &NoStdAccountInfo) -> ProgramResult {
+    let data = info
+        .try_borrow_data()
+        .ok_or(NanoTokenError::DuplicateAccount)?;
+    let Some(account) = TokenAccountZC::from_slice(&data) else {
+        log::sol_log("tokenkeg account is not initialized");
+        return Err(ProgramError::UninitializedAccount);
+    };
+    if account.is_frozen() {
+        log::sol_log("tokenkeg account is frozen");
+        return Err(NanoTokenError::AccountFrozen.into());
+    }
+    if account.is_native() {
+        log::sol_log("tokenkeg account is native");
+        return Err(NanoTokenError::TransmuteNativeAccount.into());
+    }
+    Ok(())
+}
+
+/// Works for both Tokenkeg and Token-2022. transfer_checked is used so the
+/// token program verifies decimals against the mint.
+#[inline(always)]
+fn transfer_checked_data(amount: u64, decimals: u8) -> [u8; 10] {
+    let mut data = [0; 10];
+    data[0] = 12;
+    data[1..9].copy_from_slice(&amount.to_le_bytes());
+    data[9] = decimals;
+    data
+}
+
+/// Creates the tokenkeg destination for a nanotoken -> tokenkeg transmute,
+/// owned by the nanotoken owner and funded by payer.
+///
+/// If `to` signed, it is a keypair account which is created and initialized
+/// directly. Otherwise it must be the owner's associated token account, and
+/// the associated token program must be passed somewhere in the remaining
+/// accounts.
+#[inline(never)]
+fn create_tokenkeg_destination(
+    accounts: &[NoStdAccountInfo],
+    to: &NoStdAccountInfo,
+    owner: &NoStdAccountInfo,
+    tokenkeg_mint: &NoStdAccountInfo,
+    tokenkeg_program: &NoStdAccountInfo,
+    system_program: &NoStdAccountInfo,
+    payer: &NoStdAccountInfo,
+) -> ProgramResult {
+    if to.is_signer() {
+        // Create keypair account, with room for any account extensions the
+        // (vault-validated) mint requires
+        let len = token_account_len(
+            &tokenkeg_mint
+                .try_borrow_data()
+                .ok_or(NanoTokenError::DuplicateAccount)?,
+        );
+        // SAFETY: no one holds a view into to or payer lamports.
+        unsafe {
+            create_pda_funded_by_payer(
+                to.to_info_c(),
+                tokenkeg_program.key(),
+                len as u64,
+                &[],
+                system_program.to_info_c(),
+                payer.to_info_c(),
+            )?
+        };
+
+        // Initialize account
+        // initialize_account3 has tag = 18, args = owner
+        let mut data = [0; 33];
+        data[0] = 18;
+        data[1..].copy_from_slice(owner.key().as_ref());
+        let metas = [to.to_meta_c(), tokenkeg_mint.to_meta_c()];
+        let init_account_ix = InstructionC {
+            program_id: tokenkeg_program.key(),
+            accounts: metas.as_ptr(),
+            accounts_len: 2,
+            data: data.as_ptr(),
+            data_len: 33,
+        };
+        let infos = [to.to_info_c(), tokenkeg_mint.to_info_c()];
+        let cpi_seeds: &[&[&[u8]]] = &[];
+        #[cfg(target_os = "solana")]
+        unsafe {
+            solana_program::syscalls::sol_invoke_signed_c(
+                &init_account_ix as *const InstructionC as *const u8,
+                infos.as_ptr() as *const u8,
+                2,
+                cpi_seeds.as_ptr() as *const u8,
+                0,
+            );
+        }
+        #[cfg(not(target_os = "solana"))]
+        core::hint::black_box((&init_account_ix, &infos, cpi_seeds));
+    } else {
+        let Some(ata_program) = accounts.iter().find(|a| {
+            solana_program::program_memory::sol_memcmp(
+                a.key().as_ref(),
+                ASSOCIATED_TOKEN_PROGRAM.as_ref(),
+                32,
+            ) == 0
+        }) else {
+            log::sol_log(
+                "destination does not exist and associated token program was not passed",
+            );
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        // create_idempotent has tag = 1, no args. The associated token
+        // program checks the address of to.
+        let data = [1];
+        let infos = [
+            payer.to_info_c(),
+            to.to_info_c(),
+            owner.to_info_c(),
+            tokenkeg_mint.to_info_c(),
+            system_program.to_info_c(),
+            tokenkeg_program.to_info_c(),
+            ata_program.to_info_c(),
+        ];
+        let metas = [
+            infos[0].to_meta_c(),
+            to.to_meta_c(),
+            infos[2].to_meta_c(),
+            tokenkeg_mint.to_meta_c(),
+            system_program.to_meta_c(),
+            tokenkeg_program.to_meta_c(),
+        ];
+        let create_ata_ix = InstructionC {
+            program_id: &ASSOCIATED_TOKEN_PROGRAM,
+            accounts: metas.as_ptr(),
+            accounts_len: 6,
+            data: data.as_ptr(),
+            data_len: 1,
+        };
+        let cpi_seeds: &[&[&[u8]]] = &[];
+        #[cfg(target_os = "solana")]
+        unsafe {
+            solana_program::syscalls::sol_invoke_signed_c(
+                &create_ata_ix as *const InstructionC as *const u8,
+                infos.as_ptr() as *const u8,
+                7,
+                cpi_seeds.as_ptr() as *const u8,
+                0,
+            );
+        }
+        #[cfg(not(target_os = "solana"))]
+        core::hint::black_box((&create_ata_ix, &infos, cpi_seeds));
+    }
+
+    Ok(())
+}
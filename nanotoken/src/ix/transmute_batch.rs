@@ -0,0 +1,92 @@
+use crate::solana_nostd_entrypoint::NoStdAccountInfo;
+use bytemuck::{Pod, Zeroable};
+use solana_program::{log, program_error::ProgramError};
+
+use crate::utils::split_at_unchecked;
+
+use super::{transmute, TransmuteArgs};
+
+/// Followed by `num_entries` 16-byte [`TransmuteArgs`] records (amount, bump),
+/// one per transmute entry. Each entry takes the same 8 accounts as transmute:
+/// [from, to, owner, tokenkeg_mint, nanotoken_mint, vault_info,
+/// tokenkeg_vault, tokenkeg_program]
+#[derive(PartialEq, Debug, Clone, Copy, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct TransmuteBatchArgs {
+    pub num_entries: u64,
+}
+
+impl TransmuteBatchArgs {
+    pub fn from_data<'a>(
+        data: &mut &'a [u8],
+    ) -> Result<(&'a TransmuteBatchArgs, &'a [TransmuteArgs]), ProgramError>
+    {
+        const IX_LEN: usize = core::mem::size_of::<TransmuteBatchArgs>();
+        if data.len() >= IX_LEN {
+            // SAFETY:
+            // We do the length check ourselves instead of via
+            // core::slice::split_at so we can return an error
+            // instead of panicking.
+            let (ix_data, rem) = unsafe { split_at_unchecked(data, IX_LEN) };
+
+            // This is always aligned and all bit patterns are valid
+            let args =
+                unsafe { &*(ix_data.as_ptr() as *const TransmuteBatchArgs) };
+
+            // Entries
+            let entries_len = (args.num_entries as usize)
+                .checked_mul(TransmuteArgs::size())
+                .ok_or(ProgramError::InvalidInstructionData)?;
+            if rem.len() < entries_len {
+                return Err(ProgramError::InvalidInstructionData);
+            }
+            // SAFETY: length was checked above
+            let (entries, rem) =
+                unsafe { split_at_unchecked(rem, entries_len) };
+            *data = rem;
+
+            // This is always aligned and all bit patterns are valid
+            let entries = unsafe {
+                core::slice::from_raw_parts(
+                    entries.as_ptr() as *const TransmuteArgs,
+                    args.num_entries as usize,
+                )
+            };
+
+            Ok((args, entries))
+        } else {
+            Err(ProgramError::InvalidInstructionData)
+        }
+    }
+
+    pub const fn size() -> usize {
+        core::mem::size_of::<Self>()
+    }
+}
+
+/// Runs one transmute per entry, in order, over consecutive groups of
+/// transmute accounts. Entries may use different vaults and go in either
+/// direction. The trailing config, system program, and payer are shared.
+pub fn transmute_batch(
+    accounts: &[NoStdAccountInfo],
+    _args: &TransmuteBatchArgs,
+    entries: &[TransmuteArgs],
+) -> Result<usize, ProgramError> {
+    // Unpack accounts
+    //
+    // Each entry's accounts are checked by transmute. Every entry sees its own
+    // accounts followed by everything after them, so the trailing config,
+    // system program, and payer (validated once by the dispatcher) are the
+    // same for all entries.
+    let mut consumed = 0;
+    for entry in entries {
+        let Some(entry_accounts) = accounts.get(consumed..) else {
+            log::sol_log("transmute_batch missing entry accounts");
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        consumed += transmute(entry_accounts, entry)?;
+    }
+
+    Ok(consumed)
+}
@@ -0,0 +1,52 @@
+use crate::solana_nostd_entrypoint::NoStdAccountInfo;
+use bytemuck::{Pod, Zeroable};
+use solana_program::{log, program_error::ProgramError};
+
+use crate::{error::NanoTokenError, utils::check_authority, Mint};
+
+#[derive(PartialEq, Debug, Clone, Pod, Zeroable, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct UnpauseArgs {
+    // Keeping this scaffolded just in case...
+}
+
+impl UnpauseArgs {
+    pub fn from_data<'a>(
+        _data: &mut &'a [u8],
+    ) -> Result<&'a UnpauseArgs, ProgramError> {
+        // Zero-sized, so there is nothing to consume
+        Ok(&UnpauseArgs {})
+    }
+
+    pub const fn size() -> usize {
+        core::mem::size_of::<Self>()
+    }
+}
+
+pub fn unpause(
+    accounts: &[NoStdAccountInfo],
+    _args: &UnpauseArgs,
+) -> Result<usize, ProgramError> {
+    // Unpack accounts
+    //
+    // 1) mint is checked by loader and mutated, which is an implicit owner
+    //    check
+    // 2) pause_authority must be the mint pause authority and must be signer
+    let [mint, pause_authority, _rem @ ..] = accounts else {
+        log::sol_log("unpause expecting [mint, pause_authority, .. ]");
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    let mut mint_data = mint
+        .try_borrow_mut_data()
+        .ok_or(NanoTokenError::DuplicateAccount)?;
+    let mint_account = Mint::checked_load_mut(&mut mint_data)?;
+
+    // Check that the pause authority is correct and signed this
+    check_authority(pause_authority, &mint_account.pause_authority)?;
+
+    mint_account.paused = 0;
+
+    Ok(2)
+}
@@ -0,0 +1,102 @@
+use crate::solana_nostd_entrypoint::NoStdAccountInfo;
+use bytemuck::{Pod, Zeroable};
+use solana_program::{log, program_error::ProgramError};
+
+use crate::{
+    consts::NATIVE_MINT_INDEX,
+    error::NanoTokenError,
+    math,
+    utils::{check_authority, split_at_unchecked},
+    TokenAccount,
+};
+
+#[derive(PartialEq, Debug, Clone, Copy, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct UnwrapSolArgs {
+    /// In lamports
+    pub amount: u64,
+}
+
+impl UnwrapSolArgs {
+    pub fn from_data<'a>(
+        data: &mut &'a [u8],
+    ) -> Result<&'a UnwrapSolArgs, ProgramError> {
+        const IX_LEN: usize = core::mem::size_of::<UnwrapSolArgs>();
+        if data.len() >= IX_LEN {
+            // SAFETY:
+            // We do the length check ourselves instead of via
+            // core::slice::split_at so we can return an error
+            // instead of panicking.
+            let (ix_data, rem) = unsafe { split_at_unchecked(data, IX_LEN) };
+            *data = rem;
+
+            // This is always aligned and all bit patterns are valid
+            Ok(unsafe { &*(ix_data.as_ptr() as *const UnwrapSolArgs) })
+        } else {
+            Err(ProgramError::InvalidInstructionData)
+        }
+    }
+
+    pub const fn size() -> usize {
+        core::mem::size_of::<Self>()
+    }
+}
+
+/// Debits a native token account and sends the backing lamports to
+/// `destination`.
+pub fn unwrap_sol(
+    accounts: &[NoStdAccountInfo],
+    args: &UnwrapSolArgs,
+) -> Result<usize, ProgramError> {
+    // Unpack accounts
+    //
+    // 1) token_account is checked by loader and debited, which is an implicit
+    //    owner check. Must be a native account.
+    // 2) owner must be token_account owner and must be signer
+    // 3) destination can be any writable account
+    let [token_account, owner, destination, _rem @ ..] = accounts else {
+        log::sol_log(
+            "unwrap_sol expecting [token_account, owner, destination, .. ]",
+        );
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // Check and debit token account
+    {
+        let mut token_account_data = token_account
+            .try_borrow_mut_data()
+            .ok_or(NanoTokenError::DuplicateAccount)?;
+        let token_account =
+            TokenAccount::checked_load_mut(&mut token_account_data)?;
+        if token_account.mint != NATIVE_MINT_INDEX {
+            log::sol_log("token_account is not native");
+            return Err(NanoTokenError::IncorrectMint.into());
+        }
+        token_account.check_not_frozen()?;
+        token_account.check_not_timelocked()?;
+        check_authority(owner, &token_account.owner)?;
+        if token_account.balance < args.amount {
+            log::sol_log("insufficient balance");
+            return Err(NanoTokenError::InsufficientTokenBalance.into());
+        }
+        token_account.balance =
+            math::sub_balance(token_account.balance, args.amount)?;
+    }
+
+    // Withdraw backing lamports. Runtime checks we own token_account and that
+    // destination is writable.
+    let mut token_account_lamports = token_account
+        .try_borrow_mut_lamports()
+        .expect("first borrow won't fail");
+    let mut destination_lamports = destination
+        .try_borrow_mut_lamports()
+        .ok_or(NanoTokenError::DuplicateAccount)?;
+    // Native balances are in lamports, so the same checked math applies
+    *token_account_lamports =
+        math::sub_balance(*token_account_lamports, args.amount)?;
+    *destination_lamports =
+        math::add_balance(*destination_lamports, args.amount)?;
+
+    Ok(3)
+}
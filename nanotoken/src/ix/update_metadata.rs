@@ -0,0 +1,101 @@
+use crate::solana_nostd_entrypoint::NoStdAccountInfo;
+use bytemuck::{Pod, Zeroable};
+use solana_program::{log, program_error::ProgramError};
+
+use crate::{
+    consts::{MAX_NAME_LEN, MAX_SYMBOL_LEN, MAX_URI_LEN},
+    error::NanoTokenError,
+    utils::{check_authority, split_at_unchecked},
+    Mint, MintMetadata,
+};
+
+#[derive(PartialEq, Debug, Clone, Copy, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct UpdateMetadataArgs {
+    /// Zero-padded utf-8
+    pub name: [u8; MAX_NAME_LEN],
+    /// Zero-padded utf-8
+    pub symbol: [u8; MAX_SYMBOL_LEN],
+    /// Zero-padded utf-8
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::utils::serde_byte_array")
+    )]
+    pub uri: [u8; MAX_URI_LEN],
+}
+
+impl UpdateMetadataArgs {
+    pub fn from_data<'a>(
+        data: &mut &'a [u8],
+    ) -> Result<&'a UpdateMetadataArgs, ProgramError> {
+        const IX_LEN: usize = core::mem::size_of::<UpdateMetadataArgs>();
+        if data.len() >= IX_LEN {
+            // SAFETY:
+            // We do the length check ourselves instead of via
+            // core::slice::split_at so we can return an error
+            // instead of panicking.
+            let (ix_data, rem) = unsafe { split_at_unchecked(data, IX_LEN) };
+            *data = rem;
+
+            // This is always aligned and all bit patterns are valid
+            Ok(unsafe { &*(ix_data.as_ptr() as *const UpdateMetadataArgs) })
+        } else {
+            Err(ProgramError::InvalidInstructionData)
+        }
+    }
+
+    pub const fn size() -> usize {
+        core::mem::size_of::<Self>()
+    }
+}
+
+/// Overwrites all metadata fields. Metadata becomes immutable once the mint
+/// authority is set to None.
+pub fn update_metadata(
+    accounts: &[NoStdAccountInfo],
+    args: &UpdateMetadataArgs,
+) -> Result<usize, ProgramError> {
+    // Unpack accounts
+    //
+    // 1) metadata is checked by loader and mutated, which is an implicit
+    //    owner check. Must belong to mint.
+    // 2) mint is not mutated, so it needs an explicit owner check
+    // 3) mint_authority must be the mint authority and must be signer
+    let [metadata, mint, mint_authority, _rem @ ..] = accounts else {
+        log::sol_log(
+            "update_metadata expecting [metadata, mint, mint_authority, .. ]",
+        );
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // Check mint authority
+    Mint::owner_check(mint)?;
+    let mut mint_data = mint
+        .try_borrow_mut_data()
+        .expect("first borrow won't fail");
+    let mint_account = Mint::checked_load_mut(&mut mint_data)?;
+    check_authority(mint_authority, &mint_account.authority)?;
+
+    // Load metadata
+    let mut metadata_data = metadata
+        .try_borrow_mut_data()
+        .ok_or(NanoTokenError::DuplicateAccount)?;
+    let metadata_account = MintMetadata::checked_load_mut(&mut metadata_data)?;
+    if solana_program::program_memory::sol_memcmp(
+        metadata_account.mint.as_ref(),
+        mint.key().as_ref(),
+        32,
+    ) != 0
+    {
+        log::sol_log("metadata does not belong to mint");
+        return Err(NanoTokenError::IncorrectMint.into());
+    }
+
+    // Update
+    metadata_account.name = args.name;
+    metadata_account.symbol = args.symbol;
+    metadata_account.uri = args.uri;
+
+    Ok(3)
+}
@@ -0,0 +1,133 @@
+use crate::solana_nostd_entrypoint::NoStdAccountInfo;
+use bytemuck::{Pod, Zeroable};
+use solana_program::{
+    log, program_error::ProgramError, pubkey::Pubkey, rent::Rent,
+    sysvar::Sysvar,
+};
+
+use crate::{
+    consts::NATIVE_MINT_INDEX, error::NanoTokenError, utils::check_authority,
+    AccountDiscriminator, Mint, ProgramConfig, TokenAccount,
+};
+
+#[derive(PartialEq, Debug, Clone, Pod, Zeroable, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct WithdrawExcessLamportsArgs {
+    // Keeping this scaffolded just in case...
+}
+
+impl WithdrawExcessLamportsArgs {
+    pub fn from_data<'a>(
+        _data: &mut &'a [u8],
+    ) -> Result<&'a WithdrawExcessLamportsArgs, ProgramError> {
+        // Zero-sized, so there is nothing to consume
+        Ok(&WithdrawExcessLamportsArgs {})
+    }
+
+    pub const fn size() -> usize {
+        core::mem::size_of::<Self>()
+    }
+}
+
+/// Sweeps lamports above the rent-exempt minimum out of a program-owned
+/// account.
+///
+/// Token accounts are swept by their owner and mints by their mint authority.
+/// Program accounts with no owner of their own (vault_info, config, and mints
+/// without a mint authority) are swept by the config authority.
+pub fn withdraw_excess_lamports(
+    accounts: &[NoStdAccountInfo],
+    _args: &WithdrawExcessLamportsArgs,
+) -> Result<usize, ProgramError> {
+    // Unpack accounts
+    //
+    // 1) account is debited, so the runtime checks we are its owner
+    // 2) authority is checked against the account kind below
+    // 3) destination can be any writable account
+    // 4) config is checked by memoized validator closure
+    let [account, authority, destination, _rem @ .., config, _system_program, _payer] =
+        accounts
+    else {
+        log::sol_log(
+            "withdraw_excess_lamports expecting [account, authority, destination, .. config, system_program, payer]",
+        );
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // Check authority for this kind of account. Native token account
+    // balances are backed by lamports, which are not excess.
+    let backing_lamports = {
+        let data = account
+            .try_borrow_data()
+            .expect("first borrow won't fail");
+        match data.first().copied() {
+            Some(x) if x == AccountDiscriminator::Token as u8 => {
+                // SAFETY: discriminator was just checked and token accounts
+                // are always allocated with at least TokenAccount::space()
+                let token_account =
+                    unsafe { &*(data.as_ptr().add(8) as *const TokenAccount) };
+                check_authority(authority, &token_account.owner)?;
+                if token_account.mint == NATIVE_MINT_INDEX {
+                    token_account.balance
+                } else {
+                    0
+                }
+            }
+            Some(x) if x == AccountDiscriminator::Mint as u8 => {
+                // SAFETY: discriminator was just checked and mints are always
+                // allocated with at least Mint::space() (older layouts are
+                // shorter, but authority comes before any new fields)
+                let mint = unsafe { &*(data.as_ptr().add(8) as *const Mint) };
+                if mint.authority != Pubkey::default() {
+                    check_authority(authority, &mint.authority)?;
+                } else {
+                    // Fixed supply mints have no authority of their own
+                    drop(data);
+
+                    // SAFETY: no one else has a view into config's data
+                    let config_account =
+                        unsafe { ProgramConfig::unchecked_load_mut(config)? };
+                    config_account.check_authority(authority)?;
+                }
+                0
+            }
+            Some(x)
+                if x == AccountDiscriminator::VaultInfo as u8
+                    || x == AccountDiscriminator::Config as u8 =>
+            {
+                // Drop borrow in case account is config
+                drop(data);
+
+                // SAFETY: no one else has a view into config's data
+                let config_account =
+                    unsafe { ProgramConfig::unchecked_load_mut(config)? };
+                config_account.check_authority(authority)?;
+                0
+            }
+            _ => {
+                log::sol_log("account does not support lamport withdrawals");
+                return Err(ProgramError::InvalidAccountData);
+            }
+        }
+    };
+
+    // Compute excess
+    let rent_exempt_minimum = Rent::get()?.minimum_balance(account.data_len());
+    let mut account_lamports = account
+        .try_borrow_mut_lamports()
+        .expect("first borrow won't fail");
+    let excess = account_lamports
+        .saturating_sub(rent_exempt_minimum)
+        .saturating_sub(backing_lamports);
+
+    // Transfer excess. Runtime checks we own account and that destination is
+    // writable.
+    let mut destination_lamports = destination
+        .try_borrow_mut_lamports()
+        .ok_or(NanoTokenError::DuplicateAccount)?;
+    *account_lamports -= excess;
+    *destination_lamports += excess;
+
+    Ok(3)
+}
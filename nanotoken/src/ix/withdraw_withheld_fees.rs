@@ -0,0 +1,111 @@
+use crate::solana_nostd_entrypoint::NoStdAccountInfo;
+use bytemuck::{Pod, Zeroable};
+use solana_program::{log, program_error::ProgramError};
+
+use crate::{
+    error::NanoTokenError,
+    utils::{check_authority, split_at_unchecked},
+    Mint, TokenAccount,
+};
+
+#[derive(PartialEq, Debug, Clone, Copy, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct WithdrawWithheldFeesArgs {
+    /// Number of source token accounts following destination
+    pub num_sources: u64,
+}
+
+impl WithdrawWithheldFeesArgs {
+    pub fn from_data<'a>(
+        data: &mut &'a [u8],
+    ) -> Result<&'a WithdrawWithheldFeesArgs, ProgramError> {
+        const IX_LEN: usize = core::mem::size_of::<WithdrawWithheldFeesArgs>();
+        if data.len() >= IX_LEN {
+            // SAFETY:
+            // We do the length check ourselves instead of via
+            // core::slice::split_at so we can return an error
+            // instead of panicking.
+            let (ix_data, rem) = unsafe { split_at_unchecked(data, IX_LEN) };
+            *data = rem;
+
+            // This is always aligned and all bit patterns are valid
+            Ok(unsafe {
+                &*(ix_data.as_ptr() as *const WithdrawWithheldFeesArgs)
+            })
+        } else {
+            Err(ProgramError::InvalidInstructionData)
+        }
+    }
+
+    pub const fn size() -> usize {
+        core::mem::size_of::<Self>()
+    }
+}
+
+/// Moves withheld transfer fees from each source into the destination
+/// balance.
+pub fn withdraw_withheld_fees(
+    accounts: &[NoStdAccountInfo],
+    args: &WithdrawWithheldFeesArgs,
+) -> Result<usize, ProgramError> {
+    // Unpack accounts
+    //
+    // 1) mint is not mutated, so it needs an explicit owner check
+    // 2) fee_authority must be the mint transfer fee authority and must be
+    //    signer
+    // 3) destination needs a disc check and must have the same mint. Owner
+    //    check is implicit when it is credited.
+    // 4) each source needs a disc check and must have the same mint. Owner
+    //    check is implicit when its withheld fees are cleared.
+    let [mint, fee_authority, destination, rem @ ..] = accounts else {
+        log::sol_log(
+            "withdraw_withheld_fees expecting [mint, fee_authority, destination, sources.., .. ]",
+        );
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+    let num_sources = args.num_sources as usize;
+    let Some(sources) = rem.get(..num_sources) else {
+        log::sol_log("withdraw_withheld_fees missing source accounts");
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // Check fee authority
+    Mint::owner_check(mint)?;
+    let mut mint_data = mint
+        .try_borrow_mut_data()
+        .expect("first borrow won't fail");
+    let mint_account = Mint::checked_load_mut(&mut mint_data)?;
+    check_authority(fee_authority, &mint_account.transfer_fee_authority)?;
+
+    // Load destination
+    let destination_account =
+        unsafe { TokenAccount::check_disc_raw(destination)? };
+    unsafe {
+        (*destination_account).check_not_frozen()?;
+        if (*destination_account).mint != mint_account.mint_index {
+            log::sol_log("invalid mint");
+            return Err(NanoTokenError::IncorrectMint.into());
+        }
+    }
+
+    for source in sources {
+        // Pointers are used since a source may alias destination or another
+        // source
+        let source_account = unsafe { TokenAccount::check_disc_raw(source)? };
+        unsafe {
+            if (*source_account).mint != mint_account.mint_index {
+                log::sol_log("invalid mint");
+                return Err(NanoTokenError::IncorrectMint.into());
+            }
+
+            let fees = (*source_account).withheld_fees;
+            (*source_account).withheld_fees = 0;
+            (*destination_account).credit(fees)?;
+        }
+    }
+    // Withdrawn fees carry the mint config like any other credit
+    unsafe { (*destination_account).inherit_mint_config(mint_account) };
+
+    Ok(3 + num_sources)
+}
@@ -0,0 +1,100 @@
+use crate::solana_nostd_entrypoint::NoStdAccountInfo;
+use bytemuck::{Pod, Zeroable};
+use solana_program::{log, program_error::ProgramError};
+
+use crate::{
+    consts::NATIVE_MINT_INDEX,
+    error::NanoTokenError,
+    utils::{split_at_unchecked, transfer_lamports_from_signer},
+    TokenAccount,
+};
+
+#[derive(PartialEq, Debug, Clone, Copy, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct WrapSolArgs {
+    /// In lamports
+    pub amount: u64,
+}
+
+impl WrapSolArgs {
+    pub fn from_data<'a>(
+        data: &mut &'a [u8],
+    ) -> Result<&'a WrapSolArgs, ProgramError> {
+        const IX_LEN: usize = core::mem::size_of::<WrapSolArgs>();
+        if data.len() >= IX_LEN {
+            // SAFETY:
+            // We do the length check ourselves instead of via
+            // core::slice::split_at so we can return an error
+            // instead of panicking.
+            let (ix_data, rem) = unsafe { split_at_unchecked(data, IX_LEN) };
+            *data = rem;
+
+            // This is always aligned and all bit patterns are valid
+            Ok(unsafe { &*(ix_data.as_ptr() as *const WrapSolArgs) })
+        } else {
+            Err(ProgramError::InvalidInstructionData)
+        }
+    }
+
+    pub const fn size() -> usize {
+        core::mem::size_of::<Self>()
+    }
+}
+
+/// Deposits lamports from `funder` into a native token account and credits
+/// its balance. Anyone can fund any native account.
+pub fn wrap_sol(
+    accounts: &[NoStdAccountInfo],
+    args: &WrapSolArgs,
+) -> Result<usize, ProgramError> {
+    // Unpack accounts
+    //
+    // 1) token_account is checked by loader and mutated, which is an implicit
+    //    owner check. Must be a native account.
+    // 2) funder signature is checked by the system program transfer
+    // 3) system program is checked by memoized validator closure
+    let [token_account, funder, _rem @ .., _config, _system_program, _payer] =
+        accounts
+    else {
+        log::sol_log(
+            "wrap_sol expecting [token_account, funder, .. config, system_program, payer]",
+        );
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // Return early if wrapping zero
+    //
+    // This is necessary!
+    // It is extremely cheap implicit owner check for token_account
+    if args.amount == 0 {
+        return Ok(2);
+    }
+
+    // Check and credit token account
+    {
+        let mut token_account_data = token_account
+            .try_borrow_mut_data()
+            .ok_or(NanoTokenError::DuplicateAccount)?;
+        let token_account =
+            TokenAccount::checked_load_mut(&mut token_account_data)?;
+        if token_account.mint != NATIVE_MINT_INDEX {
+            log::sol_log("token_account is not native");
+            return Err(NanoTokenError::IncorrectMint.into());
+        }
+        token_account.check_not_frozen()?;
+        token_account.credit(args.amount)?;
+    }
+
+    // Deposit lamports backing the new balance
+    // SAFETY: no one has a view into token_account or funder lamports
+    unsafe {
+        transfer_lamports_from_signer(
+            funder.to_info_c(),
+            token_account.to_info_c(),
+            args.amount,
+        );
+    }
+
+    Ok(2)
+}
@@ -1,20 +1,39 @@
 #![no_std]
 
 use crate::solana_nostd_entrypoint::NoStdAccountInfo;
+use arrayvec::ArrayVec;
 use bytemuck::{Pod, Zeroable};
-use consts::CONFIG_ACCOUNT;
+use consts::{
+    CONFIG_ACCOUNT, CONFIG_VERSION, MAX_FEE_BPS, MAX_INTENT_APPROVERS,
+    MAX_NAME_LEN, MAX_SYMBOL_LEN, MAX_URI_LEN, MINT_VERSION,
+    TOKEN_ACCOUNT_VERSION, VAULT_INFO_VERSION,
+};
+use error::NanoTokenError;
 
+#[cfg(feature = "anchor")]
+pub mod anchor;
+#[cfg(feature = "borsh")]
+pub mod borsh_compat;
 pub mod ix;
 pub mod solana_nostd_entrypoint;
 use ix::{ProgramInstructionRef as Ix, *};
+use loader::{AccountLoader, Discriminated};
 use solana_program::{
-    declare_id, entrypoint::ProgramResult, log, program_error::ProgramError,
-    pubkey::Pubkey, system_program::ID as SYSTEM_PROGRAM,
+    clock::Clock, declare_id, entrypoint::ProgramResult, log,
+    program_error::ProgramError, pubkey::Pubkey,
+    system_program::ID as SYSTEM_PROGRAM, sysvar::Sysvar,
 };
 pub mod consts;
 pub(crate) mod utils;
 
 pub mod error;
+pub mod events;
+pub mod loader;
+pub mod math;
+pub(crate) mod migrations;
+pub mod offsets;
+#[cfg(kani)]
+mod verify;
 
 declare_id!("GigabithNd6HmU4nRFPHXAkBK9nAtvNuHnSavWi3G7Zj");
 
@@ -33,19 +52,381 @@ pub mod allocator {
         unsafe fn dealloc(&self, _: *mut u8, _: core::alloc::Layout) {}
     }
 
-    #[cfg(target_os = "solana")]
+    // Programs that link nanotoken (e.g. via nanotoken-cpi) bring their own
+    #[cfg(all(target_os = "solana", not(feature = "no-entrypoint")))]
     #[global_allocator]
     static A: NoAlloc = NoAlloc;
 }
 
+/// The op being processed, for the panic handler. SBF programs can't have
+/// writable statics, so this lives at the start of the heap, which is
+/// otherwise unused since nothing allocates (see [allocator]).
+mod panic_context {
+    #[cfg(target_os = "solana")]
+    const CONTEXT: *mut [u64; 2] =
+        solana_program::entrypoint::HEAP_START_ADDRESS as *mut [u64; 2];
+
+    /// Records the op about to run
+    #[cfg(target_os = "solana")]
+    #[inline(always)]
+    pub(crate) fn set(op_index: usize, tag: u64) {
+        // SAFETY: the heap is zeroed, aligned, and never allocated from
+        unsafe { *CONTEXT = [op_index as u64 + 1, tag] };
+    }
+
+    #[cfg(not(target_os = "solana"))]
+    #[inline(always)]
+    pub(crate) fn set(_op_index: usize, _tag: u64) {}
+
+    /// The op that was running, as (op index, tag), if any
+    #[cfg(target_os = "solana")]
+    pub(crate) fn get() -> Option<(u64, u64)> {
+        // SAFETY: see set
+        let [op, tag] = unsafe { *CONTEXT };
+        (op != 0).then(|| (op - 1, tag))
+    }
+}
+
 fn process_instruction_nostd(
+    program_id: &Pubkey,
+    accounts: &[NoStdAccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    #[cfg(feature = "alias-guard")]
+    check_no_writable_duplicates(accounts)?;
+
+    // Errors raised while processing an op carry its index in the batch, see
+    // error::encode_op_error
+    let mut current_op = None;
+    process_ops(program_id, accounts, data, &mut current_op).map_err(|e| {
+        match current_op {
+            Some(op_index) => error::encode_op_error(op_index, e),
+            None => e,
+        }
+    })
+}
+
+/// Duplicate metas deserialize to clones of the same account, so handlers
+/// that take raw pointers into more than one account must handle aliasing
+/// themselves. This rejects any writable account passed twice instead, so no
+/// op can see one.
+#[cfg(feature = "alias-guard")]
+fn check_no_writable_duplicates(
+    accounts: &[NoStdAccountInfo],
+) -> ProgramResult {
+    for (i, account) in accounts.iter().enumerate() {
+        if !account.is_writable() {
+            continue;
+        }
+        // This will never be oob
+        let rest = unsafe { accounts.get_unchecked(i + 1..) };
+        if rest.iter().any(|other| {
+            solana_program::program_memory::sol_memcmp(
+                account.key().as_ref(),
+                other.key().as_ref(),
+                32,
+            ) == 0
+        }) {
+            log::sol_log("writable account passed more than once");
+            return Err(NanoTokenError::DuplicateAccount.into());
+        }
+    }
+    Ok(())
+}
+
+/// Runs one op with its accounts. See [HANDLERS].
+type HandlerFn = for<'a, 'b> fn(
+    &mut OpContext<'a, 'b>,
+    &[NoStdAccountInfo],
+    Ix<'b>,
+) -> Result<usize, ProgramError>;
+
+/// Op handlers indexed by tag. Like the parser table in
+/// [ix::InstructionIter], dispatching through it costs the same for every
+/// tag. Each handler validates the trailing config and system program only
+/// if its op uses them.
+static HANDLERS: [HandlerFn; ix::NUM_TAGS] = {
+    let mut table: [Option<HandlerFn>; ix::NUM_TAGS] = [None; ix::NUM_TAGS];
+    #[inline(always)]
+    const fn handler(handle: HandlerFn) -> Option<HandlerFn> {
+        Some(handle)
+    }
+    macro_rules! handler {
+        ($tag:ident($($args:ident),+), |$ctx:ident, $accounts:ident| $body:expr) => {
+            table[Tag::$tag as usize] = handler(|$ctx, $accounts, ix| {
+                // The parser for a tag only produces its own variant
+                let Ix::$tag($($args),+) = ix else { unreachable!() };
+                $body
+            });
+        };
+    }
+    handler!(InitializeConfig(args), |ctx, accounts| {
+        ctx.validate_config()?;
+        ctx.validate_sys_program()?;
+        initialize_config(accounts, args)
+    });
+    // config discriminant is checked and state
+    // is mutated  which is an implicit check
+    handler!(InitializeMint(args), |ctx, accounts| {
+        ctx.validate_sys_program()?;
+        initialize_mint(accounts, &args)
+    });
+    handler!(InitializeAccount(args), |ctx, accounts| {
+        ctx.validate_config()?;
+        ctx.validate_sys_program()?;
+        initialize_account(accounts, &args)
+    });
+    handler!(InitializeVault(args), |ctx, accounts| {
+        ctx.validate_config()?;
+        ctx.validate_sys_program()?;
+        initialize_vault(accounts, args)
+    });
+    handler!(Mint(args), |_ctx, accounts| mint(accounts, &args));
+    handler!(Burn(args), |_ctx, accounts| burn(accounts, &args));
+    handler!(Transfer(args), |_ctx, accounts| transfer(accounts, &args));
+    handler!(Transmute(args), |ctx, accounts| {
+        ctx.validate_config()?;
+        ctx.validate_sys_program()?;
+        transmute(accounts, &args)
+    });
+    handler!(SetAuthority(args), |_ctx, accounts| {
+        set_authority(accounts, args)
+    });
+    handler!(WithdrawExcessLamports(args), |ctx, accounts| {
+        ctx.validate_config()?;
+        withdraw_excess_lamports(accounts, args)
+    });
+    handler!(ProposeTransfer(args), |ctx, accounts| {
+        ctx.validate_sys_program()?;
+        propose_transfer(accounts, args)
+    });
+    handler!(ApproveTransfer(args), |_ctx, accounts| {
+        approve_transfer(accounts, args)
+    });
+    handler!(ExecuteTransfer(args), |_ctx, accounts| {
+        execute_transfer(accounts, args)
+    });
+    // read-only, doesn't need config or sys program
+    handler!(GetBalances(args), |_ctx, accounts| {
+        get_balances(accounts, args)
+    });
+    handler!(Approve(args), |_ctx, accounts| approve(accounts, args));
+    handler!(Revoke(args), |_ctx, accounts| revoke(accounts, args));
+    handler!(FreezeAccount(args), |_ctx, accounts| {
+        freeze_account(accounts, args)
+    });
+    handler!(ThawAccount(args), |_ctx, accounts| {
+        thaw_account(accounts, args)
+    });
+    handler!(BatchTransfer(args, amounts), |_ctx, accounts| {
+        batch_transfer(accounts, args, amounts)
+    });
+    handler!(TransferAll(args), |_ctx, accounts| {
+        transfer_all(accounts, args)
+    });
+    handler!(BurnChecked(args), |_ctx, accounts| {
+        burn_checked(accounts, args)
+    });
+    handler!(WrapSol(args), |ctx, accounts| {
+        ctx.validate_sys_program()?;
+        wrap_sol(accounts, args)
+    });
+    handler!(UnwrapSol(args), |_ctx, accounts| unwrap_sol(accounts, args));
+    handler!(SyncNative(args), |_ctx, accounts| {
+        sync_native(accounts, args)
+    });
+    handler!(InitializeMetadata(args), |ctx, accounts| {
+        ctx.validate_sys_program()?;
+        initialize_metadata(accounts, args)
+    });
+    handler!(UpdateMetadata(args), |_ctx, accounts| {
+        update_metadata(accounts, args)
+    });
+    handler!(WithdrawWithheldFees(args), |_ctx, accounts| {
+        withdraw_withheld_fees(accounts, args)
+    });
+    // only need config for its rent destination
+    handler!(CloseMint(args), |ctx, accounts| {
+        if args.rent_destination == RentDestination::Config as u64 {
+            ctx.validate_config()?;
+        }
+        close_mint(accounts, args)
+    });
+    handler!(SetMemoRequired(args), |_ctx, accounts| {
+        set_memo_required(accounts, args)
+    });
+    handler!(CreateAssociatedAccount(args), |ctx, accounts| {
+        ctx.validate_config()?;
+        ctx.validate_sys_program()?;
+        create_associated_account(accounts, args)
+    });
+    handler!(PermitTransfer(args), |_ctx, accounts| {
+        permit_transfer(accounts, args)
+    });
+    handler!(Pause(args), |_ctx, accounts| pause(accounts, args));
+    handler!(Unpause(args), |_ctx, accounts| unpause(accounts, args));
+    handler!(Clawback(args), |_ctx, accounts| clawback(accounts, args));
+    handler!(SetGcConfig(args), |ctx, accounts| {
+        ctx.validate_config()?;
+        set_gc_config(accounts, args)
+    });
+    handler!(GarbageCollect(args), |ctx, accounts| {
+        ctx.validate_config()?;
+        garbage_collect(accounts, args)
+    });
+    handler!(MigrateAccount(args), |ctx, accounts| {
+        ctx.validate_sys_program()?;
+        migrate_account(accounts, args)
+    });
+    // config discriminant is checked and state
+    // is mutated  which is an implicit check
+    handler!(InitializeMintWithMetadata(args), |ctx, accounts| {
+        ctx.validate_sys_program()?;
+        initialize_mint_with_metadata(accounts, args)
+    });
+    // Consumed by the op that follows
+    handler!(AccountIndices(_args, ix_indices), |ctx, _accounts| {
+        ctx.indices = Some(ix_indices);
+        Ok(0)
+    });
+    handler!(Realloc(args), |ctx, accounts| {
+        ctx.validate_sys_program()?;
+        realloc(accounts, args)
+    });
+    handler!(SetConfigAuthority(args), |ctx, accounts| {
+        ctx.validate_config()?;
+        set_config_authority(accounts, args)
+    });
+    handler!(AcceptConfigAuthority(args), |ctx, accounts| {
+        ctx.validate_config()?;
+        accept_config_authority(accounts, args)
+    });
+    // config discriminant is checked and state
+    // is mutated  which is an implicit check
+    handler!(InitializeMintPda(args), |ctx, accounts| {
+        ctx.validate_sys_program()?;
+        initialize_mint_pda(accounts, args)
+    });
+    // read-only, doesn't need config or sys program
+    handler!(GetAccountDataSize(args), |_ctx, accounts| {
+        get_account_data_size(accounts, args)
+    });
+    handler!(TransmuteBatch(args, entries), |ctx, accounts| {
+        ctx.validate_config()?;
+        ctx.validate_sys_program()?;
+        transmute_batch(accounts, args, entries)
+    });
+    handler!(InitializeList(args), |ctx, accounts| {
+        ctx.validate_sys_program()?;
+        initialize_list(accounts, args)
+    });
+    handler!(AddToList(args), |ctx, accounts| {
+        ctx.validate_sys_program()?;
+        add_to_list(accounts, args)
+    });
+    handler!(RemoveFromList(args), |_ctx, accounts| {
+        remove_from_list(accounts, args)
+    });
+    handler!(SetRoyalty(args), |_ctx, accounts| {
+        set_royalty(accounts, args)
+    });
+    handler!(SetRentDestination(args), |ctx, accounts| {
+        ctx.validate_config()?;
+        set_rent_destination(accounts, args)
+    });
+    // consumes no accounts, doesn't need config or sys program
+    handler!(Noop(args), |_ctx, accounts| noop(accounts, &args));
+    handler!(Timelock(args), |_ctx, accounts| timelock(accounts, args));
+    handler!(SetFeature(args), |ctx, accounts| {
+        ctx.validate_config()?;
+        set_feature(accounts, args)
+    });
+    // consumes no accounts, doesn't need config or sys program
+    handler!(Packed(args), |_ctx, accounts| packed(accounts, args));
+    handler!(MigrateState(args), |ctx, accounts| {
+        ctx.validate_config()?;
+        ctx.validate_sys_program()?;
+        migrate_state(accounts, args)
+    });
+    // only need config for its rent destination
+    handler!(CancelTransfer(args), |ctx, accounts| {
+        if args.rent_destination == RentDestination::Config as u64 {
+            ctx.validate_config()?;
+        }
+        cancel_transfer(accounts, args)
+    });
+
+    // Every tag must have a handler, otherwise its ops could never run
+    let mut handlers: [HandlerFn; ix::NUM_TAGS] =
+        [|_, _, _| Err(NanoTokenError::UnknownTag.into()); ix::NUM_TAGS];
+    let mut i = 0;
+    while i < ix::NUM_TAGS {
+        match table[i] {
+            Some(handle) => handlers[i] = handle,
+            None => panic!("op tag has no handler"),
+        }
+        i += 1;
+    }
+    handlers
+};
+
+/// State shared by the ops of one instruction
+struct OpContext<'a, 'b> {
+    config: &'a NoStdAccountInfo,
+    system_program: &'a NoStdAccountInfo,
+    validated_config: bool,
+    validated_sys_program: bool,
+    /// Set by AccountIndices for the op that follows it
+    indices: Option<&'b [u8]>,
+}
+
+impl OpContext<'_, '_> {
+    /// Checks the config account, once per instruction
+    #[inline(always)]
+    fn validate_config(&mut self) -> ProgramResult {
+        if !self.validated_config {
+            if solana_program::program_memory::sol_memcmp(
+                self.config.key().as_ref(),
+                CONFIG_ACCOUNT.as_ref(),
+                32,
+            ) != 0
+            {
+                log::sol_log("config does not have expected pubkey");
+                return Err(NanoTokenError::InvalidConfigAccount.into());
+            }
+            self.validated_config = true;
+        }
+        Ok(())
+    }
+
+    /// Checks the system program, once per instruction
+    #[inline(always)]
+    fn validate_sys_program(&mut self) -> ProgramResult {
+        if !self.validated_sys_program {
+            if solana_program::program_memory::sol_memcmp(
+                self.system_program.key().as_ref(),
+                SYSTEM_PROGRAM.as_ref(),
+                32,
+            ) != 0
+            {
+                log::sol_log("system_program does not have expected pubkey");
+                return Err(NanoTokenError::InvalidSystemProgram.into());
+            }
+            self.validated_sys_program = true;
+        }
+        Ok(())
+    }
+}
+
+fn process_ops(
     _program_id: &Pubkey,
     accounts: &[NoStdAccountInfo],
     data: &[u8],
+    current_op: &mut Option<usize>,
 ) -> ProgramResult {
     // We lazily check 2/3 of last 3 here since they may be needed
     // in the proceeding instructions.
-    // This memoization makes the validation only happen once.
+    // OpContext memoizes this so the validation only happens once.
     // The payer will be checked by any system_program cpis that need to be
     // performed.
     //
@@ -56,109 +437,150 @@ fn process_instruction_nostd(
         return Err(ProgramError::NotEnoughAccountKeys);
     };
 
-    // Check config
-    let mut validated_config = false;
-    let mut config_validator = {
-        #[inline(always)]
-        || {
-            if !validated_config {
-                if solana_program::program_memory::sol_memcmp(
-                    config.key().as_ref(),
-                    CONFIG_ACCOUNT.as_ref(),
-                    32,
-                ) != 0
-                {
-                    log::sol_log("config does not have expected pubkey");
-                    return Err(ProgramError::InvalidArgument);
-                }
-                validated_config = true;
-            }
-            Ok(true)
-        }
-    };
-
-    // Check system_program
-    let mut validated_sys_program = false;
-    let mut sys_program_validator = {
-        #[inline(always)]
-        || {
-            if !validated_sys_program {
-                if solana_program::program_memory::sol_memcmp(
-                    system_program.key().as_ref(),
-                    SYSTEM_PROGRAM.as_ref(),
-                    32,
-                ) != 0
-                {
-                    log::sol_log(
-                        "system_program does not have expected pubkey",
-                    );
-                    return Err(ProgramError::InvalidArgument);
-                }
-                validated_sys_program = true;
-            }
-            Ok(true)
-        }
+    let mut ctx = OpContext {
+        config,
+        system_program,
+        validated_config: false,
+        validated_sys_program: false,
+        indices: None,
     };
 
     // Parse program instructions
+    //
+    // Ops run strictly in order, and the same account may appear in many of
+    // them (e.g. as from in one op and to in the next). This is sound because
+    // handlers only return the number of accounts consumed: every borrow and
+    // raw pointer into account data or lamports ends with the op that took
+    // it, so each op sees all writes of the ops before it. Within an op,
+    // duplicates share borrow state, so checked borrows catch them, and ops
+    // using raw pointers (e.g. transfer) handle aliasing explicitly.
     let instruction_iter = InstructionIter::new(data);
 
     let mut ai = 0;
-    for instruction in instruction_iter {
-        // This will never be oob
-        let ix_accounts = unsafe { accounts.get_unchecked(ai..) };
-
-        ai += match instruction? {
-            Ix::InitializeConfig(args) => {
-                config_validator()?;
-                initialize_config(ix_accounts, args)
+    let mut indexed_accounts = ArrayVec::new();
+    for (op_index, instruction) in instruction_iter.enumerate() {
+        *current_op = Some(op_index);
+        let (tag, instruction) = instruction?;
+        panic_context::set(op_index, tag as u64);
+        let is_indexed = ctx.indices.is_some();
+        let ix_accounts = match ctx.indices.take() {
+            Some(indices) => {
+                gather_indexed_accounts(
+                    accounts,
+                    indices,
+                    &mut indexed_accounts,
+                )?;
+                indexed_accounts.as_slice()
             }
-            Ix::InitializeMint(args) => {
-                // config discriminant is checked and state
-                // is mutated  which is an implicit check
-                sys_program_validator()?;
-                initialize_mint(ix_accounts, args)
-            }
-            Ix::InitializeAccount(args) => {
-                config_validator()?;
-                sys_program_validator()?;
-                initialize_account(ix_accounts, args)
-            }
-            Ix::InitializeVault(args) => {
-                config_validator()?;
-                sys_program_validator()?;
-                initialize_vault(ix_accounts, args)
-            }
-            Ix::Mint(args) => {
-                // don't need to validate config or sys program
-                mint(ix_accounts, args)
-            }
-            Ix::Burn(args) => {
-                // don't need to validate config or sys program
-                burn(ix_accounts, args)
-            }
-            Ix::Transfer(args) => {
-                // don't need to validate config or sys program
-                transfer(ix_accounts, args)
-            }
-            Ix::Transmute(args) => {
-                config_validator()?;
-                sys_program_validator()?;
-                transmute(ix_accounts, args)
-            }
-        }?;
+            // This will never be oob
+            None => unsafe { accounts.get_unchecked(ai..) },
+        };
+
+        // Check the op has enough accounts before dispatching, so a short
+        // batch fails naming the op instead of somewhere inside a handler
+        if ix_accounts.len() < tag.min_accounts() {
+            log::sol_log(
+                "op is missing accounts: [op index, tag, expected, got]",
+            );
+            log::sol_log_64(
+                op_index as u64,
+                tag as u64,
+                tag.min_accounts() as u64,
+                ix_accounts.len() as u64,
+                0,
+            );
+            return Err(NanoTokenError::MissingAccounts.into());
+        }
+
+        #[cfg(feature = "cu-meter")]
+        let cu_before =
+            solana_program::compute_units::sol_remaining_compute_units();
+
+        let consumed =
+            HANDLERS[tag as usize](&mut ctx, ix_accounts, instruction)?;
+
+        // The delta includes one sol_remaining_compute_units syscall
+        #[cfg(feature = "cu-meter")]
+        {
+            let cu_after =
+                solana_program::compute_units::sol_remaining_compute_units();
+            log::sol_log("op compute units: [op index, tag, consumed]");
+            log::sol_log_64(
+                op_index as u64,
+                tag as u64,
+                cu_before - cu_after,
+                0,
+                0,
+            );
+        }
+
+        // See above. Handlers must not leak borrows into the next op.
+        debug_assert!(accounts
+            .iter()
+            .all(|a| !a.is_borrowed()));
+        if cfg!(feature = "paranoid")
+            && accounts.iter().any(|a| a.is_borrowed())
+        {
+            log::sol_log("paranoid: op leaked an account borrow");
+            return Err(ProgramError::AccountBorrowFailed);
+        }
+
+        // Indexed ops do not consume accounts in line
+        if !is_indexed {
+            ai += consumed;
+        }
     }
 
     Ok(())
 }
 
-#[derive(Clone, Copy, Pod, Zeroable)]
+#[derive(Debug, Clone, PartialEq, Copy, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct ProgramConfig {
     mint_index: u64,
+    /// Admin for privileged operations on program-owned accounts
+    authority: Pubkey,
+    /// Proposed by SetConfigAuthority, and becomes the authority once it
+    /// signs AcceptConfigAuthority. [0; 32] is used as None.
+    pending_authority: Pubkey,
+    /// Minimum age in seconds of an empty token account before anyone may
+    /// garbage collect it. 0 disables garbage collection.
+    gc_min_age: i64,
+    /// Lamports paid out of a collected account's rent to whoever collects it
+    gc_bounty: u64,
+    /// Receives the rent of closed accounts when a close call selects
+    /// [RentDestination::Config], e.g. a protocol treasury. [0; 32] is used
+    /// as None.
+    rent_destination: Pubkey,
+    /// Bitfield of enabled [ProgramFeature]s, set by the authority. Lets new
+    /// behavior ship in the program before it is turned on.
+    features: u64,
+}
+
+impl Discriminated for ProgramConfig {
+    const DISCRIMINATOR: AccountDiscriminator = AccountDiscriminator::Config;
+    const VERSION: Option<u8> = Some(CONFIG_VERSION);
 }
 
 impl ProgramConfig {
+    /// Off-chain decoder. Checks discriminator, version, and length of
+    /// account data (including the discriminator) and returns a copy.
+    #[cfg(feature = "unpack")]
+    pub fn try_unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        AccountLoader::try_unpack(data)
+    }
+
+    /// Derives [CONFIG_ACCOUNT] and its bump
+    pub fn address() -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"config"], &crate::ID)
+    }
+
+    /// Index the next initialized mint receives
+    pub fn next_mint_index(&self) -> u64 {
+        self.mint_index
+    }
+
     pub const fn space() -> usize {
         8 + core::mem::size_of::<Self>()
     }
@@ -177,20 +599,75 @@ impl ProgramConfig {
     pub(crate) unsafe fn unchecked_load_mut(
         config: &NoStdAccountInfo,
     ) -> Result<&mut ProgramConfig, ProgramError> {
-        // Unpack and split data into discriminator & config
-        let config_data = config.unchecked_borrow_mut_data();
-        let (disc, config_bytes) = config_data.split_at_mut(8);
+        AccountLoader::unchecked_load_mut(config)
+    }
 
-        // We only need to check the first byte
-        if disc[0] != AccountDiscriminator::Config as u8 {
-            log::sol_log("config discriminator is incorrect");
-            return Err(ProgramError::InvalidAccountData);
+    /// Gate for privileged operations. `authority` must be the config
+    /// authority and must be signer.
+    #[inline(always)]
+    pub(crate) fn check_authority(
+        &self,
+        authority: &NoStdAccountInfo,
+    ) -> ProgramResult {
+        utils::check_authority(authority, &self.authority)
+    }
+
+    /// Checks `feature` was enabled by the config authority
+    pub(crate) fn check_feature(
+        &self,
+        feature: ProgramFeature,
+    ) -> ProgramResult {
+        if self.features & (1 << feature as u64) == 0 {
+            log::sol_log("program feature is not enabled");
+            return Err(NanoTokenError::FeatureDisabled.into());
         }
+        Ok(())
+    }
 
-        Ok(&mut *(config_bytes.as_mut_ptr() as *mut ProgramConfig))
+    /// For close calls selecting [RentDestination::Config], checks
+    /// `destination` is the configured rent destination
+    #[inline(always)]
+    pub(crate) fn check_rent_destination(
+        &self,
+        destination: &NoStdAccountInfo,
+    ) -> ProgramResult {
+        if self.rent_destination == Pubkey::default() {
+            log::sol_log("config has no rent destination");
+            return Err(NanoTokenError::NoRentDestination.into());
+        }
+        if solana_program::program_memory::sol_memcmp(
+            destination.key().as_ref(),
+            self.rent_destination.as_ref(),
+            32,
+        ) != 0
+        {
+            log::sol_log("incorrect rent destination");
+            return Err(NanoTokenError::RentDestinationMismatch.into());
+        }
+        Ok(())
     }
 }
 
+/// Bit indices of [ProgramConfig] feature gates. All are off after
+/// InitializeConfig.
+#[repr(u64)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ProgramFeature {
+    /// Mints may be initialized with a nonzero transfer fee
+    TransferFees = 0,
+    /// Mints may be initialized with a freeze authority
+    Freezing = 1,
+}
+
+/// Where the rent of a closed account goes, selected per close call
+#[repr(u64)]
+pub enum RentDestination {
+    /// The close call's own destination, e.g. the account's rent payer
+    Default = 0,
+    /// The config's rent destination
+    Config = 1,
+}
+
 #[repr(u8)]
 pub enum AccountDiscriminator {
     Unintialized = 0,
@@ -198,9 +675,13 @@ pub enum AccountDiscriminator {
     Mint,
     Token,
     VaultInfo,
+    TransferIntent,
+    MintMetadata,
+    MintList,
 }
 
 #[derive(Debug, Clone, PartialEq, PartialOrd, Copy, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct Mint {
     pub mint_index: u64,
@@ -208,10 +689,55 @@ pub struct Mint {
     pub authority: Pubkey,
     pub supply: u64,
     pub decimals: u8,
-    pub _padding: [u8; 7],
+    /// Nonzero while paused by the pause authority
+    pub paused: u8,
+    /// Set by InitializeList, see [ListMode]. Transfers of mints with a list
+    /// must pass the list.
+    pub list_mode: u8,
+    pub _padding: [u8; 5],
+    /// [0; 32] is used as None
+    pub freeze_authority: Pubkey,
+    /// May withdraw withheld transfer fees. [0; 32] is used as None.
+    pub transfer_fee_authority: Pubkey,
+    /// Set at initialization and immutable. 0 means no transfer fee.
+    pub transfer_fee_bps: u64,
+    pub max_transfer_fee: u64,
+    /// May pause and unpause the mint. Set at initialization, and [0; 32]
+    /// (None) means the mint can never be paused.
+    pub pause_authority: Pubkey,
+    /// Set at initialization and immutable. 0 means no cap.
+    pub max_supply: u64,
+    /// May move tokens out of any account of this mint. Set at
+    /// initialization, and [0; 32] (None) means clawback is disabled.
+    pub clawback_authority: Pubkey,
+    /// Cut of every transfer paid to the royalty recipient, set by
+    /// SetRoyalty. 0 means no royalty.
+    pub royalty_bps: u64,
+    /// Token account of this mint that receives royalties
+    pub royalty_recipient: Pubkey,
+}
+
+impl Discriminated for Mint {
+    const DISCRIMINATOR: AccountDiscriminator = AccountDiscriminator::Mint;
+    const VERSION: Option<u8> = Some(MINT_VERSION);
 }
 
 impl Mint {
+    /// Off-chain decoder. Checks discriminator, version, and length of
+    /// account data (including the discriminator) and returns a copy.
+    #[cfg(feature = "unpack")]
+    pub fn try_unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        AccountLoader::try_unpack(data)
+    }
+
+    /// Address of mints created by InitializeMintPda
+    pub fn address(mint_index: u64) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[b"mint", mint_index.to_le_bytes().as_ref()],
+            &crate::ID,
+        )
+    }
+
     pub fn size() -> usize {
         core::mem::size_of::<Mint>()
     }
@@ -229,37 +755,169 @@ impl Mint {
         Ok(())
     }
 
-    pub(crate) fn checked_load_mut(
-        mint_data: &mut [u8],
-    ) -> Result<&mut Mint, ProgramError> {
-        // Unpack and split data into discriminator & mint
-        let (disc, mint_bytes) = mint_data.split_at_mut(8);
+    #[inline(always)]
+    pub fn is_pausable(&self) -> bool {
+        self.pause_authority != Pubkey::default()
+    }
+
+    #[inline(always)]
+    pub(crate) fn check_not_paused(&self) -> ProgramResult {
+        if self.paused != 0 {
+            log::sol_log("mint is paused");
+            return Err(NanoTokenError::MintPaused.into());
+        }
+        Ok(())
+    }
 
-        // We only need to check the first byte
-        if disc[0] != AccountDiscriminator::Mint as u8 {
-            log::sol_log("mint discriminator is incorrect");
-            return Err(ProgramError::InvalidAccountData);
+    /// Royalty on a transfer of `amount`. Rounds down.
+    #[inline(always)]
+    pub fn royalty(&self, amount: u64) -> u64 {
+        // royalty <= amount since bps <= MAX_FEE_BPS
+        (amount as u128 * self.royalty_bps as u128 / MAX_FEE_BPS as u128) as u64
+    }
+
+    /// Finds the mint with `mint_index` in `accounts`
+    ///
+    /// # Safety
+    /// no one else should have a mutable view into the mint's data.
+    pub(crate) unsafe fn find(
+        mint_index: u64,
+        accounts: &[NoStdAccountInfo],
+    ) -> Option<&Mint> {
+        for account in accounts {
+            if *account.owner() != crate::ID
+                || account.data_len() != Mint::space()
+            {
+                continue;
+            }
+            // Length was just checked
+            let data = account.unchecked_borrow_data();
+            if data[0] != AccountDiscriminator::Mint as u8 {
+                continue;
+            }
+            let mint = &*(data.as_ptr().add(8) as *const Mint);
+            if mint.mint_index == mint_index {
+                return Some(mint);
+            }
         }
+        None
+    }
 
-        Ok(unsafe { &mut *(mint_bytes.as_mut_ptr() as *mut Mint) })
+    pub(crate) fn checked_load_mut(
+        mint_data: &mut [u8],
+    ) -> Result<&mut Mint, ProgramError> {
+        AccountLoader::checked_load_mut(mint_data)
     }
 }
 
 #[derive(Debug, Clone, PartialEq, PartialOrd, Copy, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct TokenAccount {
     pub owner: Pubkey,
     pub mint: u64,
     pub balance: u64,
+    /// [0; 32] is used as None
+    pub delegate: Pubkey,
+    /// Remaining amount the delegate may transfer
+    pub delegated_amount: u64,
+    /// Bitflags, see [TokenAccount::FROZEN], [TokenAccount::MEMO_REQUIRED],
+    /// [TokenAccount::PAUSABLE], [TokenAccount::IMMUTABLE_OWNER],
+    /// [TokenAccount::LISTED], [TokenAccount::ROYALTY], and
+    /// [TokenAccount::TIMELOCKED]
+    pub state: u8,
+    /// Bump of the (owner, mint) pda this account lives at. Zero for keypair
+    /// accounts, and cleared when the owner changes since the address no
+    /// longer derives from the owner.
+    pub bump: u8,
+    pub _padding: [u8; 6],
+    /// Transfer fees collected on incoming transfers, claimable by the mint's
+    /// transfer fee authority
+    pub withheld_fees: u64,
+    /// Copy of the mint's (immutable) transfer fee config. It is written when
+    /// the mint credits this account and carried along by transfers, so that
+    /// transfer needs no mint account.
+    pub transfer_fee_bps: u64,
+    pub max_transfer_fee: u64,
+    /// Nonce the next owner-signed permit must use. Incremented by each
+    /// PermitTransfer so permits can't be replayed.
+    pub permit_nonce: u64,
+    /// Paid for this account's rent, and receives it back when the account is
+    /// garbage collected
+    pub rent_payer: Pubkey,
+    /// Unix timestamp at initialization
+    pub created_at: i64,
+    /// Unix timestamp before which a [TokenAccount::TIMELOCKED] account
+    /// can't send or burn
+    pub unlock_at: i64,
+}
+
+impl Discriminated for TokenAccount {
+    const DISCRIMINATOR: AccountDiscriminator = AccountDiscriminator::Token;
+    const VERSION: Option<u8> = Some(TOKEN_ACCOUNT_VERSION);
 }
 
 impl TokenAccount {
+    /// Off-chain decoder. Checks discriminator, version, and length of
+    /// account data (including the discriminator) and returns a copy.
+    #[cfg(feature = "unpack")]
+    pub fn try_unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        AccountLoader::try_unpack(data)
+    }
+
     pub fn address(mint: u64, owner: &Pubkey) -> (Pubkey, u8) {
         Pubkey::find_program_address(
             &[owner.as_ref(), mint.to_le_bytes().as_ref()],
             &crate::ID,
         )
     }
+
+    /// Seeds (with the stored bump) of the (owner, mint) pda this account
+    /// lives at, e.g. for signing as the account. Only meaningful if
+    /// [TokenAccount::bump] is nonzero.
+    #[inline(always)]
+    pub fn signer_seeds(&self) -> [&[u8]; 3] {
+        [
+            self.owner.as_ref(),
+            // Little endian on the target, same as mint.to_le_bytes()
+            bytemuck::bytes_of(&self.mint),
+            core::slice::from_ref(&self.bump),
+        ]
+    }
+
+    /// Re-derives this account's address from the stored bump, without a
+    /// bump search. None for keypair accounts and accounts whose owner
+    /// changed, which store no bump, or if the stored bump is invalid.
+    #[inline(always)]
+    pub fn derived_address(&self) -> Option<Pubkey> {
+        if self.bump == 0 {
+            return None;
+        }
+        Pubkey::create_program_address(&self.signer_seeds(), &crate::ID).ok()
+    }
+
+    /// Set by the mint freeze authority. Frozen accounts can't send, receive,
+    /// burn, or transmute.
+    pub const FROZEN: u8 = 1 << 0;
+    /// Set by the owner. Incoming transfers must come in a transaction that
+    /// includes an SPL memo instruction.
+    pub const MEMO_REQUIRED: u8 = 1 << 1;
+    /// Copied from the mint along with the transfer fee config. Transfers out
+    /// of pausable accounts must include the mint so its pause state can be
+    /// checked.
+    pub const PAUSABLE: u8 = 1 << 2;
+    /// Set at initialization. The owner of these accounts can never change.
+    pub const IMMUTABLE_OWNER: u8 = 1 << 3;
+    /// Copied from the mint like [TokenAccount::PAUSABLE]. Transfers out of
+    /// listed accounts must include the mint's [MintList] so both owners can
+    /// be checked against it.
+    pub const LISTED: u8 = 1 << 4;
+    /// Copied from the mint like [TokenAccount::PAUSABLE]. Transfers out of
+    /// royalty accounts must include the mint and its royalty recipient.
+    pub const ROYALTY: u8 = 1 << 5;
+    /// Set by the owner. Balance can't leave the account until `unlock_at`.
+    pub const TIMELOCKED: u8 = 1 << 6;
+
     pub fn size() -> usize {
         core::mem::size_of::<Self>()
     }
@@ -268,24 +926,210 @@ impl TokenAccount {
         8 + core::mem::size_of::<Self>()
     }
 
+    #[inline(always)]
+    pub fn is_frozen(&self) -> bool {
+        self.state & Self::FROZEN != 0
+    }
+
+    #[inline(always)]
+    pub fn requires_memo(&self) -> bool {
+        self.state & Self::MEMO_REQUIRED != 0
+    }
+
+    #[inline(always)]
+    pub fn has_immutable_owner(&self) -> bool {
+        self.state & Self::IMMUTABLE_OWNER != 0
+    }
+
+    #[inline(always)]
+    pub fn is_timelocked(&self) -> bool {
+        self.state & Self::TIMELOCKED != 0
+    }
+
+    #[inline(always)]
+    pub(crate) fn check_not_frozen(&self) -> ProgramResult {
+        if self.is_frozen() {
+            log::sol_log("token_account is frozen");
+            return Err(NanoTokenError::AccountFrozen.into());
+        }
+        Ok(())
+    }
+
+    /// Checks balance may leave this account. Only timelocked accounts read
+    /// the clock.
+    #[inline(always)]
+    pub(crate) fn check_not_timelocked(&self) -> ProgramResult {
+        if self.is_timelocked() && Clock::get()?.unix_timestamp < self.unlock_at
+        {
+            log::sol_log("token_account is timelocked");
+            return Err(NanoTokenError::AccountTimelocked.into());
+        }
+        Ok(())
+    }
+
+    /// For pausable accounts, finds this account's mint in `accounts` and
+    /// checks it is not paused. The mint may be passed anywhere after the
+    /// op's accounts.
+    pub(crate) fn check_not_paused(
+        &self,
+        accounts: &[NoStdAccountInfo],
+    ) -> ProgramResult {
+        if self.state & Self::PAUSABLE == 0 {
+            return Ok(());
+        }
+        // SAFETY: no one holds a mutable view into mint data during transfers
+        match unsafe { Mint::find(self.mint, accounts) } {
+            Some(mint) => mint.check_not_paused(),
+            None => {
+                log::sol_log("pausable mint must be passed");
+                Err(ProgramError::NotEnoughAccountKeys)
+            }
+        }
+    }
+
+    /// For accounts of a mint with a royalty, moves the royalty on a transfer
+    /// of `amount` out of `from` into the mint's royalty recipient, and
+    /// returns it. The mint and recipient may be passed anywhere after the
+    /// op's accounts.
+    ///
+    /// # Safety
+    /// `from` must point to a token account no one else has a view into. The
+    /// recipient may alias it or the transfer destination. Caller checks
+    /// `from` balance covers `amount`.
+    pub(crate) unsafe fn pay_royalty(
+        from: *mut TokenAccount,
+        amount: u64,
+        accounts: &[NoStdAccountInfo],
+    ) -> Result<u64, ProgramError> {
+        if (*from).state & Self::ROYALTY == 0 {
+            return Ok(0);
+        }
+        let Some(mint) = Mint::find((*from).mint, accounts) else {
+            log::sol_log("royalty mint must be passed");
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        let royalty = mint.royalty(amount);
+        if royalty == 0 {
+            return Ok(0);
+        }
+
+        // Recipient is mutated, which is an implicit owner check
+        let Some(recipient) = accounts
+            .iter()
+            .find(|account| *account.key() == mint.royalty_recipient)
+        else {
+            log::sol_log("royalty recipient must be passed");
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        let recipient = Self::check_disc_raw(recipient)?;
+        if (*recipient).mint != (*from).mint {
+            log::sol_log("royalty recipient mint mismatch");
+            return Err(NanoTokenError::IncorrectMint.into());
+        }
+        (*recipient).check_not_frozen()?;
+
+        (*from).balance = math::sub_balance((*from).balance, royalty)?;
+        (*recipient).credit(royalty)?;
+        // The royalty carries the mint config, including the royalty itself
+        (*recipient).inherit_mint_config(mint);
+        Ok(royalty)
+    }
+
+    /// For listed accounts, finds this account's mint list in `accounts` and
+    /// checks that both this account's owner and `to_owner` may hold tokens.
+    /// The list may be passed anywhere after the op's accounts.
+    pub(crate) fn check_list(
+        &self,
+        to_owner: &Pubkey,
+        accounts: &[NoStdAccountInfo],
+    ) -> ProgramResult {
+        if self.state & Self::LISTED == 0 {
+            return Ok(());
+        }
+        // SAFETY: no one holds a mutable view into list data during
+        // transfers
+        let (list, entries) = unsafe { MintList::find(self.mint, accounts)? };
+        list.check_allowed(entries, &self.owner)?;
+        list.check_allowed(entries, to_owner)
+    }
+
+    /// Fee withheld on a transfer of `amount` out of this account. Rounds up,
+    /// and is capped at the max fee.
+    #[inline(always)]
+    pub fn transfer_fee(&self, amount: u64) -> u64 {
+        if self.transfer_fee_bps == 0 {
+            return 0;
+        }
+        let fee = (amount as u128 * self.transfer_fee_bps as u128)
+            .div_ceil(MAX_FEE_BPS as u128);
+        // fee <= amount since bps <= MAX_FEE_BPS
+        (fee as u64).min(self.max_transfer_fee)
+    }
+
+    /// Adds `amount` to the balance, see [math::add_balance]
+    #[inline(always)]
+    pub(crate) fn credit(&mut self, amount: u64) -> ProgramResult {
+        match math::add_balance(self.balance, amount) {
+            Ok(balance) => {
+                self.balance = balance;
+                Ok(())
+            }
+            Err(e) => {
+                log::sol_log("balance would exceed u64::MAX");
+                Err(e.into())
+            }
+        }
+    }
+
+    /// Called whenever the mint credits this account
+    #[inline(always)]
+    pub(crate) fn inherit_mint_config(&mut self, mint: &Mint) {
+        self.transfer_fee_bps = mint.transfer_fee_bps;
+        self.max_transfer_fee = mint.max_transfer_fee;
+        if mint.is_pausable() {
+            self.state |= Self::PAUSABLE;
+        }
+        if mint.list_mode != ListMode::None as u8 {
+            self.state |= Self::LISTED;
+        }
+        if mint.royalty_bps != 0 {
+            self.state |= Self::ROYALTY;
+        }
+    }
+
+    /// Moves `amount` from `from` to `to`, withholding the transfer fee in
+    /// `to`. The fee config, pausability, list gating, and royalty travel with
+    /// the tokens.
+    ///
+    /// # Safety
+    /// Both pointers must point to token accounts no one else has a view into.
+    /// They may alias. Caller checks mints and `from` balance.
+    #[inline(always)]
+    pub(crate) unsafe fn move_balance(
+        from: *mut TokenAccount,
+        to: *mut TokenAccount,
+        amount: u64,
+    ) -> ProgramResult {
+        let fee = (*from).transfer_fee(amount);
+        if fee != 0 {
+            (*to).transfer_fee_bps = (*from).transfer_fee_bps;
+            (*to).max_transfer_fee = (*from).max_transfer_fee;
+        }
+        (*to).state |=
+            (*from).state & (Self::PAUSABLE | Self::LISTED | Self::ROYALTY);
+        (*from).balance = math::sub_balance((*from).balance, amount)?;
+        (*to).credit(amount - fee)?;
+        (*to).withheld_fees = math::add_balance((*to).withheld_fees, fee)?;
+        Ok(())
+    }
+
     /// Discriminator check. This does not do an owner check!
     /// If you call this function you MUST mutate the data to do an implicit
     /// owner check (should be mutated during e.g. mint, transfer)
     pub(crate) fn checked_load_mut(
         token_account_data: &mut [u8],
     ) -> Result<&mut TokenAccount, ProgramError> {
-        // Unpack and split data into discriminator & token_account
-        let (disc, token_account_bytes) = token_account_data.split_at_mut(8);
-
-        // We only need to check the first byte
-        if disc[0] != AccountDiscriminator::Token as u8 {
-            log::sol_log("token_account discriminator is incorrect");
-            return Err(ProgramError::InvalidAccountData);
-        }
-
-        Ok(unsafe {
-            &mut *(token_account_bytes.as_mut_ptr() as *mut TokenAccount)
-        })
+        AccountLoader::checked_load_mut(token_account_data)
     }
 
     /// # Safety
@@ -293,19 +1137,7 @@ impl TokenAccount {
     pub unsafe fn check_disc(
         token_account: &NoStdAccountInfo,
     ) -> Result<(&Pubkey, u64, *mut u64), ProgramError> {
-        // Unpack and split data into discriminator &token_account
-        let (disc, token_account_bytes) = token_account
-            .unchecked_borrow_data()
-            .split_at(8);
-
-        // We only need to check the first byte
-        if disc[0] != AccountDiscriminator::Token as u8 {
-            log::sol_log("token_account discriminator is incorrect");
-            return Err(ProgramError::InvalidAccountData);
-        }
-
-        let account =
-            unsafe { &*(token_account_bytes.as_ptr() as *const TokenAccount) };
+        let account = &*Self::check_disc_raw(token_account)?;
 
         Ok((
             &account.owner,
@@ -313,18 +1145,44 @@ impl TokenAccount {
             &account.balance as *const u64 as *mut u64,
         ))
     }
+
+    /// Like [TokenAccount::check_disc], but returns a pointer to the whole
+    /// account for ops that need more than the balance. Pointers are used
+    /// since e.g. from and to may alias.
+    ///
+    /// # Safety
+    /// no one else should have a view into this account's data.
+    pub unsafe fn check_disc_raw(
+        token_account: &NoStdAccountInfo,
+    ) -> Result<*mut TokenAccount, ProgramError> {
+        AccountLoader::unchecked_load_raw(token_account)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, PartialOrd, Copy, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct VaultInfo {
     tokenkeg_mint: Pubkey,
     tokenkeg_vault: Pubkey,
     nanotoken_mint: Pubkey,
     info_bump: u8,
+    vault_bump: u8,
+}
+
+impl Discriminated for VaultInfo {
+    const DISCRIMINATOR: AccountDiscriminator = AccountDiscriminator::VaultInfo;
+    const VERSION: Option<u8> = Some(VAULT_INFO_VERSION);
 }
 
 impl VaultInfo {
+    /// Off-chain decoder. Checks discriminator, version, and length of
+    /// account data (including the discriminator) and returns a copy.
+    #[cfg(feature = "unpack")]
+    pub fn try_unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        AccountLoader::try_unpack(data)
+    }
+
     pub fn space() -> usize {
         8 + core::mem::size_of::<Self>()
     }
@@ -343,19 +1201,39 @@ impl VaultInfo {
         )
     }
 
+    #[inline(always)]
+    pub fn tokenkeg_mint(&self) -> &Pubkey {
+        &self.tokenkeg_mint
+    }
+
+    #[inline(always)]
+    pub fn tokenkeg_vault(&self) -> &Pubkey {
+        &self.tokenkeg_vault
+    }
+
+    #[inline(always)]
+    pub fn nanotoken_mint(&self) -> &Pubkey {
+        &self.nanotoken_mint
+    }
+
+    /// Bump of the vault info pda, which signs for the vault
+    #[inline(always)]
+    pub fn info_bump(&self) -> u8 {
+        self.info_bump
+    }
+
+    /// Bump of the tokenkeg vault pda
+    #[inline(always)]
+    pub fn vault_bump(&self) -> u8 {
+        self.vault_bump
+    }
+
     /// Discriminator and owner checks are performed.
     pub(crate) fn checked_load<'a>(
         vault_info_data: &'a [u8],
         owner: &Pubkey,
     ) -> Result<&'a VaultInfo, ProgramError> {
-        // Unpack and split data into discriminator & token_account
-        let (disc, vault_info_bytes) = vault_info_data.split_at(8);
-
-        // We only need to check the first byte
-        if disc[0] != AccountDiscriminator::VaultInfo as u8 {
-            log::sol_log("vault_info discriminator is incorrect");
-            return Err(ProgramError::InvalidAccountData);
-        }
+        let vault_info = AccountLoader::checked_load(vault_info_data)?;
 
         // Check account owner
         if solana_program::program_memory::sol_memcmp(
@@ -368,12 +1246,256 @@ impl VaultInfo {
             return Err(ProgramError::IllegalOwner);
         }
 
-        Ok(unsafe { &*(vault_info_bytes.as_ptr() as *const VaultInfo) })
+        Ok(vault_info)
+    }
+}
+
+/// A proposed transfer that executes once enough approvers sign off and the
+/// optional timelock passes. Anyone can execute it after that.
+#[derive(Debug, Clone, PartialEq, Copy, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct TransferIntent {
+    pub from: Pubkey,
+    pub to: Pubkey,
+    pub amount: u64,
+    pub nonce: u64,
+    /// Unix timestamp before which the intent cannot be executed
+    pub execute_after: i64,
+    /// [0; 32] is used as an empty slot
+    pub approvers: [Pubkey; MAX_INTENT_APPROVERS],
+    pub threshold: u8,
+    /// Bitmap over approvers
+    pub approvals: u8,
+    pub executed: u8,
+    pub bump: u8,
+    pub _padding: [u8; 4],
+    /// Owner or delegate of `from` that proposed the transfer. May cancel it.
+    pub proposer: Pubkey,
+}
+
+impl Discriminated for TransferIntent {
+    const DISCRIMINATOR: AccountDiscriminator =
+        AccountDiscriminator::TransferIntent;
+}
+
+impl TransferIntent {
+    pub fn address(from: &Pubkey, nonce: u64) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[b"intent", from.as_ref(), nonce.to_le_bytes().as_ref()],
+            &crate::ID,
+        )
+    }
+
+    pub fn size() -> usize {
+        core::mem::size_of::<Self>()
+    }
+
+    pub fn space() -> usize {
+        8 + core::mem::size_of::<Self>()
+    }
+
+    /// Number of approvals collected so far
+    pub fn approval_count(&self) -> u32 {
+        self.approvals.count_ones()
+    }
+
+    /// Discriminator check. This does not do an owner check!
+    /// If you call this function you MUST mutate the data to do an implicit
+    /// owner check.
+    pub(crate) fn checked_load_mut(
+        intent_data: &mut [u8],
+    ) -> Result<&mut TransferIntent, ProgramError> {
+        AccountLoader::checked_load_mut(intent_data)
+    }
+}
+
+/// Display metadata for a mint, at the PDA [b"metadata", mint]. Strings are
+/// utf-8 and zero-padded.
+#[derive(Debug, Clone, PartialEq, Copy, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct MintMetadata {
+    pub mint: Pubkey,
+    pub name: [u8; MAX_NAME_LEN],
+    pub symbol: [u8; MAX_SYMBOL_LEN],
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_byte_array"))]
+    pub uri: [u8; MAX_URI_LEN],
+    pub bump: u8,
+    pub _padding: [u8; 7],
+}
+
+impl Discriminated for MintMetadata {
+    const DISCRIMINATOR: AccountDiscriminator =
+        AccountDiscriminator::MintMetadata;
+}
+
+impl MintMetadata {
+    /// Off-chain decoder. Checks discriminator and length of account data
+    /// (including the discriminator) and returns a copy.
+    #[cfg(feature = "unpack")]
+    pub fn try_unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        AccountLoader::try_unpack(data)
+    }
+
+    pub fn address(mint: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"metadata", mint.as_ref()], &crate::ID)
+    }
+
+    pub fn size() -> usize {
+        core::mem::size_of::<Self>()
+    }
+
+    pub fn space() -> usize {
+        8 + core::mem::size_of::<Self>()
+    }
+
+    /// Discriminator check. This does not do an owner check!
+    /// If you call this function you MUST mutate the data to do an implicit
+    /// owner check.
+    pub(crate) fn checked_load_mut(
+        metadata_data: &mut [u8],
+    ) -> Result<&mut MintMetadata, ProgramError> {
+        AccountLoader::checked_load_mut(metadata_data)
+    }
+}
+
+#[repr(u8)]
+pub enum ListMode {
+    None = 0,
+    /// Only listed owners may send or receive
+    Allow,
+    /// Listed owners may not send or receive
+    Deny,
+}
+
+/// Allowlist or denylist of owners for a mint, at the PDA [b"list", mint].
+/// Followed by `len` owners sorted by key, with room for `capacity` in
+/// total.
+#[derive(Debug, Clone, PartialEq, Copy, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct MintList {
+    pub mint_index: u64,
+    /// May add and remove owners. [0; 32] is used as None.
+    pub authority: Pubkey,
+    pub len: u64,
+    /// See [ListMode]
+    pub mode: u8,
+    pub bump: u8,
+    pub _padding: [u8; 6],
+}
+
+impl Discriminated for MintList {
+    const DISCRIMINATOR: AccountDiscriminator = AccountDiscriminator::MintList;
+}
+
+impl MintList {
+    pub fn address(mint: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"list", mint.as_ref()], &crate::ID)
+    }
+
+    pub fn size() -> usize {
+        core::mem::size_of::<Self>()
+    }
+
+    /// Space of a list without any entries
+    pub fn space() -> usize {
+        8 + core::mem::size_of::<Self>()
+    }
+
+    /// Number of entries the list account has room for
+    pub fn capacity(data_len: usize) -> usize {
+        data_len.saturating_sub(Self::space()) / 32
+    }
+
+    /// Discriminator check. This does not do an owner check!
+    /// If you call this function you MUST mutate the data to do an implicit
+    /// owner check.
+    ///
+    /// Returns the list along with all `capacity` entry slots, of which the
+    /// first `len` are in use.
+    pub(crate) fn checked_load_mut(
+        list_data: &mut [u8],
+    ) -> Result<(&mut MintList, &mut [Pubkey]), ProgramError> {
+        AccountLoader::<MintList>::check(list_data)?;
+        let capacity = Self::capacity(list_data.len());
+
+        // Split data into discriminator, list, and entries
+        let (_disc, list_bytes) = list_data.split_at_mut(8);
+        let (list_bytes, entry_bytes) = list_bytes.split_at_mut(Self::size());
+
+        // SAFETY: list and entries are 8-byte aligned and all bit patterns
+        // are valid
+        unsafe {
+            Ok((
+                &mut *(list_bytes.as_mut_ptr() as *mut MintList),
+                core::slice::from_raw_parts_mut(
+                    entry_bytes.as_mut_ptr() as *mut Pubkey,
+                    capacity,
+                ),
+            ))
+        }
+    }
+
+    /// Finds the list for `mint_index` in `accounts`, returning it along with
+    /// its entries.
+    ///
+    /// # Safety
+    /// no one else should have a mutable view into the list's data.
+    pub(crate) unsafe fn find(
+        mint_index: u64,
+        accounts: &[NoStdAccountInfo],
+    ) -> Result<(&MintList, &[Pubkey]), ProgramError> {
+        for account in accounts {
+            if *account.owner() != crate::ID
+                || account.data_len() < Self::space()
+            {
+                continue;
+            }
+            let data = account.unchecked_borrow_data();
+            if data[0] != AccountDiscriminator::MintList as u8 {
+                continue;
+            }
+            let list = &*(data.as_ptr().add(8) as *const MintList);
+            if list.mint_index == mint_index {
+                if list.len as usize > Self::capacity(data.len()) {
+                    log::sol_log("mint list len exceeds capacity");
+                    return Err(ProgramError::InvalidAccountData);
+                }
+                let entries = core::slice::from_raw_parts(
+                    data.as_ptr().add(Self::space()) as *const Pubkey,
+                    list.len as usize,
+                );
+                return Ok((list, entries));
+            }
+        }
+        log::sol_log("mint list must be passed");
+        Err(ProgramError::NotEnoughAccountKeys)
+    }
+
+    /// Checks `owner` may hold tokens under this list
+    #[inline(always)]
+    pub(crate) fn check_allowed(
+        &self,
+        entries: &[Pubkey],
+        owner: &Pubkey,
+    ) -> ProgramResult {
+        let listed = entries.binary_search(owner).is_ok();
+        if listed != (self.mode == ListMode::Allow as u8) {
+            log::sol_log("owner is not allowed by mint list");
+            return Err(NanoTokenError::OwnerNotAllowed.into());
+        }
+        Ok(())
     }
 }
 
-#[cfg(target_os = "solana")]
+#[cfg(all(target_os = "solana", not(feature = "no-entrypoint")))]
 #[no_mangle]
 fn custom_panic(_info: &core::panic::PanicInfo<'_>) {
     log::sol_log("panicked!");
+    if let Some((op_index, tag)) = panic_context::get() {
+        log::sol_log("panicked in op: [op index, tag]");
+        log::sol_log_64(op_index, tag, 0, 0, 0);
+    }
 }
@@ -0,0 +1,106 @@
+//! Generic zero-copy account loading. Every account is
+//! `[discriminator, version, 6 bytes unused][T]`, so the checks are the same
+//! for all account types and live here.
+
+use core::marker::PhantomData;
+
+use bytemuck::Pod;
+use solana_program::{log, program_error::ProgramError};
+
+use crate::{
+    error::NanoTokenError, solana_nostd_entrypoint::NoStdAccountInfo, utils,
+    AccountDiscriminator,
+};
+
+/// An account type stored after an 8-byte discriminator
+pub trait Discriminated: Pod {
+    const DISCRIMINATOR: AccountDiscriminator;
+    /// Expected second discriminator byte, for types whose layout has
+    /// changed. None skips the check.
+    const VERSION: Option<u8> = None;
+}
+
+/// Discriminator, version, and length checks for `T`. None of the loaders do
+/// an owner check (except in paranoid builds)! Callers MUST either mutate the
+/// data, which is an implicit owner check, or check the owner themselves.
+pub struct AccountLoader<T>(PhantomData<T>);
+
+impl<T: Discriminated> AccountLoader<T> {
+    /// Checks discriminator, version, and length of `data`, in that order,
+    /// so an account with an older (shorter) layout reports that it needs
+    /// migration.
+    #[inline(always)]
+    pub fn check(data: &[u8]) -> Result<(), ProgramError> {
+        // We only need to check the first byte
+        if data.first() != Some(&(T::DISCRIMINATOR as u8)) {
+            log::sol_log("account discriminator is incorrect");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        // Older layouts are shorter and must be migrated first
+        if let Some(version) = T::VERSION {
+            if data.get(1) != Some(&version) {
+                log::sol_log("account must be migrated");
+                return Err(NanoTokenError::AccountNeedsMigration.into());
+            }
+        }
+
+        if data.len() < 8 + core::mem::size_of::<T>() {
+            log::sol_log("account data is too short");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        Ok(())
+    }
+
+    pub fn checked_load(data: &[u8]) -> Result<&T, ProgramError> {
+        Self::check(data)?;
+        // SAFETY: length was checked, account data is 8-byte aligned, and
+        // all bit patterns are valid
+        Ok(unsafe { &*(data.as_ptr().add(8) as *const T) })
+    }
+
+    pub fn checked_load_mut(data: &mut [u8]) -> Result<&mut T, ProgramError> {
+        Self::check(data)?;
+        // SAFETY: see checked_load
+        Ok(unsafe { &mut *(data.as_mut_ptr().add(8) as *mut T) })
+    }
+
+    /// Like [AccountLoader::checked_load_mut], but borrows the account's data
+    /// without refcell checks and returns a pointer, for ops where accounts
+    /// may alias.
+    ///
+    /// # Safety
+    /// no one else should have a view into this account's data.
+    pub unsafe fn unchecked_load_raw(
+        account: &NoStdAccountInfo,
+    ) -> Result<*mut T, ProgramError> {
+        utils::paranoid_check_owner(account)?;
+        let data = account.unchecked_borrow_data();
+        Self::check(data)?;
+        Ok(data.as_ptr().add(8) as *mut T)
+    }
+
+    /// Checks `data` like [AccountLoader::check] and copies `T` out of it.
+    /// The data need not be aligned, e.g. account data fetched over RPC.
+    #[cfg(feature = "unpack")]
+    pub fn try_unpack(data: &[u8]) -> Result<T, ProgramError> {
+        Self::check(data)?;
+        Ok(bytemuck::pod_read_unaligned(
+            &data[8..8 + core::mem::size_of::<T>()],
+        ))
+    }
+
+    /// # Safety
+    /// unchecked refers to refcell checks, not to discriminator checks, i.e.
+    /// memory safety. You must ensure no one else has a view into the
+    /// account's data.
+    // The returned borrow points into the runtime's account buffer, not into
+    // `account` itself, so tying it to a shared borrow is the intended shape.
+    #[allow(clippy::mut_from_ref)]
+    pub unsafe fn unchecked_load_mut(
+        account: &NoStdAccountInfo,
+    ) -> Result<&mut T, ProgramError> {
+        Ok(&mut *Self::unchecked_load_raw(account)?)
+    }
+}
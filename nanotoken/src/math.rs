@@ -0,0 +1,39 @@
+//! Checked balance and supply arithmetic. All balance and supply changes go
+//! through these, so the overflow policy is the same everywhere. They don't
+//! log, so callers add context where it helps.
+
+use crate::error::NanoTokenError;
+
+/// `balance + amount`. Balances are bounded by supply, so this should never
+/// overflow, but if it does it is an error rather than a wrap or a silent cap.
+#[inline(always)]
+pub fn add_balance(balance: u64, amount: u64) -> Result<u64, NanoTokenError> {
+    balance
+        .checked_add(amount)
+        .ok_or(NanoTokenError::BalanceOverflow)
+}
+
+/// `balance - amount`
+#[inline(always)]
+pub fn sub_balance(balance: u64, amount: u64) -> Result<u64, NanoTokenError> {
+    balance
+        .checked_sub(amount)
+        .ok_or(NanoTokenError::InsufficientTokenBalance)
+}
+
+/// `supply + amount`
+#[inline(always)]
+pub fn add_supply(supply: u64, amount: u64) -> Result<u64, NanoTokenError> {
+    supply
+        .checked_add(amount)
+        .ok_or(NanoTokenError::SupplyOverflow)
+}
+
+/// `supply - amount`. Supply covers every balance, so this only fails if
+/// state is corrupt.
+#[inline(always)]
+pub fn sub_supply(supply: u64, amount: u64) -> Result<u64, NanoTokenError> {
+    supply
+        .checked_sub(amount)
+        .ok_or(NanoTokenError::SupplyUnderflow)
+}
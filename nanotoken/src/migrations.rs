@@ -0,0 +1,229 @@
+//! Account layout migrations
+//!
+//! Each versioned account type has a table indexed by version byte. Entry
+//! `v` holds the data size (excluding the 8 byte discriminator) of version
+//! `v` and the migrator that fills in the fields version `v + 1` adds. An
+//! account is upgraded one version at a time: check its size, realloc to the
+//! next version's size (growth is zeroed), run the migrator, bump the version
+//! byte. A new layout only appends an entry to its table.
+//!
+//! Token accounts may be longer than their layout, see Realloc. The
+//! extension space past the layout moves to the end of the next version's
+//! layout unchanged, and the new fields are zeroed before the migrator runs.
+//!
+//! User accounts (mints, token accounts) are upgraded permissionlessly by
+//! MigrateAccount. Program state (config, vault info) is upgraded by the
+//! config authority with MigrateState.
+
+use core::mem::size_of;
+
+use solana_program::{
+    clock::Clock, entrypoint::ProgramResult, log, program_error::ProgramError,
+    sysvar::Sysvar,
+};
+
+use crate::{
+    consts::{
+        CONFIG_VERSION, MINT_V0_SIZE, MINT_V1_SIZE, MINT_VERSION,
+        TOKEN_ACCOUNT_V0_SIZE, TOKEN_ACCOUNT_V1_SIZE, TOKEN_ACCOUNT_VERSION,
+        VAULT_INFO_V0_SIZE, VAULT_INFO_VERSION,
+    },
+    solana_nostd_entrypoint::NoStdAccountInfo,
+    utils::realloc_funded_by_payer,
+    AccountDiscriminator, Mint, ProgramConfig, TokenAccount, VaultInfo,
+};
+
+/// Fills in the fields added by the next version. Runs after the account was
+/// reallocated to the next version's size.
+///
+/// # Safety
+/// No one must hold a view into account data.
+pub(crate) type Migrator = unsafe fn(&NoStdAccountInfo) -> ProgramResult;
+
+pub(crate) struct Migration {
+    /// Data size of this version
+    pub size: usize,
+    /// Upgrades this version to the next
+    pub migrate: Migrator,
+}
+
+pub(crate) struct MigrationTable {
+    pub disc: u8,
+    /// Data size of the latest version
+    pub size: usize,
+    /// Indexed by version, so its length is the latest version
+    pub steps: &'static [Migration],
+}
+
+pub(crate) const MINT: MigrationTable = MigrationTable {
+    disc: AccountDiscriminator::Mint as u8,
+    size: size_of::<Mint>(),
+    steps: &MINT_STEPS,
+};
+const MINT_STEPS: [Migration; MINT_VERSION as usize] = [
+    Migration {
+        size: MINT_V0_SIZE,
+        migrate: zero_defaults,
+    },
+    // No royalty
+    Migration {
+        size: MINT_V1_SIZE,
+        migrate: zero_defaults,
+    },
+];
+
+pub(crate) const TOKEN_ACCOUNT: MigrationTable = MigrationTable {
+    disc: AccountDiscriminator::Token as u8,
+    size: size_of::<TokenAccount>(),
+    steps: &TOKEN_ACCOUNT_STEPS,
+};
+const TOKEN_ACCOUNT_STEPS: [Migration; TOKEN_ACCOUNT_VERSION as usize] = [
+    Migration {
+        size: TOKEN_ACCOUNT_V0_SIZE,
+        migrate: token_account_v0,
+    },
+    // Not timelocked
+    Migration {
+        size: TOKEN_ACCOUNT_V1_SIZE,
+        migrate: zero_defaults,
+    },
+];
+
+pub(crate) const CONFIG: MigrationTable = MigrationTable {
+    disc: AccountDiscriminator::Config as u8,
+    size: size_of::<ProgramConfig>(),
+    steps: &CONFIG_STEPS,
+};
+const CONFIG_STEPS: [Migration; CONFIG_VERSION as usize] = [];
+
+pub(crate) const VAULT_INFO: MigrationTable = MigrationTable {
+    disc: AccountDiscriminator::VaultInfo as u8,
+    size: size_of::<VaultInfo>(),
+    steps: &VAULT_INFO_STEPS,
+};
+const VAULT_INFO_STEPS: [Migration; VAULT_INFO_VERSION as usize] =
+    [Migration {
+        size: VAULT_INFO_V0_SIZE,
+        migrate: vault_info_v0,
+    }];
+
+/// Upgraded by MigrateAccount
+pub(crate) const USER_ACCOUNTS: [MigrationTable; 2] = [MINT, TOKEN_ACCOUNT];
+/// Upgraded by MigrateState
+pub(crate) const PROGRAM_STATE: [MigrationTable; 2] = [CONFIG, VAULT_INFO];
+
+// Layouts only ever grow
+const _: () = {
+    let tables = [MINT, TOKEN_ACCOUNT, CONFIG, VAULT_INFO];
+    let mut t = 0;
+    while t < tables.len() {
+        let steps = tables[t].steps;
+        let mut v = 0;
+        while v < steps.len() {
+            let next = if v + 1 < steps.len() {
+                steps[v + 1].size
+            } else {
+                tables[t].size
+            };
+            assert!(steps[v].size < next);
+            v += 1;
+        }
+        t += 1;
+    }
+};
+
+/// New fields default to zero (None/disabled), which realloc already did
+unsafe fn zero_defaults(_account: &NoStdAccountInfo) -> ProgramResult {
+    Ok(())
+}
+
+unsafe fn token_account_v0(account: &NoStdAccountInfo) -> ProgramResult {
+    let now = Clock::get()?.unix_timestamp;
+    let token_account = &mut *(account
+        .unchecked_borrow_mut_data()
+        .as_mut_ptr()
+        .add(8) as *mut TokenAccount);
+    // The original rent payer is unknown, so rent goes back to the owner if
+    // the account is ever collected
+    token_account.rent_payer = token_account.owner;
+    token_account.created_at = now;
+    Ok(())
+}
+
+/// Stores the vault bump, which v0 only used at creation
+unsafe fn vault_info_v0(account: &NoStdAccountInfo) -> ProgramResult {
+    let vault_info = &mut *(account
+        .unchecked_borrow_mut_data()
+        .as_mut_ptr()
+        .add(8) as *mut VaultInfo);
+    // The target_os = "solana" impl is alloc-free
+    vault_info.vault_bump = VaultInfo::vault(&vault_info.tokenkeg_mint).1;
+    Ok(())
+}
+
+/// Upgrades `account` to the latest layout of its type, which must have a
+/// table in `tables`. Payer tops up rent. Accounts already on the latest
+/// layout are left untouched.
+pub(crate) fn migrate(
+    account: &NoStdAccountInfo,
+    payer: &NoStdAccountInfo,
+    tables: &[MigrationTable],
+) -> ProgramResult {
+    let (disc, mut version) = {
+        let data = account
+            .try_borrow_data()
+            .ok_or(ProgramError::AccountBorrowFailed)?;
+        match data.get(..2) {
+            Some(&[disc, version]) => (disc, version),
+            _ => {
+                log::sol_log("account is not a nanotoken account");
+                return Err(ProgramError::InvalidAccountData);
+            }
+        }
+    };
+    let Some(table) = tables
+        .iter()
+        .find(|table| table.disc == disc)
+    else {
+        log::sol_log("account does not support migration");
+        return Err(ProgramError::InvalidAccountData);
+    };
+
+    while let Some(step) = table.steps.get(version as usize) {
+        let Some(extension_len) = account
+            .data_len()
+            .checked_sub(8 + step.size)
+        else {
+            log::sol_log("account data len is too small for its version");
+            return Err(ProgramError::InvalidAccountData);
+        };
+        let next_size = table
+            .steps
+            .get(version as usize + 1)
+            .map_or(table.size, |next| next.size);
+
+        // SAFETY: no one holds a view into account data or lamports
+        unsafe {
+            realloc_funded_by_payer(
+                account,
+                payer,
+                8 + next_size + extension_len,
+            )?;
+            if extension_len > 0 {
+                let data = account.unchecked_borrow_mut_data();
+                let (end, next_end) = (8 + step.size, 8 + next_size);
+                data.copy_within(end..end + extension_len, next_end);
+                data[end..next_end].fill(0);
+            }
+            (step.migrate)(account)?;
+            version += 1;
+            account.unchecked_borrow_mut_data()[1] = version;
+        }
+    }
+
+    if version as usize != table.steps.len() {
+        log::sol_log("account version is newer than the program");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(())
+}
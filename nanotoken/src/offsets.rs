@@ -0,0 +1,136 @@
+//! Byte offsets of every account field, and discriminator values
+//!
+//! Offsets are into account data, i.e. they include the 8 byte
+//! discriminator, so they can be used directly for raw writes and RPC
+//! `memcmp` filters. For example, all token accounts of an owner are matched
+//! by [TOKEN_DISCRIMINATOR] at [DISCRIMINATOR_OFFSET] and the owner at
+//! [TOKEN_ACCOUNT_OWNER_OFFSET].
+//!
+//! Accounts are read and written in place, and some writes go through raw
+//! offsets, so a reordered or resized field would silently corrupt state.
+//! Every offset here is checked at compile time along with size and
+//! alignment.
+
+use core::mem::{align_of, offset_of, size_of};
+
+use crate::{
+    AccountDiscriminator, Mint, MintList, MintMetadata, ProgramConfig,
+    TokenAccount, TransferIntent, VaultInfo,
+};
+
+/// Discriminator and version, followed by 6 unused bytes
+pub const DISC_LEN: usize = 8;
+pub const DISCRIMINATOR_OFFSET: usize = 0;
+/// Layout version, see [crate::consts::MINT_VERSION] and friends
+pub const VERSION_OFFSET: usize = 1;
+
+/// Discriminator byte values, see [AccountDiscriminator]
+pub const CONFIG_DISCRIMINATOR: u8 = AccountDiscriminator::Config as u8;
+pub const MINT_DISCRIMINATOR: u8 = AccountDiscriminator::Mint as u8;
+pub const TOKEN_DISCRIMINATOR: u8 = AccountDiscriminator::Token as u8;
+pub const VAULT_INFO_DISCRIMINATOR: u8 = AccountDiscriminator::VaultInfo as u8;
+pub const TRANSFER_INTENT_DISCRIMINATOR: u8 =
+    AccountDiscriminator::TransferIntent as u8;
+pub const MINT_METADATA_DISCRIMINATOR: u8 =
+    AccountDiscriminator::MintMetadata as u8;
+pub const MINT_LIST_DISCRIMINATOR: u8 = AccountDiscriminator::MintList as u8;
+
+macro_rules! layout {
+    (
+        $ty:ty, size = $size:expr, align = $align:expr,
+        { $($field:ident => $name:ident = $offset:expr,)* }
+    ) => {
+        $(
+            pub const $name: usize = $offset;
+            const _: () = assert!(DISC_LEN + offset_of!($ty, $field) == $name);
+        )*
+        const _: () = assert!(size_of::<$ty>() == $size);
+        const _: () = assert!(align_of::<$ty>() == $align);
+    };
+}
+
+layout!(ProgramConfig, size = 128, align = 8, {
+    mint_index => CONFIG_MINT_INDEX_OFFSET = 8,
+    authority => CONFIG_AUTHORITY_OFFSET = 16,
+    pending_authority => CONFIG_PENDING_AUTHORITY_OFFSET = 48,
+    gc_min_age => CONFIG_GC_MIN_AGE_OFFSET = 80,
+    gc_bounty => CONFIG_GC_BOUNTY_OFFSET = 88,
+    rent_destination => CONFIG_RENT_DESTINATION_OFFSET = 96,
+    features => CONFIG_FEATURES_OFFSET = 128,
+});
+
+layout!(Mint, size = 248, align = 8, {
+    mint_index => MINT_INDEX_OFFSET = 8,
+    authority => MINT_AUTHORITY_OFFSET = 16,
+    supply => MINT_SUPPLY_OFFSET = 48,
+    decimals => MINT_DECIMALS_OFFSET = 56,
+    paused => MINT_PAUSED_OFFSET = 57,
+    list_mode => MINT_LIST_MODE_OFFSET = 58,
+    freeze_authority => MINT_FREEZE_AUTHORITY_OFFSET = 64,
+    transfer_fee_authority => MINT_TRANSFER_FEE_AUTHORITY_OFFSET = 96,
+    transfer_fee_bps => MINT_TRANSFER_FEE_BPS_OFFSET = 128,
+    max_transfer_fee => MINT_MAX_TRANSFER_FEE_OFFSET = 136,
+    pause_authority => MINT_PAUSE_AUTHORITY_OFFSET = 144,
+    max_supply => MINT_MAX_SUPPLY_OFFSET = 176,
+    clawback_authority => MINT_CLAWBACK_AUTHORITY_OFFSET = 184,
+    royalty_bps => MINT_ROYALTY_BPS_OFFSET = 216,
+    royalty_recipient => MINT_ROYALTY_RECIPIENT_OFFSET = 224,
+});
+
+layout!(TokenAccount, size = 176, align = 8, {
+    owner => TOKEN_ACCOUNT_OWNER_OFFSET = 8,
+    mint => TOKEN_ACCOUNT_MINT_OFFSET = 40,
+    balance => TOKEN_ACCOUNT_BALANCE_OFFSET = 48,
+    delegate => TOKEN_ACCOUNT_DELEGATE_OFFSET = 56,
+    delegated_amount => TOKEN_ACCOUNT_DELEGATED_AMOUNT_OFFSET = 88,
+    state => TOKEN_ACCOUNT_STATE_OFFSET = 96,
+    bump => TOKEN_ACCOUNT_BUMP_OFFSET = 97,
+    withheld_fees => TOKEN_ACCOUNT_WITHHELD_FEES_OFFSET = 104,
+    transfer_fee_bps => TOKEN_ACCOUNT_TRANSFER_FEE_BPS_OFFSET = 112,
+    max_transfer_fee => TOKEN_ACCOUNT_MAX_TRANSFER_FEE_OFFSET = 120,
+    permit_nonce => TOKEN_ACCOUNT_PERMIT_NONCE_OFFSET = 128,
+    rent_payer => TOKEN_ACCOUNT_RENT_PAYER_OFFSET = 136,
+    created_at => TOKEN_ACCOUNT_CREATED_AT_OFFSET = 168,
+    unlock_at => TOKEN_ACCOUNT_UNLOCK_AT_OFFSET = 176,
+});
+
+layout!(VaultInfo, size = 98, align = 1, {
+    tokenkeg_mint => VAULT_INFO_TOKENKEG_MINT_OFFSET = 8,
+    tokenkeg_vault => VAULT_INFO_TOKENKEG_VAULT_OFFSET = 40,
+    nanotoken_mint => VAULT_INFO_NANOTOKEN_MINT_OFFSET = 72,
+    info_bump => VAULT_INFO_BUMP_OFFSET = 104,
+    vault_bump => VAULT_INFO_VAULT_BUMP_OFFSET = 105,
+});
+
+layout!(TransferIntent, size = 224, align = 8, {
+    from => TRANSFER_INTENT_FROM_OFFSET = 8,
+    to => TRANSFER_INTENT_TO_OFFSET = 40,
+    amount => TRANSFER_INTENT_AMOUNT_OFFSET = 72,
+    nonce => TRANSFER_INTENT_NONCE_OFFSET = 80,
+    execute_after => TRANSFER_INTENT_EXECUTE_AFTER_OFFSET = 88,
+    approvers => TRANSFER_INTENT_APPROVERS_OFFSET = 96,
+    threshold => TRANSFER_INTENT_THRESHOLD_OFFSET = 192,
+    approvals => TRANSFER_INTENT_APPROVALS_OFFSET = 193,
+    executed => TRANSFER_INTENT_EXECUTED_OFFSET = 194,
+    bump => TRANSFER_INTENT_BUMP_OFFSET = 195,
+    proposer => TRANSFER_INTENT_PROPOSER_OFFSET = 200,
+});
+
+layout!(MintMetadata, size = 344, align = 1, {
+    mint => MINT_METADATA_MINT_OFFSET = 8,
+    name => MINT_METADATA_NAME_OFFSET = 40,
+    symbol => MINT_METADATA_SYMBOL_OFFSET = 72,
+    uri => MINT_METADATA_URI_OFFSET = 88,
+    bump => MINT_METADATA_BUMP_OFFSET = 344,
+});
+
+layout!(MintList, size = 56, align = 8, {
+    mint_index => LIST_MINT_INDEX_OFFSET = 8,
+    authority => LIST_AUTHORITY_OFFSET = 16,
+    len => LIST_LEN_OFFSET = 48,
+    mode => LIST_MODE_OFFSET = 56,
+    bump => LIST_BUMP_OFFSET = 57,
+});
+
+/// The list's 32 byte entries follow the header
+pub const LIST_ENTRIES_OFFSET: usize = DISC_LEN + size_of::<MintList>();
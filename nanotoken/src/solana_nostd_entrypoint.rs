@@ -90,7 +90,7 @@ pub unsafe fn deserialize_nostd<'a, const MAX_ACCOUNTS: usize>(
                 input.add(offset) as *mut _;
 
             offset += size_of::<NoStdAccountInfoInner>();
-            offset += (*account_info).data_len;
+            offset += (*account_info).data_len as usize;
             offset += MAX_PERMITTED_DATA_INCREASE;
             offset += (offset as *const u8).align_offset(BPF_ALIGN_OF_U128);
             offset += size_of::<u64>(); // MAGNETAR FIELDS: ignore rent epoch
@@ -159,7 +159,7 @@ pub unsafe fn deserialize_nostd_no_dup<'a, const MAX_ACCOUNTS: usize>(
                 core::mem::transmute::<&mut u8, _>(&mut *(input.add(offset)));
             // bytemuck::try_from_bytes_mut(from_raw_parts_mut(input.add(offset), 88)).unwrap();
             offset += size_of::<NoStdAccountInfoInner>();
-            offset += account_info.data_len;
+            offset += account_info.data_len as usize;
             offset += MAX_PERMITTED_DATA_INCREASE;
             offset += (offset as *const u8).align_offset(BPF_ALIGN_OF_U128);
             offset += size_of::<u64>(); // MAGNETAR FIELDS: ignore rent epoch
@@ -238,7 +238,9 @@ pub struct NoStdAccountInfoInner {
 
     /// The lamports in the account.  Modifiable by programs.
     lamports: u64,
-    data_len: usize,
+    /// u64 rather than usize, as serialized by the vm, so the layout (and
+    /// Pod) holds on 32-bit targets like wasm32
+    data_len: u64,
 }
 
 #[derive(Debug)]
@@ -472,7 +474,7 @@ impl NoStdAccountInfo {
     }
     #[inline(always)]
     pub fn data_len(&self) -> usize {
-        unsafe { (*self.inner).data_len }
+        unsafe { (*self.inner).data_len as usize }
     }
 
     pub unsafe fn unchecked_borrow_lamports(&self) -> &u64 {
@@ -482,10 +484,34 @@ impl NoStdAccountInfo {
         &mut (*self.inner).lamports
     }
     pub unsafe fn unchecked_borrow_data(&self) -> &[u8] {
-        core::slice::from_raw_parts(self.data_ptr(), (*self.inner).data_len)
+        core::slice::from_raw_parts(self.data_ptr(), self.data_len())
     }
     pub unsafe fn unchecked_borrow_mut_data(&self) -> &mut [u8] {
-        core::slice::from_raw_parts_mut(self.data_ptr(), (*self.inner).data_len)
+        core::slice::from_raw_parts_mut(self.data_ptr(), self.data_len())
+    }
+
+    /// Resizes account data in place, zeroing any new bytes. The runtime
+    /// checks ownership, that data grew by at most
+    /// MAX_PERMITTED_DATA_INCREASE during this instruction, and rent
+    /// exemption.
+    ///
+    /// # Safety
+    /// No one must hold a view into this account's data.
+    pub unsafe fn unchecked_realloc(&self, new_len: usize) {
+        let old_len = self.data_len();
+        (*self.inner).data_len = new_len as u64;
+        if new_len > old_len {
+            core::ptr::write_bytes(
+                self.data_ptr().add(old_len),
+                0,
+                new_len - old_len,
+            );
+        }
+    }
+
+    /// Whether any borrow (mutable or immutable) of lamports or data is held
+    pub fn is_borrowed(&self) -> bool {
+        unsafe { (*self.inner).borrow_state != 0 }
     }
 
     pub fn try_borrow_lamports(&self) -> Option<Ref<u64>> {
@@ -556,7 +582,7 @@ impl NoStdAccountInfo {
             value: unsafe {
                 core::slice::from_raw_parts(
                     self.data_ptr(),
-                    (*self.inner).data_len,
+                    self.data_len(),
                 )
             },
             state: unsafe {
@@ -582,7 +608,7 @@ impl NoStdAccountInfo {
             value: unsafe {
                 core::slice::from_raw_parts_mut(
                     self.data_ptr(),
-                    (*self.inner).data_len,
+                    self.data_len(),
                 )
             },
             state: unsafe {
@@ -1,13 +1,31 @@
-use crate::solana_nostd_entrypoint::{AccountInfoC, InstructionC};
+use crate::solana_nostd_entrypoint::{
+    AccountInfoC, InstructionC, NoStdAccountInfo,
+};
 use solana_program::{
-    entrypoint::ProgramResult, log, program_error::ProgramError,
-    pubkey::Pubkey, rent::Rent, sysvar::Sysvar,
+    ed25519_program, entrypoint::ProgramResult, log,
+    program_error::ProgramError, pubkey::Pubkey, rent::Rent,
+    sysvar::instructions::ID as INSTRUCTIONS_SYSVAR, sysvar::Sysvar,
+};
+
+use crate::{
+    consts::{NATIVE_MINT_INDEX, SPL_MEMO_PROGRAMS},
+    error::NanoTokenError,
+    offsets::{
+        DISCRIMINATOR_OFFSET, TOKEN_ACCOUNT_BALANCE_OFFSET,
+        TOKEN_ACCOUNT_MINT_OFFSET, TOKEN_DISCRIMINATOR,
+    },
+    TokenAccount,
 };
 
+#[cfg(feature = "serde")]
+pub(crate) mod serde_byte_array;
 pub mod spl_token_utils;
 
 /// Creates a new pda.
 ///
+/// Also works for keypair accounts that signed the transaction, in which case
+/// `pda_seeds` should be empty.
+///
 /// # SAFETY:
 /// Reads lamports from `target_account`. Som no one must hold
 /// a mutable reference to its lamports elsewhere.
@@ -21,6 +39,7 @@ pub unsafe fn create_pda_funded_by_payer(
     payer: AccountInfoC,
 ) -> ProgramResult {
     let rent_sysvar = Rent::get()?;
+    let num_signer_seeds = (!pda_seeds.is_empty()) as u64;
     let target_account_lamports = unsafe { *target_account.lamports };
     if target_account_lamports == 0 {
         let rent_due = rent_sysvar
@@ -83,7 +102,7 @@ pub unsafe fn create_pda_funded_by_payer(
                 create_account_account_infos.as_ptr() as *const u8,
                 3,
                 cpi_seeds.as_ptr() as *const u8,
-                1,
+                num_signer_seeds,
             );
         }
         #[cfg(not(target_os = "solana"))]
@@ -91,6 +110,7 @@ pub unsafe fn create_pda_funded_by_payer(
             &create_account_instruction,
             &create_account_account_infos,
             cpi_seeds,
+            num_signer_seeds,
         ));
     } else {
         // Can't use create_account on accounts with nonzero lamports.
@@ -186,7 +206,7 @@ pub unsafe fn create_pda_funded_by_payer(
                 allocate_account_infos.as_ptr() as *const u8,
                 1,
                 cpi_seeds.as_ptr() as *const u8,
-                1,
+                num_signer_seeds,
             );
         }
         target_account.data_len = space;
@@ -195,6 +215,7 @@ pub unsafe fn create_pda_funded_by_payer(
             &allocate_instruction,
             &allocate_account_infos,
             cpi_seeds,
+            num_signer_seeds,
         ));
 
         // 3) assign our program as the owner
@@ -231,7 +252,7 @@ pub unsafe fn create_pda_funded_by_payer(
                 assign_account_infos.as_ptr() as *const u8,
                 1,
                 cpi_seeds.as_ptr() as *const u8,
-                1,
+                num_signer_seeds,
             );
         }
         #[cfg(not(target_os = "solana"))]
@@ -239,13 +260,388 @@ pub unsafe fn create_pda_funded_by_payer(
             &assign_instruction,
             &assign_account_infos,
             cpi_seeds,
+            num_signer_seeds,
         ));
     }
 
     Ok(())
 }
 
-#[allow(unused)]
+/// Transfers lamports from a system-owned signer via the system program.
+///
+/// # SAFETY:
+/// No one must hold a mutable reference to either account's lamports.
+#[inline(always)]
+pub unsafe fn transfer_lamports_from_signer(
+    from: AccountInfoC,
+    to: AccountInfoC,
+    lamports: u64,
+) {
+    // 12 bytes = [4 byte enum disc][8 byte lamports]
+    let mut transfer_ix_data = [0; 12];
+    // Transfer discriminant is 2_u32 = [2, 0, 0, 0]
+    transfer_ix_data[0] = 2;
+
+    // Write lamports as u64 le bytes
+    core::ptr::copy_nonoverlapping(
+        &lamports as *const u64 as *const u8,
+        transfer_ix_data.as_mut_ptr().add(4),
+        8,
+    );
+
+    // Instruction accounts: from, to
+    let instruction_accounts = [from.to_meta_c(), to.to_meta_c()];
+
+    // Build instruction
+    let transfer_instruction = InstructionC {
+        data: transfer_ix_data.as_ptr(),
+        data_len: 12,
+        accounts: instruction_accounts.as_ptr(),
+        accounts_len: 2,
+        program_id: &solana_program::system_program::ID,
+    };
+    let transfer_account_infos = [from, to];
+    let cpi_seeds: &[&[&[u8]]] = &[];
+    #[cfg(target_os = "solana")]
+    unsafe {
+        solana_program::syscalls::sol_invoke_signed_c(
+            (&transfer_instruction) as *const InstructionC as *const u8,
+            transfer_account_infos.as_ptr() as *const u8,
+            2,
+            cpi_seeds.as_ptr() as *const u8,
+            0,
+        );
+    }
+    #[cfg(not(target_os = "solana"))]
+    core::hint::black_box((
+        &transfer_instruction,
+        &transfer_account_infos,
+        cpi_seeds,
+    ));
+}
+
+/// Reallocates a program-owned account to `new_len`, topping up rent from
+/// payer. Growth is zeroed.
+///
+/// Lamports backing the balance of a native token account are not rent, so
+/// they don't count towards `new_len`'s minimum balance.
+///
+/// # SAFETY:
+/// No one must hold a view into account data or lamports.
+#[inline(always)]
+pub unsafe fn realloc_funded_by_payer(
+    account: &NoStdAccountInfo,
+    payer: &NoStdAccountInfo,
+    new_len: usize,
+) -> ProgramResult {
+    let rent_lamports = account
+        .unchecked_borrow_lamports()
+        .saturating_sub(native_balance(account.unchecked_borrow_data()));
+    let rent_due = Rent::get()?
+        .minimum_balance(new_len)
+        .saturating_sub(rent_lamports);
+    if rent_due > 0 {
+        transfer_lamports_from_signer(
+            payer.to_info_c(),
+            account.to_info_c(),
+            rent_due,
+        );
+    }
+    account.unchecked_realloc(new_len);
+    Ok(())
+}
+
+/// The balance of a native token account, or zero for any other account
+#[inline(always)]
+fn native_balance(data: &[u8]) -> u64 {
+    let read_u64 = |offset: usize| {
+        data.get(offset..offset + 8)
+            .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+    };
+    match data.get(DISCRIMINATOR_OFFSET) {
+        Some(&TOKEN_DISCRIMINATOR)
+            if read_u64(TOKEN_ACCOUNT_MINT_OFFSET)
+                == Some(NATIVE_MINT_INDEX) =>
+        {
+            read_u64(TOKEN_ACCOUNT_BALANCE_OFFSET).unwrap_or(0)
+        }
+        _ => 0,
+    }
+}
+
+/// Native token account balances are backed 1:1 by lamports held in the
+/// account itself, so lamports move along with the balance.
+///
+/// # SAFETY:
+/// No one must hold a view into either account's lamports.
+#[inline(always)]
+pub unsafe fn move_native_lamports(
+    from: &NoStdAccountInfo,
+    to: &NoStdAccountInfo,
+    amount: u64,
+) {
+    if solana_program::program_memory::sol_memcmp(
+        from.key().as_ref(),
+        to.key().as_ref(),
+        32,
+    ) != 0
+    {
+        *from.unchecked_borrow_mut_lamports() -= amount;
+        *to.unchecked_borrow_mut_lamports() += amount;
+    }
+}
+
+/// Finds the instructions sysvar, which may be passed anywhere in `accounts`.
+#[inline(always)]
+pub fn find_instructions_sysvar(
+    accounts: &[NoStdAccountInfo],
+) -> Option<&NoStdAccountInfo> {
+    accounts.iter().find(|a| {
+        solana_program::program_memory::sol_memcmp(
+            a.key().as_ref(),
+            INSTRUCTIONS_SYSVAR.as_ref(),
+            32,
+        ) == 0
+    })
+}
+
+/// Number of instructions in serialized instructions sysvar data.
+///
+/// solana_program's sysvar helpers allocate, so the serialized layout is read
+/// directly:
+///
+/// [u16 num_ixs][u16 offset; num_ixs]
+/// then at each offset:
+/// [u16 num_accounts][(u8 flags, [u8; 32] key); num_accounts][[u8; 32] program]
+/// [u16 data_len][data]
+#[inline(always)]
+pub fn num_instructions(ixs_sysvar_data: &[u8]) -> Option<usize> {
+    read_u16(ixs_sysvar_data, 0)
+}
+
+/// Program id and data of the instruction at `index` in serialized
+/// instructions sysvar data. See [num_instructions] for the layout.
+#[inline(always)]
+pub fn instruction_at(
+    ixs_sysvar_data: &[u8],
+    index: usize,
+) -> Option<(&[u8], &[u8])> {
+    let ix_offset = read_u16(ixs_sysvar_data, 2 + 2 * index)?;
+    let num_accounts = read_u16(ixs_sysvar_data, ix_offset)?;
+    let program_offset = ix_offset + 2 + num_accounts * 33;
+    let program_id =
+        ixs_sysvar_data.get(program_offset..program_offset + 32)?;
+    let data_len = read_u16(ixs_sysvar_data, program_offset + 32)?;
+    let data_offset = program_offset + 34;
+    let data = ixs_sysvar_data.get(data_offset..data_offset + data_len)?;
+    Some((program_id, data))
+}
+
+#[inline(always)]
+pub(crate) fn read_u16(data: &[u8], offset: usize) -> Option<usize> {
+    let bytes = data.get(offset..offset + 2)?;
+    Some(u16::from_le_bytes([bytes[0], bytes[1]]) as usize)
+}
+
+/// Checks that the transaction includes an SPL memo instruction, by reading
+/// the instructions sysvar. The sysvar may be passed anywhere in `accounts`.
+pub fn check_memo_present(accounts: &[NoStdAccountInfo]) -> ProgramResult {
+    let Some(ixs_sysvar) = find_instructions_sysvar(accounts) else {
+        log::sol_log("memo required but instructions sysvar was not passed");
+        return Err(NanoTokenError::MemoRequired.into());
+    };
+    let data = ixs_sysvar
+        .try_borrow_data()
+        .ok_or(ProgramError::AccountBorrowFailed)?;
+
+    let num_ixs =
+        num_instructions(&data).ok_or(ProgramError::InvalidAccountData)?;
+    for i in 0..num_ixs {
+        let (program_id, _data) =
+            instruction_at(&data, i).ok_or(ProgramError::InvalidAccountData)?;
+
+        if SPL_MEMO_PROGRAMS.iter().any(|memo| {
+            solana_program::program_memory::sol_memcmp(
+                program_id,
+                memo.as_ref(),
+                32,
+            ) == 0
+        }) {
+            return Ok(());
+        }
+    }
+
+    log::sol_log("memo required for incoming transfer");
+    Err(NanoTokenError::MemoRequired.into())
+}
+
+/// Checks that the transaction includes an ed25519 program instruction that
+/// verified `signer`'s signature over `message`. The runtime verifies all
+/// precompile instructions before executing the transaction, so finding one is
+/// enough.
+///
+/// Only signatures whose pubkey, message, and signature all live in the
+/// ed25519 instruction itself are considered, so the data read here is
+/// exactly the data that was verified.
+///
+/// ed25519 instruction data layout:
+///
+/// [u8 num_signatures][u8 padding]
+/// then for each signature, 7 u16s:
+/// [signature_offset][signature_ix_index][pubkey_offset][pubkey_ix_index]
+/// [message_offset][message_size][message_ix_index]
+pub fn check_ed25519_signed(
+    ixs_sysvar: &NoStdAccountInfo,
+    signer: &Pubkey,
+    message: &[u8],
+) -> ProgramResult {
+    /// Instruction index meaning "this instruction"
+    const CURRENT_IX: usize = u16::MAX as usize;
+
+    let data = ixs_sysvar
+        .try_borrow_data()
+        .ok_or(ProgramError::AccountBorrowFailed)?;
+
+    let num_ixs =
+        num_instructions(&data).ok_or(ProgramError::InvalidAccountData)?;
+    for i in 0..num_ixs {
+        let (program_id, ix_data) =
+            instruction_at(&data, i).ok_or(ProgramError::InvalidAccountData)?;
+        if solana_program::program_memory::sol_memcmp(
+            program_id,
+            ed25519_program::ID.as_ref(),
+            32,
+        ) != 0
+        {
+            continue;
+        }
+
+        let num_signatures = ix_data.first().copied().unwrap_or(0) as usize;
+        for j in 0..num_signatures {
+            let offsets = 2 + 14 * j;
+            let field = |k: usize| read_u16(ix_data, offsets + 2 * k);
+            let (
+                Some(signature_ix),
+                Some(pubkey_offset),
+                Some(pubkey_ix),
+                Some(message_offset),
+                Some(message_size),
+                Some(message_ix),
+            ) = (field(1), field(2), field(3), field(4), field(5), field(6))
+            else {
+                break;
+            };
+            if signature_ix != CURRENT_IX
+                || pubkey_ix != CURRENT_IX
+                || message_ix != CURRENT_IX
+                || message_size != message.len()
+            {
+                continue;
+            }
+            let (Some(signed_pubkey), Some(signed_message)) = (
+                ix_data.get(pubkey_offset..pubkey_offset + 32),
+                ix_data.get(message_offset..message_offset + message_size),
+            ) else {
+                continue;
+            };
+            if solana_program::program_memory::sol_memcmp(
+                signed_pubkey,
+                signer.as_ref(),
+                32,
+            ) == 0
+                && solana_program::program_memory::sol_memcmp(
+                    signed_message,
+                    message,
+                    message.len(),
+                ) == 0
+            {
+                return Ok(());
+            }
+        }
+    }
+
+    log::sol_log("no ed25519 signature found for permit");
+    Err(NanoTokenError::InvalidPermit.into())
+}
+
+/// Checks that `authority` signed this invocation and is the `expected`
+/// authority.
+///
+/// The runtime sets `is_signer` for PDAs that sign via `invoke_signed`, so
+/// program-owned authorities (e.g. a governance PDA) pass this check the same
+/// way a keypair does. There is no separate PDA path.
+#[inline(always)]
+pub fn check_authority(
+    authority: &NoStdAccountInfo,
+    expected: &Pubkey,
+) -> ProgramResult {
+    if !authority.is_signer() {
+        log::sol_log("authority must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if solana_program::program_memory::sol_memcmp(
+        authority.key().as_ref(),
+        expected.as_ref(),
+        32,
+    ) != 0
+    {
+        log::sol_log("incorrect authority");
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    Ok(())
+}
+
+/// With `paranoid`, explicitly checks we own `account`. Normally this is
+/// implied by the runtime rejecting writes to accounts we don't own.
+#[inline(always)]
+pub(crate) fn paranoid_check_owner(
+    account: &NoStdAccountInfo,
+) -> ProgramResult {
+    if cfg!(feature = "paranoid") && *account.owner() != crate::ID {
+        log::sol_log("paranoid: account has incorrect owner");
+        return Err(ProgramError::IllegalOwner);
+    }
+    Ok(())
+}
+
+/// With `paranoid`, re-derives the pda for `seeds` (without the bump) and
+/// checks `account` is at the canonical address. Normally creation with the
+/// client's bump is enough, since the seeds must sign for `account`.
+#[inline(always)]
+pub(crate) fn paranoid_check_pda(
+    seeds: &[&[u8]],
+    account: &NoStdAccountInfo,
+) -> ProgramResult {
+    if cfg!(feature = "paranoid") {
+        check_pda_address(seeds, &crate::ID, account.key())?;
+    }
+    Ok(())
+}
+
+/// With `paranoid`, re-derives the (owner, mint) pda from the owner, mint,
+/// and bump stored in `token_account` and checks `account` is at that
+/// address. This catches a lookalike account with a forged discriminator
+/// being passed in place of the real one.
+///
+/// Keypair accounts and accounts whose owner has changed store no bump, so
+/// they can't be re-derived and are skipped.
+#[inline(always)]
+pub(crate) fn paranoid_check_token_account_address(
+    account: &NoStdAccountInfo,
+    token_account: &TokenAccount,
+) -> ProgramResult {
+    if cfg!(feature = "paranoid")
+        && token_account.bump != 0
+        && token_account.derived_address().as_ref() != Some(account.key())
+    {
+        log::sol_log("paranoid: token account is not at its pda");
+        return Err(NanoTokenError::TokenAccountAddressMismatch.into());
+    }
+    Ok(())
+}
+
 pub fn check_pda_address(
     seeds: &[&[u8]],
     program_id: &Pubkey,
@@ -334,11 +730,30 @@ pub unsafe fn split_at_mut_unchecked<T>(
 //     solana_program::program_memory::sol_memcmp(a.as_ref(), b.as_ref(), 32) !=
 // 0 }
 
+/// Leveled logging that compiles to nothing unless the level's feature is
+/// enabled, so production builds spend no compute on it.
+///
+/// - `info` (the default level): `nanolog`
+/// - `debug`: `nanolog-debug`
+/// - `trace`: `nanolog-trace`, e.g. which op is running
 #[macro_export]
 macro_rules! nanolog {
-    ($str:literal) => {
+    (trace, $str:literal) => {
+        if cfg!(feature = "nanolog-trace") {
+            solana_program::log::sol_log($str);
+        }
+    };
+    (debug, $str:literal) => {
+        if cfg!(feature = "nanolog-debug") {
+            solana_program::log::sol_log($str);
+        }
+    };
+    (info, $str:literal) => {
         if cfg!(feature = "nanolog") {
             solana_program::log::sol_log($str);
         }
     };
+    ($str:literal) => {
+        $crate::nanolog!(info, $str)
+    };
 }
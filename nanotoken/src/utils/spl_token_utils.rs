@@ -1,4 +1,7 @@
-use crate::solana_nostd_entrypoint::{NoStdAccountInfo, Ref};
+use crate::{
+    error::NanoTokenError,
+    solana_nostd_entrypoint::{NoStdAccountInfo, Ref},
+};
 use solana_program::{
     log, program_error::ProgramError, program_option::COption, pubkey::Pubkey,
 };
@@ -12,13 +15,160 @@ pub struct MintAccountInfo<'a> {
 pub const SPL_TOKEN_PROGRAM: Pubkey =
     solana_program::pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
 
+pub const SPL_TOKEN_2022_PROGRAM: Pubkey =
+    solana_program::pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+
+pub const ASSOCIATED_TOKEN_PROGRAM: Pubkey =
+    solana_program::pubkey!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+
+/// Base (spl-token) mint length. Token-2022 mints with extensions are padded
+/// to the base account length, followed by an account type byte and TLV
+/// extension entries.
+pub const MINT_BASE_LEN: usize = 82;
+const EXTENSIONS_OFFSET: usize = token::TOKENKEG_ACCOUNT_LEN;
+const ACCOUNT_TYPE_MINT: u8 = 1;
+const TRANSFER_FEE_CONFIG_EXTENSION: u16 = 1;
+
+/// Token accounts of transfer fee mints carry a TransferFeeAmount extension:
+/// [base account][u8 account type][u16 type, u16 len, u64 withheld amount]
+const TRANSFER_FEE_ACCOUNT_LEN: usize = token::TOKENKEG_ACCOUNT_LEN + 1 + 4 + 8;
+
+/// Token-2022 mint extensions that don't interfere with holding tokens in a
+/// vault and moving them with transfer_checked. Anything else (transfer
+/// hooks, confidential transfers, permanent delegates, non-transferable,
+/// default frozen state, ...) is rejected.
+///
+/// Transfer fees are supported because transmute credits what the vault
+/// actually receives, but their token accounts need an extra extension.
+const COMPATIBLE_MINT_EXTENSIONS: [u16; 9] = [
+    TRANSFER_FEE_CONFIG_EXTENSION,
+    3,  // MintCloseAuthority
+    10, // InterestBearingConfig
+    18, // MetadataPointer
+    19, // TokenMetadata
+    20, // GroupPointer
+    21, // TokenGroup
+    22, // GroupMemberPointer
+    23, // TokenGroupMember
+];
+
+#[inline(always)]
+pub fn is_token_program(program_id: &Pubkey) -> bool {
+    solana_program::program_memory::sol_memcmp(
+        program_id.as_ref(),
+        SPL_TOKEN_PROGRAM.as_ref(),
+        32,
+    ) == 0
+        || solana_program::program_memory::sol_memcmp(
+            program_id.as_ref(),
+            SPL_TOKEN_2022_PROGRAM.as_ref(),
+            32,
+        ) == 0
+}
+
+/// Iterator over the `(extension type, value)` TLV entries of a Token-2022
+/// mint. Base mints have none.
+///
+/// [base mint][padding to 165][u8 account type][(u16 type, u16 len, value)..]
+///
+/// Iteration stops at the first uninitialized (type 0) entry, which marks
+/// trailing padding. An entry whose value runs past the end of the account
+/// yields an error.
+pub struct MintExtensions<'d> {
+    data: &'d [u8],
+    offset: usize,
+}
+
+impl<'d> MintExtensions<'d> {
+    pub fn new(data: &'d [u8]) -> Result<MintExtensions<'d>, ProgramError> {
+        if data.len() == MINT_BASE_LEN {
+            return Ok(Self {
+                data,
+                offset: data.len(),
+            });
+        }
+        if data.get(EXTENSIONS_OFFSET) != Some(&ACCOUNT_TYPE_MINT) {
+            log::sol_log("invalid token-2022 mint account type");
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(Self {
+            data,
+            offset: EXTENSIONS_OFFSET + 1,
+        })
+    }
+}
+
+impl<'d> Iterator for MintExtensions<'d> {
+    type Item = Result<(u16, &'d [u8]), ProgramError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let header = self
+            .data
+            .get(self.offset..self.offset + 4)?;
+        let extension_type = u16::from_le_bytes([header[0], header[1]]);
+        let extension_len = u16::from_le_bytes([header[2], header[3]]) as usize;
+
+        // Uninitialized entries are trailing padding
+        if extension_type == 0 {
+            self.offset = self.data.len();
+            return None;
+        }
+
+        let value_start = self.offset + 4;
+        let Some(value) = self
+            .data
+            .get(value_start..value_start + extension_len)
+        else {
+            log::sol_log("token-2022 mint extension is truncated");
+            self.offset = self.data.len();
+            return Some(Err(ProgramError::InvalidAccountData));
+        };
+        self.offset = value_start + extension_len;
+
+        Some(Ok((extension_type, value)))
+    }
+}
+
+/// Walks the TLV extensions of a Token-2022 mint and rejects any that are not
+/// in [COMPATIBLE_MINT_EXTENSIONS].
+pub fn check_mint_extensions(data: &[u8]) -> Result<(), ProgramError> {
+    for extension in MintExtensions::new(data)? {
+        let (extension_type, _value) = extension?;
+        if !COMPATIBLE_MINT_EXTENSIONS.contains(&extension_type) {
+            log::sol_log("mint has an unsupported token-2022 extension");
+            return Err(NanoTokenError::UnsupportedMintExtension.into());
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether a Token-2022 mint has the given TLV extension. Assumes the
+/// extensions were already validated by [check_mint_extensions].
+pub fn mint_has_extension(data: &[u8], extension: u16) -> bool {
+    MintExtensions::new(data).is_ok_and(|mut extensions| {
+        extensions.any(|entry| {
+            matches!(entry, Ok((extension_type, _)) if extension_type == extension)
+        })
+    })
+}
+
+/// Space a token account for this (validated) mint needs. Only transfer fee
+/// mints need account extensions.
+pub fn token_account_len(mint_data: &[u8]) -> usize {
+    if mint_has_extension(mint_data, TRANSFER_FEE_CONFIG_EXTENSION) {
+        TRANSFER_FEE_ACCOUNT_LEN
+    } else {
+        token::TOKENKEG_ACCOUNT_LEN
+    }
+}
+
 impl<'a> MintAccountInfo<'a> {
     pub fn new(
         info: &'a NoStdAccountInfo,
     ) -> Result<MintAccountInfo<'a>, ProgramError> {
-        // TODO cmp
-        if *info.owner() != SPL_TOKEN_PROGRAM {
-            log::sol_log("Mint account must be owned by the Token Program");
+        if !is_token_program(info.owner()) {
+            log::sol_log("Mint account must be owned by a Token Program");
             return Err(ProgramError::IllegalOwner);
         }
 
@@ -30,6 +180,7 @@ impl<'a> MintAccountInfo<'a> {
             log::sol_log("invalid mint account");
             ProgramError::InvalidAccountData
         })?;
+        check_mint_extensions(&data)?;
 
         Ok(Self {
             info,
@@ -84,7 +235,7 @@ impl MintZC {
 
 // returns offset to next element
 unsafe fn check_copt_disc(ptr: *const u32) -> Option<usize> {
-    match *ptr {
+    match ptr.read_unaligned() {
         // None or Some
         0 | 1 => Some(36),
 
@@ -167,13 +318,67 @@ fn mint_zc() {
     }
 }
 
+#[test]
+fn mint_extensions() {
+    // Base mint has no extensions
+    let mut data = [0_u8; 256];
+    assert!(MintExtensions::new(&data[..MINT_BASE_LEN])
+        .unwrap()
+        .next()
+        .is_none());
+
+    // Token-2022 mint with a metadata pointer followed by padding
+    let pointer = EXTENSIONS_OFFSET + 1;
+    let entry = pointer + 4 + 64;
+    let len = entry + 8;
+    data[EXTENSIONS_OFFSET] = ACCOUNT_TYPE_MINT;
+    data[pointer..pointer + 2].copy_from_slice(&18_u16.to_le_bytes());
+    data[pointer + 2..pointer + 4].copy_from_slice(&64_u16.to_le_bytes());
+    data[pointer + 4..entry].fill(7);
+    let mut extensions = MintExtensions::new(&data[..len]).unwrap();
+    assert_eq!(extensions.next(), Some(Ok((18, &[7_u8; 64][..]))));
+    assert_eq!(extensions.next(), None);
+    assert!(check_mint_extensions(&data[..len]).is_ok());
+    assert!(mint_has_extension(&data[..len], 18));
+    assert!(!mint_has_extension(
+        &data[..len],
+        TRANSFER_FEE_CONFIG_EXTENSION
+    ));
+
+    // Transfer hooks and confidential transfers are rejected
+    for unsupported in [14_u16, 4] {
+        data[entry..entry + 2].copy_from_slice(&unsupported.to_le_bytes());
+        data[entry + 2..entry + 4].copy_from_slice(&2_u16.to_le_bytes());
+        assert_eq!(
+            check_mint_extensions(&data[..entry + 6]),
+            Err(NanoTokenError::UnsupportedMintExtension.into())
+        );
+    }
+
+    // Truncated entries and bad account types are malformed
+    data[entry..entry + 2].copy_from_slice(&3_u16.to_le_bytes());
+    data[entry + 2..entry + 4].copy_from_slice(&32_u16.to_le_bytes());
+    assert_eq!(
+        check_mint_extensions(&data[..entry + 12]),
+        Err(ProgramError::InvalidAccountData)
+    );
+    data[EXTENSIONS_OFFSET] = 2;
+    assert_eq!(
+        check_mint_extensions(&data[..len]),
+        Err(ProgramError::InvalidAccountData)
+    );
+}
+
 pub mod token {
     use crate::solana_nostd_entrypoint::NoStdAccountInfo;
-    use solana_program::{log, program_error::ProgramError, pubkey::Pubkey};
+    use solana_program::{
+        log, program_error::ProgramError, program_option::COption,
+        pubkey::Pubkey,
+    };
 
     use crate::error::NanoTokenError;
 
-    use super::SPL_TOKEN_PROGRAM;
+    use super::{check_copt_disc, is_token_program, SPL_TOKEN_PROGRAM};
 
     #[derive(Clone)]
     pub struct TokenAccountInfo<'a> {
@@ -182,31 +387,131 @@ pub mod token {
 
     pub const TOKENKEG_ACCOUNT_LEN: usize = 165;
 
+    const ACCOUNT_STATE_INITIALIZED: u8 = 1;
+    const ACCOUNT_STATE_FROZEN: u8 = 2;
+
+    /// Token account data. Token-2022 extensions, if any, follow the base
+    /// account and are not part of this view.
+    #[repr(C, packed)]
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct TokenAccountZC {
+        /// The mint associated with this account
+        pub mint: Pubkey,
+        /// The owner of this account.
+        pub owner: Pubkey,
+        /// The amount of tokens this account holds.
+        pub amount: u64,
+        /// If `delegate` is `Some` then `delegated_amount` represents
+        /// the amount authorized by the delegate
+        pub delegate: COption<Pubkey>,
+        /// The account's state: 1 is initialized, 2 is frozen
+        pub state: u8,
+        /// `COption<u64>` discriminant. Nonzero if this is a native token
+        /// account, see [TokenAccountZC::is_native].
+        pub is_native_option: u32,
+        /// For native accounts, the rent-exempt reserve. An account is
+        /// required to be rent-exempt, so the value is used by the processor
+        /// to ensure that wrapped SOL accounts do not drop below this
+        /// threshold.
+        pub rent_exempt_reserve: u64,
+        /// The amount delegated
+        pub delegated_amount: u64,
+        /// Optional authority to close the account.
+        pub close_authority: COption<Pubkey>,
+    }
+
+    const _: () =
+        assert!(core::mem::size_of::<TokenAccountZC>() == TOKENKEG_ACCOUNT_LEN);
+
+    impl TokenAccountZC {
+        /// Returns None unless `data` holds an initialized (or frozen) token
+        /// account with valid option discriminants.
+        pub fn from_slice<'d>(data: &'d [u8]) -> Option<&'d TokenAccountZC> {
+            if data.len() < TOKENKEG_ACCOUNT_LEN {
+                return None;
+            }
+            let mut ptr = data.as_ptr();
+
+            unsafe {
+                // Skip over mint, owner, amount
+                ptr = ptr.add(72);
+
+                // Check delegate discriminant
+                ptr = ptr.add(check_copt_disc(ptr as *const u32)?);
+
+                // Check account is initialized
+                if !matches!(
+                    *ptr,
+                    ACCOUNT_STATE_INITIALIZED | ACCOUNT_STATE_FROZEN
+                ) {
+                    return None;
+                }
+                ptr = ptr.add(1);
+
+                // Check is_native discriminant, then skip over it and
+                // delegated_amount
+                if !matches!((ptr as *const u32).read_unaligned(), 0 | 1) {
+                    return None;
+                }
+                ptr = ptr.add(12 + 8);
+
+                // Check close authority discriminant
+                check_copt_disc(ptr as *const u32)?;
+
+                Some(&*(data.as_ptr() as *const TokenAccountZC))
+            }
+        }
+
+        #[inline(always)]
+        pub fn is_frozen(&self) -> bool {
+            self.state == ACCOUNT_STATE_FROZEN
+        }
+
+        #[inline(always)]
+        pub fn is_native(&self) -> bool {
+            self.is_native_option != 0
+        }
+    }
+
+    /// Reads the amount of a token account that was already validated
+    pub fn amount(info: &NoStdAccountInfo) -> Result<u64, ProgramError> {
+        let data = info
+            .try_borrow_data()
+            .ok_or(NanoTokenError::DuplicateAccount)?;
+        let amount = data
+            .get(64..72)
+            .ok_or(ProgramError::AccountDataTooSmall)?;
+        Ok(u64::from_le_bytes(amount.try_into().unwrap()))
+    }
+
     impl<'a> TokenAccountInfo<'a> {
         pub fn new(
             info: &'a NoStdAccountInfo,
             mint: &Pubkey,
             print: bool,
         ) -> Result<TokenAccountInfo<'a>, ProgramError> {
-            // Check account is owned by spl token program
-            if solana_program::program_memory::sol_memcmp(
-                info.owner().as_ref(),
-                SPL_TOKEN_PROGRAM.as_ref(),
-                32,
-            ) != 0
-            {
+            // Check account is owned by a spl token program
+            if !is_token_program(info.owner()) {
                 if print {
                     log::sol_log(
-                        "Token account must be owned by the Token Program",
+                        "Token account must be owned by a Token Program",
                     );
                 }
                 return Err(ProgramError::IllegalOwner);
             }
 
-            // Check account data is correct length
-            if info.data_len() != TOKENKEG_ACCOUNT_LEN {
+            // Check account data is correct length. Token-2022 accounts may
+            // carry extensions past the base length.
+            let is_tokenkeg = solana_program::program_memory::sol_memcmp(
+                info.owner().as_ref(),
+                SPL_TOKEN_PROGRAM.as_ref(),
+                32,
+            ) == 0;
+            if (is_tokenkeg && info.data_len() != TOKENKEG_ACCOUNT_LEN)
+                || info.data_len() < TOKENKEG_ACCOUNT_LEN
+            {
                 if print {
-                    log::sol_log("Token account data length must be 165 bytes");
+                    log::sol_log("Token account data length is incorrect");
                 }
                 return Err(ProgramError::InvalidAccountData);
             }
@@ -257,3 +562,62 @@ pub mod token {
         }
     }
 }
+
+#[test]
+fn token_account_zc() {
+    use token::TokenAccountZC;
+
+    if cfg!(target_endian = "little") {
+        let mut data = [0_u8; token::TOKENKEG_ACCOUNT_LEN];
+        // mint, owner
+        data[0..32].fill(1);
+        data[32..64].fill(2);
+        // amount
+        data[64..72].copy_from_slice(&500_u64.to_le_bytes());
+        // Some(delegate)
+        data[72] = 1;
+        data[76..108].fill(3);
+        // frozen
+        data[108] = 2;
+        // Some(rent exempt reserve)
+        data[109] = 1;
+        data[113..121].copy_from_slice(&7_u64.to_le_bytes());
+        // delegated amount
+        data[121..129].copy_from_slice(&40_u64.to_le_bytes());
+        // None close authority
+        data[129] = 0;
+
+        let account = TokenAccountZC::from_slice(&data).unwrap();
+        assert_eq!(account.mint, Pubkey::new_from_array([1; 32]));
+        assert_eq!(account.owner, Pubkey::new_from_array([2; 32]));
+        let amount = account.amount;
+        assert_eq!(amount, 500);
+        let delegate = account.delegate;
+        assert_eq!(delegate, COption::Some(Pubkey::new_from_array([3; 32])));
+        assert!(account.is_frozen());
+        assert!(account.is_native());
+        let reserve = account.rent_exempt_reserve;
+        assert_eq!(reserve, 7);
+        let delegated_amount = account.delegated_amount;
+        assert_eq!(delegated_amount, 40);
+        let close_authority = account.close_authority;
+        assert_eq!(close_authority, COption::None);
+
+        // Uninitialized
+        data[108] = 0;
+        assert!(TokenAccountZC::from_slice(&data).is_none());
+        data[108] = 1;
+        assert!(!TokenAccountZC::from_slice(&data)
+            .unwrap()
+            .is_frozen());
+
+        // Bad option discriminant
+        data[129] = 2;
+        assert!(TokenAccountZC::from_slice(&data).is_none());
+
+        // Too short
+        assert!(TokenAccountZC::from_slice(&data[..164]).is_none());
+    } else {
+        // TODO: big endian system (not really needed tbh)
+    }
+}
@@ -0,0 +1,83 @@
+//! Kani proofs for the transfer arithmetic, only compiled under `cfg(kani)`.
+//! Run them with `cargo kani -p nanotoken`.
+//!
+//! Transfer writes balances through raw pointers that may alias, so these
+//! check [TokenAccount::move_balance] for both distinct and aliased accounts
+//! under the preconditions the transfer handler establishes first: `from`
+//! holds at least `amount`, and every balance and withheld fee of the mint is
+//! covered by a u64 supply.
+
+use bytemuck::Zeroable;
+
+use crate::{consts::MAX_FEE_BPS, math, TokenAccount};
+
+/// A token account with arbitrary balances and fee config. The fee config is
+/// copied from a mint, which caps the bps at [MAX_FEE_BPS].
+fn any_token_account() -> TokenAccount {
+    let mut account = TokenAccount::zeroed();
+    account.balance = kani::any();
+    account.withheld_fees = kani::any();
+    account.transfer_fee_bps = kani::any();
+    account.max_transfer_fee = kani::any();
+    kani::assume(account.transfer_fee_bps <= MAX_FEE_BPS);
+    account
+}
+
+/// Everything the transfer can move between
+fn total(account: &TokenAccount) -> u128 {
+    account.balance as u128 + account.withheld_fees as u128
+}
+
+#[kani::proof]
+fn transfer_fee_never_exceeds_amount() {
+    let account = any_token_account();
+    let amount: u64 = kani::any();
+    assert!(account.transfer_fee(amount) <= amount);
+}
+
+#[kani::proof]
+fn move_balance_preserves_sum() {
+    let mut from = any_token_account();
+    let mut to = any_token_account();
+    let amount: u64 = kani::any();
+
+    // Checked by transfer before moving
+    kani::assume(from.balance >= amount);
+    // Both accounts are covered by supply
+    kani::assume(total(&from) + total(&to) <= u64::MAX as u128);
+
+    let (from_balance, before) = (from.balance, total(&from) + total(&to));
+    let result =
+        unsafe { TokenAccount::move_balance(&mut from, &mut to, amount) };
+
+    assert!(result.is_ok());
+    assert!(from.balance == from_balance - amount);
+    assert!(total(&from) + total(&to) == before);
+}
+
+#[kani::proof]
+fn move_balance_to_self_preserves_sum() {
+    let mut account = any_token_account();
+    let amount: u64 = kani::any();
+    kani::assume(account.balance >= amount);
+    kani::assume(total(&account) <= u64::MAX as u128);
+
+    let before = total(&account);
+    let ptr: *mut TokenAccount = &mut account;
+    let result = unsafe { TokenAccount::move_balance(ptr, ptr, amount) };
+
+    assert!(result.is_ok());
+    assert!(total(&account) == before);
+}
+
+#[kani::proof]
+fn sub_balance_fails_iff_insufficient() {
+    let balance: u64 = kani::any();
+    let amount: u64 = kani::any();
+    match math::sub_balance(balance, amount) {
+        Ok(remaining) => {
+            assert!(amount <= balance && remaining + amount == balance)
+        }
+        Err(_) => assert!(amount > balance),
+    }
+}
@@ -1,55 +1,53 @@
-//! An end-to-end integration test
+//! End-to-end integration tests
 
-use std::{env, error::Error, path::Path};
+use std::error::Error;
 
+use bytemuck::Zeroable;
 use nanotoken::{
+    consts::{
+        CONFIG_ACCOUNT, CONFIG_BUMP, NATIVE_MINT_INDEX, SPL_MEMO_PROGRAMS,
+        TOKEN_ACCOUNT_V1_SIZE, TOKEN_ACCOUNT_VERSION,
+    },
+    error::{decode_op_error, encode_op_error, NanoTokenError},
     ix::{
+        AccountIndicesArgs, ApproveArgs, BurnArgs, InitConfigArgs,
         InitializeAccountArgs, InitializeMintArgs, MintArgs, Tag, TransferArgs,
+        TransferFeeConfig,
     },
-    Mint, ProgramConfig, TokenAccount,
+    offsets::{CONFIG_AUTHORITY_OFFSET, CONFIG_PENDING_AUTHORITY_OFFSET},
+    ListMode, Mint, MintList, MintMetadata, ProgramConfig, RentDestination,
+    TokenAccount, TransferIntent,
+};
+use nanotoken_client::{batch::BatchBuilder, instructions, pda};
+use nanotoken_test_utils::{
+    create_funded_account, create_mint, create_mint_with, get_mint,
+    get_token_account, op_error, process, process_instructions, setup_config,
+    simulate_return_data, start,
 };
 use solana_program::{
-    instruction::{AccountMeta, Instruction},
+    clock::Clock,
+    instruction::{AccountMeta, Instruction, InstructionError},
     native_token::LAMPORTS_PER_SOL,
+    program_error::ProgramError,
     pubkey::Pubkey,
     rent::Rent,
     system_program,
 };
-use solana_program_test::{BanksClient, ProgramTest};
+use solana_program_test::BanksClient;
 use solana_sdk::{
-    signature::{read_keypair_file, Keypair},
+    signature::Keypair,
     signer::Signer,
     system_transaction,
-    transaction::Transaction,
+    transaction::{Transaction, TransactionError},
 };
 
 #[tokio::test(flavor = "current_thread")]
 async fn end_to_end() -> Result<(), Box<dyn Error>> {
-    let mut program_test = ProgramTest::new("nanotoken", nanotoken::ID, None);
-    program_test.prefer_bpf(true);
-    let mut ctx = program_test.start_with_context().await;
+    let mut ctx = start().await;
 
     // Initialize config
-    let config_keypair = read_keypair_file(
-        Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap())
-            .parent()
-            .unwrap()
-            .join("config.json"),
-    )
-    .unwrap();
-    let config = config_keypair.pubkey();
-    let create_config = system_transaction::create_account(
-        &ctx.payer,
-        &config_keypair,
-        ctx.last_blockhash,
-        Rent::default().minimum_balance(ProgramConfig::space()),
-        ProgramConfig::space() as u64,
-        &nanotoken::ID,
-    );
-    ctx.banks_client
-        .process_transaction(create_config)
-        .await
-        .unwrap();
+    let config = CONFIG_ACCOUNT;
+    assert_eq!(ProgramConfig::address(), (CONFIG_ACCOUNT, CONFIG_BUMP));
 
     // Initialize mint
     let mint_keypair = Keypair::new();
@@ -68,14 +66,17 @@ async fn end_to_end() -> Result<(), Box<dyn Error>> {
         .unwrap();
 
     // Initialize config
-    let ix_data = (Tag::InitializeConfig as u64)
+    let mut ix_data = (Tag::InitializeConfig as u64)
         .to_le_bytes()
         .to_vec();
+    ix_data.extend_from_slice(bytemuck::bytes_of(&InitConfigArgs {
+        authority: ctx.payer.pubkey(),
+    }));
 
     let accounts = vec![
         AccountMeta::new(config, false),
         AccountMeta::new_readonly(system_program::ID, false),
-        AccountMeta::new_readonly(ctx.payer.pubkey(), false),
+        AccountMeta::new(ctx.payer.pubkey(), true),
     ];
     let instruction = Instruction {
         program_id: nanotoken::ID,
@@ -99,9 +100,19 @@ async fn end_to_end() -> Result<(), Box<dyn Error>> {
     let InitializeMintArgs {
         authority,
         decimals,
+        freeze_authority,
+        transfer_fee,
+        pause_authority,
+        max_supply,
+        clawback_authority,
     } = bytemuck::try_from_bytes_mut(&mut ix_data[8..]).unwrap();
     *authority = ctx.payer.pubkey();
     *decimals = 6;
+    *freeze_authority = Pubkey::default();
+    *transfer_fee = Default::default();
+    *pause_authority = Pubkey::default();
+    *max_supply = 0;
+    *clawback_authority = Pubkey::default();
 
     let accounts = vec![
         AccountMeta::new(mint, false),
@@ -134,11 +145,16 @@ async fn end_to_end() -> Result<(), Box<dyn Error>> {
     {
         ix_data[0..8]
             .copy_from_slice(&(Tag::InitializeAccount as u64).to_le_bytes());
-        let InitializeAccountArgs { owner, mint, bump } =
-            bytemuck::try_from_bytes_mut(
-                &mut ix_data[8..8 + InitializeAccountArgs::size()],
-            )
-            .unwrap();
+        let InitializeAccountArgs {
+            owner,
+            mint,
+            bump,
+            immutable_owner: _,
+            owner_must_sign: _,
+        } = bytemuck::try_from_bytes_mut(
+            &mut ix_data[8..8 + InitializeAccountArgs::size()],
+        )
+        .unwrap();
         *owner = ctx.payer.pubkey();
         *mint = 0;
         *bump = token_account_bump as u64;
@@ -184,10 +200,12 @@ async fn end_to_end() -> Result<(), Box<dyn Error>> {
 
     // check state
     let user_token_account =
-        get_nanotoken_account(&mut ctx.banks_client, token_account).await?;
+        get_token_account(&mut ctx.banks_client, token_account).await?;
     assert_eq!(user_token_account.mint, 0);
     assert_eq!(user_token_account.owner, ctx.payer.pubkey());
     assert_eq!(user_token_account.balance, 1000);
+    assert_eq!(user_token_account.bump, token_account_bump);
+    assert_eq!(user_token_account.derived_address(), Some(token_account));
 
     // Initialize a second token account
     // First fund a second user
@@ -208,11 +226,16 @@ async fn end_to_end() -> Result<(), Box<dyn Error>> {
     {
         ix_data[0..8]
             .copy_from_slice(&(Tag::InitializeAccount as u64).to_le_bytes());
-        let InitializeAccountArgs { owner, mint, bump } =
-            bytemuck::try_from_bytes_mut(
-                &mut ix_data[8..8 + InitializeAccountArgs::size()],
-            )
-            .unwrap();
+        let InitializeAccountArgs {
+            owner,
+            mint,
+            bump,
+            immutable_owner: _,
+            owner_must_sign: _,
+        } = bytemuck::try_from_bytes_mut(
+            &mut ix_data[8..8 + InitializeAccountArgs::size()],
+        )
+        .unwrap();
         *owner = second_user.pubkey();
         *mint = 0;
         *bump = token_account_bump as u64;
@@ -287,11 +310,10 @@ async fn end_to_end() -> Result<(), Box<dyn Error>> {
 
     // check state (transfered 5 atoms)
     let user_token_account =
-        get_nanotoken_account(&mut ctx.banks_client, token_account).await?;
+        get_token_account(&mut ctx.banks_client, token_account).await?;
     assert_eq!(user_token_account.balance, 995);
     let second_user_token_account =
-        get_nanotoken_account(&mut ctx.banks_client, second_token_account)
-            .await?;
+        get_token_account(&mut ctx.banks_client, second_token_account).await?;
     assert_eq!(second_user_token_account.mint, 0);
     assert_eq!(second_user_token_account.owner, second_user.pubkey());
     assert_eq!(second_user_token_account.balance, 5);
@@ -346,34 +368,2913 @@ async fn end_to_end() -> Result<(), Box<dyn Error>> {
 
     // Transfered equal amounts back and forth so same state
     let user_token_account =
-        get_nanotoken_account(&mut ctx.banks_client, token_account).await?;
+        get_token_account(&mut ctx.banks_client, token_account).await?;
     assert_eq!(user_token_account.balance, 995);
     let second_user_token_account =
-        get_nanotoken_account(&mut ctx.banks_client, second_token_account)
-            .await?;
+        get_token_account(&mut ctx.banks_client, second_token_account).await?;
     assert_eq!(second_user_token_account.balance, 5);
 
+    // packed transfer: [u64 Packed tag][u8 Transfer tag][unaligned amount]
+    let mut ix_data = (Tag::Packed as u64)
+        .to_le_bytes()
+        .to_vec();
+    ix_data.push(Tag::Transfer as u8);
+    ix_data.extend_from_slice(&2_u64.to_le_bytes());
+    let instruction = Instruction {
+        program_id: nanotoken::ID,
+        accounts: vec![
+            AccountMeta::new(token_account, false),
+            AccountMeta::new(second_token_account, false),
+            AccountMeta::new_readonly(ctx.payer.pubkey(), true),
+        ],
+        data: ix_data,
+    };
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&ctx.payer.pubkey()),
+        &[&ctx.payer],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let user_token_account =
+        get_token_account(&mut ctx.banks_client, token_account).await?;
+    assert_eq!(user_token_account.balance, 993);
+    let second_user_token_account =
+        get_token_account(&mut ctx.banks_client, second_token_account).await?;
+    assert_eq!(second_user_token_account.balance, 7);
+
     Ok(())
 }
 
-pub async fn get_nanotoken_account(
-    client: &mut BanksClient,
-    key: Pubkey,
-) -> Result<TokenAccount, Box<dyn Error>> {
-    use core::mem::MaybeUninit;
-    let mut ta = MaybeUninit::uninit();
-    let account = client
-        .get_account(key)
-        .await?
-        .ok_or("could not find account")?;
+/// A delegate spends from its allowance via `transfer`, cannot overspend it,
+/// and is revoked once the allowance is used up.
+#[tokio::test(flavor = "current_thread")]
+async fn transfer_from_delegate() -> Result<(), Box<dyn Error>> {
+    let mut ctx = start().await;
+    let payer = ctx.payer.pubkey();
+    let config = CONFIG_ACCOUNT;
+    setup_config(&mut ctx).await?;
+    let (mint, _mint_index) = create_mint(&mut ctx, &payer, 6).await?;
 
-    unsafe {
-        core::ptr::copy_nonoverlapping(
-            account.data.as_ptr().add(8),
-            ta.as_mut_ptr() as *mut u8,
-            core::mem::size_of::<TokenAccount>(),
-        );
+    // Initialize owner and destination token accounts, and mint to owner
+    let delegate = Keypair::new();
+    let token_account =
+        create_funded_account(&mut ctx, &payer, &mint, 100).await?;
+    let destination =
+        create_funded_account(&mut ctx, &delegate.pubkey(), &mint, 0).await?;
+
+    // Approve delegate for 50
+    let mut ix_data = (Tag::Approve as u64)
+        .to_le_bytes()
+        .to_vec();
+    ix_data.extend_from_slice(bytemuck::bytes_of(&ApproveArgs {
+        delegate: delegate.pubkey(),
+        amount: 50,
+    }));
+    process(
+        &mut ctx,
+        ix_data,
+        vec![
+            AccountMeta::new(token_account, false),
+            AccountMeta::new_readonly(payer, true),
+            // entrypoint needs at least 3 accounts
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    // Delegate transfers part of its allowance
+    let delegate_transfer = |amount: u64| {
+        let mut ix_data = (Tag::Transfer as u64)
+            .to_le_bytes()
+            .to_vec();
+        ix_data.extend_from_slice(bytemuck::bytes_of(&TransferArgs { amount }));
+        (
+            ix_data,
+            vec![
+                AccountMeta::new(token_account, false),
+                AccountMeta::new(destination, false),
+                AccountMeta::new_readonly(delegate.pubkey(), true),
+            ],
+        )
+    };
+    let (ix_data, accounts) = delegate_transfer(30);
+    process(&mut ctx, ix_data, accounts, &[&delegate])
+        .await
+        .unwrap();
+    let owner_account =
+        get_token_account(&mut ctx.banks_client, token_account).await?;
+    assert_eq!(owner_account.balance, 70);
+    assert_eq!(owner_account.delegate, delegate.pubkey());
+    assert_eq!(owner_account.delegated_amount, 20);
+    let destination_account =
+        get_token_account(&mut ctx.banks_client, destination).await?;
+    assert_eq!(destination_account.balance, 30);
+
+    // Delegate cannot overspend its allowance. The error names the op.
+    let (ix_data, accounts) = delegate_transfer(21);
+    let err = process(&mut ctx, ix_data, accounts, &[&delegate])
+        .await
+        .unwrap_err();
+    let TransactionError::InstructionError(0, InstructionError::Custom(code)) =
+        err.unwrap()
+    else {
+        panic!("expected custom error, got {err:?}");
+    };
+    assert_eq!(
+        decode_op_error(code),
+        (Some(0), NanoTokenError::InsufficientDelegatedAmount.into())
+    );
+
+    // Spending the rest of the allowance revokes the delegate
+    let (ix_data, accounts) = delegate_transfer(20);
+    process(&mut ctx, ix_data, accounts, &[&delegate])
+        .await
+        .unwrap();
+    let owner_account =
+        get_token_account(&mut ctx.banks_client, token_account).await?;
+    assert_eq!(owner_account.balance, 50);
+    assert_eq!(owner_account.delegate, Pubkey::default());
+    assert_eq!(owner_account.delegated_amount, 0);
+    let destination_account =
+        get_token_account(&mut ctx.banks_client, destination).await?;
+    assert_eq!(destination_account.balance, 50);
+
+    // Revoked delegate can no longer transfer
+    let (ix_data, accounts) = delegate_transfer(1);
+    assert!(process(&mut ctx, ix_data, accounts, &[&delegate])
+        .await
+        .is_err());
 
-        Ok(ta.assume_init())
+    // Ping-pong transfers reference accounts by index, so each account is
+    // passed once
+    let mut ix_data = vec![];
+    for (indices, amount) in [([0, 1, 2], 10), ([1, 0, 3], 4), ([0, 1, 2], 1)] {
+        ix_data.extend((Tag::AccountIndices as u64).to_le_bytes());
+        ix_data.extend_from_slice(bytemuck::bytes_of(&AccountIndicesArgs {
+            num_accounts: 3,
+        }));
+        ix_data.extend([indices[0], indices[1], indices[2], 0, 0, 0, 0, 0]);
+        ix_data.extend((Tag::Transfer as u64).to_le_bytes());
+        ix_data.extend_from_slice(bytemuck::bytes_of(&TransferArgs { amount }));
     }
+    process(
+        &mut ctx,
+        ix_data,
+        vec![
+            AccountMeta::new(token_account, false),
+            AccountMeta::new(destination, false),
+            AccountMeta::new_readonly(payer, true),
+            AccountMeta::new_readonly(delegate.pubkey(), true),
+            // remainder
+            AccountMeta::new(config, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new(payer, true),
+        ],
+        &[&delegate],
+    )
+    .await
+    .unwrap();
+    let owner_account =
+        get_token_account(&mut ctx.banks_client, token_account).await?;
+    assert_eq!(owner_account.balance, 43);
+    let destination_account =
+        get_token_account(&mut ctx.banks_client, destination).await?;
+    assert_eq!(destination_account.balance, 57);
+
+    Ok(())
+}
+
+/// Burning requires the token account owner (or its delegate, within the
+/// allowance) to sign. The token account itself signing is not enough, and
+/// nobody else can burn.
+#[tokio::test(flavor = "current_thread")]
+async fn burn_authority() -> Result<(), Box<dyn Error>> {
+    let mut ctx = start().await;
+    let payer = ctx.payer.pubkey();
+    let config = CONFIG_ACCOUNT;
+    setup_config(&mut ctx).await?;
+    let (mint, _mint_index) = create_mint(&mut ctx, &payer, 6).await?;
+
+    // Initialize a keypair token account owned by the payer, mint to it, and
+    // approve a delegate for 30
+    let token_account_keypair = Keypair::new();
+    let token_account = token_account_keypair.pubkey();
+    let delegate = Keypair::new();
+    let mut ix_data = vec![];
+    ix_data.extend((Tag::InitializeAccount as u64).to_le_bytes());
+    ix_data.extend_from_slice(bytemuck::bytes_of(&InitializeAccountArgs {
+        owner: payer,
+        mint: 0,
+        bump: 0,
+        immutable_owner: 0,
+        owner_must_sign: 0,
+    }));
+    ix_data.extend((Tag::Mint as u64).to_le_bytes());
+    ix_data.extend_from_slice(bytemuck::bytes_of(&MintArgs { amount: 100 }));
+    ix_data.extend((Tag::Approve as u64).to_le_bytes());
+    ix_data.extend_from_slice(bytemuck::bytes_of(&ApproveArgs {
+        delegate: delegate.pubkey(),
+        amount: 30,
+    }));
+    process(
+        &mut ctx,
+        ix_data,
+        vec![
+            // create
+            AccountMeta::new(token_account, true),
+            // mint
+            AccountMeta::new(token_account, true),
+            AccountMeta::new(mint, false),
+            AccountMeta::new(payer, true),
+            // approve
+            AccountMeta::new(token_account, true),
+            AccountMeta::new_readonly(payer, true),
+            // remainder
+            AccountMeta::new(config, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new(payer, true),
+        ],
+        &[&token_account_keypair],
+    )
+    .await
+    .unwrap();
+
+    let burn = |amount: u64, authority: Pubkey, authority_signs: bool| {
+        let mut ix_data = (Tag::Burn as u64)
+            .to_le_bytes()
+            .to_vec();
+        ix_data.extend_from_slice(bytemuck::bytes_of(&BurnArgs { amount }));
+        (
+            ix_data,
+            vec![
+                AccountMeta::new(token_account, true),
+                AccountMeta::new(mint, false),
+                AccountMeta::new_readonly(authority, authority_signs),
+            ],
+        )
+    };
+
+    // The token account signing without the owner is rejected
+    let (ix_data, accounts) = burn(1, payer, false);
+    let err = process(&mut ctx, ix_data, accounts, &[&token_account_keypair])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        op_error(err),
+        (Some(0), ProgramError::MissingRequiredSignature)
+    );
+
+    // Nobody but the owner or delegate can burn
+    let stranger = Keypair::new();
+    let (ix_data, accounts) = burn(1, stranger.pubkey(), true);
+    let err = process(&mut ctx, ix_data, accounts, &[&stranger])
+        .await
+        .unwrap_err();
+    assert_eq!(op_error(err), (Some(0), ProgramError::IllegalOwner));
+
+    // Delegate cannot burn past its allowance
+    let (ix_data, accounts) = burn(31, delegate.pubkey(), true);
+    let err = process(&mut ctx, ix_data, accounts, &[&delegate])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        op_error(err),
+        (Some(0), NanoTokenError::InsufficientDelegatedAmount.into())
+    );
+
+    // Delegate burns its whole allowance and is revoked
+    let (ix_data, accounts) = burn(30, delegate.pubkey(), true);
+    process(&mut ctx, ix_data, accounts, &[&delegate])
+        .await
+        .unwrap();
+    let account =
+        get_token_account(&mut ctx.banks_client, token_account).await?;
+    assert_eq!(account.balance, 70);
+    assert_eq!(account.delegate, Pubkey::default());
+    assert_eq!(account.delegated_amount, 0);
+
+    // Owner burns without the token account signing
+    let (ix_data, mut accounts) = burn(20, payer, true);
+    accounts[0].is_signer = false;
+    process(&mut ctx, ix_data, accounts, &[])
+        .await
+        .unwrap();
+    let account =
+        get_token_account(&mut ctx.banks_client, token_account).await?;
+    assert_eq!(account.balance, 50);
+    let mint_account = get_mint(&mut ctx.banks_client, mint).await?;
+    assert_eq!(mint_account.supply, 50);
+
+    Ok(())
+}
+
+/// Wrapped lamports back the balance, not rent, so growing a native account
+/// charges the payer the full rent increase and the whole balance can still
+/// be unwrapped afterwards.
+#[tokio::test(flavor = "current_thread")]
+async fn realloc_native_account() -> Result<(), Box<dyn Error>> {
+    let mut ctx = start().await;
+    let payer = ctx.payer.pubkey();
+    setup_config(&mut ctx).await?;
+
+    // Initialize the payer's native account and wrap 1 SOL
+    let (token_account, _bump) =
+        pda::token_account_address(&payer, NATIVE_MINT_INDEX);
+    process_instructions(
+        &mut ctx,
+        &[
+            instructions::initialize_account(
+                &payer,
+                NATIVE_MINT_INDEX,
+                false,
+                false,
+                &payer,
+            ),
+            instructions::wrap_sol(&payer, &payer, LAMPORTS_PER_SOL, &payer),
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    // Grow it by 1 KiB
+    let new_len = TokenAccount::space() + 1024;
+    process_instructions(
+        &mut ctx,
+        &[instructions::realloc(
+            &token_account,
+            &payer,
+            new_len as u64,
+            &payer,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    let rent = Rent::default().minimum_balance(new_len);
+    let account = ctx
+        .banks_client
+        .get_account(token_account)
+        .await?
+        .unwrap();
+    assert_eq!(account.data.len(), new_len);
+    assert_eq!(account.lamports, rent + LAMPORTS_PER_SOL);
+
+    // Unwrap everything, leaving the account rent exempt at its new size
+    let destination = Pubkey::new_unique();
+    process_instructions(
+        &mut ctx,
+        &[instructions::unwrap_sol(
+            &payer,
+            &destination,
+            LAMPORTS_PER_SOL,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    let account = ctx
+        .banks_client
+        .get_account(token_account)
+        .await?
+        .unwrap();
+    assert_eq!(account.lamports, rent);
+    assert_eq!(
+        get_token_account(&mut ctx.banks_client, token_account)
+            .await?
+            .balance,
+        0
+    );
+    assert_eq!(
+        ctx.banks_client
+            .get_balance(destination)
+            .await?,
+        LAMPORTS_PER_SOL
+    );
+
+    Ok(())
+}
+
+/// Proposed transfers execute only once enough distinct approvers sign off
+/// and the timelock has passed, and only once. The proposer may then close
+/// the intent.
+#[tokio::test(flavor = "current_thread")]
+async fn transfer_intent() -> Result<(), Box<dyn Error>> {
+    let mut ctx = start().await;
+    let payer = ctx.payer.pubkey();
+    setup_config(&mut ctx).await?;
+    let (mint, _mint_index) = create_mint(&mut ctx, &payer, 6).await?;
+    let from = create_funded_account(&mut ctx, &payer, &mint, 100).await?;
+    let to = create_funded_account(&mut ctx, &Pubkey::new_unique(), &mint, 0)
+        .await?;
+    let approvers: [Keypair; 3] = core::array::from_fn(|_| Keypair::new());
+    let approver_keys = approvers
+        .iter()
+        .map(Keypair::pubkey)
+        .collect::<Vec<_>>();
+    let now = ctx
+        .banks_client
+        .get_sysvar::<Clock>()
+        .await?
+        .unix_timestamp;
+    let propose = |to: &Pubkey, approvers: &[Pubkey], nonce: u64| {
+        instructions::propose_transfer(
+            &from,
+            &payer,
+            to,
+            40,
+            nonce,
+            now + 3600,
+            approvers,
+            2,
+            &payer,
+        )
+    };
+
+    // Each approval must count once, and from and to must be distinct
+    let duplicate = [approver_keys[0], approver_keys[0]];
+    let err =
+        process_instructions(&mut ctx, &[propose(&to, &duplicate, 0)], &[])
+            .await
+            .unwrap_err();
+    assert_eq!(
+        op_error(err),
+        (Some(0), ProgramError::InvalidInstructionData)
+    );
+    let err = process_instructions(
+        &mut ctx,
+        &[propose(&from, &approver_keys, 0)],
+        &[],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        op_error(err),
+        (Some(0), NanoTokenError::DuplicateAccount.into())
+    );
+
+    // Propose 40 with a 2 of 3 threshold and a one hour timelock
+    process_instructions(&mut ctx, &[propose(&to, &approver_keys, 0)], &[])
+        .await
+        .unwrap();
+    let (intent, _bump) = TransferIntent::address(&from, 0);
+    let execute = instructions::execute_transfer(&intent, &from, &to);
+
+    // Strangers cannot approve
+    let stranger = Keypair::new();
+    let err = process_instructions(
+        &mut ctx,
+        &[instructions::approve_transfer(&intent, &stranger.pubkey())],
+        &[&stranger],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(op_error(err), (Some(0), ProgramError::IllegalOwner));
+
+    // One approval is not enough
+    process_instructions(
+        &mut ctx,
+        &[instructions::approve_transfer(&intent, &approver_keys[0])],
+        &[&approvers[0]],
+    )
+    .await
+    .unwrap();
+    let err =
+        process_instructions(&mut ctx, std::slice::from_ref(&execute), &[])
+            .await
+            .unwrap_err();
+    assert_eq!(
+        op_error(err),
+        (Some(0), NanoTokenError::IntentThresholdNotMet.into())
+    );
+
+    // Two are, but the timelock has not passed. Identical transactions are
+    // deduplicated, so retries need a new blockhash.
+    process_instructions(
+        &mut ctx,
+        &[instructions::approve_transfer(&intent, &approver_keys[1])],
+        &[&approvers[1]],
+    )
+    .await
+    .unwrap();
+    ctx.get_new_latest_blockhash().await?;
+    let err =
+        process_instructions(&mut ctx, std::slice::from_ref(&execute), &[])
+            .await
+            .unwrap_err();
+    assert_eq!(
+        op_error(err),
+        (Some(0), NanoTokenError::IntentTimelocked.into())
+    );
+
+    // Execute once the timelock has passed
+    let mut clock = ctx
+        .banks_client
+        .get_sysvar::<Clock>()
+        .await?;
+    clock.unix_timestamp = now + 3600;
+    ctx.set_sysvar(&clock);
+    ctx.get_new_latest_blockhash().await?;
+    process_instructions(&mut ctx, std::slice::from_ref(&execute), &[])
+        .await
+        .unwrap();
+    assert_eq!(
+        get_token_account(&mut ctx.banks_client, from)
+            .await?
+            .balance,
+        60
+    );
+    assert_eq!(
+        get_token_account(&mut ctx.banks_client, to)
+            .await?
+            .balance,
+        40
+    );
+
+    // But only once
+    ctx.get_new_latest_blockhash().await?;
+    let err = process_instructions(&mut ctx, &[execute], &[])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        op_error(err),
+        (Some(0), NanoTokenError::IntentAlreadyExecuted.into())
+    );
+
+    // Only the proposer may close the intent
+    let cancel = |proposer: &Pubkey| {
+        instructions::cancel_transfer(
+            &intent,
+            proposer,
+            proposer,
+            RentDestination::Default,
+        )
+    };
+    let err = process_instructions(
+        &mut ctx,
+        &[cancel(&stranger.pubkey())],
+        &[&stranger],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(op_error(err), (Some(0), ProgramError::IllegalOwner));
+    process_instructions(&mut ctx, &[cancel(&payer)], &[])
+        .await
+        .unwrap();
+    assert!(ctx
+        .banks_client
+        .get_account(intent)
+        .await?
+        .is_none());
+
+    Ok(())
+}
+
+/// A delegate proposes out of its allowance, and becomes the proposer that
+/// may cancel the intent.
+#[tokio::test(flavor = "current_thread")]
+async fn propose_transfer_from_delegate() -> Result<(), Box<dyn Error>> {
+    let mut ctx = start().await;
+    let payer = ctx.payer.pubkey();
+    setup_config(&mut ctx).await?;
+    let (mint, _mint_index) = create_mint(&mut ctx, &payer, 6).await?;
+    let from = create_funded_account(&mut ctx, &payer, &mint, 100).await?;
+    let to = create_funded_account(&mut ctx, &Pubkey::new_unique(), &mint, 0)
+        .await?;
+    let delegate = Keypair::new();
+    process_instructions(
+        &mut ctx,
+        &[instructions::approve(&from, &payer, &delegate.pubkey(), 50)],
+        &[],
+    )
+    .await
+    .unwrap();
+    let propose = |amount: u64, nonce: u64| {
+        instructions::propose_transfer(
+            &from,
+            &delegate.pubkey(),
+            &to,
+            amount,
+            nonce,
+            0,
+            &[Pubkey::new_unique()],
+            1,
+            &payer,
+        )
+    };
+
+    // Proposing spends the allowance
+    process_instructions(&mut ctx, &[propose(30, 0)], &[&delegate])
+        .await
+        .unwrap();
+    let from_account = get_token_account(&mut ctx.banks_client, from).await?;
+    assert_eq!(from_account.balance, 100);
+    assert_eq!(from_account.delegated_amount, 20);
+    let err = process_instructions(&mut ctx, &[propose(21, 1)], &[&delegate])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        op_error(err),
+        (Some(0), NanoTokenError::InsufficientDelegatedAmount.into())
+    );
+
+    // The delegate, not the owner, may cancel
+    let (intent, _bump) = TransferIntent::address(&from, 0);
+    let intent_account = ctx
+        .banks_client
+        .get_account(intent)
+        .await?
+        .unwrap();
+    let intent_state: TransferIntent =
+        bytemuck::pod_read_unaligned(&intent_account.data[8..]);
+    assert_eq!(intent_state.proposer, delegate.pubkey());
+    let cancel = |proposer: &Pubkey| {
+        instructions::cancel_transfer(
+            &intent,
+            proposer,
+            &payer,
+            RentDestination::Default,
+        )
+    };
+    let err = process_instructions(&mut ctx, &[cancel(&payer)], &[])
+        .await
+        .unwrap_err();
+    assert_eq!(op_error(err), (Some(0), ProgramError::IllegalOwner));
+    process_instructions(&mut ctx, &[cancel(&delegate.pubkey())], &[&delegate])
+        .await
+        .unwrap();
+    assert!(ctx
+        .banks_client
+        .get_account(intent)
+        .await?
+        .is_none());
+
+    Ok(())
+}
+
+/// Anyone may submit a transfer the from owner signed offline, once, before
+/// it expires. The ed25519 instruction must verify the owner's signature over
+/// exactly the permitted transfer.
+#[tokio::test(flavor = "current_thread")]
+async fn permit_transfer() -> Result<(), Box<dyn Error>> {
+    let mut ctx = start().await;
+    let payer = ctx.payer.pubkey();
+    setup_config(&mut ctx).await?;
+    let (mint, _mint_index) = create_mint(&mut ctx, &payer, 6).await?;
+    let owner = Keypair::new();
+    let from =
+        create_funded_account(&mut ctx, &owner.pubkey(), &mint, 100).await?;
+    let to = create_funded_account(&mut ctx, &payer, &mint, 0).await?;
+    let now = ctx
+        .banks_client
+        .get_sysvar::<Clock>()
+        .await?
+        .unix_timestamp;
+
+    // The payer relays a permit for `amount` with the signature of `signer`
+    // over a permit for `signed_amount`
+    let permit = |signer: &Keypair,
+                  signed_amount: u64,
+                  amount: u64,
+                  nonce: u64,
+                  expiry: i64| {
+        let message = bytemuck::bytes_of(&instructions::permit_message(
+            &from,
+            &to,
+            signed_amount,
+            nonce,
+            expiry,
+        ))
+        .to_vec();
+        [
+            instructions::permit_transfer(&from, &to, amount, nonce, expiry),
+            instructions::ed25519_verify(
+                &signer.pubkey(),
+                &signer.sign_message(&message),
+                &message,
+            ),
+        ]
+    };
+    let expiry = now + 3600;
+
+    // Signed by someone other than the owner
+    let err = process_instructions(
+        &mut ctx,
+        &permit(&Keypair::new(), 10, 10, 0, expiry),
+        &[],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        op_error(err),
+        (Some(0), NanoTokenError::InvalidPermit.into())
+    );
+
+    // The owner signed a different amount
+    let err =
+        process_instructions(&mut ctx, &permit(&owner, 10, 99, 0, expiry), &[])
+            .await
+            .unwrap_err();
+    assert_eq!(
+        op_error(err),
+        (Some(0), NanoTokenError::InvalidPermit.into())
+    );
+
+    // Not the account's next nonce
+    let err =
+        process_instructions(&mut ctx, &permit(&owner, 10, 10, 1, expiry), &[])
+            .await
+            .unwrap_err();
+    assert_eq!(
+        op_error(err),
+        (Some(0), NanoTokenError::InvalidPermit.into())
+    );
+
+    // Expired
+    let err = process_instructions(
+        &mut ctx,
+        &permit(&owner, 10, 10, 0, now - 1),
+        &[],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        op_error(err),
+        (Some(0), NanoTokenError::PermitExpired.into())
+    );
+
+    // Valid, and consumes the nonce
+    let valid = permit(&owner, 10, 10, 0, expiry);
+    process_instructions(&mut ctx, &valid, &[])
+        .await
+        .unwrap();
+    let from_account = get_token_account(&mut ctx.banks_client, from).await?;
+    assert_eq!(from_account.balance, 90);
+    assert_eq!(from_account.permit_nonce, 1);
+    assert_eq!(
+        get_token_account(&mut ctx.banks_client, to)
+            .await?
+            .balance,
+        10
+    );
+
+    // So it cannot be replayed, even in a fresh transaction
+    ctx.get_new_latest_blockhash().await?;
+    let err = process_instructions(&mut ctx, &valid, &[])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        op_error(err),
+        (Some(0), NanoTokenError::InvalidPermit.into())
+    );
+
+    Ok(())
+}
+
+/// Only the mint's clawback authority may move tokens out of other owners'
+/// accounts, and never more than their balance.
+#[tokio::test(flavor = "current_thread")]
+async fn clawback() -> Result<(), Box<dyn Error>> {
+    let mut ctx = start().await;
+    let payer = ctx.payer.pubkey();
+    setup_config(&mut ctx).await?;
+    let clawback_authority = Keypair::new();
+    let (mint, _mint_index) = create_mint_with(
+        &mut ctx,
+        InitializeMintArgs {
+            authority: payer,
+            decimals: 6,
+            clawback_authority: clawback_authority.pubkey(),
+            ..Zeroable::zeroed()
+        },
+    )
+    .await?;
+    let holder =
+        create_funded_account(&mut ctx, &Pubkey::new_unique(), &mint, 100)
+            .await?;
+    let treasury = create_funded_account(&mut ctx, &payer, &mint, 0).await?;
+
+    // Not the clawback authority
+    let stranger = Keypair::new();
+    let err = process_instructions(
+        &mut ctx,
+        &[instructions::clawback(
+            &holder,
+            &treasury,
+            &mint,
+            &stranger.pubkey(),
+            10,
+        )],
+        &[&stranger],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(op_error(err), (Some(0), ProgramError::IllegalOwner));
+
+    // More than the holder has
+    let clawback = |amount: u64| {
+        instructions::clawback(
+            &holder,
+            &treasury,
+            &mint,
+            &clawback_authority.pubkey(),
+            amount,
+        )
+    };
+    let err = process_instructions(
+        &mut ctx,
+        &[clawback(101)],
+        &[&clawback_authority],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        op_error(err),
+        (Some(0), NanoTokenError::InsufficientTokenBalance.into())
+    );
+
+    process_instructions(&mut ctx, &[clawback(60)], &[&clawback_authority])
+        .await
+        .unwrap();
+    assert_eq!(
+        get_token_account(&mut ctx.banks_client, holder)
+            .await?
+            .balance,
+        40
+    );
+    assert_eq!(
+        get_token_account(&mut ctx.banks_client, treasury)
+            .await?
+            .balance,
+        60
+    );
+
+    Ok(())
+}
+
+/// Only empty, unfrozen accounts past the config's min age are collected, and
+/// their rent goes to the recorded rent payer or the config rent destination,
+/// less the collector's bounty.
+#[tokio::test(flavor = "current_thread")]
+async fn garbage_collect() -> Result<(), Box<dyn Error>> {
+    let mut ctx = start().await;
+    let payer = ctx.payer.pubkey();
+    setup_config(&mut ctx).await?;
+    let (mint, mint_index) = create_mint_with(
+        &mut ctx,
+        InitializeMintArgs {
+            authority: payer,
+            decimals: 6,
+            freeze_authority: payer,
+            ..Zeroable::zeroed()
+        },
+    )
+    .await?;
+    let bounty = LAMPORTS_PER_SOL / 1000;
+    process_instructions(
+        &mut ctx,
+        &[instructions::set_gc_config(&payer, 3600, bounty, &payer)],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    // The empty account's rent is paid by a separate funder
+    let rent_payer = Keypair::new();
+    process_instructions(
+        &mut ctx,
+        &[solana_program::system_instruction::transfer(
+            &payer,
+            &rent_payer.pubkey(),
+            LAMPORTS_PER_SOL,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    let owner = Pubkey::new_unique();
+    process_instructions(
+        &mut ctx,
+        &[instructions::initialize_account(
+            &owner,
+            mint_index,
+            false,
+            false,
+            &rent_payer.pubkey(),
+        )],
+        &[&rent_payer],
+    )
+    .await
+    .unwrap();
+    let (empty, _bump) = pda::token_account_address(&owner, mint_index);
+    let funded =
+        create_funded_account(&mut ctx, &Pubkey::new_unique(), &mint, 10)
+            .await?;
+    let collector = Keypair::new().pubkey();
+    let collect = |account: &Pubkey, rent_payer: &Pubkey| {
+        instructions::garbage_collect(
+            account,
+            rent_payer,
+            &collector,
+            RentDestination::Default,
+            &payer,
+        )
+    };
+
+    let err = process_instructions(
+        &mut ctx,
+        &[collect(&empty, &rent_payer.pubkey())],
+        &[],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        op_error(err),
+        (Some(0), NanoTokenError::AccountTooYoung.into())
+    );
+
+    let mut clock = ctx
+        .banks_client
+        .get_sysvar::<Clock>()
+        .await?;
+    clock.unix_timestamp += 3600;
+    ctx.set_sysvar(&clock);
+    ctx.get_new_latest_blockhash().await?;
+
+    // Non-zero balance
+    let err = process_instructions(&mut ctx, &[collect(&funded, &payer)], &[])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        op_error(err),
+        (Some(0), NanoTokenError::NonZeroBalance.into())
+    );
+
+    // Frozen
+    process_instructions(
+        &mut ctx,
+        &[instructions::freeze_account(&empty, &mint, &payer)],
+        &[],
+    )
+    .await
+    .unwrap();
+    let err = process_instructions(
+        &mut ctx,
+        &[collect(&empty, &rent_payer.pubkey())],
+        &[],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        op_error(err),
+        (Some(0), NanoTokenError::AccountFrozen.into())
+    );
+    process_instructions(
+        &mut ctx,
+        &[instructions::thaw_account(&empty, &mint, &payer)],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    // Rent can't be redirected away from the rent payer
+    let err = process_instructions(&mut ctx, &[collect(&empty, &payer)], &[])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        op_error(err),
+        (Some(0), NanoTokenError::RentDestinationMismatch.into())
+    );
+
+    let rent = ctx
+        .banks_client
+        .get_balance(empty)
+        .await?;
+    let rent_payer_before = ctx
+        .banks_client
+        .get_balance(rent_payer.pubkey())
+        .await?;
+    process_instructions(
+        &mut ctx,
+        &[collect(&empty, &rent_payer.pubkey())],
+        &[],
+    )
+    .await
+    .unwrap();
+    assert!(ctx
+        .banks_client
+        .get_account(empty)
+        .await?
+        .is_none());
+    assert_eq!(
+        ctx.banks_client
+            .get_balance(collector)
+            .await?,
+        bounty
+    );
+    assert_eq!(
+        ctx.banks_client
+            .get_balance(rent_payer.pubkey())
+            .await?,
+        rent_payer_before + rent - bounty
+    );
+
+    // Or to the config rent destination, if selected
+    let treasury = Pubkey::new_unique();
+    let empty =
+        create_funded_account(&mut ctx, &Pubkey::new_unique(), &mint, 0)
+            .await?;
+    let mut clock = ctx
+        .banks_client
+        .get_sysvar::<Clock>()
+        .await?;
+    clock.unix_timestamp += 3600;
+    ctx.set_sysvar(&clock);
+    let rent = ctx
+        .banks_client
+        .get_balance(empty)
+        .await?;
+    process_instructions(
+        &mut ctx,
+        &[
+            instructions::set_rent_destination(&payer, Some(&treasury), &payer),
+            instructions::garbage_collect(
+                &empty,
+                &treasury,
+                &collector,
+                RentDestination::Config,
+                &payer,
+            ),
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        ctx.banks_client
+            .get_balance(treasury)
+            .await?,
+        rent - bounty
+    );
+    assert_eq!(
+        ctx.banks_client
+            .get_balance(collector)
+            .await?,
+        2 * bounty
+    );
+
+    Ok(())
+}
+
+/// Lists grow one entry at a time as owners are added and stay sorted as they
+/// are removed. An allowlist blocks transfers to unlisted owners and a
+/// denylist blocks transfers to listed ones.
+#[tokio::test(flavor = "current_thread")]
+async fn mint_list() -> Result<(), Box<dyn Error>> {
+    let mut ctx = start().await;
+    let payer = ctx.payer.pubkey();
+    setup_config(&mut ctx).await?;
+    let sender = Keypair::new();
+    let listed = Pubkey::new_unique();
+    let unlisted = Pubkey::new_unique();
+
+    for mode in [ListMode::Allow, ListMode::Deny] {
+        let allow = matches!(mode, ListMode::Allow);
+        let (mint, _mint_index) = create_mint(&mut ctx, &payer, 6).await?;
+        let mut ixs = vec![instructions::initialize_list(
+            &mint, &payer, &payer, mode, &payer,
+        )];
+        if allow {
+            ixs.push(instructions::add_to_list(
+                &mint,
+                &payer,
+                &sender.pubkey(),
+                &payer,
+            ));
+        }
+        ixs.push(instructions::add_to_list(&mint, &payer, &listed, &payer));
+        process_instructions(&mut ctx, &ixs, &[])
+            .await
+            .unwrap();
+
+        let from =
+            create_funded_account(&mut ctx, &sender.pubkey(), &mint, 100)
+                .await?;
+        let to_listed =
+            create_funded_account(&mut ctx, &listed, &mint, 0).await?;
+        let to_unlisted =
+            create_funded_account(&mut ctx, &unlisted, &mint, 0).await?;
+        let transfer = |to: &Pubkey| {
+            instructions::with_mint_list(
+                instructions::transfer(&from, to, &sender.pubkey(), 10),
+                &mint,
+            )
+        };
+        let (allowed, blocked) = match allow {
+            true => (to_listed, to_unlisted),
+            false => (to_unlisted, to_listed),
+        };
+        process_instructions(&mut ctx, &[transfer(&allowed)], &[&sender])
+            .await
+            .unwrap();
+        let err =
+            process_instructions(&mut ctx, &[transfer(&blocked)], &[&sender])
+                .await
+                .unwrap_err();
+        assert_eq!(
+            op_error(err),
+            (Some(0), NanoTokenError::OwnerNotAllowed.into())
+        );
+        assert_eq!(
+            get_token_account(&mut ctx.banks_client, allowed)
+                .await?
+                .balance,
+            10
+        );
+    }
+
+    // Start from an empty list, which has no room for entries
+    let (mint, _mint_index) = create_mint(&mut ctx, &payer, 6).await?;
+    let (list, _bump) = MintList::address(&mint);
+    process_instructions(
+        &mut ctx,
+        &[instructions::initialize_list(
+            &mint,
+            &payer,
+            &payer,
+            ListMode::Deny,
+            &payer,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    let read_list = |data: &[u8]| {
+        let list: MintList =
+            bytemuck::pod_read_unaligned(&data[8..MintList::space()]);
+        data[MintList::space()..]
+            .chunks_exact(32)
+            .take(list.len as usize)
+            .map(bytemuck::pod_read_unaligned)
+            .collect::<Vec<Pubkey>>()
+    };
+    let account = ctx
+        .banks_client
+        .get_account(list)
+        .await?
+        .ok_or("could not find list")?;
+    assert_eq!(MintList::capacity(account.data.len()), 0);
+
+    let owners: [Pubkey; 3] = core::array::from_fn(|_| Pubkey::new_unique());
+    process_instructions(
+        &mut ctx,
+        &owners
+            .iter()
+            .map(|owner| {
+                instructions::add_to_list(&mint, &payer, owner, &payer)
+            })
+            .collect::<Vec<_>>(),
+        &[],
+    )
+    .await
+    .unwrap();
+    let account = ctx
+        .banks_client
+        .get_account(list)
+        .await?
+        .ok_or("could not find list")?;
+    assert_eq!(MintList::capacity(account.data.len()), 3);
+    assert_eq!(
+        account.lamports,
+        Rent::default().minimum_balance(account.data.len())
+    );
+    let mut sorted = owners.to_vec();
+    sorted.sort();
+    assert_eq!(read_list(&account.data), sorted);
+
+    // Removing from the middle keeps the rest sorted and the freed slot
+    process_instructions(
+        &mut ctx,
+        &[instructions::remove_from_list(&mint, &payer, &sorted[1])],
+        &[],
+    )
+    .await
+    .unwrap();
+    let account = ctx
+        .banks_client
+        .get_account(list)
+        .await?
+        .ok_or("could not find list")?;
+    assert_eq!(MintList::capacity(account.data.len()), 3);
+    assert_eq!(read_list(&account.data), [sorted[0], sorted[2]]);
+
+    Ok(())
+}
+
+/// Royalties round down and come out of the transferred amount. The recipient
+/// may be the transfer's own from or to, but not an intent's.
+#[tokio::test(flavor = "current_thread")]
+async fn royalty() -> Result<(), Box<dyn Error>> {
+    let mut ctx = start().await;
+    let payer = ctx.payer.pubkey();
+    setup_config(&mut ctx).await?;
+    let (mint, _mint_index) = create_mint(&mut ctx, &payer, 6).await?;
+    let vault =
+        create_funded_account(&mut ctx, &Pubkey::new_unique(), &mint, 0)
+            .await?;
+
+    // The recipient account must be the one in the args
+    let mut set_royalty = instructions::set_royalty(&mint, &payer, 250, &vault);
+    set_royalty.accounts[2].pubkey = Pubkey::new_unique();
+    let err = process_instructions(&mut ctx, &[set_royalty], &[])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        op_error(err),
+        (Some(0), NanoTokenError::RoyaltyRecipientMismatch.into())
+    );
+    process_instructions(
+        &mut ctx,
+        &[instructions::set_royalty(&mint, &payer, 250, &vault)],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    let from = create_funded_account(&mut ctx, &payer, &mint, 1000).await?;
+    let to = create_funded_account(&mut ctx, &Pubkey::new_unique(), &mint, 0)
+        .await?;
+    async fn balances(
+        client: &mut BanksClient,
+        keys: [Pubkey; 3],
+    ) -> Result<[u64; 3], Box<dyn Error>> {
+        let mut balances = [0; 3];
+        for (balance, key) in balances.iter_mut().zip(keys) {
+            *balance = get_token_account(client, key)
+                .await?
+                .balance;
+        }
+        Ok(balances)
+    }
+    let transfer = |amount: u64, recipient: &Pubkey| {
+        instructions::with_royalty(
+            instructions::transfer(&from, &to, &payer, amount),
+            &mint,
+            recipient,
+        )
+    };
+
+    // 2.5% of 399 is 9.975, rounded down
+    process_instructions(&mut ctx, &[transfer(399, &vault)], &[])
+        .await
+        .unwrap();
+    assert_eq!(
+        balances(&mut ctx.banks_client, [from, to, vault]).await?,
+        [601, 390, 9]
+    );
+    // The vault predates the royalty, and picks it up with its first one
+    let vault_account = get_token_account(&mut ctx.banks_client, vault).await?;
+    assert_ne!(vault_account.state & TokenAccount::ROYALTY, 0);
+
+    // A recipient aliasing to or from just gets its share back
+    process_instructions(
+        &mut ctx,
+        &[
+            instructions::set_royalty(&mint, &payer, 250, &to),
+            transfer(400, &to),
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        balances(&mut ctx.banks_client, [from, to, vault]).await?,
+        [201, 790, 9]
+    );
+    process_instructions(
+        &mut ctx,
+        &[
+            instructions::set_royalty(&mint, &payer, 250, &from),
+            transfer(200, &from),
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        balances(&mut ctx.banks_client, [from, to, vault]).await?,
+        [6, 985, 9]
+    );
+
+    // Executing an intent borrows from and to, so the recipient can't alias
+    // them there
+    let (intent, _bump) = TransferIntent::address(&from, 0);
+    process_instructions(
+        &mut ctx,
+        &[instructions::propose_transfer(
+            &from,
+            &payer,
+            &to,
+            5,
+            0,
+            0,
+            &[],
+            0,
+            &payer,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    let err = process_instructions(
+        &mut ctx,
+        &[instructions::with_royalty(
+            instructions::execute_transfer(&intent, &from, &to),
+            &mint,
+            &from,
+        )],
+        &[],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        op_error(err),
+        (Some(0), NanoTokenError::DuplicateAccount.into())
+    );
+
+    // A royalty can be turned off after minting, but not back on
+    process_instructions(
+        &mut ctx,
+        &[instructions::set_royalty(&mint, &payer, 0, &vault)],
+        &[],
+    )
+    .await
+    .unwrap();
+    let err = process_instructions(
+        &mut ctx,
+        &[instructions::set_royalty(&mint, &payer, 250, &vault)],
+        &[],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        op_error(err),
+        (Some(0), NanoTokenError::NonZeroSupply.into())
+    );
+
+    Ok(())
+}
+
+/// Only the freeze authority may freeze an account, and a frozen account
+/// can't send until it is thawed.
+#[tokio::test(flavor = "current_thread")]
+async fn freeze_and_thaw() -> Result<(), Box<dyn Error>> {
+    let mut ctx = start().await;
+    let payer = ctx.payer.pubkey();
+    setup_config(&mut ctx).await?;
+    let freeze_authority = Keypair::new();
+    let (mint, _mint_index) = create_mint_with(
+        &mut ctx,
+        InitializeMintArgs {
+            authority: payer,
+            decimals: 6,
+            freeze_authority: freeze_authority.pubkey(),
+            ..Zeroable::zeroed()
+        },
+    )
+    .await?;
+    let from = create_funded_account(&mut ctx, &payer, &mint, 100).await?;
+    let to = create_funded_account(&mut ctx, &Pubkey::new_unique(), &mint, 0)
+        .await?;
+
+    let err = process_instructions(
+        &mut ctx,
+        &[instructions::freeze_account(&from, &mint, &payer)],
+        &[],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(op_error(err), (Some(0), ProgramError::IllegalOwner));
+
+    process_instructions(
+        &mut ctx,
+        &[instructions::freeze_account(
+            &from,
+            &mint,
+            &freeze_authority.pubkey(),
+        )],
+        &[&freeze_authority],
+    )
+    .await
+    .unwrap();
+    let transfer = instructions::transfer(&from, &to, &payer, 10);
+    let err =
+        process_instructions(&mut ctx, std::slice::from_ref(&transfer), &[])
+            .await
+            .unwrap_err();
+    assert_eq!(
+        op_error(err),
+        (Some(0), NanoTokenError::AccountFrozen.into())
+    );
+
+    process_instructions(
+        &mut ctx,
+        &[
+            instructions::thaw_account(
+                &from,
+                &mint,
+                &freeze_authority.pubkey(),
+            ),
+            transfer,
+        ],
+        &[&freeze_authority],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        get_token_account(&mut ctx.banks_client, to)
+            .await?
+            .balance,
+        10
+    );
+
+    Ok(())
+}
+
+/// Only the pause authority may pause a mint, and transfers out of its
+/// accounts are refused until it is unpaused.
+#[tokio::test(flavor = "current_thread")]
+async fn pause_and_unpause() -> Result<(), Box<dyn Error>> {
+    let mut ctx = start().await;
+    let payer = ctx.payer.pubkey();
+    setup_config(&mut ctx).await?;
+    let pause_authority = Keypair::new();
+    let (mint, _mint_index) = create_mint_with(
+        &mut ctx,
+        InitializeMintArgs {
+            authority: payer,
+            decimals: 6,
+            pause_authority: pause_authority.pubkey(),
+            ..Zeroable::zeroed()
+        },
+    )
+    .await?;
+    let from = create_funded_account(&mut ctx, &payer, &mint, 100).await?;
+    let to = create_funded_account(&mut ctx, &Pubkey::new_unique(), &mint, 0)
+        .await?;
+    // Pause ops have two accounts, so they are padded by the batch
+    let batch = |op: Instruction| {
+        BatchBuilder::new(&payer)
+            .push(op)
+            .build()
+    };
+
+    let err = process_instructions(
+        &mut ctx,
+        &[batch(instructions::pause(&mint, &payer))],
+        &[],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(op_error(err), (Some(0), ProgramError::IllegalOwner));
+
+    process_instructions(
+        &mut ctx,
+        &[batch(instructions::pause(&mint, &pause_authority.pubkey()))],
+        &[&pause_authority],
+    )
+    .await
+    .unwrap();
+    let transfer = instructions::with_mint(
+        instructions::transfer(&from, &to, &payer, 10),
+        &mint,
+    );
+    let err =
+        process_instructions(&mut ctx, std::slice::from_ref(&transfer), &[])
+            .await
+            .unwrap_err();
+    assert_eq!(op_error(err), (Some(0), NanoTokenError::MintPaused.into()));
+
+    // The mint must be passed to check it isn't paused
+    let err = process_instructions(
+        &mut ctx,
+        &[instructions::transfer(&from, &to, &payer, 10)],
+        &[],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(op_error(err), (Some(0), ProgramError::NotEnoughAccountKeys));
+
+    process_instructions(
+        &mut ctx,
+        &[
+            batch(instructions::unpause(&mint, &pause_authority.pubkey())),
+            transfer,
+        ],
+        &[&pause_authority],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        get_token_account(&mut ctx.banks_client, to)
+            .await?
+            .balance,
+        10
+    );
+
+    Ok(())
+}
+
+/// Transfer fees round up, are capped at the max fee, and are withheld in the
+/// destination until the fee authority withdraws them.
+#[tokio::test(flavor = "current_thread")]
+async fn transfer_fees() -> Result<(), Box<dyn Error>> {
+    let mut ctx = start().await;
+    let payer = ctx.payer.pubkey();
+    setup_config(&mut ctx).await?;
+    let fee_authority = Keypair::new();
+    let (mint, _mint_index) = create_mint_with(
+        &mut ctx,
+        InitializeMintArgs {
+            authority: payer,
+            decimals: 6,
+            transfer_fee: TransferFeeConfig {
+                authority: fee_authority.pubkey(),
+                bps: 100,
+                max_fee: 5,
+            },
+            ..Zeroable::zeroed()
+        },
+    )
+    .await?;
+    let from = create_funded_account(&mut ctx, &payer, &mint, 1000).await?;
+    let to = create_funded_account(&mut ctx, &Pubkey::new_unique(), &mint, 0)
+        .await?;
+    let treasury =
+        create_funded_account(&mut ctx, &fee_authority.pubkey(), &mint, 0)
+            .await?;
+
+    // 1% of 150 is 1.5, rounded up. 1% of 800 is 8, capped at 5.
+    process_instructions(
+        &mut ctx,
+        &[
+            instructions::transfer(&from, &to, &payer, 150),
+            instructions::transfer(&from, &to, &payer, 800),
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
+    let to_account = get_token_account(&mut ctx.banks_client, to).await?;
+    assert_eq!(to_account.balance, 950 - 7);
+    assert_eq!(to_account.withheld_fees, 7);
+
+    let err = process_instructions(
+        &mut ctx,
+        &[instructions::withdraw_withheld_fees(
+            &mint,
+            &payer,
+            &treasury,
+            &[to],
+        )],
+        &[],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(op_error(err), (Some(0), ProgramError::IllegalOwner));
+
+    process_instructions(
+        &mut ctx,
+        &[instructions::withdraw_withheld_fees(
+            &mint,
+            &fee_authority.pubkey(),
+            &treasury,
+            &[to],
+        )],
+        &[&fee_authority],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        get_token_account(&mut ctx.banks_client, to)
+            .await?
+            .withheld_fees,
+        0
+    );
+    // The treasury never held tokens before, so it now carries the fee too
+    let treasury_account =
+        get_token_account(&mut ctx.banks_client, treasury).await?;
+    assert_eq!(treasury_account.balance, 7);
+    assert_eq!(treasury_account.transfer_fee_bps, 100);
+    assert_eq!(treasury_account.max_transfer_fee, 5);
+
+    Ok(())
+}
+
+/// Wrapped lamports, including ones synced in from a direct transfer, come
+/// back out exactly on unwrap.
+#[tokio::test(flavor = "current_thread")]
+async fn wrap_and_unwrap_sol() -> Result<(), Box<dyn Error>> {
+    let mut ctx = start().await;
+    let payer = ctx.payer.pubkey();
+    setup_config(&mut ctx).await?;
+    let owner = Keypair::new();
+    let (token_account, _bump) =
+        pda::token_account_address(&owner.pubkey(), NATIVE_MINT_INDEX);
+    process_instructions(
+        &mut ctx,
+        &[
+            instructions::initialize_account(
+                &owner.pubkey(),
+                NATIVE_MINT_INDEX,
+                false,
+                false,
+                &payer,
+            ),
+            instructions::wrap_sol(
+                &owner.pubkey(),
+                &payer,
+                2 * LAMPORTS_PER_SOL,
+                &payer,
+            ),
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
+    let rent = Rent::default().minimum_balance(TokenAccount::space());
+    assert_eq!(
+        ctx.banks_client
+            .get_balance(token_account)
+            .await?,
+        rent + 2 * LAMPORTS_PER_SOL
+    );
+
+    // Lamports sent directly only count once synced. Sync has one account,
+    // so it is padded by the batch.
+    process_instructions(
+        &mut ctx,
+        &[
+            solana_program::system_instruction::transfer(
+                &payer,
+                &token_account,
+                LAMPORTS_PER_SOL / 2,
+            ),
+            BatchBuilder::new(&payer)
+                .push(instructions::sync_native(&token_account))
+                .build(),
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
+    let wrapped = 5 * LAMPORTS_PER_SOL / 2;
+    assert_eq!(
+        get_token_account(&mut ctx.banks_client, token_account)
+            .await?
+            .balance,
+        wrapped
+    );
+
+    let destination = Pubkey::new_unique();
+    let err = process_instructions(
+        &mut ctx,
+        &[instructions::unwrap_sol(
+            &owner.pubkey(),
+            &destination,
+            wrapped + 1,
+        )],
+        &[&owner],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        op_error(err),
+        (Some(0), NanoTokenError::InsufficientTokenBalance.into())
+    );
+
+    process_instructions(
+        &mut ctx,
+        &[instructions::unwrap_sol(
+            &owner.pubkey(),
+            &destination,
+            wrapped,
+        )],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        ctx.banks_client
+            .get_balance(destination)
+            .await?,
+        wrapped
+    );
+    assert_eq!(
+        ctx.banks_client
+            .get_balance(token_account)
+            .await?,
+        rent
+    );
+    assert_eq!(
+        get_token_account(&mut ctx.banks_client, token_account)
+            .await?
+            .balance,
+        0
+    );
+
+    Ok(())
+}
+
+/// MigrateAccount moves a v1 token account to the latest layout. The new
+/// fields are zeroed and the extension bytes past the layout move to the end
+/// of the new layout unchanged.
+#[tokio::test(flavor = "current_thread")]
+async fn migrate_account() -> Result<(), Box<dyn Error>> {
+    let mut ctx = start().await;
+    let payer = ctx.payer.pubkey();
+    setup_config(&mut ctx).await?;
+    let (mint, _mint_index) = create_mint(&mut ctx, &payer, 6).await?;
+    let token_account =
+        create_funded_account(&mut ctx, &payer, &mint, 1_000).await?;
+
+    // Roll it back to v1 with 64 bytes of extension past the layout
+    let extension = [0xab; 64];
+    let mut account = ctx
+        .banks_client
+        .get_account(token_account)
+        .await?
+        .ok_or("could not find token account")?;
+    account
+        .data
+        .truncate(8 + TOKEN_ACCOUNT_V1_SIZE);
+    account.data.extend(extension);
+    account.data[1] = 1;
+    ctx.set_account(&token_account, &account.into());
+
+    // It can't be used until it is migrated
+    let err = process_instructions(
+        &mut ctx,
+        &[instructions::transfer(
+            &token_account,
+            &token_account,
+            &payer,
+            1,
+        )],
+        &[],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        op_error(err),
+        (Some(0), NanoTokenError::AccountNeedsMigration.into())
+    );
+
+    process_instructions(
+        &mut ctx,
+        &[instructions::migrate_account(&token_account, &payer)],
+        &[],
+    )
+    .await
+    .unwrap();
+    let data = ctx
+        .banks_client
+        .get_account(token_account)
+        .await?
+        .ok_or("could not find token account")?
+        .data;
+    assert_eq!(data.len(), TokenAccount::space() + extension.len());
+    assert_eq!(data[1], TOKEN_ACCOUNT_VERSION);
+    assert_eq!(data[TokenAccount::space()..], extension);
+    let migrated = TokenAccount::try_unpack(&data)?;
+    assert_eq!(migrated.balance, 1_000);
+    assert_eq!(migrated.owner, payer);
+    assert_eq!(migrated.unlock_at, 0);
+
+    Ok(())
+}
+
+/// A BatchTransfer debits the source once and credits every destination its
+/// own amount.
+#[tokio::test(flavor = "current_thread")]
+async fn batch_transfer() -> Result<(), Box<dyn Error>> {
+    let mut ctx = start().await;
+    let payer = ctx.payer.pubkey();
+    setup_config(&mut ctx).await?;
+    let (mint, _mint_index) = create_mint(&mut ctx, &payer, 6).await?;
+    let from = create_funded_account(&mut ctx, &payer, &mint, 1_000).await?;
+
+    let mut transfers = vec![];
+    for amount in [100, 200, 300] {
+        let to =
+            create_funded_account(&mut ctx, &Pubkey::new_unique(), &mint, 0)
+                .await?;
+        transfers.push((to, amount));
+    }
+    process_instructions(
+        &mut ctx,
+        &[instructions::batch_transfer(&from, &payer, &transfers)],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    let from_account = get_token_account(&mut ctx.banks_client, from).await?;
+    assert_eq!(from_account.balance, 400);
+    for (to, amount) in transfers {
+        let to_account = get_token_account(&mut ctx.banks_client, to).await?;
+        assert_eq!(to_account.balance, amount);
+    }
+
+    Ok(())
+}
+
+/// TransferAll sweeps the whole source balance into the destination.
+#[tokio::test(flavor = "current_thread")]
+async fn transfer_all() -> Result<(), Box<dyn Error>> {
+    let mut ctx = start().await;
+    let payer = ctx.payer.pubkey();
+    setup_config(&mut ctx).await?;
+    let (mint, _mint_index) = create_mint(&mut ctx, &payer, 6).await?;
+    let from = create_funded_account(&mut ctx, &payer, &mint, 1_234).await?;
+    let to = create_funded_account(&mut ctx, &Pubkey::new_unique(), &mint, 100)
+        .await?;
+
+    process_instructions(
+        &mut ctx,
+        &[instructions::transfer_all(&from, &to, &payer)],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    let from_account = get_token_account(&mut ctx.banks_client, from).await?;
+    let to_account = get_token_account(&mut ctx.banks_client, to).await?;
+    assert_eq!(from_account.balance, 0);
+    assert_eq!(to_account.balance, 1_334);
+
+    Ok(())
+}
+
+/// BurnChecked burns like Burn but rejects decimals that don't match the
+/// mint, and a mint that doesn't match the token account.
+#[tokio::test(flavor = "current_thread")]
+async fn burn_checked() -> Result<(), Box<dyn Error>> {
+    let mut ctx = start().await;
+    let payer = ctx.payer.pubkey();
+    setup_config(&mut ctx).await?;
+    let (mint, _mint_index) = create_mint(&mut ctx, &payer, 6).await?;
+    let (other_mint, _other_mint_index) =
+        create_mint(&mut ctx, &payer, 6).await?;
+    let from = create_funded_account(&mut ctx, &payer, &mint, 1_000).await?;
+
+    // Wrong decimals
+    let err = process_instructions(
+        &mut ctx,
+        &[instructions::burn_checked(&from, &mint, &payer, 100, 9)],
+        &[],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        op_error(err),
+        (Some(0), NanoTokenError::MintDecimalsMismatch.into())
+    );
+
+    // Right decimals, wrong mint
+    let err = process_instructions(
+        &mut ctx,
+        &[instructions::burn_checked(
+            &from,
+            &other_mint,
+            &payer,
+            100,
+            6,
+        )],
+        &[],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        op_error(err),
+        (Some(0), NanoTokenError::IncorrectMint.into())
+    );
+
+    process_instructions(
+        &mut ctx,
+        &[instructions::burn_checked(&from, &mint, &payer, 100, 6)],
+        &[],
+    )
+    .await
+    .unwrap();
+    let from_account = get_token_account(&mut ctx.banks_client, from).await?;
+    assert_eq!(from_account.balance, 900);
+    assert_eq!(
+        get_mint(&mut ctx.banks_client, mint)
+            .await?
+            .supply,
+        900
+    );
+
+    Ok(())
+}
+
+/// CloseMint reclaims the rent of a mint with zero supply and refuses one
+/// that still has tokens outstanding.
+#[tokio::test(flavor = "current_thread")]
+async fn close_mint() -> Result<(), Box<dyn Error>> {
+    let mut ctx = start().await;
+    let payer = ctx.payer.pubkey();
+    setup_config(&mut ctx).await?;
+    let destination = Pubkey::new_unique();
+
+    // Outstanding supply
+    let (mint, _mint_index) = create_mint(&mut ctx, &payer, 6).await?;
+    create_funded_account(&mut ctx, &payer, &mint, 1_000).await?;
+    let err = process_instructions(
+        &mut ctx,
+        &[instructions::close_mint(
+            &mint,
+            &payer,
+            &destination,
+            RentDestination::Default,
+        )],
+        &[],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        op_error(err),
+        (Some(0), NanoTokenError::NonZeroSupply.into())
+    );
+
+    // Zero supply
+    let (empty_mint, _empty_mint_index) =
+        create_mint(&mut ctx, &payer, 6).await?;
+    let mint_lamports = ctx
+        .banks_client
+        .get_balance(empty_mint)
+        .await?;
+    process_instructions(
+        &mut ctx,
+        &[instructions::close_mint(
+            &empty_mint,
+            &payer,
+            &destination,
+            RentDestination::Default,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    assert!(ctx
+        .banks_client
+        .get_account(empty_mint)
+        .await?
+        .is_none());
+    assert_eq!(
+        ctx.banks_client
+            .get_balance(destination)
+            .await?,
+        mint_lamports
+    );
+
+    Ok(())
+}
+
+/// Transfers into an account that requires memos fail unless the transaction
+/// carries an SPL memo instruction.
+#[tokio::test(flavor = "current_thread")]
+async fn memo_required() -> Result<(), Box<dyn Error>> {
+    let mut ctx = start().await;
+    let payer = ctx.payer.pubkey();
+    setup_config(&mut ctx).await?;
+    let (mint, _mint_index) = create_mint(&mut ctx, &payer, 6).await?;
+    let from = create_funded_account(&mut ctx, &payer, &mint, 1_000).await?;
+    let owner = Keypair::new();
+    let to = create_funded_account(&mut ctx, &owner.pubkey(), &mint, 0).await?;
+    process_instructions(
+        &mut ctx,
+        &[BatchBuilder::new(&payer)
+            .push(instructions::set_memo_required(&to, &owner.pubkey(), true))
+            .build()],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+
+    let transfer = instructions::with_instructions_sysvar(
+        instructions::transfer(&from, &to, &payer, 100),
+    );
+    let err =
+        process_instructions(&mut ctx, std::slice::from_ref(&transfer), &[])
+            .await
+            .unwrap_err();
+    assert_eq!(
+        op_error(err),
+        (Some(0), NanoTokenError::MemoRequired.into())
+    );
+
+    let memo = Instruction {
+        program_id: SPL_MEMO_PROGRAMS[0],
+        accounts: vec![],
+        data: b"invoice 42".to_vec(),
+    };
+    process_instructions(&mut ctx, &[transfer, memo], &[])
+        .await
+        .unwrap();
+    let to_account = get_token_account(&mut ctx.banks_client, to).await?;
+    assert_eq!(to_account.balance, 100);
+
+    Ok(())
+}
+
+/// SetOwner hands over an account unless it was created with an immutable
+/// owner.
+#[tokio::test(flavor = "current_thread")]
+async fn immutable_owner() -> Result<(), Box<dyn Error>> {
+    let mut ctx = start().await;
+    let payer = ctx.payer.pubkey();
+    setup_config(&mut ctx).await?;
+    let (_mint, mint_index) = create_mint(&mut ctx, &payer, 6).await?;
+    let new_owner = Pubkey::new_unique();
+    let set_owner = |token_account: &Pubkey| {
+        BatchBuilder::new(&payer)
+            .push(instructions::set_owner(token_account, &payer, &new_owner))
+            .build()
+    };
+
+    let (immutable, _bump) = pda::token_account_address(&payer, mint_index);
+    process_instructions(
+        &mut ctx,
+        &[instructions::initialize_account(
+            &payer, mint_index, true, false, &payer,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    let account = get_token_account(&mut ctx.banks_client, immutable).await?;
+    assert_ne!(account.state & TokenAccount::IMMUTABLE_OWNER, 0);
+    let err = process_instructions(&mut ctx, &[set_owner(&immutable)], &[])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        op_error(err),
+        (Some(0), NanoTokenError::ImmutableOwner.into())
+    );
+
+    // An account without the flag can be handed over
+    let (other_mint, _other_mint_index) =
+        create_mint(&mut ctx, &payer, 6).await?;
+    let mutable =
+        create_funded_account(&mut ctx, &payer, &other_mint, 0).await?;
+    process_instructions(&mut ctx, &[set_owner(&mutable)], &[])
+        .await
+        .unwrap();
+    let account = get_token_account(&mut ctx.banks_client, mutable).await?;
+    assert_eq!(account.owner, new_owner);
+
+    Ok(())
+}
+
+/// The config authority only changes hands once the proposed authority signs
+/// AcceptConfigAuthority. Any other signer is rejected.
+#[tokio::test(flavor = "current_thread")]
+async fn config_authority_handoff() -> Result<(), Box<dyn Error>> {
+    let mut ctx = start().await;
+    let payer = ctx.payer.pubkey();
+    setup_config(&mut ctx).await?;
+    let new_authority = Keypair::new();
+    let stranger = Keypair::new();
+
+    process_instructions(
+        &mut ctx,
+        &[instructions::set_config_authority(
+            &payer,
+            Some(&new_authority.pubkey()),
+            &payer,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    let config = ctx
+        .banks_client
+        .get_account(CONFIG_ACCOUNT)
+        .await?
+        .ok_or("could not find config")?;
+    assert_eq!(
+        config.data[CONFIG_PENDING_AUTHORITY_OFFSET..][..32],
+        new_authority.pubkey().to_bytes()
+    );
+
+    let err = process_instructions(
+        &mut ctx,
+        &[instructions::accept_config_authority(
+            &stranger.pubkey(),
+            &payer,
+        )],
+        &[&stranger],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(op_error(err), (Some(0), ProgramError::IllegalOwner));
+
+    process_instructions(
+        &mut ctx,
+        &[instructions::accept_config_authority(
+            &new_authority.pubkey(),
+            &payer,
+        )],
+        &[&new_authority],
+    )
+    .await
+    .unwrap();
+    let config = ctx
+        .banks_client
+        .get_account(CONFIG_ACCOUNT)
+        .await?
+        .ok_or("could not find config")?;
+    assert_eq!(
+        config.data[CONFIG_AUTHORITY_OFFSET..][..32],
+        new_authority.pubkey().to_bytes()
+    );
+    assert_eq!(
+        config.data[CONFIG_PENDING_AUTHORITY_OFFSET..][..32],
+        [0; 32]
+    );
+
+    // The old authority is locked out
+    let err = process_instructions(
+        &mut ctx,
+        &[instructions::set_gc_config(&payer, 60, 0, &payer)],
+        &[],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(op_error(err), (Some(0), ProgramError::IllegalOwner));
+
+    Ok(())
+}
+
+/// WithdrawExcessLamports sweeps stray lamports above rent exemption. The
+/// lamports backing a native account's balance are not excess.
+#[tokio::test(flavor = "current_thread")]
+async fn withdraw_excess_lamports() -> Result<(), Box<dyn Error>> {
+    let mut ctx = start().await;
+    let payer = ctx.payer.pubkey();
+    setup_config(&mut ctx).await?;
+    let (mint, _mint_index) = create_mint(&mut ctx, &payer, 6).await?;
+
+    // Wrap 1 SOL into the payer's native account
+    let (native_account, _bump) =
+        pda::token_account_address(&payer, NATIVE_MINT_INDEX);
+    process_instructions(
+        &mut ctx,
+        &[
+            instructions::initialize_account(
+                &payer,
+                NATIVE_MINT_INDEX,
+                false,
+                false,
+                &payer,
+            ),
+            instructions::wrap_sol(&payer, &payer, LAMPORTS_PER_SOL, &payer),
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    // Send 5000 stray lamports to both the native account and the mint, and
+    // fund a destination for them
+    let destination = Pubkey::new_unique();
+    for (account, lamports) in [
+        (native_account, 5_000),
+        (mint, 5_000),
+        (destination, LAMPORTS_PER_SOL),
+    ] {
+        let blockhash = ctx.get_new_latest_blockhash().await?;
+        ctx.banks_client
+            .process_transaction(system_transaction::transfer(
+                &ctx.payer, &account, lamports, blockhash,
+            ))
+            .await
+            .unwrap();
+    }
+
+    // Only the stray lamports come out of the native account, and only once
+    let native_rent = Rent::default().minimum_balance(TokenAccount::space());
+    for _ in 0..2 {
+        ctx.get_new_latest_blockhash().await?;
+        process_instructions(
+            &mut ctx,
+            &[instructions::withdraw_excess_lamports(
+                &native_account,
+                &payer,
+                &destination,
+                &payer,
+            )],
+            &[],
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            ctx.banks_client
+                .get_balance(native_account)
+                .await?,
+            native_rent + LAMPORTS_PER_SOL
+        );
+        assert_eq!(
+            ctx.banks_client
+                .get_balance(destination)
+                .await?,
+            LAMPORTS_PER_SOL + 5_000
+        );
+    }
+    let account =
+        get_token_account(&mut ctx.banks_client, native_account).await?;
+    assert_eq!(account.balance, LAMPORTS_PER_SOL);
+
+    // Mints are swept by their mint authority
+    let mint_rent = Rent::default().minimum_balance(Mint::space());
+    process_instructions(
+        &mut ctx,
+        &[instructions::withdraw_excess_lamports(
+            &mint,
+            &payer,
+            &destination,
+            &payer,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        ctx.banks_client
+            .get_balance(mint)
+            .await?,
+        mint_rent
+    );
+    assert_eq!(
+        ctx.banks_client
+            .get_balance(destination)
+            .await?,
+        LAMPORTS_PER_SOL + 10_000
+    );
+
+    Ok(())
+}
+
+/// The config authority sweeps excess lamports out of program state, and the
+/// owner alone sweeps them out of a token account.
+#[tokio::test(flavor = "current_thread")]
+async fn withdraw_excess_lamports_authority() -> Result<(), Box<dyn Error>> {
+    let mut ctx = start().await;
+    let payer = ctx.payer.pubkey();
+    setup_config(&mut ctx).await?;
+    let (mint, _mint_index) = create_mint(&mut ctx, &payer, 6).await?;
+    let owner = Keypair::new();
+    let token_account =
+        create_funded_account(&mut ctx, &owner.pubkey(), &mint, 0).await?;
+
+    let destination = Pubkey::new_unique();
+    for (account, lamports) in [
+        (CONFIG_ACCOUNT, 5_000),
+        (token_account, 5_000),
+        (destination, LAMPORTS_PER_SOL),
+    ] {
+        let blockhash = ctx.get_new_latest_blockhash().await?;
+        ctx.banks_client
+            .process_transaction(system_transaction::transfer(
+                &ctx.payer, &account, lamports, blockhash,
+            ))
+            .await
+            .unwrap();
+    }
+
+    // Only the config authority may sweep the config
+    let stranger = Keypair::new();
+    let err = process_instructions(
+        &mut ctx,
+        &[instructions::withdraw_excess_lamports(
+            &CONFIG_ACCOUNT,
+            &stranger.pubkey(),
+            &destination,
+            &payer,
+        )],
+        &[&stranger],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(op_error(err), (Some(0), ProgramError::IllegalOwner));
+    process_instructions(
+        &mut ctx,
+        &[instructions::withdraw_excess_lamports(
+            &CONFIG_ACCOUNT,
+            &payer,
+            &destination,
+            &payer,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        ctx.banks_client
+            .get_balance(CONFIG_ACCOUNT)
+            .await?,
+        Rent::default().minimum_balance(ProgramConfig::space())
+    );
+
+    // Only the owner may sweep its token account, even when the config
+    // authority signs
+    let err = process_instructions(
+        &mut ctx,
+        &[instructions::withdraw_excess_lamports(
+            &token_account,
+            &payer,
+            &destination,
+            &payer,
+        )],
+        &[],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(op_error(err), (Some(0), ProgramError::IllegalOwner));
+    process_instructions(
+        &mut ctx,
+        &[instructions::withdraw_excess_lamports(
+            &token_account,
+            &owner.pubkey(),
+            &destination,
+            &payer,
+        )],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        ctx.banks_client
+            .get_balance(token_account)
+            .await?,
+        Rent::default().minimum_balance(TokenAccount::space())
+    );
+    assert_eq!(
+        ctx.banks_client
+            .get_balance(destination)
+            .await?,
+        LAMPORTS_PER_SOL + 10_000
+    );
+
+    Ok(())
+}
+
+/// SyncNative only ever raises a native balance. An account holding fewer
+/// lamports than its balance claims is rejected rather than synced down.
+#[tokio::test(flavor = "current_thread")]
+async fn sync_native_never_lowers_balance() -> Result<(), Box<dyn Error>> {
+    let mut ctx = start().await;
+    let payer = ctx.payer.pubkey();
+    setup_config(&mut ctx).await?;
+    let (native_account, _bump) =
+        pda::token_account_address(&payer, NATIVE_MINT_INDEX);
+    process_instructions(
+        &mut ctx,
+        &[
+            instructions::initialize_account(
+                &payer,
+                NATIVE_MINT_INDEX,
+                false,
+                false,
+                &payer,
+            ),
+            instructions::wrap_sol(&payer, &payer, LAMPORTS_PER_SOL, &payer),
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    // Drain lamports out from under the balance
+    let mut account = ctx
+        .banks_client
+        .get_account(native_account)
+        .await?
+        .ok_or("could not find native account")?;
+    account.lamports -= LAMPORTS_PER_SOL / 2;
+    ctx.set_account(&native_account, &account.into());
+
+    let err = process_instructions(
+        &mut ctx,
+        &[BatchBuilder::new(&payer)
+            .push(instructions::sync_native(&native_account))
+            .build()],
+        &[],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(op_error(err), (Some(0), ProgramError::InvalidAccountData));
+    let account =
+        get_token_account(&mut ctx.banks_client, native_account).await?;
+    assert_eq!(account.balance, LAMPORTS_PER_SOL);
+
+    Ok(())
+}
+
+/// A close call that selects the config rent destination must send the rent
+/// there and nowhere else.
+#[tokio::test(flavor = "current_thread")]
+async fn config_rent_destination() -> Result<(), Box<dyn Error>> {
+    let mut ctx = start().await;
+    let payer = ctx.payer.pubkey();
+    setup_config(&mut ctx).await?;
+    let treasury = Pubkey::new_unique();
+    process_instructions(
+        &mut ctx,
+        &[instructions::set_rent_destination(
+            &payer,
+            Some(&treasury),
+            &payer,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    let (mint, _mint_index) = create_mint(&mut ctx, &payer, 6).await?;
+    let mint_lamports = ctx
+        .banks_client
+        .get_balance(mint)
+        .await?;
+
+    let err = process_instructions(
+        &mut ctx,
+        &[instructions::close_mint(
+            &mint,
+            &payer,
+            &Pubkey::new_unique(),
+            RentDestination::Config,
+        )],
+        &[],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        op_error(err),
+        (Some(0), NanoTokenError::RentDestinationMismatch.into())
+    );
+
+    process_instructions(
+        &mut ctx,
+        &[instructions::close_mint(
+            &mint,
+            &payer,
+            &treasury,
+            RentDestination::Config,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        ctx.banks_client
+            .get_balance(treasury)
+            .await?,
+        mint_lamports
+    );
+
+    Ok(())
+}
+
+/// A timelocked account can't send until its unlock time, after which it
+/// transfers as usual.
+#[tokio::test(flavor = "current_thread")]
+async fn timelock() -> Result<(), Box<dyn Error>> {
+    let mut ctx = start().await;
+    let payer = ctx.payer.pubkey();
+    setup_config(&mut ctx).await?;
+    let (mint, _mint_index) = create_mint(&mut ctx, &payer, 6).await?;
+    let from = create_funded_account(&mut ctx, &payer, &mint, 1_000).await?;
+    let to = create_funded_account(&mut ctx, &Pubkey::new_unique(), &mint, 0)
+        .await?;
+
+    let now = ctx
+        .banks_client
+        .get_sysvar::<Clock>()
+        .await?
+        .unix_timestamp;
+    process_instructions(
+        &mut ctx,
+        &[BatchBuilder::new(&payer)
+            .push(instructions::timelock(&from, &payer, now + 3600))
+            .build()],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    let transfer = instructions::transfer(&from, &to, &payer, 100);
+    let err =
+        process_instructions(&mut ctx, std::slice::from_ref(&transfer), &[])
+            .await
+            .unwrap_err();
+    assert_eq!(
+        op_error(err),
+        (Some(0), NanoTokenError::AccountTimelocked.into())
+    );
+
+    let mut clock = ctx
+        .banks_client
+        .get_sysvar::<Clock>()
+        .await?;
+    clock.unix_timestamp = now + 3600;
+    ctx.set_sysvar(&clock);
+    ctx.get_new_latest_blockhash().await?;
+    process_instructions(&mut ctx, &[transfer], &[])
+        .await
+        .unwrap();
+    let to_account = get_token_account(&mut ctx.banks_client, to).await?;
+    assert_eq!(to_account.balance, 100);
+
+    Ok(())
+}
+
+/// An op given fewer accounts than it needs fails before dispatch, and the
+/// error code names the op.
+#[tokio::test(flavor = "current_thread")]
+async fn missing_accounts() -> Result<(), Box<dyn Error>> {
+    let mut ctx = start().await;
+    let payer = ctx.payer.pubkey();
+    setup_config(&mut ctx).await?;
+    let (mint, _mint_index) = create_mint(&mut ctx, &payer, 6).await?;
+    let from = create_funded_account(&mut ctx, &payer, &mint, 1_000).await?;
+    let to = create_funded_account(&mut ctx, &Pubkey::new_unique(), &mint, 0)
+        .await?;
+
+    // A transfer followed by a burn that is one account (its owner) short
+    let mut ix_data = vec![];
+    ix_data.extend((Tag::Transfer as u64).to_le_bytes());
+    ix_data.extend(bytemuck::bytes_of(&TransferArgs { amount: 100 }));
+    ix_data.extend((Tag::Burn as u64).to_le_bytes());
+    ix_data.extend(bytemuck::bytes_of(&BurnArgs { amount: 100 }));
+    let accounts = vec![
+        AccountMeta::new(from, false),
+        AccountMeta::new(to, false),
+        AccountMeta::new_readonly(payer, true),
+        AccountMeta::new(from, false),
+        AccountMeta::new(mint, false),
+    ];
+    let err = process(&mut ctx, ix_data, accounts, &[])
+        .await
+        .unwrap_err();
+    let TransactionError::InstructionError(0, InstructionError::Custom(code)) =
+        err.unwrap()
+    else {
+        panic!("expected custom error, got {err:?}");
+    };
+    assert_eq!(
+        ProgramError::Custom(code),
+        encode_op_error(1, NanoTokenError::MissingAccounts.into())
+    );
+    assert_eq!(
+        decode_op_error(code),
+        (Some(1), NanoTokenError::MissingAccounts.into())
+    );
+
+    Ok(())
+}
+
+/// With alias-guard, passing the same writable account twice fails before
+/// any op runs, so the error names no op. Run against a program built with
+/// the feature, e.g. `cargo test-sbf --features alias-guard`.
+#[cfg(feature = "alias-guard")]
+#[tokio::test(flavor = "current_thread")]
+async fn alias_guard() -> Result<(), Box<dyn Error>> {
+    let mut ctx = start().await;
+    let payer = ctx.payer.pubkey();
+    setup_config(&mut ctx).await?;
+    let (mint, _mint_index) = create_mint(&mut ctx, &payer, 6).await?;
+    let from = create_funded_account(&mut ctx, &payer, &mint, 1_000).await?;
+    let to = create_funded_account(&mut ctx, &Pubkey::new_unique(), &mint, 0)
+        .await?;
+
+    let err = process_instructions(
+        &mut ctx,
+        &[instructions::transfer(&from, &from, &payer, 100)],
+        &[],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        op_error(err),
+        (None, NanoTokenError::DuplicateAccount.into())
+    );
+
+    // Distinct accounts are unaffected
+    process_instructions(
+        &mut ctx,
+        &[instructions::transfer(&from, &to, &payer, 100)],
+        &[],
+    )
+    .await
+    .unwrap();
+    let to_account = get_token_account(&mut ctx.banks_client, to).await?;
+    assert_eq!(to_account.balance, 100);
+
+    Ok(())
+}
+
+/// GetBalances returns the mint and balance of each account, in order.
+#[tokio::test(flavor = "current_thread")]
+async fn get_balances() -> Result<(), Box<dyn Error>> {
+    let mut ctx = start().await;
+    let payer = ctx.payer.pubkey();
+    setup_config(&mut ctx).await?;
+    let (mint, mint_index) = create_mint(&mut ctx, &payer, 6).await?;
+    let (other_mint, other_mint_index) =
+        create_mint(&mut ctx, &payer, 6).await?;
+    let accounts = [
+        create_funded_account(&mut ctx, &payer, &mint, 1_000).await?,
+        create_funded_account(&mut ctx, &payer, &other_mint, 0).await?,
+        create_funded_account(&mut ctx, &Pubkey::new_unique(), &mint, 42)
+            .await?,
+    ];
+
+    let return_data = simulate_return_data(
+        &mut ctx,
+        &[instructions::get_balances(&accounts, &payer)],
+    )
+    .await?;
+    let balances = instructions::parse_balances(&return_data)
+        .ok_or("malformed return data")?;
+    let balances: Vec<(u64, u64)> = balances
+        .iter()
+        .map(|entry| (entry.mint, entry.balance))
+        .collect();
+    assert_eq!(
+        balances,
+        [(mint_index, 1_000), (other_mint_index, 0), (mint_index, 42)]
+    );
+
+    Ok(())
+}
+
+/// Metadata is stored at the mint's metadata pda and can be rewritten by the
+/// mint authority.
+#[tokio::test(flavor = "current_thread")]
+async fn metadata() -> Result<(), Box<dyn Error>> {
+    let mut ctx = start().await;
+    let payer = ctx.payer.pubkey();
+    setup_config(&mut ctx).await?;
+    let (mint, _mint_index) = create_mint(&mut ctx, &payer, 6).await?;
+    let (metadata, bump) = MintMetadata::address(&mint);
+
+    process_instructions(
+        &mut ctx,
+        &[instructions::initialize_metadata(
+            &mint,
+            &payer,
+            "Nano",
+            "NANO",
+            "https://example.com/nano.json",
+            &payer,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    let data = ctx
+        .banks_client
+        .get_account(metadata)
+        .await?
+        .ok_or("could not find metadata")?
+        .data;
+    let stored = MintMetadata::try_unpack(&data)?;
+    assert_eq!(stored.mint, mint);
+    assert_eq!(stored.bump, bump);
+    assert_eq!(instructions::unpad_str(&stored.name), Some("Nano"));
+    assert_eq!(instructions::unpad_str(&stored.symbol), Some("NANO"));
+    assert_eq!(
+        instructions::unpad_str(&stored.uri),
+        Some("https://example.com/nano.json")
+    );
+
+    process_instructions(
+        &mut ctx,
+        &[instructions::update_metadata(
+            &mint,
+            &payer,
+            "Nanotoken",
+            "NT",
+            "",
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    let data = ctx
+        .banks_client
+        .get_account(metadata)
+        .await?
+        .ok_or("could not find metadata")?
+        .data;
+    let stored = MintMetadata::try_unpack(&data)?;
+    assert_eq!(instructions::unpad_str(&stored.name), Some("Nanotoken"));
+    assert_eq!(instructions::unpad_str(&stored.symbol), Some("NT"));
+    assert_eq!(instructions::unpad_str(&stored.uri), Some(""));
+
+    Ok(())
+}
+
+/// A token account can live at a keypair address that signs for its
+/// creation, and works like the (owner, mint) pda.
+#[tokio::test(flavor = "current_thread")]
+async fn keypair_account() -> Result<(), Box<dyn Error>> {
+    let mut ctx = start().await;
+    let payer = ctx.payer.pubkey();
+    setup_config(&mut ctx).await?;
+    let (mint, mint_index) = create_mint(&mut ctx, &payer, 6).await?;
+    let owner = Keypair::new();
+    let token_account = Keypair::new();
+
+    process_instructions(
+        &mut ctx,
+        &[
+            instructions::initialize_keypair_account(
+                &token_account.pubkey(),
+                &owner.pubkey(),
+                mint_index,
+                false,
+                false,
+                &payer,
+            ),
+            instructions::mint_to(&token_account.pubkey(), &mint, &payer, 500),
+        ],
+        &[&token_account],
+    )
+    .await
+    .unwrap();
+    let account = ctx
+        .banks_client
+        .get_account(token_account.pubkey())
+        .await?
+        .ok_or("could not find token account")?;
+    assert_eq!(account.owner, nanotoken::ID);
+    assert_eq!(account.data.len(), TokenAccount::space());
+    let account =
+        get_token_account(&mut ctx.banks_client, token_account.pubkey())
+            .await?;
+    assert_eq!(account.owner, owner.pubkey());
+    assert_eq!(account.mint, mint_index);
+    assert_eq!(account.balance, 500);
+
+    // Its owner can spend from it
+    let to = create_funded_account(&mut ctx, &payer, &mint, 0).await?;
+    process_instructions(
+        &mut ctx,
+        &[instructions::transfer(
+            &token_account.pubkey(),
+            &to,
+            &owner.pubkey(),
+            200,
+        )],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+    let to_account = get_token_account(&mut ctx.banks_client, to).await?;
+    assert_eq!(to_account.balance, 200);
+
+    Ok(())
+}
+
+/// CreateAssociatedAccount creates the (owner, mint) pda with an immutable
+/// owner, is idempotent, and rejects any other address.
+#[tokio::test(flavor = "current_thread")]
+async fn associated_account() -> Result<(), Box<dyn Error>> {
+    let mut ctx = start().await;
+    let payer = ctx.payer.pubkey();
+    setup_config(&mut ctx).await?;
+    let (_mint, mint_index) = create_mint(&mut ctx, &payer, 6).await?;
+    let owner = Pubkey::new_unique();
+
+    let create =
+        instructions::create_associated_account(&owner, mint_index, &payer);
+    let (token_account, bump) = pda::token_account_address(&owner, mint_index);
+    assert_eq!(create.accounts[0].pubkey, token_account);
+    process_instructions(&mut ctx, std::slice::from_ref(&create), &[])
+        .await
+        .unwrap();
+    let account =
+        get_token_account(&mut ctx.banks_client, token_account).await?;
+    assert_eq!(account.owner, owner);
+    assert_eq!(account.mint, mint_index);
+    assert_eq!(account.bump, bump);
+    assert_ne!(account.state & TokenAccount::IMMUTABLE_OWNER, 0);
+
+    // Creating it again does nothing
+    ctx.get_new_latest_blockhash().await?;
+    process_instructions(&mut ctx, std::slice::from_ref(&create), &[])
+        .await
+        .unwrap();
+
+    // Any other address is rejected
+    let mut wrong_address = create;
+    wrong_address.accounts[0].pubkey = Pubkey::new_unique();
+    let err = process_instructions(&mut ctx, &[wrong_address], &[])
+        .await
+        .unwrap_err();
+    assert_eq!(op_error(err), (Some(0), ProgramError::InvalidSeeds));
+
+    Ok(())
+}
+
+/// InitializeMintPda creates the mint at the ["mint", mint_index] pda for
+/// the config's next index. A pda derived from any other index is rejected.
+#[tokio::test(flavor = "current_thread")]
+async fn initialize_mint_pda() -> Result<(), Box<dyn Error>> {
+    let mut ctx = start().await;
+    let payer = ctx.payer.pubkey();
+    setup_config(&mut ctx).await?;
+    let config = ctx
+        .banks_client
+        .get_account(CONFIG_ACCOUNT)
+        .await?
+        .ok_or("could not find config")?;
+    let mint_index = ProgramConfig::try_unpack(&config.data)?.next_mint_index();
+    let mint_args = InitializeMintArgs {
+        authority: payer,
+        decimals: 6,
+        ..Zeroable::zeroed()
+    };
+
+    // Seeded with the index after next
+    let err = process_instructions(
+        &mut ctx,
+        &[instructions::initialize_mint_pda(
+            mint_index + 1,
+            mint_args,
+            &payer,
+        )],
+        &[],
+    )
+    .await
+    .unwrap_err();
+    assert!(matches!(
+        err.unwrap(),
+        TransactionError::InstructionError(0, _)
+    ));
+    let (wrong_mint, _bump) = pda::mint_address(mint_index + 1);
+    assert!(ctx
+        .banks_client
+        .get_account(wrong_mint)
+        .await?
+        .is_none());
+
+    process_instructions(
+        &mut ctx,
+        &[instructions::initialize_mint_pda(
+            mint_index, mint_args, &payer,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    let (mint, _bump) = pda::mint_address(mint_index);
+    let mint_account = get_mint(&mut ctx.banks_client, mint).await?;
+    assert_eq!(mint_account.mint_index, mint_index);
+    assert_eq!(mint_account.authority, payer);
+    assert_eq!(mint_account.decimals, 6);
+
+    Ok(())
+}
+
+/// GetAccountDataSize returns the space of a token account with the given
+/// extension length.
+#[tokio::test(flavor = "current_thread")]
+async fn get_account_data_size() -> Result<(), Box<dyn Error>> {
+    let mut ctx = start().await;
+    setup_config(&mut ctx).await?;
+
+    for extension_len in [0, 100] {
+        let return_data = simulate_return_data(
+            &mut ctx,
+            &[instructions::get_account_data_size(extension_len)],
+        )
+        .await?;
+        assert_eq!(
+            instructions::parse_account_data_size(&return_data),
+            Some(TokenAccount::space() as u64 + extension_len)
+        );
+    }
+
+    Ok(())
 }
@@ -0,0 +1,221 @@
+//! State machine tests of the supply invariants
+//!
+//! Random sequences of init/mint/burn/transfer ops run against LiteSVM and a
+//! model. After every op, successful or not, the program must agree with the
+//! model, the balances must sum to the mint supply, and the supply must stay
+//! within the cap.
+
+use nanotoken::error::NanoTokenError;
+use nanotoken_client::{instructions, pda};
+use nanotoken_test_utils::svm::{op_error, SvmContext};
+use proptest::prelude::*;
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use solana_sdk::{signature::Keypair, signer::Signer};
+
+const OWNERS: usize = 3;
+
+#[derive(Clone, Copy, Debug)]
+enum Op {
+    Init(usize),
+    Mint(usize, u64),
+    Burn(usize, u64),
+    Transfer(usize, usize, u64),
+}
+
+/// Mostly small amounts, so that balances move around, with the odd huge one
+/// to hit the cap and u64::MAX
+fn amount() -> impl Strategy<Value = u64> {
+    prop_oneof![
+        8 => 0..1_000u64,
+        1 => Just(0u64),
+        1 => u64::MAX / 2..=u64::MAX,
+    ]
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        1 => (0..OWNERS).prop_map(Op::Init),
+        3 => (0..OWNERS, amount()).prop_map(|(i, a)| Op::Mint(i, a)),
+        2 => (0..OWNERS, amount()).prop_map(|(i, a)| Op::Burn(i, a)),
+        4 => (0..OWNERS, 0..OWNERS, amount())
+            .prop_map(|(i, j, a)| Op::Transfer(i, j, a)),
+    ]
+}
+
+/// What the program should do with an op
+enum Expected {
+    Ok,
+    /// Fails with this error
+    Err(ProgramError),
+    /// Fails, with whatever error the missing account trips first
+    AnyErr,
+}
+
+struct Model {
+    /// None until initialized
+    balances: [Option<u64>; OWNERS],
+    supply: u64,
+    /// Zero for no cap
+    max_supply: u64,
+}
+
+impl Model {
+    /// Applies `op` to the model if it should succeed
+    fn apply(&mut self, op: Op) -> Expected {
+        match op {
+            Op::Init(i) => match self.balances[i] {
+                Some(_) => Expected::AnyErr,
+                None => {
+                    self.balances[i] = Some(0);
+                    Expected::Ok
+                }
+            },
+            // Zero amounts return before loading any account
+            Op::Mint(_, 0) | Op::Burn(_, 0) | Op::Transfer(_, _, 0) => {
+                Expected::Ok
+            }
+            Op::Mint(i, amount) => {
+                let Some(balance) = self.balances[i] else {
+                    return Expected::AnyErr;
+                };
+                let Some(supply) = self.supply.checked_add(amount) else {
+                    return Expected::Err(
+                        NanoTokenError::SupplyOverflow.into(),
+                    );
+                };
+                if self.max_supply != 0 && supply > self.max_supply {
+                    return Expected::Err(
+                        NanoTokenError::SupplyCapExceeded.into(),
+                    );
+                }
+                self.supply = supply;
+                self.balances[i] = Some(balance + amount);
+                Expected::Ok
+            }
+            Op::Burn(i, amount) => {
+                let Some(balance) = self.balances[i] else {
+                    return Expected::AnyErr;
+                };
+                if balance < amount {
+                    return Expected::Err(
+                        NanoTokenError::InsufficientTokenBalance.into(),
+                    );
+                }
+                self.supply -= amount;
+                self.balances[i] = Some(balance - amount);
+                Expected::Ok
+            }
+            Op::Transfer(i, j, amount) => {
+                let (Some(from), Some(_)) =
+                    (self.balances[i], self.balances[j])
+                else {
+                    return Expected::AnyErr;
+                };
+                if from < amount {
+                    return Expected::Err(
+                        NanoTokenError::InsufficientTokenBalance.into(),
+                    );
+                }
+                self.balances[i] = Some(from - amount);
+                self.balances[j] = Some(self.balances[j].unwrap() + amount);
+                Expected::Ok
+            }
+        }
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(256))]
+
+    #[test]
+    fn supply_is_conserved(
+        max_supply in prop_oneof![Just(0u64), 1..10_000u64],
+        ops in prop::collection::vec(op(), 1..64),
+    ) {
+        let mut ctx = SvmContext::start();
+        ctx.setup_config().unwrap();
+        let payer = ctx.payer.pubkey();
+        let (mint, mint_index) = ctx
+            .create_capped_mint(&payer, 0, Some(max_supply))
+            .unwrap();
+        let owners: Vec<Keypair> = (0..OWNERS).map(|_| Keypair::new()).collect();
+        let accounts: Vec<Pubkey> = owners
+            .iter()
+            .map(|owner| pda::token_account_address(&owner.pubkey(), mint_index).0)
+            .collect();
+        let mut model = Model {
+            balances: [None; OWNERS],
+            supply: 0,
+            max_supply,
+        };
+
+        for op in ops {
+            let (ix, signer) = match op {
+                Op::Init(i) => (
+                    instructions::initialize_account(
+                        &owners[i].pubkey(),
+                        mint_index,
+                        false,
+                        false,
+                        &payer,
+                    ),
+                    None,
+                ),
+                Op::Mint(i, amount) => (
+                    instructions::mint_to(&accounts[i], &mint, &payer, amount),
+                    None,
+                ),
+                Op::Burn(i, amount) => (
+                    instructions::burn(&accounts[i], &mint, &owners[i].pubkey(), amount),
+                    Some(&owners[i]),
+                ),
+                Op::Transfer(i, j, amount) => (
+                    instructions::transfer(
+                        &accounts[i],
+                        &accounts[j],
+                        &owners[i].pubkey(),
+                        amount,
+                    ),
+                    Some(&owners[i]),
+                ),
+            };
+            let signers: Vec<&Keypair> = signer.into_iter().collect();
+            let result = ctx.process_instructions(&[ix], &signers);
+
+            match model.apply(op) {
+                Expected::Ok => prop_assert!(
+                    result.is_ok(),
+                    "{op:?} failed: {:?}",
+                    result.unwrap_err().err
+                ),
+                Expected::Err(err) => {
+                    prop_assert!(result.is_err(), "{op:?} should fail");
+                    prop_assert_eq!(op_error(result.unwrap_err()), (Some(0), err));
+                }
+                Expected::AnyErr => {
+                    prop_assert!(result.is_err(), "{op:?} should fail")
+                }
+            }
+
+            // The program agrees with the model
+            let mut sum = 0u64;
+            for (account, balance) in accounts.iter().zip(model.balances) {
+                let Some(balance) = balance else {
+                    prop_assert!(ctx.svm.get_account(account).is_none());
+                    continue;
+                };
+                let token_account = ctx.get_token_account(account).unwrap();
+                prop_assert_eq!(token_account.balance, balance);
+                sum = sum.checked_add(balance).unwrap();
+            }
+            let supply = ctx.get_mint(&mint).unwrap().supply;
+            prop_assert_eq!(supply, model.supply);
+
+            // Global invariants
+            prop_assert_eq!(sum, supply);
+            if max_supply != 0 {
+                prop_assert!(supply <= max_supply);
+            }
+        }
+    }
+}
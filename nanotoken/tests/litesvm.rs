@@ -0,0 +1,174 @@
+//! LiteSVM mirror of the program-test suite, plus property tests that would
+//! be too slow to run against a bank
+
+use nanotoken::error::NanoTokenError;
+use nanotoken_client::instructions;
+use nanotoken_test_utils::svm::{op_error, SvmContext};
+use proptest::prelude::*;
+use solana_program::program_error::ProgramError;
+use solana_sdk::{signature::Keypair, signer::Signer};
+
+#[test]
+fn end_to_end() -> Result<(), Box<dyn std::error::Error>> {
+    let mut ctx = SvmContext::start();
+    ctx.setup_config()?;
+    let payer = ctx.payer.pubkey();
+    let (mint, mint_index) = ctx.create_mint(&payer, 6)?;
+
+    let user = Keypair::new();
+    let user_account =
+        ctx.create_funded_account(&user.pubkey(), &mint, 1_000)?;
+    let payer_account = ctx.create_funded_account(&payer, &mint, 0)?;
+
+    // Transfer, then burn
+    ctx.process_instructions(
+        &[
+            instructions::transfer(
+                &user_account,
+                &payer_account,
+                &user.pubkey(),
+                400,
+            ),
+            instructions::burn(&payer_account, &mint, &payer, 100),
+        ],
+        &[&user],
+    )
+    .unwrap();
+
+    let user_token_account = ctx.get_token_account(&user_account)?;
+    assert_eq!(user_token_account.owner, user.pubkey());
+    assert_eq!(user_token_account.mint, mint_index);
+    assert_eq!(user_token_account.balance, 600);
+    assert_eq!(
+        ctx.get_token_account(&payer_account)?
+            .balance,
+        300
+    );
+    assert_eq!(ctx.get_mint(&mint)?.supply, 900);
+
+    // Overdraw
+    let err = ctx
+        .process_instructions(
+            &[instructions::transfer(
+                &user_account,
+                &payer_account,
+                &user.pubkey(),
+                601,
+            )],
+            &[&user],
+        )
+        .unwrap_err();
+    assert_eq!(
+        op_error(err),
+        (Some(0), NanoTokenError::InsufficientTokenBalance.into())
+    );
+
+    // Only the owner may transfer
+    let err = ctx
+        .process_instructions(
+            &[instructions::transfer(
+                &user_account,
+                &payer_account,
+                &payer,
+                1,
+            )],
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(op_error(err).0, Some(0));
+
+    Ok(())
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Op {
+    /// Transfer from account i to the other one
+    Transfer(usize, u64),
+    Burn(usize, u64),
+    Mint(usize, u64),
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        (0..2usize, 0..2_000u64)
+            .prop_map(|(i, amount)| Op::Transfer(i, amount)),
+        (0..2usize, 0..2_000u64).prop_map(|(i, amount)| Op::Burn(i, amount)),
+        (0..2usize, 0..1_000u64).prop_map(|(i, amount)| Op::Mint(i, amount)),
+    ]
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(256))]
+
+    /// Balances and supply track a simple model, and failed ops change
+    /// nothing
+    #[test]
+    fn balances_match_model(
+        initial in prop::array::uniform2(0..1_000u64),
+        ops in prop::collection::vec(op(), 1..32),
+    ) {
+        let mut ctx = SvmContext::start();
+        ctx.setup_config().unwrap();
+        let payer = ctx.payer.pubkey();
+        let (mint, _mint_index) = ctx.create_mint(&payer, 0).unwrap();
+        let owners = [Keypair::new(), Keypair::new()];
+        let accounts = [
+            ctx.create_funded_account(&owners[0].pubkey(), &mint, initial[0]).unwrap(),
+            ctx.create_funded_account(&owners[1].pubkey(), &mint, initial[1]).unwrap(),
+        ];
+        let mut balances = initial;
+
+        for op in ops {
+            let (ix, signer, ok) = match op {
+                Op::Transfer(i, amount) => (
+                    instructions::transfer(
+                        &accounts[i],
+                        &accounts[1 - i],
+                        &owners[i].pubkey(),
+                        amount,
+                    ),
+                    Some(&owners[i]),
+                    balances[i] >= amount,
+                ),
+                Op::Burn(i, amount) => (
+                    instructions::burn(&accounts[i], &mint, &owners[i].pubkey(), amount),
+                    Some(&owners[i]),
+                    balances[i] >= amount,
+                ),
+                Op::Mint(i, amount) => (
+                    instructions::mint_to(&accounts[i], &mint, &payer, amount),
+                    None,
+                    true,
+                ),
+            };
+            let signers: Vec<&Keypair> = signer.into_iter().collect();
+            let result = ctx.process_instructions(&[ix], &signers);
+
+            if ok {
+                prop_assert!(result.is_ok(), "{op:?} failed: {:?}", result.unwrap_err().err);
+                match op {
+                    Op::Transfer(i, amount) => {
+                        balances[i] -= amount;
+                        balances[1 - i] += amount;
+                    }
+                    Op::Burn(i, amount) => balances[i] -= amount,
+                    Op::Mint(i, amount) => balances[i] += amount,
+                }
+            } else {
+                let err: ProgramError = NanoTokenError::InsufficientTokenBalance.into();
+                prop_assert_eq!(op_error(result.unwrap_err()), (Some(0), err));
+            }
+
+            for i in 0..2 {
+                prop_assert_eq!(
+                    ctx.get_token_account(&accounts[i]).unwrap().balance,
+                    balances[i]
+                );
+            }
+            prop_assert_eq!(
+                ctx.get_mint(&mint).unwrap().supply,
+                balances[0] + balances[1]
+            );
+        }
+    }
+}
@@ -0,0 +1,22 @@
+//! Overflow policy of the balance/supply helpers
+
+use nanotoken::{error::NanoTokenError, math};
+
+#[test]
+fn checked_math() {
+    assert_eq!(math::add_balance(1, 2), Ok(3));
+    assert_eq!(
+        math::add_balance(u64::MAX, 1),
+        Err(NanoTokenError::BalanceOverflow)
+    );
+    assert_eq!(math::sub_balance(3, 3), Ok(0));
+    assert_eq!(
+        math::sub_balance(2, 3),
+        Err(NanoTokenError::InsufficientTokenBalance)
+    );
+    assert_eq!(
+        math::add_supply(u64::MAX, 1),
+        Err(NanoTokenError::SupplyOverflow)
+    );
+    assert_eq!(math::sub_supply(0, 1), Err(NanoTokenError::SupplyUnderflow));
+}
@@ -1,89 +1,59 @@
 //! An end-to-end integration test
 
-use std::{env, error::Error, path::Path};
+use std::error::Error;
 
 use nanotoken::{
+    consts::{
+        CONFIG_ACCOUNT, NATIVE_MINT_INDEX, VAULT_INFO_V0_SIZE,
+        VAULT_INFO_VERSION,
+    },
+    error::NanoTokenError,
     ix::{
         InitializeAccountArgs, InitializeVaultArgs, Tag, TransferArgs,
         TransmuteArgs,
     },
-    Mint, ProgramConfig, TokenAccount, VaultInfo,
+    Mint, TokenAccount, VaultInfo,
+};
+use nanotoken_client::{batch::BatchBuilder, instructions, pda};
+use nanotoken_test_utils::{
+    create_funded_account, get_mint, get_token_account, op_error, process,
+    process_instructions, setup_config, start, TestResult,
 };
 use solana_program::{
     instruction::{AccountMeta, Instruction},
     native_token::LAMPORTS_PER_SOL,
+    program_error::ProgramError,
     program_pack::Pack,
+    pubkey::Pubkey,
     rent::Rent,
     system_instruction, system_program,
 };
-use solana_program_test::ProgramTest;
+use solana_program_test::ProgramTestContext;
 use solana_sdk::{
-    signature::{read_keypair_file, Keypair},
-    signer::Signer,
-    system_transaction,
+    signature::Keypair, signer::Signer, system_transaction,
     transaction::Transaction,
 };
+use spl_token_2022::extension::{
+    transfer_fee::{
+        instruction as transfer_fee_instruction, TransferFeeAmount,
+    },
+    BaseStateWithExtensions, ExtensionType, StateWithExtensions,
+};
 
 /// 1. Set up program environment and nanotoken program (initialize config)
 /// 2. Initialize Tokenkeg token mint, token account, and mint Tokenkeg token to token account
 /// 3. Create nanotoken vault, nanotoken accounts, and port over
 /// 4. nanotoken transfer back and forth
 /// 5. port back over to Tokenkeg
+/// 6. port over and back again in one TransmuteBatch
+/// 7. degenerate transmute account configurations are rejected
 #[tokio::test(flavor = "current_thread")]
 async fn round_trip() -> Result<(), Box<dyn Error>> {
     // 1. Set up program environment and nanotoken program (initialize config)
-    let mut program_test = ProgramTest::new("nanotoken", nanotoken::ID, None);
-    program_test.prefer_bpf(true);
-    let mut ctx = program_test.start_with_context().await;
+    let mut ctx = start().await;
     let rent = Rent::default();
-
-    // Initialize config
-    let config_keypair = read_keypair_file(
-        Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap())
-            .parent()
-            .unwrap()
-            .join("config.json"),
-    )
-    .unwrap();
-    let config = config_keypair.pubkey();
-    let create_config = system_transaction::create_account(
-        &ctx.payer,
-        &config_keypair,
-        ctx.last_blockhash,
-        Rent::default().minimum_balance(ProgramConfig::space()),
-        ProgramConfig::space() as u64,
-        &nanotoken::ID,
-    );
-    ctx.banks_client
-        .process_transaction(create_config)
-        .await
-        .unwrap();
-
-    // Initialize config
-    let ix_data = (Tag::InitializeConfig as u64)
-        .to_le_bytes()
-        .to_vec();
-
-    let accounts = vec![
-        AccountMeta::new(config, false),
-        AccountMeta::new_readonly(system_program::ID, false),
-        AccountMeta::new_readonly(ctx.payer.pubkey(), false),
-    ];
-    let instruction = Instruction {
-        program_id: nanotoken::ID,
-        accounts,
-        data: ix_data,
-    };
-    let transaction = Transaction::new_signed_with_payer(
-        &[instruction],
-        Some(&ctx.payer.pubkey()),
-        &[&ctx.payer],
-        ctx.last_blockhash,
-    );
-    ctx.banks_client
-        .process_transaction(transaction)
-        .await
-        .unwrap();
+    let config = CONFIG_ACCOUNT;
+    setup_config(&mut ctx).await?;
 
     // 2. Initialize Tokenkeg token mint, token account, and mint Tokenkeg token to token account
     let tokenkeg_mint = Keypair::new();
@@ -179,17 +149,17 @@ async fn round_trip() -> Result<(), Box<dyn Error>> {
             .as_ref(),
     );
     // ix 3: create account
-    step_4_data[72] = Tag::InitializeAccount as u8;
-    step_4_data[80..112].copy_from_slice(second_user.pubkey().as_ref());
-    step_4_data[112..120].copy_from_slice(0_u64.to_le_bytes().as_ref());
-    step_4_data[120..128].copy_from_slice(
+    step_4_data[88] = Tag::InitializeAccount as u8;
+    step_4_data[96..128].copy_from_slice(second_user.pubkey().as_ref());
+    step_4_data[128..136].copy_from_slice(0_u64.to_le_bytes().as_ref());
+    step_4_data[136..144].copy_from_slice(
         (nanotoken_bump_2 as u64)
             .to_le_bytes()
             .as_ref(),
     );
     // ix 4: transmute
-    step_4_data[128] = Tag::Transmute as u8;
-    step_4_data[136..144].copy_from_slice(10_u64.to_le_bytes().as_ref());
+    step_4_data[160] = Tag::Transmute as u8;
+    step_4_data[168..176].copy_from_slice(10_u64.to_le_bytes().as_ref());
 
     let pre_token_balance = spl_token::state::Account::unpack(
         &ctx.banks_client
@@ -267,24 +237,35 @@ async fn round_trip() -> Result<(), Box<dyn Error>> {
     );
     assert_eq!(post_token_balance, 999990);
 
-    let post_nanotoken_balance = u64::from_le_bytes(
-        ctx.banks_client
+    // Vault info stores both bumps, so clients never re-derive them
+    let info_data = ctx
+        .banks_client
+        .get_account(info)
+        .await?
+        .unwrap()
+        .data;
+    let vault_info = VaultInfo::try_unpack(&info_data)?;
+    assert_eq!(vault_info.tokenkeg_mint(), &tokenkeg_mint.pubkey());
+    assert_eq!(vault_info.tokenkeg_vault(), &vault);
+    assert_eq!(vault_info.nanotoken_mint(), &nanotoken_mint.pubkey());
+    assert_eq!(vault_info.info_bump(), info_bump);
+    assert_eq!(vault_info.vault_bump(), vault_bump);
+
+    let post_nanotoken_balance = TokenAccount::try_unpack(
+        &ctx.banks_client
             .get_account(nanotoken_account_1)
             .await?
             .unwrap()
-            .data
-            .get(48..56)
-            .unwrap()
-            .try_into()
-            .unwrap(),
-    );
+            .data,
+    )?
+    .balance;
     println!("tokenkeg account before/after transmute = {pre_token_balance}/{post_token_balance}");
     println!(
         "nanotoken account before/after transmute = 0/{post_nanotoken_balance}"
     );
     assert_eq!(post_nanotoken_balance, 10);
 
-    // 5. nanotoken transfer back and forth
+    // 4. nanotoken transfer back and forth
     // multi-transfer
     let num_transfers = 2;
     let mut ix_data = vec![0; num_transfers * (8 + TransferArgs::size())];
@@ -330,7 +311,58 @@ async fn round_trip() -> Result<(), Box<dyn Error>> {
         .await
         .unwrap();
 
-    // 6. port back over to Tokenkeg
+    // 5. port back over to Tokenkeg, first into a tokenkeg account which
+    // does not exist yet and is created by transmute
+    let new_tokenkeg_account = Keypair::new();
+    let step_6_accounts = vec![
+        // transmute
+        // from, to, owner, tokenkeg_mint, nanotoken_mint, vault_info, tokenkeg_vault, tokenkeg_program, _rem @ .., config, system_program, payer
+        AccountMeta::new(nanotoken_account_1, false),
+        AccountMeta::new(new_tokenkeg_account.pubkey(), true),
+        AccountMeta::new(ctx.payer.pubkey(), true),
+        AccountMeta::new(tokenkeg_mint.pubkey(), false),
+        AccountMeta::new(nanotoken_mint.pubkey(), false),
+        AccountMeta::new_readonly(info, false),
+        AccountMeta::new(vault, false),
+        AccountMeta::new_readonly(spl_token::ID, false),
+        // config
+        AccountMeta::new(config, false),
+        AccountMeta::new_readonly(system_program::ID, false),
+        AccountMeta::new(ctx.payer.pubkey(), true),
+    ];
+    let mut step_6_data = vec![];
+    step_6_data.extend((Tag::Transmute as u64).to_le_bytes());
+    step_6_data.extend((1_u64).to_le_bytes());
+    // bump is unused for nanotoken -> tokenkeg
+    step_6_data.extend((0_u64).to_le_bytes());
+    let port_back = Instruction {
+        program_id: nanotoken::ID,
+        accounts: step_6_accounts,
+        data: step_6_data,
+    };
+    let transaction = Transaction::new_signed_with_payer(
+        &[port_back],
+        Some(&ctx.payer.pubkey()),
+        &[&ctx.payer, &new_tokenkeg_account],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    let onchain_new_tokenkeg_account = ctx
+        .banks_client
+        .get_account(new_tokenkeg_account.pubkey())
+        .await?
+        .unwrap();
+    let new_tokenkeg_account_state =
+        spl_token::state::Account::unpack(&onchain_new_tokenkeg_account.data)
+            .unwrap();
+    assert_eq!(onchain_new_tokenkeg_account.owner, spl_token::ID);
+    assert_eq!(new_tokenkeg_account_state.owner, ctx.payer.pubkey());
+    assert_eq!(new_tokenkeg_account_state.amount, 1);
+
+    // then the rest into the existing tokenkeg account
     let step_6_accounts = vec![
         // transmute
         // from, to, owner, tokenkeg_mint, nanotoken_mint, vault_info, tokenkeg_vault, tokenkeg_program, _rem @ .., config, system_program, payer
@@ -349,7 +381,9 @@ async fn round_trip() -> Result<(), Box<dyn Error>> {
     ];
     let mut step_6_data = vec![];
     step_6_data.extend((Tag::Transmute as u64).to_le_bytes());
-    step_6_data.extend((10_u64).to_le_bytes());
+    step_6_data.extend((9_u64).to_le_bytes());
+    // bump is unused for nanotoken -> tokenkeg
+    step_6_data.extend((0_u64).to_le_bytes());
     let port_back = Instruction {
         program_id: nanotoken::ID,
         accounts: step_6_accounts,
@@ -381,23 +415,551 @@ async fn round_trip() -> Result<(), Box<dyn Error>> {
         onchain_tokenkeg_account.owner
     );
 
-    let post_nanotoken_balance = u64::from_le_bytes(
-        ctx.banks_client
+    let post_nanotoken_balance = TokenAccount::try_unpack(
+        &ctx.banks_client
             .get_account(nanotoken_account_1)
             .await?
             .unwrap()
-            .data
-            .get(48..56)
-            .unwrap()
-            .try_into()
-            .unwrap(),
-    );
+            .data,
+    )?
+    .balance;
     println!("tokenkeg account before/after transmute = {pre_token_balance}/{post_token_balance}");
     println!(
         "nanotoken account before/after transmute = 0/{post_nanotoken_balance}"
     );
-    assert_eq!(post_token_balance, 1000000);
+    assert_eq!(post_token_balance, 1000000 - 1);
     assert_eq!(post_nanotoken_balance, 0);
 
+    // 6. port over and back again in one TransmuteBatch
+    let owner = ctx.payer.pubkey();
+    let entry_accounts = |from: Pubkey, to: Pubkey| {
+        vec![
+            AccountMeta::new(from, false),
+            AccountMeta::new(to, false),
+            AccountMeta::new(owner, true),
+            AccountMeta::new(tokenkeg_mint.pubkey(), false),
+            AccountMeta::new(nanotoken_mint.pubkey(), false),
+            AccountMeta::new_readonly(info, false),
+            AccountMeta::new(vault, false),
+            AccountMeta::new_readonly(spl_token::ID, false),
+        ]
+    };
+    let mut step_7_accounts =
+        entry_accounts(tokenkeg_account.pubkey(), nanotoken_account_1);
+    step_7_accounts.extend(entry_accounts(
+        nanotoken_account_1,
+        tokenkeg_account.pubkey(),
+    ));
+    step_7_accounts.extend([
+        AccountMeta::new(config, false),
+        AccountMeta::new_readonly(system_program::ID, false),
+        AccountMeta::new(ctx.payer.pubkey(), true),
+    ]);
+    let mut step_7_data = vec![];
+    step_7_data.extend((Tag::TransmuteBatch as u64).to_le_bytes());
+    step_7_data.extend((2_u64).to_le_bytes());
+    step_7_data.extend((5_u64).to_le_bytes());
+    step_7_data.extend((0_u64).to_le_bytes());
+    step_7_data.extend((2_u64).to_le_bytes());
+    step_7_data.extend((0_u64).to_le_bytes());
+    let transmute_batch = Instruction {
+        program_id: nanotoken::ID,
+        accounts: step_7_accounts,
+        data: step_7_data,
+    };
+    let transaction = Transaction::new_signed_with_payer(
+        &[transmute_batch],
+        Some(&ctx.payer.pubkey()),
+        &[&ctx.payer],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let post_token_balance = spl_token::state::Account::unpack(
+        &ctx.banks_client
+            .get_account(tokenkeg_account.pubkey())
+            .await?
+            .unwrap()
+            .data,
+    )
+    .unwrap()
+    .amount;
+    let post_nanotoken_balance = TokenAccount::try_unpack(
+        &ctx.banks_client
+            .get_account(nanotoken_account_1)
+            .await?
+            .unwrap()
+            .data,
+    )?
+    .balance;
+    assert_eq!(post_token_balance, 1000000 - 1 - 3);
+    assert_eq!(post_nanotoken_balance, 3);
+
+    // 7. degenerate transmute account configurations are rejected
+    // from == to
+    let accounts = entry_accounts(nanotoken_account_1, nanotoken_account_1);
+    assert_eq!(
+        transmute_error(&mut ctx, accounts, config).await,
+        NanoTokenError::TransmuteSameAccount.into()
+    );
+
+    // vault as from or to
+    let accounts = entry_accounts(vault, nanotoken_account_1);
+    assert_eq!(
+        transmute_error(&mut ctx, accounts, config).await,
+        NanoTokenError::TransmuteVaultAsUserAccount.into()
+    );
+    let accounts = entry_accounts(nanotoken_account_1, vault);
+    assert_eq!(
+        transmute_error(&mut ctx, accounts, config).await,
+        NanoTokenError::TransmuteVaultAsUserAccount.into()
+    );
+
+    // nanotoken destination of another mint
+    let (native_account, native_bump) =
+        TokenAccount::address(NATIVE_MINT_INDEX, &ctx.payer.pubkey());
+    let mut init_native_data = vec![];
+    init_native_data.extend((Tag::InitializeAccount as u64).to_le_bytes());
+    init_native_data.extend(bytemuck::bytes_of(&InitializeAccountArgs {
+        owner: ctx.payer.pubkey(),
+        mint: NATIVE_MINT_INDEX,
+        bump: native_bump as u64,
+        immutable_owner: 0,
+        owner_must_sign: 0,
+    }));
+    let init_native = Instruction {
+        program_id: nanotoken::ID,
+        accounts: vec![
+            AccountMeta::new(native_account, false),
+            AccountMeta::new(config, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new(ctx.payer.pubkey(), true),
+        ],
+        data: init_native_data,
+    };
+    let transaction = Transaction::new_signed_with_payer(
+        &[init_native],
+        Some(&ctx.payer.pubkey()),
+        &[&ctx.payer],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    let accounts = entry_accounts(tokenkeg_account.pubkey(), native_account);
+    assert_eq!(
+        transmute_error(&mut ctx, accounts, config).await,
+        NanoTokenError::TransmuteDestinationMintMismatch.into()
+    );
+
+    Ok(())
+}
+
+/// Tokens transmuted into a new nanotoken account carry the mint's transfer
+/// fee and pausability, like minted tokens do
+#[tokio::test(flavor = "current_thread")]
+async fn transmute_into_new_account() -> Result<(), Box<dyn Error>> {
+    let mut ctx = start().await;
+    let payer = ctx.payer.pubkey();
+    setup_config(&mut ctx).await?;
+    let (tokenkeg_mint, tokenkeg_account) =
+        create_tokenkeg_account(&mut ctx, 1_000).await?;
+    let (nanotoken_mint, mint_index) =
+        create_vault(&mut ctx, &tokenkeg_mint).await?;
+
+    // Vault mints charge no fee and can't be paused, so set both directly
+    let pause_authority = Keypair::new();
+    let mut mint_account = ctx
+        .banks_client
+        .get_account(nanotoken_mint)
+        .await?
+        .ok_or("could not find mint")?;
+    let mut mint = Mint::try_unpack(&mint_account.data)?;
+    mint.transfer_fee_bps = 100;
+    mint.max_transfer_fee = u64::MAX;
+    mint.pause_authority = pause_authority.pubkey();
+    mint_account.data[8..Mint::space()]
+        .copy_from_slice(bytemuck::bytes_of(&mint));
+    ctx.set_account(&nanotoken_mint, &mint_account.into());
+
+    let (nanotoken_account, bump) =
+        pda::token_account_address(&payer, mint_index);
+    process_instructions(
+        &mut ctx,
+        &[instructions::transmute(
+            &tokenkeg_account,
+            &nanotoken_account,
+            &payer,
+            &tokenkeg_mint,
+            &spl_token::ID,
+            &nanotoken_mint,
+            500,
+            bump,
+            &payer,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    let account =
+        get_token_account(&mut ctx.banks_client, nanotoken_account).await?;
+    assert_eq!(account.balance, 500);
+    assert_eq!(account.transfer_fee_bps, 100);
+    assert_ne!(account.state & TokenAccount::PAUSABLE, 0);
+
+    // Transfers out of it withhold the fee...
+    let to = create_funded_account(
+        &mut ctx,
+        &Pubkey::new_unique(),
+        &nanotoken_mint,
+        0,
+    )
+    .await?;
+    let transfer = |amount: u64| {
+        instructions::with_mint(
+            instructions::transfer(&nanotoken_account, &to, &payer, amount),
+            &nanotoken_mint,
+        )
+    };
+    process_instructions(&mut ctx, &[transfer(200)], &[])
+        .await
+        .unwrap();
+    let to_account = get_token_account(&mut ctx.banks_client, to).await?;
+    assert_eq!(to_account.balance, 198);
+    assert_eq!(to_account.withheld_fees, 2);
+
+    // ...and are refused while the mint is paused
+    process_instructions(
+        &mut ctx,
+        &[BatchBuilder::new(&payer)
+            .push(instructions::pause(
+                &nanotoken_mint,
+                &pause_authority.pubkey(),
+            ))
+            .build()],
+        &[&pause_authority],
+    )
+    .await
+    .unwrap();
+    let err = process_instructions(&mut ctx, &[transfer(100)], &[])
+        .await
+        .unwrap_err();
+    assert_eq!(op_error(err), (Some(0), NanoTokenError::MintPaused.into()));
+
+    Ok(())
+}
+
+/// MigrateState leaves the config, whose only layout is v0, as is and moves a
+/// v0 vault info to the latest layout. Only the config authority may do so.
+#[tokio::test(flavor = "current_thread")]
+async fn migrate_state() -> Result<(), Box<dyn Error>> {
+    let mut ctx = start().await;
+    let payer = ctx.payer.pubkey();
+    setup_config(&mut ctx).await?;
+    let (tokenkeg_mint, _tokenkeg_account) =
+        create_tokenkeg_account(&mut ctx, 1_000).await?;
+    create_vault(&mut ctx, &tokenkeg_mint).await?;
+
+    // The config is already on the latest layout
+    let config_before = ctx
+        .banks_client
+        .get_account(CONFIG_ACCOUNT)
+        .await?
+        .ok_or("could not find config")?;
+    process_instructions(
+        &mut ctx,
+        &[instructions::migrate_state(&CONFIG_ACCOUNT, &payer, &payer)],
+        &[],
+    )
+    .await
+    .unwrap();
+    let config_after = ctx
+        .banks_client
+        .get_account(CONFIG_ACCOUNT)
+        .await?
+        .ok_or("could not find config")?;
+    assert_eq!(config_after.data, config_before.data);
+
+    // Roll the vault info back to v0, which has no vault bump
+    let (info, _info_bump) = VaultInfo::info(&tokenkeg_mint);
+    let mut info_account = ctx
+        .banks_client
+        .get_account(info)
+        .await?
+        .ok_or("could not find vault info")?;
+    info_account
+        .data
+        .truncate(8 + VAULT_INFO_V0_SIZE);
+    info_account.data[1] = 0;
+    ctx.set_account(&info, &info_account.into());
+
+    // A signer other than the config authority is rejected
+    let stranger = Keypair::new();
+    let err = process_instructions(
+        &mut ctx,
+        &[instructions::migrate_state(
+            &info,
+            &stranger.pubkey(),
+            &payer,
+        )],
+        &[&stranger],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(op_error(err), (Some(0), ProgramError::IllegalOwner));
+
+    process_instructions(
+        &mut ctx,
+        &[instructions::migrate_state(&info, &payer, &payer)],
+        &[],
+    )
+    .await
+    .unwrap();
+    let info_data = ctx
+        .banks_client
+        .get_account(info)
+        .await?
+        .ok_or("could not find vault info")?
+        .data;
+    assert_eq!(info_data.len(), VaultInfo::space());
+    assert_eq!(info_data[1], VAULT_INFO_VERSION);
+    let vault_info = VaultInfo::try_unpack(&info_data)?;
+    assert_eq!(vault_info.vault_bump(), VaultInfo::vault(&tokenkeg_mint).1);
+
+    Ok(())
+}
+
+/// Transmuting a Token-2022 transfer fee mint credits only what the vault
+/// received. The fee stays withheld in the vault's token account.
+#[tokio::test(flavor = "current_thread")]
+async fn transmute_transfer_fee_mint() -> Result<(), Box<dyn Error>> {
+    let mut ctx = start().await;
+    let payer = ctx.payer.pubkey();
+    setup_config(&mut ctx).await?;
+
+    // Token-2022 mint charging 1%, and a payer account holding 1_000
+    let rent = Rent::default();
+    let tokenkeg_mint = Keypair::new();
+    let tokenkeg_account = Keypair::new();
+    let mint_len = ExtensionType::try_calculate_account_len::<
+        spl_token_2022::state::Mint,
+    >(&[ExtensionType::TransferFeeConfig])?;
+    let account_len = ExtensionType::try_calculate_account_len::<
+        spl_token_2022::state::Account,
+    >(&[ExtensionType::TransferFeeAmount])?;
+    let ixs = [
+        system_instruction::create_account(
+            &payer,
+            &tokenkeg_mint.pubkey(),
+            rent.minimum_balance(mint_len),
+            mint_len as u64,
+            &spl_token_2022::ID,
+        ),
+        transfer_fee_instruction::initialize_transfer_fee_config(
+            &spl_token_2022::ID,
+            &tokenkeg_mint.pubkey(),
+            Some(&payer),
+            Some(&payer),
+            100,
+            u64::MAX,
+        )?,
+        spl_token_2022::instruction::initialize_mint2(
+            &spl_token_2022::ID,
+            &tokenkeg_mint.pubkey(),
+            &payer,
+            None,
+            6,
+        )?,
+        system_instruction::create_account(
+            &payer,
+            &tokenkeg_account.pubkey(),
+            rent.minimum_balance(account_len),
+            account_len as u64,
+            &spl_token_2022::ID,
+        ),
+        spl_token_2022::instruction::initialize_account3(
+            &spl_token_2022::ID,
+            &tokenkeg_account.pubkey(),
+            &tokenkeg_mint.pubkey(),
+            &payer,
+        )?,
+        spl_token_2022::instruction::mint_to(
+            &spl_token_2022::ID,
+            &tokenkeg_mint.pubkey(),
+            &tokenkeg_account.pubkey(),
+            &payer,
+            &[],
+            1_000,
+        )?,
+    ];
+    process_instructions(&mut ctx, &ixs, &[&tokenkeg_mint, &tokenkeg_account])
+        .await?;
+    let (tokenkeg_mint, tokenkeg_account) =
+        (tokenkeg_mint.pubkey(), tokenkeg_account.pubkey());
+
+    let nanotoken_mint = Keypair::new();
+    process_instructions(
+        &mut ctx,
+        &instructions::create_vault(
+            &tokenkeg_mint,
+            &spl_token_2022::ID,
+            &nanotoken_mint.pubkey(),
+            rent.minimum_balance(Mint::space()),
+            &payer,
+        ),
+        &[&nanotoken_mint],
+    )
+    .await?;
+    let nanotoken_mint = nanotoken_mint.pubkey();
+    let mint_index = get_mint(&mut ctx.banks_client, nanotoken_mint)
+        .await?
+        .mint_index;
+
+    let (nanotoken_account, bump) =
+        pda::token_account_address(&payer, mint_index);
+    process_instructions(
+        &mut ctx,
+        &[instructions::transmute(
+            &tokenkeg_account,
+            &nanotoken_account,
+            &payer,
+            &tokenkeg_mint,
+            &spl_token_2022::ID,
+            &nanotoken_mint,
+            1_000,
+            bump,
+            &payer,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    // 1% of 1_000 is withheld by Token-2022, so only 990 is credited
+    let account =
+        get_token_account(&mut ctx.banks_client, nanotoken_account).await?;
+    assert_eq!(account.balance, 990);
+    assert_eq!(
+        get_mint(&mut ctx.banks_client, nanotoken_mint)
+            .await?
+            .supply,
+        990
+    );
+    let (vault, _vault_bump) = VaultInfo::vault(&tokenkeg_mint);
+    let vault_data = ctx
+        .banks_client
+        .get_account(vault)
+        .await?
+        .ok_or("could not find vault")?
+        .data;
+    let vault_account =
+        StateWithExtensions::<spl_token_2022::state::Account>::unpack(
+            &vault_data,
+        )?;
+    assert_eq!(vault_account.base.amount, 990);
+    let withheld = vault_account
+        .get_extension::<TransferFeeAmount>()?
+        .withheld_amount;
+    assert_eq!(u64::from(withheld), 10);
+
     Ok(())
 }
+
+/// Creates a Tokenkeg mint with the payer as authority, and a keypair token
+/// account of the payer holding `amount`. Returns both addresses.
+async fn create_tokenkeg_account(
+    ctx: &mut ProgramTestContext,
+    amount: u64,
+) -> TestResult<(Pubkey, Pubkey)> {
+    let rent = Rent::default();
+    let payer = ctx.payer.pubkey();
+    let tokenkeg_mint = Keypair::new();
+    let tokenkeg_account = Keypair::new();
+    let ixs = [
+        system_instruction::create_account(
+            &payer,
+            &tokenkeg_mint.pubkey(),
+            rent.minimum_balance(spl_token::state::Mint::LEN),
+            spl_token::state::Mint::LEN as u64,
+            &spl_token::ID,
+        ),
+        spl_token::instruction::initialize_mint2(
+            &spl_token::ID,
+            &tokenkeg_mint.pubkey(),
+            &payer,
+            None,
+            6,
+        )?,
+        system_instruction::create_account(
+            &payer,
+            &tokenkeg_account.pubkey(),
+            rent.minimum_balance(spl_token::state::Account::LEN),
+            spl_token::state::Account::LEN as u64,
+            &spl_token::ID,
+        ),
+        spl_token::instruction::initialize_account3(
+            &spl_token::ID,
+            &tokenkeg_account.pubkey(),
+            &tokenkeg_mint.pubkey(),
+            &payer,
+        )?,
+        spl_token::instruction::mint_to(
+            &spl_token::ID,
+            &tokenkeg_mint.pubkey(),
+            &tokenkeg_account.pubkey(),
+            &payer,
+            &[],
+            amount,
+        )?,
+    ];
+    process_instructions(ctx, &ixs, &[&tokenkeg_mint, &tokenkeg_account])
+        .await?;
+    Ok((tokenkeg_mint.pubkey(), tokenkeg_account.pubkey()))
+}
+
+/// Creates the vault for a Tokenkeg mint, returning its nanotoken mint and
+/// that mint's index
+async fn create_vault(
+    ctx: &mut ProgramTestContext,
+    tokenkeg_mint: &Pubkey,
+) -> TestResult<(Pubkey, u64)> {
+    let payer = ctx.payer.pubkey();
+    let nanotoken_mint = Keypair::new();
+    let ixs = instructions::create_vault(
+        tokenkeg_mint,
+        &spl_token::ID,
+        &nanotoken_mint.pubkey(),
+        Rent::default().minimum_balance(Mint::space()),
+        &payer,
+    );
+    process_instructions(ctx, &ixs, &[&nanotoken_mint]).await?;
+    let mint_index = get_mint(&mut ctx.banks_client, nanotoken_mint.pubkey())
+        .await?
+        .mint_index;
+    Ok((nanotoken_mint.pubkey(), mint_index))
+}
+
+/// Sends a lone transmute of 1 with the given op accounts and returns the
+/// error it failed with
+async fn transmute_error(
+    ctx: &mut ProgramTestContext,
+    mut accounts: Vec<AccountMeta>,
+    config: Pubkey,
+) -> ProgramError {
+    accounts.extend([
+        AccountMeta::new(config, false),
+        AccountMeta::new_readonly(system_program::ID, false),
+        AccountMeta::new(ctx.payer.pubkey(), true),
+    ]);
+    let mut data = vec![];
+    data.extend((Tag::Transmute as u64).to_le_bytes());
+    data.extend(bytemuck::bytes_of(&TransmuteArgs { amount: 1, bump: 0 }));
+    let err = process(ctx, data, accounts, &[])
+        .await
+        .unwrap_err();
+    op_error(err).1
+}
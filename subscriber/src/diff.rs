@@ -0,0 +1,201 @@
+//! Typed diffs of nanotoken account updates.
+
+use std::collections::HashMap;
+
+use nanotoken::{Mint, TokenAccount};
+use solana_sdk::pubkey::Pubkey;
+
+/// Decoded state of a nanotoken account
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AccountState {
+    TokenAccount(TokenAccount),
+    Mint(Mint),
+    /// Closed or garbage collected
+    Closed,
+}
+
+impl AccountState {
+    /// None for other account types, and for accounts that still need
+    /// MigrateAccount
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        if data.is_empty() {
+            return Some(AccountState::Closed);
+        }
+        if let Ok(account) = TokenAccount::try_unpack(data) {
+            return Some(AccountState::TokenAccount(account));
+        }
+        Mint::try_unpack(data)
+            .ok()
+            .map(AccountState::Mint)
+    }
+
+    fn token_account(&self) -> Option<&TokenAccount> {
+        match self {
+            AccountState::TokenAccount(account) => Some(account),
+            _ => None,
+        }
+    }
+}
+
+/// An account update, with the state it replaced
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StateDiff {
+    pub address: Pubkey,
+    pub slot: u64,
+    /// None if the tracker hadn't seen the account before
+    pub before: Option<AccountState>,
+    pub after: AccountState,
+}
+
+impl StateDiff {
+    /// The token balance change, if this is a token account update that
+    /// changed the balance. Closing an account changes it to zero.
+    pub fn balance_change(&self) -> Option<BalanceChange> {
+        let before = self
+            .before
+            .as_ref()
+            .and_then(AccountState::token_account);
+        let (account, after) = match (&self.after, before) {
+            (AccountState::TokenAccount(account), _) => {
+                (account, account.balance)
+            }
+            (AccountState::Closed, Some(account)) => (account, 0),
+            _ => return None,
+        };
+        let change = BalanceChange {
+            address: self.address,
+            owner: account.owner,
+            mint_index: account.mint,
+            slot: self.slot,
+            before: before.map(|account| account.balance),
+            after,
+        };
+        (change.before != Some(after)).then_some(change)
+    }
+}
+
+/// A token account balance change
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BalanceChange {
+    pub address: Pubkey,
+    /// Owner after the change, or before it if the account was closed
+    pub owner: Pubkey,
+    pub mint_index: u64,
+    pub slot: u64,
+    /// None if the tracker hadn't seen the account before, in which case
+    /// the change is unknown
+    pub before: Option<u64>,
+    pub after: u64,
+}
+
+impl BalanceChange {
+    /// Positive for deposits. None if the balance before is unknown.
+    pub fn delta(&self) -> Option<i128> {
+        self.before
+            .map(|before| self.after as i128 - before as i128)
+    }
+}
+
+/// Last seen state of each account. Seed it with [Tracker::insert] from a
+/// getProgramAccounts snapshot taken after subscribing, so that the first
+/// update of every account has a known `before`.
+#[derive(Debug, Default)]
+pub struct Tracker {
+    accounts: HashMap<Pubkey, (u64, AccountState)>,
+}
+
+impl Tracker {
+    /// Records the state of an account without producing a diff. Ignored if
+    /// the tracker already has a newer state.
+    pub fn insert(&mut self, address: Pubkey, slot: u64, data: &[u8]) {
+        self.apply(address, slot, data);
+    }
+
+    /// Records an account update and returns what changed. None for
+    /// updates older than the last seen state, accounts that aren't
+    /// nanotoken mints or token accounts, and updates that change nothing.
+    pub fn apply(
+        &mut self,
+        address: Pubkey,
+        slot: u64,
+        data: &[u8],
+    ) -> Option<StateDiff> {
+        self.apply_state(address, slot, AccountState::decode(data)?)
+    }
+
+    /// Like [Tracker::apply], for an already decoded state, e.g. from
+    /// `nanotoken_client` queries
+    pub fn apply_state(
+        &mut self,
+        address: Pubkey,
+        slot: u64,
+        after: AccountState,
+    ) -> Option<StateDiff> {
+        let before = match self.accounts.get(&address) {
+            Some((last_slot, _)) if *last_slot > slot => return None,
+            Some((_, state)) if *state == after => return None,
+            Some((_, state)) => Some(*state),
+            None => None,
+        };
+        self.accounts
+            .insert(address, (slot, after));
+        Some(StateDiff {
+            address,
+            slot,
+            before,
+            after,
+        })
+    }
+
+    /// Last seen state of `address`
+    pub fn get(&self, address: &Pubkey) -> Option<&AccountState> {
+        self.accounts
+            .get(address)
+            .map(|(_, state)| state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytemuck::Zeroable;
+    use nanotoken::{consts::TOKEN_ACCOUNT_VERSION, AccountDiscriminator};
+
+    use super::*;
+
+    fn token_account_data(balance: u64) -> Vec<u8> {
+        let mut account = TokenAccount::zeroed();
+        account.balance = balance;
+        let mut data = vec![0; TokenAccount::space()];
+        data[0] = AccountDiscriminator::Token as u8;
+        data[1] = TOKEN_ACCOUNT_VERSION;
+        data[8..].copy_from_slice(bytemuck::bytes_of(&account));
+        data
+    }
+
+    #[test]
+    fn tracker_diffs_balances_in_slot_order() {
+        let address = Pubkey::new_unique();
+        let mut tracker = Tracker::default();
+        tracker.insert(address, 10, &token_account_data(5));
+
+        let change = tracker
+            .apply(address, 11, &token_account_data(8))
+            .and_then(|diff| diff.balance_change())
+            .unwrap();
+        assert_eq!(change.delta(), Some(3));
+
+        // Stale and unchanged updates
+        assert!(tracker
+            .apply(address, 9, &token_account_data(1))
+            .is_none());
+        assert!(tracker
+            .apply(address, 12, &token_account_data(8))
+            .is_none());
+
+        let change = tracker
+            .apply(address, 13, &[])
+            .and_then(|diff| diff.balance_change())
+            .unwrap();
+        assert_eq!((change.before, change.after), (Some(8), 0));
+    }
+}
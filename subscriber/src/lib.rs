@@ -0,0 +1,24 @@
+//! Streams of nanotoken account updates, for deposit detection.
+//!
+//! [Tracker] turns raw account updates into typed [StateDiff]s against the
+//! last state it saw of each account. It doesn't care where updates come
+//! from: [ws::Subscriber] feeds it from a websocket `programSubscribe`, and
+//! a Yellowstone gRPC client can pass its account updates to
+//! [Tracker::apply] directly.
+//!
+//! ```ignore
+//! let subscriber = Subscriber::new("ws://localhost:8900").await?;
+//! let mut changes = subscriber
+//!     .balance_changes(Tracker::default(), CommitmentConfig::finalized())
+//!     .await?;
+//! while let Some(change) = changes.next().await {
+//!     if let Some(delta @ 1..) = change.delta() {
+//!         credit(change.owner, change.mint_index, delta);
+//!     }
+//! }
+//! ```
+
+pub mod diff;
+pub mod ws;
+
+pub use diff::{AccountState, BalanceChange, StateDiff, Tracker};
@@ -0,0 +1,88 @@
+//! Account updates from a websocket `programSubscribe`.
+
+use futures_util::{future, Stream, StreamExt};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    nonblocking::pubsub_client::{PubsubClient, PubsubClientError},
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::RpcFilterType,
+    rpc_response::{Response, RpcKeyedAccount},
+};
+use solana_sdk::{
+    account::Account, commitment_config::CommitmentConfig, pubkey::Pubkey,
+};
+
+use crate::{BalanceChange, StateDiff, Tracker};
+
+/// Subscriptions end when the subscriber is dropped
+pub struct Subscriber {
+    client: PubsubClient,
+}
+
+impl Subscriber {
+    pub async fn new(url: &str) -> Result<Self, PubsubClientError> {
+        Ok(Self {
+            client: PubsubClient::new(url).await?,
+        })
+    }
+
+    /// Updates of nanotoken accounts matching `filters` (e.g. those in
+    /// `nanotoken_client::filters`, or none for every account), diffed
+    /// against `tracker`.
+    ///
+    /// Closed accounts no longer match data filters, so closes are only
+    /// seen without them.
+    pub async fn state_diffs(
+        &self,
+        tracker: Tracker,
+        filters: Vec<RpcFilterType>,
+        commitment: CommitmentConfig,
+    ) -> Result<impl Stream<Item = StateDiff> + '_, PubsubClientError> {
+        let config = RpcProgramAccountsConfig {
+            filters: (!filters.is_empty()).then_some(filters),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                commitment: Some(commitment),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let (updates, _unsubscribe) = self
+            .client
+            .program_subscribe(&nanotoken::ID, Some(config))
+            .await?;
+        Ok(updates
+            .scan(tracker, |tracker, update| {
+                let diff = decode(update).and_then(|(address, slot, data)| {
+                    tracker.apply(address, slot, &data)
+                });
+                future::ready(Some(diff))
+            })
+            .filter_map(future::ready))
+    }
+
+    /// Balance changes of every token account, including closes
+    pub async fn balance_changes(
+        &self,
+        tracker: Tracker,
+        commitment: CommitmentConfig,
+    ) -> Result<impl Stream<Item = BalanceChange> + '_, PubsubClientError> {
+        Ok(self
+            .state_diffs(tracker, vec![], commitment)
+            .await?
+            .filter_map(|diff| future::ready(diff.balance_change())))
+    }
+}
+
+/// The address, slot, and data of an update. Accounts no longer owned by
+/// the program have been closed, and are given empty data.
+fn decode(update: Response<RpcKeyedAccount>) -> Option<(Pubkey, u64, Vec<u8>)> {
+    let address = update.value.pubkey.parse().ok()?;
+    let account: Account = update.value.account.decode()?;
+    let data = if account.owner == nanotoken::ID && account.lamports > 0 {
+        account.data
+    } else {
+        vec![]
+    };
+    Some((address, update.context.slot, data))
+}
@@ -0,0 +1,225 @@
+//! solana-program-test fixtures shared by the nanotoken integration tests.
+//!
+//! The program is loaded from the sbf build (`cargo build-sbf`), found via
+//! `BPF_OUT_DIR` or `target/deploy` like any [ProgramTest]. See [svm] for
+//! the much faster LiteSVM equivalents.
+
+use std::error::Error;
+
+use nanotoken::{
+    error::decode_op_error, ix::InitializeMintArgs, Mint, TokenAccount,
+};
+use nanotoken_client::{instructions, pda};
+use solana_program_test::{
+    BanksClient, BanksClientError, ProgramTest, ProgramTestContext,
+};
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction, InstructionError},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::Keypair,
+    signer::Signer,
+    system_transaction,
+    transaction::{Transaction, TransactionError},
+};
+
+pub mod svm;
+
+pub type TestResult<T> = Result<T, Box<dyn Error>>;
+
+/// Starts a bank with the nanotoken program
+pub async fn start() -> ProgramTestContext {
+    let mut program_test = ProgramTest::new("nanotoken", nanotoken::ID, None);
+    program_test.prefer_bpf(true);
+    program_test.start_with_context().await
+}
+
+/// Sends a single nanotoken instruction paid for and signed by the payer,
+/// plus any extra signers.
+pub async fn process(
+    ctx: &mut ProgramTestContext,
+    data: Vec<u8>,
+    accounts: Vec<AccountMeta>,
+    extra_signers: &[&Keypair],
+) -> Result<(), BanksClientError> {
+    let instruction = Instruction {
+        program_id: nanotoken::ID,
+        accounts,
+        data,
+    };
+    process_instructions(ctx, &[instruction], extra_signers).await
+}
+
+/// Sends `instructions` in one transaction paid for and signed by the payer,
+/// plus any extra signers.
+pub async fn process_instructions(
+    ctx: &mut ProgramTestContext,
+    instructions: &[Instruction],
+    extra_signers: &[&Keypair],
+) -> Result<(), BanksClientError> {
+    let mut signers = vec![&ctx.payer];
+    signers.extend(extra_signers);
+    let transaction = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&ctx.payer.pubkey()),
+        &signers,
+        ctx.last_blockhash,
+    );
+    ctx.banks_client
+        .process_transaction(transaction)
+        .await
+}
+
+/// Simulates `instructions` in one transaction paid for and signed by the
+/// payer, returning the return data they set (empty if none). Read-only
+/// queries like GetBalances are meant to be simulated.
+pub async fn simulate_return_data(
+    ctx: &mut ProgramTestContext,
+    instructions: &[Instruction],
+) -> TestResult<Vec<u8>> {
+    let transaction = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&ctx.payer.pubkey()),
+        &[&ctx.payer],
+        ctx.last_blockhash,
+    );
+    let simulation = ctx
+        .banks_client
+        .simulate_transaction(transaction)
+        .await?;
+    simulation
+        .result
+        .ok_or("simulation did not run")??;
+    Ok(simulation
+        .simulation_details
+        .and_then(|details| details.return_data)
+        .map(|return_data| return_data.data)
+        .unwrap_or_default())
+}
+
+/// Extracts the failing op index and error from a failed transaction whose
+/// first instruction is the nanotoken one.
+pub fn op_error(err: BanksClientError) -> (Option<usize>, ProgramError) {
+    let TransactionError::InstructionError(0, InstructionError::Custom(code)) =
+        err.unwrap()
+    else {
+        panic!("expected custom error, got {err:?}");
+    };
+    decode_op_error(code)
+}
+
+/// Initializes the program config with the payer as its authority
+pub async fn setup_config(ctx: &mut ProgramTestContext) -> TestResult<()> {
+    let payer = ctx.payer.pubkey();
+    let ix = instructions::initialize_config(&payer, &payer);
+    process_instructions(ctx, &[ix], &[]).await?;
+    Ok(())
+}
+
+/// Creates a keypair mint with no fees or optional authorities, returning
+/// its address and index. The config must be set up.
+pub async fn create_mint(
+    ctx: &mut ProgramTestContext,
+    authority: &Pubkey,
+    decimals: u8,
+) -> TestResult<(Pubkey, u64)> {
+    create_mint_with(
+        ctx,
+        InitializeMintArgs {
+            authority: *authority,
+            decimals: decimals as u64,
+            freeze_authority: Pubkey::default(),
+            transfer_fee: Default::default(),
+            pause_authority: Pubkey::default(),
+            max_supply: 0,
+            clawback_authority: Pubkey::default(),
+        },
+    )
+    .await
+}
+
+/// [create_mint] with the optional authorities, transfer fee, and max supply
+/// in `args`
+pub async fn create_mint_with(
+    ctx: &mut ProgramTestContext,
+    args: InitializeMintArgs,
+) -> TestResult<(Pubkey, u64)> {
+    let mint_keypair = Keypair::new();
+    let mint = mint_keypair.pubkey();
+    let create_mint = system_transaction::create_account(
+        &ctx.payer,
+        &mint_keypair,
+        ctx.last_blockhash,
+        Rent::default().minimum_balance(Mint::space()),
+        Mint::space() as u64,
+        &nanotoken::ID,
+    );
+    ctx.banks_client
+        .process_transaction(create_mint)
+        .await?;
+
+    // Unset authorities are [0; 32] either way
+    let payer = ctx.payer.pubkey();
+    let ix = instructions::initialize_mint(
+        &mint,
+        &args.authority,
+        args.decimals as u8,
+        Some(&args.freeze_authority),
+        args.transfer_fee,
+        Some(&args.pause_authority),
+        Some(args.max_supply),
+        Some(&args.clawback_authority),
+        &payer,
+    );
+    process_instructions(ctx, &[ix], &[]).await?;
+    let mint_index = get_mint(&mut ctx.banks_client, mint)
+        .await?
+        .mint_index;
+    Ok((mint, mint_index))
+}
+
+/// Creates the (owner, mint) token account and mints `amount` to it,
+/// returning its address. The payer must be the mint authority.
+pub async fn create_funded_account(
+    ctx: &mut ProgramTestContext,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    amount: u64,
+) -> TestResult<Pubkey> {
+    let mint_index = get_mint(&mut ctx.banks_client, *mint)
+        .await?
+        .mint_index;
+    let payer = ctx.payer.pubkey();
+    let (token_account, _bump) = pda::token_account_address(owner, mint_index);
+    let mut ixs = vec![instructions::initialize_account(
+        owner, mint_index, false, false, &payer,
+    )];
+    if amount > 0 {
+        ixs.push(instructions::mint_to(&token_account, mint, &payer, amount));
+    }
+    process_instructions(ctx, &ixs, &[]).await?;
+    Ok(token_account)
+}
+
+pub async fn get_token_account(
+    client: &mut BanksClient,
+    key: Pubkey,
+) -> TestResult<TokenAccount> {
+    let account = client
+        .get_account(key)
+        .await?
+        .ok_or("could not find account")?;
+    Ok(TokenAccount::try_unpack(&account.data)?)
+}
+
+pub async fn get_mint(
+    client: &mut BanksClient,
+    key: Pubkey,
+) -> TestResult<Mint> {
+    let account = client
+        .get_account(key)
+        .await?
+        .ok_or("could not find mint")?;
+    Ok(Mint::try_unpack(&account.data)?)
+}
@@ -0,0 +1,202 @@
+//! [LiteSVM] counterparts of the program-test fixtures.
+//!
+//! LiteSVM runs transactions in-process without a bank or banks server, so
+//! a test case costs microseconds instead of milliseconds. That makes
+//! property tests with thousands of cases practical. The program is loaded
+//! from `BPF_OUT_DIR` or `target/deploy`, so run `cargo build-sbf` first.
+
+use std::path::PathBuf;
+
+use litesvm::{types::FailedTransactionMetadata, LiteSVM};
+use nanotoken::{error::decode_op_error, Mint, TokenAccount};
+use nanotoken_client::{instructions, pda};
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction, InstructionError},
+    native_token::LAMPORTS_PER_SOL,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    signature::Keypair,
+    signer::Signer,
+    system_instruction,
+    transaction::{Transaction, TransactionError},
+};
+
+use crate::TestResult;
+
+/// A LiteSVM with the nanotoken program and a funded payer
+pub struct SvmContext {
+    pub svm: LiteSVM,
+    pub payer: Keypair,
+}
+
+fn program_path() -> PathBuf {
+    let dir = std::env::var("BPF_OUT_DIR").map_or_else(
+        |_| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../target/deploy"),
+        PathBuf::from,
+    );
+    dir.join("nanotoken.so")
+}
+
+impl SvmContext {
+    pub fn start() -> Self {
+        let mut svm = LiteSVM::new();
+        svm.add_program_from_file(nanotoken::ID, program_path())
+            .expect("nanotoken.so not found, run cargo build-sbf");
+        let payer = Keypair::new();
+        svm.airdrop(&payer.pubkey(), 100 * LAMPORTS_PER_SOL)
+            .expect("airdrop failed");
+        Self { svm, payer }
+    }
+
+    /// Sends a single nanotoken instruction paid for and signed by the
+    /// payer, plus any extra signers.
+    pub fn process(
+        &mut self,
+        data: Vec<u8>,
+        accounts: Vec<AccountMeta>,
+        extra_signers: &[&Keypair],
+    ) -> Result<(), FailedTransactionMetadata> {
+        let instruction = Instruction {
+            program_id: nanotoken::ID,
+            accounts,
+            data,
+        };
+        self.process_instructions(&[instruction], extra_signers)
+    }
+
+    /// Sends `instructions` in one transaction paid for and signed by the
+    /// payer, plus any extra signers.
+    ///
+    /// The blockhash is expired after every transaction so that repeating
+    /// an identical transaction is not rejected as already processed.
+    pub fn process_instructions(
+        &mut self,
+        instructions: &[Instruction],
+        extra_signers: &[&Keypair],
+    ) -> Result<(), FailedTransactionMetadata> {
+        let mut signers = vec![&self.payer];
+        signers.extend(extra_signers);
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.payer.pubkey()),
+            &signers,
+            self.svm.latest_blockhash(),
+        );
+        let result = self.svm.send_transaction(transaction);
+        self.svm.expire_blockhash();
+        result.map(|_| ())
+    }
+
+    /// Initializes the program config with the payer as its authority
+    pub fn setup_config(&mut self) -> TestResult<()> {
+        let payer = self.payer.pubkey();
+        let ix = instructions::initialize_config(&payer, &payer);
+        self.process_instructions(&[ix], &[])
+            .map_err(|e| format!("{:?}", e.err))?;
+        Ok(())
+    }
+
+    /// Creates a keypair mint with no fees or optional authorities,
+    /// returning its address and index. The config must be set up.
+    pub fn create_mint(
+        &mut self,
+        authority: &Pubkey,
+        decimals: u8,
+    ) -> TestResult<(Pubkey, u64)> {
+        self.create_capped_mint(authority, decimals, None)
+    }
+
+    /// [SvmContext::create_mint] with an optional max supply
+    pub fn create_capped_mint(
+        &mut self,
+        authority: &Pubkey,
+        decimals: u8,
+        max_supply: Option<u64>,
+    ) -> TestResult<(Pubkey, u64)> {
+        let mint_keypair = Keypair::new();
+        let mint = mint_keypair.pubkey();
+        let payer = self.payer.pubkey();
+        let create_mint = system_instruction::create_account(
+            &payer,
+            &mint,
+            self.svm
+                .minimum_balance_for_rent_exemption(Mint::space()),
+            Mint::space() as u64,
+            &nanotoken::ID,
+        );
+        let initialize_mint = instructions::initialize_mint(
+            &mint,
+            authority,
+            decimals,
+            None,
+            Default::default(),
+            None,
+            max_supply,
+            None,
+            &payer,
+        );
+        self.process_instructions(
+            &[create_mint, initialize_mint],
+            &[&mint_keypair],
+        )
+        .map_err(|e| format!("{:?}", e.err))?;
+        Ok((mint, self.get_mint(&mint)?.mint_index))
+    }
+
+    /// Creates the (owner, mint) token account and mints `amount` to it,
+    /// returning its address. The payer must be the mint authority.
+    pub fn create_funded_account(
+        &mut self,
+        owner: &Pubkey,
+        mint: &Pubkey,
+        amount: u64,
+    ) -> TestResult<Pubkey> {
+        let mint_index = self.get_mint(mint)?.mint_index;
+        let payer = self.payer.pubkey();
+        let (token_account, _bump) =
+            pda::token_account_address(owner, mint_index);
+        let mut ixs = vec![instructions::initialize_account(
+            owner, mint_index, false, false, &payer,
+        )];
+        if amount > 0 {
+            ixs.push(instructions::mint_to(
+                &token_account,
+                mint,
+                &payer,
+                amount,
+            ));
+        }
+        self.process_instructions(&ixs, &[])
+            .map_err(|e| format!("{:?}", e.err))?;
+        Ok(token_account)
+    }
+
+    pub fn get_token_account(&self, key: &Pubkey) -> TestResult<TokenAccount> {
+        let account = self
+            .svm
+            .get_account(key)
+            .ok_or("could not find account")?;
+        Ok(TokenAccount::try_unpack(&account.data)?)
+    }
+
+    pub fn get_mint(&self, key: &Pubkey) -> TestResult<Mint> {
+        let account = self
+            .svm
+            .get_account(key)
+            .ok_or("could not find mint")?;
+        Ok(Mint::try_unpack(&account.data)?)
+    }
+}
+
+/// Extracts the failing op index and error from a failed transaction whose
+/// first instruction is the nanotoken one.
+pub fn op_error(
+    err: FailedTransactionMetadata,
+) -> (Option<usize>, ProgramError) {
+    let TransactionError::InstructionError(0, InstructionError::Custom(code)) =
+        err.err
+    else {
+        panic!("expected custom error, got {:?}", err.err);
+    };
+    decode_op_error(code)
+}